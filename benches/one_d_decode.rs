@@ -0,0 +1,77 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use ultracode::one_d::{decode_code128, decode_ean13_upca, ean13, DecodeOptions};
+use ultracode::{synthesize_row_code128, GrayImage};
+
+/// Высота «высокой» картинки: строк заметно больше, чем `scan_rows`.
+const HEIGHT: usize = 480;
+
+/// Ряд повторяется на всю высоту; справа и слева добиваем белым до `width`.
+fn tall_image(row: &[u8], width: usize) -> Vec<u8> {
+    let pad = width.saturating_sub(row.len());
+    let mut line = vec![255u8; pad / 2];
+    line.extend_from_slice(row);
+    line.resize(width.max(row.len()), 255);
+    line.repeat(HEIGHT)
+}
+
+/// Аддитивный шум ±amp (LCG, детерминированный) — уводит часть строк
+/// с адаптивной бинаризации на глобальный фоллбэк.
+fn add_noise(data: &[u8], amp: i32, seed: u32) -> Vec<u8> {
+    let mut x = seed;
+    data.iter()
+        .map(|&v| {
+            x = x.wrapping_mul(1664525).wrapping_add(1013904223);
+            let n = ((x >> 24) as i32 % (2 * amp + 1)) - amp;
+            (i32::from(v) + n).clamp(0, 255) as u8
+        })
+        .collect()
+}
+
+fn img(data: &[u8]) -> GrayImage<'_> {
+    GrayImage {
+        width: data.len() / HEIGHT,
+        height: HEIGHT,
+        data,
+    }
+}
+
+fn bench_one_d(c: &mut Criterion) {
+    let ean_row = ean13::synthesize_ean13_row("5901234123457", 9, 3).expect("valid EAN-13");
+    let c128_row = synthesize_row_code128("BENCH-128", 'B', 10, 3).expect("set B");
+
+    // Ширины: почти по коду (ряды ~340 px), с полями и «широкий кадр».
+    for width in [400usize, 800, 1600] {
+        let ean = tall_image(&ean_row, width);
+        let ean_noisy = add_noise(&ean, 40, 7);
+        let c128 = tall_image(&c128_row, width);
+        let c128_noisy = add_noise(&c128, 40, 11);
+
+        let defaults = DecodeOptions::default();
+        assert!(!decode_ean13_upca(&img(&ean), &defaults).is_empty());
+        assert!(!decode_code128(&img(&c128), &defaults).is_empty());
+
+        let mut group = c.benchmark_group(format!("one_d/w{width}"));
+        for scan_rows in [5usize, 15, 60] {
+            let opts = DecodeOptions {
+                scan_rows,
+                ..DecodeOptions::default()
+            };
+            for (name, data) in [("ean13", &ean), ("ean13_noisy", &ean_noisy)] {
+                let gray = img(data);
+                group.bench_with_input(BenchmarkId::new(name, scan_rows), &opts, |b, o| {
+                    b.iter(|| black_box(decode_ean13_upca(black_box(&gray), o).len()))
+                });
+            }
+            for (name, data) in [("code128", &c128), ("code128_noisy", &c128_noisy)] {
+                let gray = img(data);
+                group.bench_with_input(BenchmarkId::new(name, scan_rows), &opts, |b, o| {
+                    b.iter(|| black_box(decode_code128(black_box(&gray), o).len()))
+                });
+            }
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_one_d);
+criterion_main!(benches);
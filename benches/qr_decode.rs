@@ -0,0 +1,83 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use ultracode::api::PipelineBuilder;
+use ultracode::prelude::{DecodedSymbol, LumaImage};
+use ultracode::qr::encode::encode_qr_v1;
+use ultracode::qr::format::EcLevel;
+use ultracode::qr::{finder, sample, QrOptions};
+
+/// Лёгкая перспектива: обратное отображение каждого пикселя через гомографию
+/// (сжатие правого края + сдвиг), билинейная выборка, фон белый.
+fn perspective_warp(src: &LumaImage) -> LumaImage {
+    let (w, h) = (src.width, src.height);
+    // dst -> src: x' = (a*x + b*y + c) / (g*x + 1), y' = (d*x + e*y + f) / (g*x + 1)
+    let (a, b, c) = (1.0f32, 0.04, -2.0);
+    let (d, e, f) = (-0.03f32, 1.0, 3.0);
+    let g = 0.0012f32;
+    let at = |x: i32, y: i32| -> f32 {
+        if x < 0 || y < 0 || x >= w as i32 || y >= h as i32 {
+            255.0
+        } else {
+            f32::from(src.data[y as usize * w + x as usize])
+        }
+    };
+    let mut data = Vec::with_capacity(w * h);
+    for y in 0..h {
+        for x in 0..w {
+            let (xf, yf) = (x as f32, y as f32);
+            let den = g * xf + 1.0;
+            let sx = (a * xf + b * yf + c) / den;
+            let sy = (d * xf + e * yf + f) / den;
+            let (x0, y0) = (sx.floor() as i32, sy.floor() as i32);
+            let (tx, ty) = (sx - sx.floor(), sy - sy.floor());
+            let top = at(x0, y0) * (1.0 - tx) + at(x0 + 1, y0) * tx;
+            let bot = at(x0, y0 + 1) * (1.0 - tx) + at(x0 + 1, y0 + 1) * tx;
+            data.push((top * (1.0 - ty) + bot * ty).round().clamp(0.0, 255.0) as u8);
+        }
+    }
+    LumaImage {
+        data,
+        width: w,
+        height: h,
+    }
+}
+
+fn bench_qr(c: &mut Criterion) {
+    let opts = QrOptions::default();
+    let pipe = PipelineBuilder::new().build();
+
+    let flat = encode_qr_v1(b"HELLO", EcLevel::L, 3, 4, 6).expect("fits v1-L");
+    assert_eq!(
+        pipe.decode_all(&flat).first().map(|s| s.text.as_str()),
+        Some("HELLO")
+    );
+    // Перспективный вариант сейчас не читается: семплер рассчитан на почти
+    // аффинную геометрию. Бенч меряет цену полного перебора автокалибровки —
+    // и станет мерить успешный путь, когда перспектива заработает.
+    let warped = perspective_warp(&flat);
+
+    for (name, img) in [("flat", &flat), ("perspective", &warped)] {
+        let gray = img.as_gray();
+        let finders = finder::find_finder_patterns(&gray, &opts);
+
+        c.bench_function(&format!("qr/{name}/find_finder_patterns"), |b| {
+            b.iter(|| black_box(finder::find_finder_patterns(black_box(&gray), &opts).len()))
+        });
+
+        // Семплинг имеет смысл мерить, только если finder'ы нашлись.
+        if finders.len() >= 3 {
+            c.bench_function(&format!("qr/{name}/sample_qr_v1_grid"), |b| {
+                b.iter(|| black_box(sample::sample_qr_v1_grid(black_box(&gray), &opts, &finders)))
+            });
+        }
+
+        c.bench_function(&format!("qr/{name}/decode_all"), |b| {
+            b.iter(|| {
+                let out: Vec<DecodedSymbol> = pipe.decode_all(black_box(img));
+                black_box(out.len())
+            })
+        });
+    }
+}
+
+criterion_group!(benches, bench_qr);
+criterion_main!(benches);
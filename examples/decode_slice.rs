@@ -0,0 +1,84 @@
+// Распознавание прямо из `&[u8]` и размеров кадра — без копии в `LumaImage`.
+//
+// Буфер здесь собирается в памяти как бинарный PGM (заголовок + пиксели), но путь тот же
+// для отображённого в память файла или кадрового буфера камеры: нужен только срез
+// пикселей (строки подряд, шаг = ширина) и `width`/`height`.
+//
+//     cargo run --example decode_slice
+
+use ultracode::api::PipelineBuilder;
+use ultracode::prelude::Symbology;
+use ultracode::qr::encode::encode_qr_v1;
+use ultracode::qr::format::EcLevel;
+use ultracode::{synthesize_row_code128, GrayImage};
+
+fn main() {
+    let mapped = frame_as_pgm();
+
+    // Заголовок P5: "P5\n<w> <h>\n255\n" — пиксели начинаются после третьей строки.
+    let header_len = mapped
+        .iter()
+        .enumerate()
+        .filter(|&(_, &b)| b == b'\n')
+        .nth(2)
+        .map_or(0, |(i, _)| i + 1);
+    let header = std::str::from_utf8(&mapped[..header_len]).expect("ASCII header");
+    let dims: Vec<usize> = header
+        .split_whitespace()
+        .skip(1)
+        .take(2)
+        .map(|v| v.parse().unwrap_or(0))
+        .collect();
+
+    // Кадр поверх хвоста буфера: проверяются размеры, пиксели не копируются.
+    let img = match GrayImage::from_slice(&mapped[header_len..], dims[0], dims[1]) {
+        Ok(img) => img,
+        Err(e) => {
+            eprintln!("bad frame: {e:?}");
+            std::process::exit(1);
+        }
+    };
+
+    // decode_iter_gray отдаёт символы по мере нахождения; decode_all_gray — всё сразу.
+    let pipe = PipelineBuilder::new().build();
+    for sym in pipe.decode_iter_gray(&img) {
+        println!(
+            "{:?}: {} (confidence {:.2})",
+            sym.symbology, sym.text, sym.confidence
+        );
+    }
+}
+
+/// Полоса Code128 над QR, упакованные в бинарный PGM.
+fn frame_as_pgm() -> Vec<u8> {
+    let bars = synthesize_row_code128(
+        "FRAME-0042",
+        'B',
+        Symbology::Code128.standard_quiet_zone(),
+        2,
+    )
+    .expect("set B");
+    let qr = encode_qr_v1(
+        b"zero-copy",
+        EcLevel::M,
+        0,
+        Symbology::QR.standard_quiet_zone(),
+        6,
+    )
+    .expect("fits v1-M");
+    let width = bars.len().max(qr.width);
+    let band = 40;
+
+    let mut pixels = vec![255u8; width * (band + qr.height)];
+    for y in 0..band {
+        pixels[y * width..y * width + bars.len()].copy_from_slice(&bars);
+    }
+    for y in 0..qr.height {
+        let dst = (band + y) * width;
+        pixels[dst..dst + qr.width].copy_from_slice(&qr.data[y * qr.width..(y + 1) * qr.width]);
+    }
+
+    let mut out = format!("P5\n{width} {}\n255\n", band + qr.height).into_bytes();
+    out.extend(pixels);
+    out
+}
@@ -1,26 +1,274 @@
 // src/api.rs
 //
 // Высокоуровневый API: единая точка входа для распознавания.
-// Поддержка 1D (EAN-13/UPC-A, Code128) и QR v1 (L/M/Q/H) с проверкой/коррекцией RS.
+// Поддержка 1D (EAN-13/UPC-A, Code128, Code 93, MSI Plessey), QR v1 (L/M/Q/H) с проверкой/коррекцией RS
+// и компактного Aztec.
 
+use crate::core::cast::{f32_to_usize, usize_to_f32};
 use crate::one_d;
 use crate::one_d::DecodeOptions;
 use crate::prelude::*;
 
 // QR-конвейер использует подмодули внутри `qr`
 use crate::qr::{self, bytes, data, finder, format, rs, sample, QrOptions};
+use crate::two_d::aztec::{self, AztecCode};
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Декодер одной строки пикселей — для своих 1D-символогий. Строка передаётся
+/// как есть, слева направо: развёрнутые коды декодер читает сам. К пайплайну
+/// подключается через [`RowScan`].
+pub trait RowDecoder: Send + Sync {
+    fn decode_row(&self, row: &[u8], opts: &DecodeOptions) -> Option<DecodedSymbol>;
+}
+
+/// Декодер целого кадра — например, своей (проприетарной) символогии
+/// ([`Symbology::Custom`]). Регистрируется через [`PipelineBuilder::add_decoder`]
+/// и запускается после встроенных стадий.
+pub trait ImageDecoder: Send + Sync {
+    fn decode(&self, img: &GrayImage<'_>) -> Vec<DecodedSymbol>;
+}
+
+/// Что известно о прочитанном 1D-символе к моменту оценки уверенности
+/// (см. [`ConfidenceModel::one_d`]).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OneDEvidence {
+    pub symbology: Symbology,
+    /// EAN/UPC: первая цифра подобрана эвристикой, а не прочитана.
+    pub recovered: bool,
+    /// Символ прочитан без тихой зоны (см. [`QuietZone::Relaxed`]).
+    pub quiet_zone_missing: bool,
+    /// Контрольная сумма не сошлась, но символ принят (см. [`ChecksumPolicy`]).
+    pub checksum_failed: bool,
+}
+
+/// Что известно о прочитанном QR (см. [`ConfidenceModel::qr`]).
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct QrEvidence {
+    pub ec_level: format::EcLevel,
+    /// Синдромы нулевые ещё до коррекции (только при [`PipelineOptions::qr_verify_rs`]).
+    pub rs_match: bool,
+    /// Сколько кодвордов исправил Reed–Solomon.
+    pub corrected_bytes: usize,
+    pub quiet_zone_missing: bool,
+    /// Доля модулей с яркостью у самого порога (см. [`sample::borderline_ratio`]).
+    pub borderline_ratio: f32,
+    pub module_px: f32,
+    /// Расстояние Хэмминга формат-слова до ближайшего валидного (0..=3).
+    pub format_distance: u32,
+}
+
+/// Что известно о прочитанном Aztec (см. [`ConfidenceModel::aztec`]).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AztecEvidence {
+    pub layers: u8,
+    /// Сколько кодвордов исправил Reed–Solomon.
+    pub corrected_words: usize,
+    pub module_px: f32,
+}
+
+/// Оценка уверенности встроенных декодеров (`DecodedSymbol::confidence`).
+/// Методы по умолчанию — эвристики крейта ([`DefaultConfidence`]); достаточно
+/// переопределить нужный, чтобы подогнать шкалу под свои пороги приёма. Подключается
+/// через [`PipelineBuilder::confidence_model`]; символы пользовательских декодеров
+/// оценивают сами декодеры.
+pub trait ConfidenceModel: Send + Sync {
+    /// 1D: 0.95 (0.85 с восстановленной цифрой), минус штрафы за тихую зону и
+    /// контрольную сумму.
+    fn one_d(&self, e: &OneDEvidence) -> f32 {
+        // Восстановленное чтение опирается на эвристику — уверенность ниже.
+        let mut confidence = if e.recovered { 0.85 } else { 0.95 };
+        if e.quiet_zone_missing {
+            confidence -= QUIET_ZONE_PENALTY;
+        }
+        if e.checksum_failed {
+            confidence -= CHECKSUM_PENALTY;
+        }
+        confidence
+    }
+
+    /// QR: 0.80, выше за уровень EC, совпавший RS и исправленные кодворды (не
+    /// больше 0.99), с множителем за расстояние формата
+    /// ([`format::format_distance_credit`]), минус штраф за тихую зону.
+    /// `borderline_ratio` не учитывается.
+    fn qr(&self, e: &QrEvidence) -> f32 {
+        let mut confidence: f32 = 0.80;
+        // за более высокий уровень EC — чуть выше уверенность
+        confidence += match e.ec_level {
+            format::EcLevel::L => 0.00,
+            format::EcLevel::M => 0.02,
+            format::EcLevel::Q => 0.03,
+            format::EcLevel::H => 0.05,
+        };
+        if e.rs_match {
+            confidence += 0.10;
+        }
+        if e.corrected_bytes > 0 {
+            confidence += 0.05;
+        }
+        confidence = confidence.min(0.99) * format::format_distance_credit(e.format_distance);
+        if e.quiet_zone_missing {
+            confidence -= QUIET_ZONE_PENALTY;
+        }
+        confidence
+    }
+
+    /// Aztec: 0.95, с исправленными кодвордами — 0.90.
+    fn aztec(&self, e: &AztecEvidence) -> f32 {
+        if e.corrected_words == 0 {
+            0.95
+        } else {
+            0.90
+        }
+    }
+}
+
+/// Эвристики уверенности крейта (методы [`ConfidenceModel`] по умолчанию).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultConfidence;
+
+impl ConfidenceModel for DefaultConfidence {}
+
+/// [`RowDecoder`] как [`ImageDecoder`]: строки берутся так же, как во встроенных
+/// 1D-стадиях (`opts.scan_rows` равномерно по высоте или по `opts.smart_row_selection`), номер строки пишется
+/// в `extras["row"]`, если декодер не указал свой. Повторы (та же симвология
+/// и тот же текст с разных строк) пропускаются.
+pub struct RowScan<D> {
+    pub decoder: D,
+    pub opts: DecodeOptions,
+}
+
+impl<D: RowDecoder> RowScan<D> {
+    #[inline]
+    pub fn new(decoder: D, opts: DecodeOptions) -> Self {
+        Self { decoder, opts }
+    }
+}
+
+impl<D: RowDecoder> ImageDecoder for RowScan<D> {
+    fn decode(&self, img: &GrayImage<'_>) -> Vec<DecodedSymbol> {
+        let mut out: Vec<DecodedSymbol> = Vec::new();
+        for y in one_d::scan_row_ys(img, &self.opts) {
+            let Some(mut sym) = self.decoder.decode_row(img.row(y), &self.opts) else {
+                continue;
+            };
+            if out
+                .iter()
+                .any(|s| s.symbology == sym.symbology && s.text == sym.text)
+            {
+                continue;
+            }
+            if sym.extras.get_str("row").is_none() {
+                sym.extras = sym.extras.with("row", y.to_string());
+            }
+            out.push(sym);
+        }
+        out
+    }
+}
+
+/// Зарегистрированный пользовательский декодер (см. [`PipelineBuilder::add_decoder`]).
+#[derive(Clone)]
+pub struct CustomDecoder(Arc<dyn ImageDecoder>);
+
+impl core::fmt::Debug for CustomDecoder {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("CustomDecoder(..)")
+    }
+}
+
+/// Пользовательская модель уверенности (см. [`PipelineBuilder::confidence_model`]).
+#[derive(Clone)]
+pub struct CustomConfidence(Arc<dyn ConfidenceModel>);
+
+impl core::fmt::Debug for CustomConfidence {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("CustomConfidence(..)")
+    }
+}
 
 /// Опции пайплайна (задаются через Builder).
 #[derive(Clone, Debug)]
 pub struct PipelineOptions {
     pub enable_ean13_upca: bool,
     pub enable_code128: bool,
+    /// Code 93 выключен по умолчанию, как и MSI: обе контрольные (C/K) стоят в
+    /// конце символа, и обрывок чужого кода иногда читается как Code 93.
+    pub enable_code93: bool,
+    /// MSI Plessey выключен по умолчанию: у символики нет самоконтроля
+    /// структуры, и на чужих штрих-кодах возможны ложные срабатывания.
+    pub enable_msi: bool,
     pub enable_qr: bool,
+    /// Aztec (пока только компактный, 1–4 слоя). Выключен по умолчанию: каждый
+    /// кандидат в яблочко проверяется перебором сетки, это дороже стадии QR.
+    pub enable_aztec: bool,
     /// Разрешённые уровни коррекции ошибок для QR v1.
     /// Если пусто — считаем, что разрешены все уровни.
     pub qr_allowed_ec_levels: Vec<format::EcLevel>,
-    /// Проверять и логировать совпадение RS перед коррекцией.
+    /// Логировать совпадение RS перед коррекцией и класть его в extras
+    /// (`qr.rs_match`) и в [`QrEvidence::rs_match`]. На то, какие символы
+    /// принимаются, не влияет: неисправимый блок отбрасывается и без неё.
     pub qr_verify_rs: bool,
+    /// Параметры поиска/семплинга QR (finder, кластеризация и т.п.).
+    pub qr: QrOptions,
+    /// Параметры 1D-декодеров (строки сканирования, UPC-A и т.п.).
+    pub one_d: DecodeOptions,
+    /// Если первый проход ничего не нашёл — повторить его на копии с растянутой
+    /// гистограммой ([`LumaImage::normalize_contrast`]). Помогает на тусклых снимках,
+    /// где почти все пиксели у середины диапазона. Однотонный кадр
+    /// ([`ImageStats::dynamic_range`] = 0) второй раз не проходится. По умолчанию выключено.
+    pub auto_contrast: bool,
+    /// Колбэк прогресса `(сделано, всего)`: в [`Pipeline::decode_iter`] шаг —
+    /// строка 1D-стадии или стадия QR, в [`Pipeline::decode_tiled`] — фрагмент.
+    /// Вернуть `false` — остановиться; уже найденные символы сохраняются.
+    pub progress: Option<Progress>,
+    /// Пользовательские декодеры в порядке регистрации (см. [`PipelineBuilder::add_decoder`]).
+    pub decoders: Vec<CustomDecoder>,
+    /// Ожидаемая симвология: её стадия идёт первой, остальные встроенные — следом
+    /// в обычном порядке (см. [`Pipeline::decode_iter`]). Выключенный декодер
+    /// подсказка не включает; `UpcA` — это стадия EAN-13/UPC-A, `Custom` игнорируется.
+    /// [`Pipeline::decode_first`] так отдаёт основной символ, не трогая прочие
+    /// декодеры, если он на кадре есть. По умолчанию `None`.
+    pub primary: Option<Symbology>,
+    /// Если основная стадия (`primary`) что-то нашла — остальные стадии, включая
+    /// пользовательские декодеры, не запускаются. `decode_first` останавливается
+    /// на первом символе и без этого флага, он нужен для `decode_all`/`decode_iter`.
+    /// По умолчанию выключено.
+    pub stop_after_primary: bool,
+    /// EAN-13/UPC-A всегда отдавать 13 цифрами (GTIN-13): UPC-A получает ведущий `0`,
+    /// симвология не меняется, прочитанный текст остаётся в `extras["raw"]`
+    /// (см. [`gtin13_symbol`]). По умолчанию выключено — UPC-A остаётся 12-значным.
+    pub normalize_gtin13: bool,
+    /// Разбирать внутримагазинные EAN-13/UPC-A с ценой (префикс `2`, см.
+    /// [`one_d::in_store`]): extras `ean.in_store_item`, `ean.in_store_price` (в младших
+    /// единицах) и `ean.in_store_price_check` (`"ok"`/`"failed"`). Раскладка полей
+    /// у магазинов разная — разбирается самая распространённая `2x IIIII C PPPP K`.
+    /// По умолчанию выключено.
+    pub parse_in_store_price: bool,
+    /// Своя оценка уверенности встроенных декодеров (см. [`ConfidenceModel`]).
+    /// `None` — [`DefaultConfidence`].
+    pub confidence: Option<CustomConfidence>,
+    /// Класть в extras QR все 26 кодвордов в hex: `qr.cw_orig` — как прочитаны,
+    /// `qr.cw_corrected` — после Reed–Solomon. Для разбора, какие кодворды
+    /// исправлены и где ошибся семплинг. По умолчанию выключено.
+    pub include_raw_codewords: bool,
+    /// Нижняя граница `confidence`: символы ниже неё не попадают в результат
+    /// [`Pipeline::decode_all`] (и `decode_all_gray`, `decode_tiled`, `decode_batch`),
+    /// а [`Pipeline::decode_first`] (и `decode_first_gray`) пропускает их до первого
+    /// подходящего. Ленивый [`Pipeline::decode_iter`] отдаёт всё. `0.0` — без
+    /// отсева. По умолчанию `0.0`.
+    pub min_confidence: f32,
+    /// Свои пороги для отдельных символогий — вместо `min_confidence`: например,
+    /// EAN с сошедшейся контрольной суммой можно принимать ниже, чем QR.
+    /// Пусто — для всех действует `min_confidence`. По умолчанию пусто.
+    pub min_confidence_by_symbology: BTreeMap<Symbology, f32>,
 }
 
 impl Default for PipelineOptions {
@@ -28,13 +276,89 @@ impl Default for PipelineOptions {
         Self {
             enable_ean13_upca: true,
             enable_code128: true,
+            enable_code93: false,
+            enable_msi: false,
             enable_qr: true,
+            enable_aztec: false,
             qr_allowed_ec_levels: vec![],
             qr_verify_rs: true,
+            qr: QrOptions::default(),
+            one_d: DecodeOptions::default(),
+            auto_contrast: false,
+            progress: None,
+            decoders: Vec::new(),
+            primary: None,
+            stop_after_primary: false,
+            normalize_gtin13: false,
+            parse_in_store_price: false,
+            confidence: None,
+            include_raw_codewords: false,
+            min_confidence: 0.0,
+            min_confidence_by_symbology: BTreeMap::new(),
         }
     }
 }
 
+impl PipelineOptions {
+    /// Модель уверенности: своя, если задана, иначе [`DefaultConfidence`].
+    fn confidence_model(&self) -> &dyn ConfidenceModel {
+        self.confidence
+            .as_ref()
+            .map_or(&DefaultConfidence, |c| &*c.0)
+    }
+
+    /// Порог уверенности для `symbology`: свой из `min_confidence_by_symbology`
+    /// или общий `min_confidence`.
+    fn min_confidence_for(&self, symbology: Symbology) -> f32 {
+        self.min_confidence_by_symbology
+            .get(&symbology)
+            .copied()
+            .unwrap_or(self.min_confidence)
+    }
+
+    /// Символ не ниже порога своей символогии (см. `min_confidence`).
+    fn is_confident(&self, sym: &DecodedSymbol) -> bool {
+        sym.confidence >= self.min_confidence_for(sym.symbology)
+    }
+
+    /// Отбросить символы ниже порога своей символогии (см. `min_confidence`).
+    fn retain_confident(&self, symbols: &mut Vec<DecodedSymbol>) {
+        symbols.retain(|s| self.is_confident(s));
+    }
+
+    /// Стадия основной символогии (`primary`), если она встроенная.
+    fn primary_stage(&self) -> Option<IterStage> {
+        self.primary.and_then(IterStage::for_symbology)
+    }
+
+    /// Первая стадия прохода: основная, если задана, иначе EAN-13/UPC-A.
+    fn first_stage(&self) -> IterStage {
+        self.primary_stage().unwrap_or(IterStage::Ean13(0))
+    }
+
+    /// Стадия после завершившейся встроенной `finished` (`hit` — она что-то нашла):
+    /// основная идёт первой, остальные — в порядке [`IterStage::BUILTIN`] без неё,
+    /// затем пользовательские декодеры.
+    fn stage_after(&self, finished: IterStage, hit: bool) -> IterStage {
+        let primary = self.primary_stage();
+        let rest = |s: &IterStage| primary.map_or(true, |p| !p.same_kind(*s));
+        let next = match primary {
+            Some(p) if p.same_kind(finished) => {
+                if hit && self.stop_after_primary {
+                    return IterStage::Done;
+                }
+                IterStage::BUILTIN.iter().find(|s| rest(s))
+            }
+            _ => IterStage::BUILTIN
+                .iter()
+                .skip_while(|s| !s.same_kind(finished))
+                .skip(1)
+                .find(|s| rest(s)),
+        };
+        next.copied().unwrap_or(IterStage::Custom(0))
+    }
+}
+
 /// Builder для PipelineOptions.
 #[derive(Clone, Debug)]
 pub struct PipelineBuilder {
@@ -49,43 +373,190 @@ impl Default for PipelineBuilder {
 
 impl PipelineBuilder {
     #[inline]
+    #[must_use]
     pub fn new() -> Self {
         Self::default()
     }
 
     #[inline]
+    #[must_use]
     pub fn enable_ean13_upca(mut self, v: bool) -> Self {
         self.opts.enable_ean13_upca = v;
         self
     }
 
     #[inline]
+    #[must_use]
     pub fn enable_code128(mut self, v: bool) -> Self {
         self.opts.enable_code128 = v;
         self
     }
 
     #[inline]
+    #[must_use]
+    pub fn enable_code93(mut self, v: bool) -> Self {
+        self.opts.enable_code93 = v;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn enable_msi(mut self, v: bool) -> Self {
+        self.opts.enable_msi = v;
+        self
+    }
+
+    #[inline]
+    #[must_use]
     pub fn enable_qr(mut self, v: bool) -> Self {
         self.opts.enable_qr = v;
         self
     }
 
+    #[inline]
+    #[must_use]
+    pub fn enable_aztec(mut self, v: bool) -> Self {
+        self.opts.enable_aztec = v;
+        self
+    }
+
     /// Разрешённые уровни EC для QR. Пусто => все уровни.
     #[inline]
+    #[must_use]
     pub fn qr_allowed_levels(mut self, levels: &[format::EcLevel]) -> Self {
         self.opts.qr_allowed_ec_levels = levels.to_vec();
         self
     }
 
-    /// Включить/выключить проверку RS.
+    /// Включить/выключить отчёт о проверке RS (см. [`PipelineOptions::qr_verify_rs`]).
     #[inline]
+    #[must_use]
     pub fn qr_verify_rs(mut self, v: bool) -> Self {
         self.opts.qr_verify_rs = v;
         self
     }
 
+    /// Параметры QR-детектора (например, `finder_cluster_fraction`).
+    #[inline]
+    #[must_use]
+    pub fn qr_options(mut self, qr: QrOptions) -> Self {
+        self.opts.qr = qr;
+        self
+    }
+
+    /// Параметры 1D-декодеров (`scan_rows`, `ean13_leading_zero_as_upca`, ...).
+    #[inline]
+    #[must_use]
+    pub fn one_d_options(mut self, one_d: DecodeOptions) -> Self {
+        self.opts.one_d = one_d;
+        self
+    }
+
+    /// Политика тихих зон сразу для 1D и QR (`one_d.quiet_zone`, `qr.quiet_zone`).
+    /// Задавать после [`PipelineBuilder::one_d_options`]/[`PipelineBuilder::qr_options`],
+    /// иначе они её перезапишут.
     #[inline]
+    #[must_use]
+    pub fn quiet_zone(mut self, mode: QuietZone) -> Self {
+        self.opts.one_d.quiet_zone = mode;
+        self.opts.qr.quiet_zone = mode;
+        self
+    }
+
+    /// Ожидаемая симвология — её стадия первой (см. [`PipelineOptions::primary`]).
+    #[inline]
+    #[must_use]
+    pub fn primary(mut self, symbology: Symbology) -> Self {
+        self.opts.primary = Some(symbology);
+        self
+    }
+
+    /// Не запускать остальные стадии, если основная что-то нашла
+    /// (см. [`PipelineOptions::stop_after_primary`]).
+    #[inline]
+    #[must_use]
+    pub fn stop_after_primary(mut self, v: bool) -> Self {
+        self.opts.stop_after_primary = v;
+        self
+    }
+
+    /// EAN-13/UPC-A — всегда 13 цифр (см. [`PipelineOptions::normalize_gtin13`]).
+    #[inline]
+    #[must_use]
+    pub fn normalize_gtin13(mut self, v: bool) -> Self {
+        self.opts.normalize_gtin13 = v;
+        self
+    }
+
+    /// Цена внутримагазинных EAN/UPC в extras (см. [`PipelineOptions::parse_in_store_price`]).
+    #[inline]
+    #[must_use]
+    pub fn parse_in_store_price(mut self, v: bool) -> Self {
+        self.opts.parse_in_store_price = v;
+        self
+    }
+
+    /// Сырые и исправленные кодворды QR в extras (см. [`PipelineOptions::include_raw_codewords`]).
+    #[inline]
+    #[must_use]
+    pub fn include_raw_codewords(mut self, v: bool) -> Self {
+        self.opts.include_raw_codewords = v;
+        self
+    }
+
+    /// Общий порог уверенности (см. [`PipelineOptions::min_confidence`]).
+    #[inline]
+    #[must_use]
+    pub fn min_confidence(mut self, v: f32) -> Self {
+        self.opts.min_confidence = v;
+        self
+    }
+
+    /// Порог уверенности для одной символогии вместо общего
+    /// (см. [`PipelineOptions::min_confidence_by_symbology`]).
+    #[inline]
+    #[must_use]
+    pub fn min_confidence_for(mut self, symbology: Symbology, v: f32) -> Self {
+        self.opts.min_confidence_by_symbology.insert(symbology, v);
+        self
+    }
+
+    /// Второй проход с нормализацией контраста, если первый ничего не нашёл.
+    #[inline]
+    #[must_use]
+    pub fn auto_contrast(mut self, v: bool) -> Self {
+        self.opts.auto_contrast = v;
+        self
+    }
+
+    /// Колбэк прогресса/отмены (см. [`PipelineOptions::progress`]).
+    #[inline]
+    #[must_use]
+    pub fn progress(mut self, f: impl Fn(usize, usize) -> bool + Send + Sync + 'static) -> Self {
+        self.opts.progress = Some(Progress::new(f));
+        self
+    }
+
+    /// Добавить свой декодер: он запускается после встроенных стадий (в том числе
+    /// во втором проходе `auto_contrast`), его символы дедуплицируются и
+    /// сортируются вместе с остальными. Строчный декодер оборачивается в [`RowScan`].
+    #[inline]
+    #[must_use]
+    pub fn add_decoder(mut self, decoder: Box<dyn ImageDecoder>) -> Self {
+        self.opts.decoders.push(CustomDecoder(Arc::from(decoder)));
+        self
+    }
+
+    /// Своя модель уверенности вместо [`DefaultConfidence`] (см. [`ConfidenceModel`]).
+    #[inline]
+    #[must_use]
+    pub fn confidence_model(mut self, model: Box<dyn ConfidenceModel>) -> Self {
+        self.opts.confidence = Some(CustomConfidence(Arc::from(model)));
+        self
+    }
+
+    #[inline]
+    #[must_use]
     pub fn build(self) -> Pipeline {
         Pipeline { opts: self.opts }
     }
@@ -105,55 +576,252 @@ impl Default for Pipeline {
 
 impl Pipeline {
     #[inline]
+    #[must_use]
     pub fn new() -> Self {
         Self::default()
     }
 
     /// Совместимость с существующим вызовом из `lib.rs`: вернуть первый найденный символ.
+    /// Ленивый: останавливается на первом символе не ниже порога уверенности
+    /// (`PipelineOptions::min_confidence`), остальные декодеры не запускаются.
     #[inline]
+    #[must_use]
     pub fn decode_first(&self, img: &LumaImage) -> Option<DecodedSymbol> {
-        self.decode_all(img).into_iter().next()
+        self.decode_iter(img).find(|s| self.opts.is_confident(s))
     }
 
     /// Главная функция: распознать всё, что можем, на изображении.
+    ///
+    /// Результат упорядочен стабильно (см. [`sort_symbols`]): по приоритету
+    /// символогии в порядке пайплайна, затем по верхней y, затем по тексту.
+    /// Для одного и того же изображения и настроек порядок всегда одинаков.
     #[inline]
+    #[must_use]
     pub fn decode_all(&self, img: &LumaImage) -> Vec<DecodedSymbol> {
-        let mut out: Vec<DecodedSymbol> = Vec::new();
+        let mut out: Vec<DecodedSymbol> = self.decode_iter(img).collect();
+        self.opts.retain_confident(&mut out);
+        sort_symbols(&mut out);
+        out
+    }
 
-        // === 1) 1D: EAN-13 / UPC-A ===
-        if self.opts.enable_ean13_upca {
-            let opts = DecodeOptions::default();
-            let ean = one_d::decode_ean13_upca(&img.as_gray(), &opts);
-            for b in ean {
-                out.push(
-                    DecodedSymbol::new(Symbology::Ean13, b.text)
-                        .with_confidence(0.95)
-                        .with_extras(DecodedExtras::new().with("row", b.row.to_string())),
-                );
-            }
+    /// Ленивый обход: символы отдаются по мере нахождения, работа идёт только
+    /// при вызове `next()` — можно остановиться раньше или стримить в канал.
+    ///
+    /// Порядок гарантирован и (с точностью до сортировки по тексту внутри
+    /// одной строки) совпадает с [`Pipeline::decode_all`]:
+    /// 1) EAN-13/UPC-A по строкам сверху вниз;
+    /// 2) Code128 по строкам сверху вниз;
+    /// 3) Code 93 по строкам сверху вниз;
+    /// 4) MSI Plessey по строкам сверху вниз (если включён);
+    /// 5) QR;
+    /// 6) Aztec (если включён);
+    /// 7) пользовательские декодеры ([`PipelineBuilder::add_decoder`]) в порядке регистрации.
+    ///
+    /// С `PipelineOptions::primary` стадия этой символогии переносится в начало,
+    /// а с `stop_after_primary` её успех завершает обход. [`Pipeline::decode_all`]
+    /// всё равно сортирует результат по порядку выше.
+    ///
+    /// С `auto_contrast`, если за весь обход ничего не нашлось, те же стадии
+    /// повторяются на изображении с нормализованным контрастом.
+    ///
+    /// Повторы (та же симвология и тот же текст) пропускаются — отдаётся первое вхождение.
+    ///
+    /// `PipelineOptions::progress` получает `(шаг, всего)` после каждой строки
+    /// включённых 1D-стадий, после QR, Aztec и каждого пользовательского декодера;
+    /// второй проход `auto_contrast` считается заново.
+    #[inline]
+    #[must_use]
+    pub fn decode_iter<'a>(&'a self, img: &'a LumaImage) -> DecodeIter<'a> {
+        self.iter_with_progress(img.as_gray(), self.opts.progress.clone())
+    }
+
+    /// Как [`Pipeline::decode_all`], но по заимствованному [`GrayImage`] — без копии
+    /// кадра в [`LumaImage`] (контракт буфера — в описании [`GrayImage`]).
+    #[must_use]
+    pub fn decode_all_gray(&self, img: &GrayImage<'_>) -> Vec<DecodedSymbol> {
+        let mut out: Vec<DecodedSymbol> = self.decode_iter_gray(img).collect();
+        self.opts.retain_confident(&mut out);
+        sort_symbols(&mut out);
+        out
+    }
+
+    /// Как [`Pipeline::decode_first`], но по заимствованному [`GrayImage`].
+    #[inline]
+    #[must_use]
+    pub fn decode_first_gray(&self, img: &GrayImage<'_>) -> Option<DecodedSymbol> {
+        self.decode_iter_gray(img)
+            .find(|s| self.opts.is_confident(s))
+    }
+
+    /// Как [`Pipeline::decode_iter`], но по заимствованному [`GrayImage`]: итератор
+    /// держит буфер кадра, а не сам `GrayImage`, так что его можно собрать на месте.
+    #[inline]
+    #[must_use]
+    pub fn decode_iter_gray<'a>(&'a self, img: &GrayImage<'a>) -> DecodeIter<'a> {
+        self.iter_with_progress(*img, self.opts.progress.clone())
+    }
+
+    fn iter_with_progress<'a>(
+        &'a self,
+        img: GrayImage<'a>,
+        progress: Option<Progress>,
+    ) -> DecodeIter<'a> {
+        DecodeIter {
+            pipeline: self,
+            img,
+            normalized: None,
+            row_opts: self.opts.one_d.clone(),
+            aligned: None,
+            skew_checked: false,
+            row_ys: None,
+            stage: self.opts.first_stage(),
+            seen: BTreeSet::new(),
+            progress,
+            done: 0,
+            cancelled: false,
+            deferred: Vec::new(),
+            stage_ok_spans: Vec::new(),
+            row_rest: Vec::new(),
+            custom: Vec::new(),
         }
+    }
+
+    /// Распознать большое изображение по перекрывающимся фрагментам `tile_w×tile_h`.
+    ///
+    /// Каждый фрагмент проходит обычный [`Pipeline::decode_iter`] со своими
+    /// `scan_rows` строками, так что плотность сканирования растёт без увеличения
+    /// `scan_rows` на всё изображение. Фрагменты идут с шагом `tile - overlap`,
+    /// последний в ряду прижат к краю. Координаты (`quad`, `extras["row"]`,
+    /// `extras["x_start"]`/`["x_end"]`) переводятся в систему всего изображения.
+    ///
+    /// Код на стыке фрагментов попадает в оба — повторы по (симвология, текст)
+    /// схлопываются, как и в [`Pipeline::decode_all`] (у QR с неразобранными
+    /// данными — по кодвордам): остаётся первое вхождение (фрагменты обходятся
+    /// построчно). Порядок результата — [`sort_symbols`].
+    ///
+    /// `overlap` стоит брать не меньше размера самого крупного кода: тогда любой
+    /// код целиком лежит хотя бы в одном фрагменте.
+    ///
+    /// `PipelineOptions::progress` вызывается после каждого фрагмента
+    /// с `(фрагментов_пройдено, фрагментов_всего)`.
+    #[must_use]
+    pub fn decode_tiled(
+        &self,
+        img: &LumaImage,
+        tile_w: usize,
+        tile_h: usize,
+        overlap: usize,
+    ) -> Vec<DecodedSymbol> {
+        let mut out: Vec<DecodedSymbol> = Vec::new();
+        let mut seen: BTreeSet<SeenKey> = BTreeSet::new();
+        let (ys, xs) = (
+            tile_origins(img.height, tile_h, overlap),
+            tile_origins(img.width, tile_w, overlap),
+        );
+        let total = ys.len() * xs.len();
+        let mut done = 0;
 
-        // === 2) 1D: Code128 ===
-        if self.opts.enable_code128 {
-            let opts = DecodeOptions::default();
-            let c128 = one_d::decode_code128(&img.as_gray(), &opts);
-            for b in c128 {
-                out.push(
-                    DecodedSymbol::new(Symbology::Code128, b.text)
-                        .with_confidence(0.95)
-                        .with_extras(DecodedExtras::new().with("row", b.row.to_string())),
+        'tiles: for &y0 in &ys {
+            for &x0 in &xs {
+                let tile = img.crop(x0, y0, tile_w, tile_h);
+                let (dx, dy) = (
+                    i32::try_from(x0).unwrap_or(i32::MAX),
+                    i32::try_from(y0).unwrap_or(i32::MAX),
                 );
+                // прогресс считаем по фрагментам, не по строкам внутри них
+                for mut sym in self.iter_with_progress(tile.as_gray(), None) {
+                    // порог — до дедупликации: чтение с обрезанной тихой зоной не
+                    // должно занять место полного чтения из соседнего фрагмента
+                    if !self.opts.is_confident(&sym) || !seen.insert(seen_key(&sym)) {
+                        continue;
+                    }
+                    sym.quad = sym.quad.map(|q| q.translated(dx, dy));
+                    if let Some(r) = sym.extras.get_parsed::<usize>("row") {
+                        sym.extras
+                            .properties
+                            .insert("row".into(), (r + y0).to_string());
+                    }
+                    for key in ["x_start", "x_end"] {
+                        if let Some(x) = sym.extras.get_parsed::<usize>(key) {
+                            sym.extras
+                                .properties
+                                .insert(key.into(), (x + x0).to_string());
+                        }
+                    }
+                    out.push(sym);
+                }
+                done += 1;
+                if self
+                    .opts
+                    .progress
+                    .as_ref()
+                    .is_some_and(|p| !p.report(done, total))
+                {
+                    break 'tiles;
+                }
             }
         }
 
-        // === 3) QR v1 (L/M/Q/H) ===
-        if self.opts.enable_qr {
-            if let Some(qr) = self.try_decode_qr_v1_all_levels_with_correction(img) {
-                out.push(qr);
+        sort_symbols(&mut out);
+        out
+    }
+
+    /// Распознать пачку кадров: `out[i]` — то же, что `decode_all(&imgs[i])`,
+    /// порядок совпадает с входом. С фичей `rayon` кадры раскладываются по
+    /// потокам пула, результат от этого не меняется.
+    ///
+    /// `PipelineOptions::progress` вызывается после каждого кадра с
+    /// `(кадров_пройдено, кадров_всего)` (под `rayon` — из рабочих потоков, в порядке
+    /// завершения). Вернуть `false` — оставшиеся кадры не разбираются, их элементы
+    /// в результате пустые.
+    #[must_use]
+    pub fn decode_batch(&self, imgs: &[LumaImage]) -> Vec<Vec<DecodedSymbol>> {
+        let total = imgs.len();
+        let done = AtomicUsize::new(0);
+        let stop = AtomicBool::new(false);
+        let decode = |img: &LumaImage| -> Vec<DecodedSymbol> {
+            if stop.load(Ordering::Relaxed) {
+                return Vec::new();
             }
+            // прогресс считаем по кадрам, не по строкам внутри них
+            let mut out: Vec<DecodedSymbol> =
+                self.iter_with_progress(img.as_gray(), None).collect();
+            self.opts.retain_confident(&mut out);
+            sort_symbols(&mut out);
+            let n = done.fetch_add(1, Ordering::Relaxed) + 1;
+            if self
+                .opts
+                .progress
+                .as_ref()
+                .is_some_and(|p| !p.report(n, total))
+            {
+                stop.store(true, Ordering::Relaxed);
+            }
+            out
+        };
+
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+            imgs.par_iter().map(decode).collect()
+        }
+        #[cfg(not(feature = "rayon"))]
+        {
+            imgs.iter().map(decode).collect()
         }
+    }
 
-        dedup_by_sym_and_text(out)
+    /// Геометрия QR на кадре — те же `qr.module_px` и `qr.grid_n`, что в extras
+    /// прочитанного символа, но и тогда, когда символ не прочитался (формат, RS):
+    /// чтобы по размеру модуля подсказать «поднесите ближе». Finder'ы, версия
+    /// (тайминги, номер версии) — как при декодировании; `None` — трёх finder'ов
+    /// на кадре нет.
+    #[must_use]
+    pub fn qr_geometry(&self, img: &LumaImage) -> Option<sample::QrGeometry> {
+        let img = img.as_gray();
+        let finders = self.find_qr_finders(&img)?;
+        let (_, version, _) = self.qr_version_estimate(&img, &finders);
+        sample::qr_geometry(&finders, version.unwrap_or(1))
     }
 
     /// Узконаправленный декодер QR v1:
@@ -166,34 +834,345 @@ impl Pipeline {
     /// - парсим Byte mode (ожидаем «HELLO» в тесте).
     fn try_decode_qr_v1_all_levels_with_correction(
         &self,
-        img: &LumaImage,
+        img: &GrayImage<'_>,
     ) -> Option<DecodedSymbol> {
-        let qr_opts = QrOptions::default();
+        let qr_opts = &self.opts.qr;
+
+        // 0) Диапазон версий без поддерживаемых — искать нечего.
+        let (lo, hi) = qr_opts.version_range;
+        if lo > qr::SUPPORTED_VERSIONS.1 || hi < qr::SUPPORTED_VERSIONS.0 {
+            return None;
+        }
+
+        // Кадр меньше символа v1 (21 модуль по пикселю) — например, одна строка
+        // скана высотой 1: finder'ы на нём находятся только ложные.
+        if img.width.min(img.height) < data::N1 {
+            diag!(
+                "[qr] image {}x{} smaller than a v1 symbol -> skip",
+                img.width,
+                img.height
+            );
+            return None;
+        }
 
         // 1) Finder patterns
-        let finders = finder::find_finder_patterns(&img.as_gray(), &qr_opts);
-        if finders.len() < 3 {
+        let Some(finders) = self.find_qr_finders(img) else {
+            return self.decode_qr_v1_near_candidate(img);
+        };
+        if let Some(sym) = self.decode_qr_v1_at(img, &finders) {
+            return Some(sym);
+        }
+        self.decode_qr_v1_upscaled(img, &finders)
+    }
+
+    /// Три центра finder'ов (два найденных достраиваются, если разрешено
+    /// [`QrOptions::recover_two_finders`]); меньше трёх — `None`.
+    fn find_qr_finders(&self, img: &GrayImage<'_>) -> Option<Vec<PointF>> {
+        let qr_opts = &self.opts.qr;
+        let mut finders = timed!("finder_scan", finder::find_finder_patterns(img, qr_opts));
+        if finders.len() == 2 && qr_opts.recover_two_finders {
+            finders =
+                sample::complete_two_finders(img, qr_opts, [finders[0], finders[1]])?.to_vec();
+        }
+        (finders.len() >= 3).then_some(finders)
+    }
+
+    /// Число модулей по таймингам и версия: оценка по разносу finder'ов,
+    /// поправленная таймингами и (от версии 7) номером версии символа. Третье
+    /// значение — версию подтвердили тайминги или номер версии, а не один разнос.
+    fn qr_version_estimate(
+        &self,
+        img: &GrayImage<'_>,
+        finders: &[PointF],
+    ) -> (Option<usize>, Option<u8>, bool) {
+        let qr_opts = &self.opts.qr;
+        // Тайминги считают модули напрямую, без опоры на размер finder'ов: при
+        // расхождении с оценкой версии верим им (промах центра finder'а сбивает оценку).
+        let timing_n = sample::timing_module_count(img, qr_opts, finders);
+        let mut version = sample::estimate_version(img, qr_opts, finders);
+        let mut confirmed = timing_n.is_some();
+        if let Some(n) = timing_n {
+            let by_timing = u8::try_from((n - 17) / 4).ok();
+            if version != by_timing {
+                diag!(
+                    "[qr] timing: {} modules, version estimate {:?} -> {:?}",
+                    n,
+                    version,
+                    by_timing
+                );
+                version = by_timing;
+            }
+        }
+        // От версии 7 у символа есть свой номер (BCH(18,6)) — он точнее разноса
+        // finder'ов; оценку 6 тоже проверяем: она может оказаться 7 на границе.
+        if let Some(estimate) = version.filter(|&v| v + 1 >= format::VERSION_INFO_MIN) {
+            if let Some(v) = sample::read_version_info(img, qr_opts, finders, estimate) {
+                if v != estimate {
+                    diag!("[qr] version info: {} (estimate {})", v, estimate);
+                }
+                version = Some(v);
+                confirmed = true;
+            }
+        }
+        (timing_n, version, confirmed)
+    }
+
+    /// Шаги после поиска finder'ов: версия, геометрия, тихая зона и чтение сетки
+    /// (со сдвигами, см. [`QrOptions::grid_shift_recovery`]).
+    fn decode_qr_v1_at(&self, img: &GrayImage<'_>, finders: &[PointF]) -> Option<DecodedSymbol> {
+        let qr_opts = &self.opts.qr;
+        let range = qr_opts.version_range.0..=qr_opts.version_range.1;
+        let (timing_n, mut version, confirmed) = self.qr_version_estimate(img, finders);
+        if let Some(v) = version.filter(|v| !range.contains(v)) {
+            // Один разнос finder'ов легко ошибается на версию (центр или рамка
+            // finder'а промерены неточно) — отбрасываем только подтверждённую.
+            if confirmed {
+                diag!("[qr] version {} outside {:?} -> skip", v, range);
+                return None;
+            }
+            diag!(
+                "[qr] unconfirmed version estimate {} outside {:?} -> ignored",
+                v,
+                range
+            );
+            version = None;
+        }
+        // Сетка семплера — 21×21: другой счёт таймингов значит, что она ляжет мимо.
+        if timing_n.is_some_and(|n| n != data::N1) {
+            diag!(
+                "[qr] timing: {:?} modules, sampler grid is {} -> skip",
+                timing_n,
+                data::N1
+            );
+            return None;
+        }
+        // Рамку промерить не удалось — считаем v1 (её и семплируем).
+        let geometry = sample::qr_geometry(finders, version.unwrap_or(1))?;
+        diag!(
+            "[qr] geometry: grid {}x{}, module {:.2}px",
+            geometry.grid_n,
+            geometry.grid_n,
+            geometry.module_px
+        );
+        let quiet = qr_opts.quiet_zone == QuietZone::Ignore
+            || sample::qr_v1_has_quiet_zone(img, qr_opts, finders);
+        if !quiet && qr_opts.quiet_zone == QuietZone::Strict {
+            diag!("[qr] no quiet zone -> skip (strict)");
             return None;
         }
 
-        // 2) Семплинг сетки 21×21 (flatten: Vec<bool> длиной 441).
-        let grid: Vec<bool> = sample::sample_qr_v1_grid(&img.as_gray(), &qr_opts, &finders)?;
+        // 2) Сетка по finder'ам; не прочиталась (формат или RS) — та же сетка,
+        // сдвинутая на модуль по u/v: центры finder'ов могли промахнуться на целый модуль.
+        let frame = QrFrame {
+            finders,
+            geometry,
+            timing_n,
+            quiet,
+            calibration: Cell::new(None),
+        };
+        if let Some(sym) = self.decode_qr_v1_sampled(img, &frame, (0, 0)) {
+            return Some(sym);
+        }
+        if !qr_opts.grid_shift_recovery {
+            return None;
+        }
+        QR_GRID_SHIFTS.iter().find_map(|&shift| {
+            diag!("[qr] retry with grid shifted by {:?} modules", shift);
+            let sym = self.decode_qr_v1_sampled(img, &frame, shift)?;
+            let extras = sym
+                .extras
+                .clone()
+                .with("qr.grid_shift", alloc::format!("{},{}", shift.0, shift.1));
+            Some(sym.with_extras(extras))
+        })
+    }
 
-        // Матрица 21×21
-        let mut matrix: Vec<Vec<bool>> = vec![vec![false; data::N1]; data::N1];
-        for y in 0..data::N1 {
-            for x in 0..data::N1 {
-                matrix[y][x] = grid[y * data::N1 + x];
+    /// Повтор для мелкого символа (см. [`QrOptions::upscale_small_codes`]): область
+    /// вокруг `finders` увеличивается, finder'ы ищутся заново, результат (углы,
+    /// `qr.module_px`) пересчитывается в координаты `img`; extras `qr.upscale` —
+    /// множитель.
+    fn decode_qr_v1_upscaled(
+        &self,
+        img: &GrayImage<'_>,
+        finders: &[PointF],
+    ) -> Option<DecodedSymbol> {
+        let qr_opts = &self.opts.qr;
+        let threshold = qr_opts.upscale_small_codes?;
+        let module_px = sample::qr_geometry(finders, 1)?.module_px;
+        let factor = sample::upscale_factor(module_px, threshold)?;
+        diag!(
+            "[qr] module {:.2}px < {:.2}px -> retry upscaled x{}",
+            module_px,
+            threshold,
+            factor
+        );
+        let (roi, origin) = sample::upscale_qr_v1_roi(img, finders, factor)?;
+        let roi = roi.as_gray();
+        let found = self.find_qr_finders(&roi)?;
+        let mut sym = self.decode_qr_v1_at(&roi, &found)?;
+
+        let scale = usize_to_f32(factor);
+        let to_source = |p: Point| {
+            let p = PointF::from(p);
+            PointF {
+                x: origin.x + (p.x + 0.5) / scale - 0.5,
+                y: origin.y + (p.y + 0.5) / scale - 0.5,
             }
+            .to_point()
+        };
+        sym.quad = sym.quad.map(|q| Quad {
+            p0: to_source(q.p0),
+            p1: to_source(q.p1),
+            p2: to_source(q.p2),
+            p3: to_source(q.p3),
+        });
+        let mut extras = sym.extras.clone().with("qr.upscale", factor.to_string());
+        if let Some(px) = extras.get_parsed::<f32>("qr.module_px") {
+            extras = extras.with("qr.module_px", alloc::format!("{:.2}", px / scale));
+        }
+        Some(sym.with_extras(extras))
+    }
+
+    /// Тот же повтор, когда трёх finder'ов не нашлось вовсе: у мелкого символа на
+    /// крупном кадре они ближе порога кластеризации (доли кадра) и сливаются в один
+    /// кластер. Finder'ы ищутся заново на окне вокруг сильнейшего кластера
+    /// ([`finder::strongest_candidate`]), в которое символ с модулем мельче порога
+    /// помещается целиком; дальше — как обычно, с увеличением по найденным finder'ам.
+    fn decode_qr_v1_near_candidate(&self, img: &GrayImage<'_>) -> Option<DecodedSymbol> {
+        let qr_opts = &self.opts.qr;
+        let threshold = qr_opts.upscale_small_codes?;
+        let center = finder::strongest_candidate(img, qr_opts)?;
+        let reach = usize_to_f32(data::N1 + Symbology::QR.standard_quiet_zone()) * threshold;
+        let (x0, y0) = (
+            f32_to_usize(center.x - reach),
+            f32_to_usize(center.y - reach),
+        );
+        let x1 = f32_to_usize(center.x + reach).min(img.width);
+        let y1 = f32_to_usize(center.y + reach).min(img.height);
+        // Окно во весь кадр ничего нового не даст (и пустое — тоже).
+        if x1 <= x0 || y1 <= y0 || (x1 - x0, y1 - y0) == (img.width, img.height) {
+            return None;
         }
+        diag!(
+            "[qr] <3 finders -> retry in {}x{} window at ({}, {})",
+            x1 - x0,
+            y1 - y0,
+            x0,
+            y0
+        );
+        let pixels = (y0..y1)
+            .flat_map(|y| &img.row(y)[x0..x1])
+            .copied()
+            .collect();
+        let window = LumaImage {
+            data: pixels,
+            width: x1 - x0,
+            height: y1 - y0,
+        };
+        let window = window.as_gray();
+        let finders = self.find_qr_finders(&window)?;
+        let mut sym = self
+            .decode_qr_v1_at(&window, &finders)
+            .or_else(|| self.decode_qr_v1_upscaled(&window, &finders))?;
+        let (dx, dy) = (i32::try_from(x0).ok()?, i32::try_from(y0).ok()?);
+        sym.quad = sym.quad.map(|q| q.translated(dx, dy));
+        Some(sym)
+    }
+
+    /// Шаги 2–12 для одной сетки: семплинг со сдвигом `shift` (в модулях, см.
+    /// [`sample::sample_qr_v1_grid_shifted`]), формат, RS, разбор. Повторы чтения
+    /// формата ([`QrOptions::format_retries`]) — только для несдвинутой сетки.
+    fn decode_qr_v1_sampled(
+        &self,
+        img: &GrayImage<'_>,
+        frame: &QrFrame<'_>,
+        shift: (i8, i8),
+    ) -> Option<DecodedSymbol> {
+        let qr_opts = &self.opts.qr;
+        let QrFrame {
+            finders,
+            geometry,
+            timing_n,
+            quiet,
+            ..
+        } = *frame;
+
+        // 2) Семплинг сетки 21×21 (flatten: Vec<bool> длиной 441).
+        // Яркости модулей — для доли «неуверенных» (см. QrEvidence::borderline_ratio).
+        // Калибровку подбирает только первое чтение, повторы берут её же.
+        let (mut grid, mut luma) = if shift == (0, 0) {
+            let (grid, luma, calibration) = timed!(
+                "qr_sample",
+                sample::sample_qr_v1_grid_calibrated(img, qr_opts, finders)
+            )?;
+            frame.calibration.set(calibration);
+            (grid, luma)
+        } else {
+            let calibration = frame.calibration(img, qr_opts)?;
+            timed!(
+                "qr_sample",
+                sample::sample_qr_v1_grid_shifted(img, qr_opts, finders, calibration, shift)
+            )?
+        };
+
+        // Матрица 21×21
+        let to_matrix = |grid: &[bool]| -> Vec<Vec<bool>> {
+            grid.chunks(data::N1).map(<[bool]>::to_vec).collect()
+        };
+        let mut matrix = to_matrix(&grid);
 
         // 3) Формат (две копии по 15 бит) → (ec_level, mask, ...).
-        let (ec_level, mask_id, _hamming_dist, _src_index) =
-            qr::decode_v1_format_from_matrix(&matrix)?;
-        println!(
-            "[qr] format OK: ec={} mask={}",
+        // Не прочитался или структура (finder'ы, сепараторы) не сошлась — пересемплируем
+        // со сдвинутой калибровкой (не больше format_retries раз).
+        let mut format_read = qr::decode_v1_format_from_matrix(&matrix);
+        let mut struct_score = data::structure_score_v1(&grid);
+        let struct_ok = |score: f32| score >= qr_opts.min_struct_score;
+        let mut retries_used = 0usize;
+        let max_retries = if shift == (0, 0) {
+            usize::from(qr_opts.format_retries)
+        } else {
+            0
+        };
+        while (format_read.is_none() || !struct_ok(struct_score)) && retries_used < max_retries {
+            let Some(calibration) = frame.calibration(img, qr_opts) else {
+                break;
+            };
+            let Some((retry_grid, retry_luma)) = timed!(
+                "qr_sample",
+                sample::resample_qr_v1_grid(img, qr_opts, finders, calibration, retries_used)
+            ) else {
+                break;
+            };
+            retries_used += 1;
+            (grid, luma) = (retry_grid, retry_luma);
+            matrix = to_matrix(&grid);
+            format_read = qr::decode_v1_format_from_matrix(&matrix);
+            struct_score = data::structure_score_v1(&grid);
+            diag!(
+                "[qr] format retry {}: {} (struct_score={:.2})",
+                retries_used,
+                if format_read.is_some() {
+                    "ok"
+                } else {
+                    "unreadable"
+                },
+                struct_score
+            );
+        }
+        if !struct_ok(struct_score) {
+            diag!(
+                "[qr] struct_score={:.2} < {:.2} -> skip before RS",
+                struct_score,
+                qr_opts.min_struct_score
+            );
+            return None;
+        }
+        let (ec_level, format_mask, format_distance, _src_index) = format_read?;
+        diag!(
+            "[qr] format OK: ec={} mask={} distance={}",
             ec_level_to_str(ec_level),
-            mask_id
+            format_mask,
+            format_distance
         );
 
         // Белый список уровней EC (если непустой).
@@ -207,43 +1186,60 @@ impl Pipeline {
             return None;
         }
 
-        // 4) Снять маску только с data-модулей.
-        let unmask = unmask_matrix_v1(&matrix, mask_id);
+        // 4) Разное разбиение 26 кодвордов для уровней L/M/Q/H:
+        let (data_len, ec_len) = qr::encode::v1_block_split(ec_level);
 
-        // 5) В плоский вектор
-        let mut flat: Vec<bool> = Vec::with_capacity(data::N1 * data::N1);
-        for y in 0..data::N1 {
-            for x in 0..data::N1 {
-                flat.push(unmask[y][x]);
-            }
-        }
+        let codewords_for = |mask_id: u8| -> Vec<u8> {
+            // 5) Снять маску только с data-модулей.
+            let unmask = unmask_matrix_v1(&matrix, mask_id);
 
-        // 6) Извлечь 208 data-бит (для v1 — фиксированная схема обхода).
-        let data_bits: Vec<bool> = data::extract_data_bits_v1(&flat);
+            // 6) В плоский вектор
+            let flat: Vec<bool> = unmask.iter().flatten().copied().collect();
 
-        // 7) Разное разбиение 26 кодвордов для уровней L/M/Q/H:
-        let (data_len, ec_len) = match ec_level {
-            format::EcLevel::L => (19usize, 7usize),
-            format::EcLevel::M => (16usize, 10usize),
-            format::EcLevel::Q => (13usize, 13usize),
-            format::EcLevel::H => (9usize, 17usize),
-        };
+            // 7) Извлечь 208 data-бит (для v1 — фиксированная схема обхода);
+            // промах длины не фатален — добиваем/обрезаем до 208, дальше разберётся RS.
+            let data_bits: Vec<bool> = data::fit_data_bits_v1(data::extract_data_bits_v1(&flat));
 
-        // 8) 208 бит → 26 байт кодвордов (MSB первым в байте).
-        if data_bits.len() != 208 {
-            println!("[qr] unexpected data bits length: {}", data_bits.len());
-            return None;
-        }
-        let mut codewords: Vec<u8> = Vec::with_capacity(26);
-        for i in 0..26 {
-            let mut b = 0u8;
-            for j in 0..8 {
-                if data_bits[i * 8 + j] {
-                    b |= 1 << (7 - j);
+            // 8) 208 бит → 26 байт кодвордов (MSB первым в байте).
+            let mut codewords: Vec<u8> = Vec::with_capacity(26);
+            for i in 0..26 {
+                let mut b = 0u8;
+                for j in 0..8 {
+                    if data_bits[i * 8 + j] {
+                        b |= 1 << (7 - j);
+                    }
                 }
+                codewords.push(b);
+            }
+            codewords
+        };
+
+        // Формат прочитан на пределе BCH — маске из него не верим: арбитр — RS
+        // (см. QrOptions::mask_search_distance).
+        let mut mask_id = format_mask;
+        let mask_searched = qr_opts
+            .mask_search_distance
+            .is_some_and(|d| format_distance >= u32::from(d));
+        if mask_searched {
+            let best = core::iter::once(format_mask)
+                .chain((0..8).filter(|&m| m != format_mask))
+                .filter_map(|m| {
+                    let mut cw = codewords_for(m);
+                    rs::rs_correct_codeword_block(&mut cw, data_len, ec_len)
+                        .ok()
+                        .map(|n| (n, m))
+                })
+                .min_by_key(|&(n, _)| n);
+            diag!(
+                "[qr] mask search (format distance {}): {:?}",
+                format_distance,
+                best
+            );
+            if let Some((_, m)) = best {
+                mask_id = m;
             }
-            codewords.push(b);
         }
+        let codewords = codewords_for(mask_id);
 
         // Оригинальные кодворды (для сравнения/логов).
         let cw_orig = codewords.clone();
@@ -251,15 +1247,31 @@ impl Pipeline {
 
         let mut extras = DecodedExtras::new()
             .with("qr.ec", ec_level_to_str(ec_level))
-            .with("qr.mask", mask_id.to_string());
+            .with("qr.mask", mask_id.to_string())
+            .with("qr.format_dist", format_distance.to_string())
+            .with("qr.module_px", alloc::format!("{:.2}", geometry.module_px))
+            .with("qr.grid_n", geometry.grid_n.to_string())
+            .with("qr.struct_score", alloc::format!("{struct_score:.2}"));
+        if let Some(n) = timing_n {
+            extras = extras.with("qr.timing_modules", n.to_string());
+        }
+        if retries_used > 0 {
+            extras = extras.with("qr.format_retries", retries_used.to_string());
+        }
+        if mask_searched {
+            extras = extras.with("qr.mask_search", "true");
+            if mask_id != format_mask {
+                extras = extras.with("qr.format_mask", format_mask.to_string());
+            }
+        }
 
-        // 9) Проверка RS «как есть».
-        let mut rs_match = false;
+        // 9) Проверка RS «как есть»: нужна для приёма символа (см. шаг 10) всегда,
+        // `qr_verify_rs` лишь добавляет её в логи и extras.
+        let (d, e) = cw_orig.split_at(data_len);
+        let calc = rs::rs_ec_bytes(d, ec_len);
+        let rs_match = calc == e;
         if self.opts.qr_verify_rs {
-            let (d, e) = cw_orig.split_at(data_len);
-            let calc = rs::rs_ec_bytes(d, ec_len);
-            rs_match = calc == e;
-            println!(
+            diag!(
                 "[qr] RS check (pre-correction): match={} (have={} calc={})",
                 rs_match,
                 hex_bytes(e),
@@ -277,39 +1289,70 @@ impl Pipeline {
                     .with("qr.rs_corrected", "true")
                     .with("qr.rs_corrected_bytes", ncorr.to_string());
             }
-            Err(_) => {
-                extras = extras.with("qr.rs_corrected", "false");
+            Err(e) => {
+                // Неисправимый блок: байты «как есть» принимаем, только если
+                // синдромы были нулевыми ещё до коррекции — иначе это мусор,
+                // который случайно может распарситься. `InvalidLength` — не
+                // повреждение, а рассогласование раскладки блока: такой не берём никогда.
+                if !rs_match || e == rs::RsError::InvalidLength {
+                    diag!(
+                        "[qr] RS uncorrectable ({}), pre-check match={} -> reject",
+                        e.as_str(),
+                        rs_match
+                    );
+                    return None;
+                }
+                extras = extras
+                    .with("qr.rs_corrected", "false")
+                    .with("qr.rs_error", e.as_str());
             }
         }
 
+        if self.opts.include_raw_codewords {
+            extras = extras
+                .with("qr.cw_orig", hex_bytes(&cw_orig))
+                .with("qr.cw_corrected", hex_bytes(&cw));
+        }
+
         // 11) Парсим Byte-mode из ИСПРАВЛЕННЫХ кодвордов (если коррекция не удалась,
-        // cw == cw_orig — парсим исходное).
+        // сюда доходим только при rs_match, и cw == cw_orig).
         let bits_from_cw = bytes_to_bits_msb(&cw);
-        let text: String = match bytes::parse_byte_mode_bits_v1_l(&bits_from_cw) {
-            Some(t) => t,
-            None => return None,
+        let (text, payload) = if let Some(payload) =
+            bytes::parse_byte_mode_payload_v1(&bits_from_cw[..data_len * 8])
+        {
+            let (text, charset) = bytes::decode_byte_payload(&payload);
+            extras = extras.with("qr.charset", charset.as_str());
+            (text, payload)
+        } else {
+            // Символ прочитан и исправлен, но разборщик не понял данные (режим не Byte,
+            // длина сегмента за пределами блока): не выбрасываем его, а отдаём
+            // data-кодворды как есть с пустым текстом и номером режима.
+            let mode = cw[0] >> 4;
+            diag!(
+                "[qr] payload parse failed (mode {:04b}) -> raw data codewords",
+                mode
+            );
+            extras = extras
+                .with("qr.parse", "failed")
+                .with("qr.mode", mode.to_string());
+            (String::new(), cw[..data_len].to_vec())
         };
 
-        // 12) Итоговая уверенность (эвристика).
-        let mut confidence = 0.80;
-        // за более высокий уровень EC — чуть выше уверенность
-        confidence += match ec_level {
-            format::EcLevel::L => 0.00,
-            format::EcLevel::M => 0.02,
-            format::EcLevel::Q => 0.03,
-            format::EcLevel::H => 0.05,
-        };
-        if self.opts.qr_verify_rs && rs_match {
-            confidence += 0.10;
-        }
-        if corrected_bytes > 0 {
-            confidence += 0.05;
-        }
-        if confidence > 0.99 {
-            confidence = 0.99;
+        // 12) Итоговая уверенность (см. ConfidenceModel::qr).
+        if !quiet {
+            extras = extras.with("quiet_zone", "missing");
         }
+        let confidence = self.opts.confidence_model().qr(&QrEvidence {
+            ec_level,
+            rs_match: self.opts.qr_verify_rs && rs_match,
+            corrected_bytes,
+            quiet_zone_missing: !quiet,
+            borderline_ratio: sample::borderline_ratio(qr_opts, &luma),
+            module_px: geometry.module_px,
+            format_distance,
+        });
 
-        println!(
+        diag!(
             "[qr] OK: text=\"{}\" ec={} mask={} corrected_bytes={}",
             text,
             ec_level_to_str(ec_level),
@@ -317,12 +1360,692 @@ impl Pipeline {
             corrected_bytes
         );
 
-        Some(
-            DecodedSymbol::new(Symbology::QR, text)
-                .with_confidence(confidence)
-                .with_extras(extras),
-        )
+        // Геометрия внутри — в f32; в целые пиксели только на выходе.
+        let mut sym = DecodedSymbol::new(Symbology::QR, text)
+            .with_confidence(confidence)
+            .with_bytes(payload)
+            .with_extras(extras)
+            .with_meta(SymbolMeta::Qr {
+                ec: ec_level,
+                mask: mask_id,
+                rs_corrected: corrected_bytes,
+                version: geometry.version,
+            });
+        if let Some(corners) = sample::qr_v1_corners(finders) {
+            sym = sym.with_quad(corners.to_quad());
+        }
+        Some(sym)
+    }
+}
+
+/// Ленивый итератор по символам изображения (см. [`Pipeline::decode_iter`]).
+pub struct DecodeIter<'a> {
+    pipeline: &'a Pipeline,
+    /// Исходное изображение (заимствованное, без копии).
+    img: GrayImage<'a>,
+    /// Копия с растянутым контрастом — второй проход `auto_contrast`.
+    normalized: Option<LumaImage>,
+    row_opts: DecodeOptions,
+    /// Выровненное для 1D изображение (`DecodeOptions::deskew`); `None` —
+    /// наклона нет, сканируем `img`.
+    aligned: Option<one_d::skew::Deskewed>,
+    /// Наклон для текущего `img` уже оценивали.
+    skew_checked: bool,
+    /// y-координаты строк 1D-стадий (см. [`one_d::scan_row_ys`]) в сканируемом
+    /// изображении; `None` — ещё не считались (или `aligned` пересчитано).
+    row_ys: Option<Vec<usize>>,
+    stage: IterStage,
+    /// Уже отданные символы (см. [`seen_key`]) — для дедупликации.
+    seen: BTreeSet<SeenKey>,
+    progress: Option<Progress>,
+    /// Пройдено шагов (строк 1D и QR) в текущем проходе.
+    done: usize,
+    /// Колбэк прогресса попросил остановиться.
+    cancelled: bool,
+    /// [`ChecksumPolicy::Prefer`]: прочтения текущей 1D-стадии с несошедшейся
+    /// суммой — отдаются в конце стадии, если на их месте в строке (по x) не
+    /// нашлось сошедшегося.
+    deferred: Vec<DecodedSymbol>,
+    /// x-отрезки прочтений текущей 1D-стадии с сошедшейся суммой (см. [`x_span`]).
+    stage_ok_spans: Vec<Option<(usize, usize)>>,
+    /// Ещё не отданные символы пользовательского декодера (в обратном порядке).
+    custom: Vec<DecodedSymbol>,
+    /// Остальные символы строки 1D-стадии (несколько EAN на одной строке) —
+    /// в обратном порядке; отдаются до следующей строки.
+    row_rest: Vec<DecodedSymbol>,
+}
+
+/// Конец 1D-стадии: следующий отложенный символ (см. `DecodeIter::deferred`),
+/// место которого не занято сошедшимся прочтением, или `None`, когда отдавать
+/// больше нечего. Поля — по отдельности: остальной
+/// `DecodeIter` в этот момент заимствован под изображение.
+fn flush_deferred(
+    deferred: &mut Vec<DecodedSymbol>,
+    stage_ok_spans: &mut Vec<Option<(usize, usize)>>,
+    seen: &mut BTreeSet<SeenKey>,
+) -> Option<DecodedSymbol> {
+    while !deferred.is_empty() {
+        let sym = deferred.remove(0);
+        let span = x_span(&sym);
+        // без положения — место не сравнить, уступаем любому сошедшемуся
+        let covered = stage_ok_spans.iter().any(|&ok| match (ok, span) {
+            (Some(ok), Some(span)) => one_d::spans_overlap(ok, span),
+            _ => true,
+        });
+        if !covered && seen.insert(seen_key(&sym)) {
+            return Some(sym);
+        }
     }
+    stage_ok_spans.clear();
+    None
+}
+
+/// Ключ дедупликации (см. `DecodeIter::seen`): симвология и текст, а у QR с
+/// неразобранными данными (`qr.parse = "failed"`, текст пуст) — ещё и его
+/// кодворды, иначе разные такие символы склеились бы в один.
+type SeenKey = (Symbology, String, Vec<u8>);
+
+fn seen_key(sym: &DecodedSymbol) -> SeenKey {
+    let raw = match &sym.bytes {
+        Some(bytes) if sym.extras.get_str("qr.parse") == Some("failed") => bytes.clone(),
+        _ => Vec::new(),
+    };
+    (sym.symbology, sym.text.clone(), raw)
+}
+
+/// Отрезок строки под 1D-символом (`extras["x_start"]`..`["x_end"]`), если известен.
+fn x_span(sym: &DecodedSymbol) -> Option<(usize, usize)> {
+    Some((
+        sym.extras.get_parsed("x_start")?,
+        sym.extras.get_parsed("x_end")?,
+    ))
+}
+
+/// Символ стадии на выход: при [`ChecksumPolicy::Prefer`] несошедшийся
+/// откладывается до конца стадии (см. [`flush_deferred`]); повторы
+/// (см. `DecodeIter::seen`) не отдаются.
+fn admit(
+    sym: DecodedSymbol,
+    checksum: ChecksumPolicy,
+    deferred: &mut Vec<DecodedSymbol>,
+    stage_ok_spans: &mut Vec<Option<(usize, usize)>>,
+    seen: &mut BTreeSet<SeenKey>,
+) -> Option<DecodedSymbol> {
+    if checksum == ChecksumPolicy::Prefer {
+        if sym.extras.get_str("checksum") == Some("failed") {
+            deferred.push(sym);
+            return None;
+        }
+        stage_ok_spans.push(x_span(&sym));
+    }
+    seen.insert(seen_key(&sym)).then_some(sym)
+}
+
+/// Текущая стадия обхода: индекс следующей строки для 1D, затем QR, Aztec и
+/// индекс следующего пользовательского декодера.
+#[derive(Clone, Copy, Debug)]
+enum IterStage {
+    Ean13(usize),
+    Code128(usize),
+    Code93(usize),
+    Msi(usize),
+    Qr,
+    Aztec,
+    Custom(usize),
+    Done,
+}
+
+impl IterStage {
+    /// Встроенные стадии в обычном порядке, каждая — с начала.
+    const BUILTIN: [IterStage; 6] = [
+        IterStage::Ean13(0),
+        IterStage::Code128(0),
+        IterStage::Code93(0),
+        IterStage::Msi(0),
+        IterStage::Qr,
+        IterStage::Aztec,
+    ];
+
+    /// Стадия, которая читает `s`; у пользовательских символогий своей нет.
+    fn for_symbology(s: Symbology) -> Option<IterStage> {
+        match s {
+            Symbology::Ean13 | Symbology::UpcA => Some(IterStage::Ean13(0)),
+            Symbology::Code128 => Some(IterStage::Code128(0)),
+            Symbology::Code93 => Some(IterStage::Code93(0)),
+            Symbology::Msi => Some(IterStage::Msi(0)),
+            Symbology::QR => Some(IterStage::Qr),
+            Symbology::Aztec => Some(IterStage::Aztec),
+            Symbology::Custom(_) => None,
+        }
+    }
+
+    /// Та же стадия (без учёта номера строки).
+    fn same_kind(self, other: IterStage) -> bool {
+        core::mem::discriminant(&self) == core::mem::discriminant(&other)
+    }
+}
+
+impl Iterator for DecodeIter<'_> {
+    type Item = DecodedSymbol;
+
+    fn next(&mut self) -> Option<DecodedSymbol> {
+        let source = self
+            .normalized
+            .as_ref()
+            .map_or(self.img, LumaImage::as_gray);
+        if !self.skew_checked
+            && !matches!(
+                self.stage,
+                IterStage::Qr | IterStage::Aztec | IterStage::Custom(_) | IterStage::Done
+            )
+        {
+            self.aligned = one_d::skew::deskewed(&source, &self.row_opts);
+            self.row_ys = None;
+            self.skew_checked = true;
+        }
+        let aligned = self.aligned.as_ref();
+        // 1D-стадии сканируют выровненное изображение, если оно есть
+        let gray = aligned.map_or(source, |d| d.image.as_gray());
+        if self.row_ys.is_none() {
+            self.row_ys = Some(one_d::scan_row_ys(&gray, &self.row_opts));
+        }
+        let row_ys = self.row_ys.as_deref().unwrap_or_default();
+        let rows = row_ys.len();
+        let opts = &self.pipeline.opts;
+        let stages_1d = [
+            opts.enable_ean13_upca,
+            opts.enable_code128,
+            opts.enable_code93,
+            opts.enable_msi,
+        ];
+        let total = rows * stages_1d.iter().filter(|&&on| on).count()
+            + usize::from(opts.enable_qr)
+            + usize::from(opts.enable_aztec)
+            + opts.decoders.len();
+        let to_source = |mut b: one_d::Barcode| {
+            if let Some(d) = aligned {
+                b.row = d.source_row(b.row);
+            }
+            b
+        };
+
+        loop {
+            if let Some(sym) = self.custom.pop() {
+                if self.seen.insert(seen_key(&sym)) {
+                    return Some(sym);
+                }
+                continue;
+            }
+            if let Some(sym) = self.row_rest.pop() {
+                let checksum = self.row_opts.checksum;
+                if let Some(sym) = admit(
+                    sym,
+                    checksum,
+                    &mut self.deferred,
+                    &mut self.stage_ok_spans,
+                    &mut self.seen,
+                ) {
+                    return Some(sym);
+                }
+                continue;
+            }
+            let found = match self.stage {
+                // === 1) 1D: EAN-13 / UPC-A ===
+                IterStage::Ean13(i) => {
+                    if !self.pipeline.opts.enable_ean13_upca || i >= rows {
+                        if let Some(sym) = flush_deferred(
+                            &mut self.deferred,
+                            &mut self.stage_ok_spans,
+                            &mut self.seen,
+                        ) {
+                            return Some(sym);
+                        }
+                        self.stage = self
+                            .pipeline
+                            .opts
+                            .stage_after(IterStage::Ean13(i), !self.seen.is_empty());
+                        continue;
+                    }
+                    self.stage = IterStage::Ean13(i + 1);
+                    let y = row_ys[i];
+                    let gtin13 = self.pipeline.opts.normalize_gtin13;
+                    let in_store = self.pipeline.opts.parse_in_store_price;
+                    // несколько кодов на строке: первый — сейчас, остальные — следом
+                    let mut syms: Vec<DecodedSymbol> =
+                        one_d::decode_ean13_upca_row_all(&gray, y, &self.row_opts)
+                            .into_iter()
+                            .map(|b| {
+                                symbol_from_barcode(
+                                    to_source(b),
+                                    self.pipeline.opts.confidence_model(),
+                                )
+                            })
+                            .map(|sym| if in_store { in_store_symbol(sym) } else { sym })
+                            .map(|sym| if gtin13 { gtin13_symbol(sym) } else { sym })
+                            .collect();
+                    syms.reverse();
+                    let first = syms.pop();
+                    self.row_rest = syms;
+                    first
+                }
+                // === 2) 1D: Code128 ===
+                IterStage::Code128(i) => {
+                    if !self.pipeline.opts.enable_code128 || i >= rows {
+                        if let Some(sym) = flush_deferred(
+                            &mut self.deferred,
+                            &mut self.stage_ok_spans,
+                            &mut self.seen,
+                        ) {
+                            return Some(sym);
+                        }
+                        self.stage = self
+                            .pipeline
+                            .opts
+                            .stage_after(IterStage::Code128(i), !self.seen.is_empty());
+                        continue;
+                    }
+                    self.stage = IterStage::Code128(i + 1);
+                    let y = row_ys[i];
+                    one_d::decode_code128_row_bytes(&gray, y, &self.row_opts).map(|(b, data)| {
+                        code128_symbol(to_source(b), data, self.pipeline.opts.confidence_model())
+                    })
+                }
+                // === 3) 1D: Code 93 ===
+                IterStage::Code93(i) => {
+                    if !self.pipeline.opts.enable_code93 || i >= rows {
+                        if let Some(sym) = flush_deferred(
+                            &mut self.deferred,
+                            &mut self.stage_ok_spans,
+                            &mut self.seen,
+                        ) {
+                            return Some(sym);
+                        }
+                        self.stage = self
+                            .pipeline
+                            .opts
+                            .stage_after(IterStage::Code93(i), !self.seen.is_empty());
+                        continue;
+                    }
+                    self.stage = IterStage::Code93(i + 1);
+                    let y = row_ys[i];
+                    one_d::decode_code93_row(&gray, y, &self.row_opts).map(|b| {
+                        symbol_from_barcode(to_source(b), self.pipeline.opts.confidence_model())
+                    })
+                }
+                // === 4) 1D: MSI Plessey ===
+                IterStage::Msi(i) => {
+                    if !self.pipeline.opts.enable_msi || i >= rows {
+                        if let Some(sym) = flush_deferred(
+                            &mut self.deferred,
+                            &mut self.stage_ok_spans,
+                            &mut self.seen,
+                        ) {
+                            return Some(sym);
+                        }
+                        self.stage = self
+                            .pipeline
+                            .opts
+                            .stage_after(IterStage::Msi(i), !self.seen.is_empty());
+                        continue;
+                    }
+                    self.stage = IterStage::Msi(i + 1);
+                    let y = row_ys[i];
+                    one_d::decode_msi_row(&gray, y, &self.row_opts).map(|b| {
+                        symbol_from_barcode(to_source(b), self.pipeline.opts.confidence_model())
+                    })
+                }
+                // === 5) QR v1 (L/M/Q/H) ===
+                IterStage::Qr => {
+                    if !self.pipeline.opts.enable_qr {
+                        self.stage = self.pipeline.opts.stage_after(IterStage::Qr, false);
+                        continue;
+                    }
+                    let found = self
+                        .pipeline
+                        .try_decode_qr_v1_all_levels_with_correction(&source);
+                    self.stage = self
+                        .pipeline
+                        .opts
+                        .stage_after(IterStage::Qr, found.is_some());
+                    found
+                }
+                // === 6) Aztec (компактный) ===
+                IterStage::Aztec => {
+                    if !self.pipeline.opts.enable_aztec {
+                        self.stage = self.pipeline.opts.stage_after(IterStage::Aztec, false);
+                        continue;
+                    }
+                    let found = aztec::decode(&source)
+                        .map(|code| aztec_symbol(code, self.pipeline.opts.confidence_model()));
+                    self.stage = self
+                        .pipeline
+                        .opts
+                        .stage_after(IterStage::Aztec, found.is_some());
+                    found
+                }
+                // === 7) Пользовательские декодеры: символы отдаются в начале цикла ===
+                IterStage::Custom(k) => {
+                    let Some(CustomDecoder(decoder)) = self.pipeline.opts.decoders.get(k) else {
+                        self.stage = IterStage::Done;
+                        continue;
+                    };
+                    self.stage = IterStage::Custom(k + 1);
+                    self.custom = decoder.decode(&source);
+                    self.custom.reverse();
+                    None
+                }
+                IterStage::Done => {
+                    // Ничего не нашли — второй проход по изображению с растянутым
+                    // контрастом (см. `PipelineOptions::auto_contrast`). Однотонный
+                    // кадр растягивать нечему — второй проход пропускаем.
+                    if self.pipeline.opts.auto_contrast
+                        && !self.cancelled
+                        && self.seen.is_empty()
+                        && self.normalized.is_none()
+                    {
+                        let stats = self.img.stats();
+                        if stats.dynamic_range() == 0 {
+                            diag!(
+                                "[contrast] flat frame (level {}) -> skip second pass",
+                                stats.min
+                            );
+                            return None;
+                        }
+                        self.normalized = Some(self.img.normalize_contrast());
+                        self.aligned = None;
+                        self.skew_checked = false;
+                        self.row_ys = None;
+                        self.done = 0;
+                        self.stage = self.pipeline.opts.first_stage();
+                        return self.next();
+                    }
+                    return None;
+                }
+            };
+
+            self.done += 1;
+            if self
+                .progress
+                .as_ref()
+                .is_some_and(|p| !p.report(self.done, total))
+            {
+                self.stage = IterStage::Done;
+                self.cancelled = true;
+            }
+
+            if let Some(sym) = found {
+                let checksum = self.row_opts.checksum;
+                if let Some(sym) = admit(
+                    sym,
+                    checksum,
+                    &mut self.deferred,
+                    &mut self.stage_ok_spans,
+                    &mut self.seen,
+                ) {
+                    return Some(sym);
+                }
+            }
+        }
+    }
+}
+
+/// Левые (верхние) края фрагментов вдоль стороны `len`: шаг `tile - overlap`
+/// (не меньше 1), последний фрагмент прижат к краю.
+fn tile_origins(len: usize, tile: usize, overlap: usize) -> Vec<usize> {
+    let tile = tile.max(1);
+    if len <= tile {
+        return vec![0];
+    }
+    let step = tile.saturating_sub(overlap).max(1);
+    let last = len - tile;
+    let mut v: Vec<usize> = (0..last).step_by(step).collect();
+    v.push(last);
+    v
+}
+
+/// Приоритет символогии — в порядке стадий пайплайна (EAN → Code128 → Code 93 → MSI → QR → Aztec → свои).
+fn symbology_rank(s: Symbology) -> u8 {
+    match s {
+        Symbology::Ean13 | Symbology::UpcA => 0,
+        Symbology::Code128 => 1,
+        Symbology::Code93 => 2,
+        Symbology::Msi => 3,
+        Symbology::QR => 4,
+        Symbology::Aztec => 5,
+        Symbology::Custom(_) => 6,
+    }
+}
+
+/// Верхняя y символа: по `quad`, иначе по строке 1D-скана (`extras["row"]`), иначе 0.
+fn top_y(s: &DecodedSymbol) -> i64 {
+    if let Some(q) = s.quad {
+        return i64::from(q.bounding_box().0.y);
+    }
+    s.extras.get_parsed::<i64>("row").unwrap_or(0)
+}
+
+/// Левая x символа: по `quad`, иначе по началу 1D-символа в строке (`extras["x_start"]`), иначе 0.
+fn left_x(s: &DecodedSymbol) -> i64 {
+    if let Some(q) = s.quad {
+        return i64::from(q.bounding_box().0.x);
+    }
+    s.extras.get_parsed::<i64>("x_start").unwrap_or(0)
+}
+
+/// Стабильный порядок результатов: (приоритет символогии, верхняя y, левая x, текст).
+pub fn sort_symbols(symbols: &mut [DecodedSymbol]) {
+    symbols.sort_by(|a, b| {
+        symbology_rank(a.symbology)
+            .cmp(&symbology_rank(b.symbology))
+            .then_with(|| top_y(a).cmp(&top_y(b)))
+            .then_with(|| left_x(a).cmp(&left_x(b)))
+            .then_with(|| a.text.cmp(&b.text))
+    });
+}
+
+/// Сдвиги сетки QR (в модулях по u/v), которые пробуются, если несдвинутая не
+/// прочиталась (см. [`QrOptions::grid_shift_recovery`]).
+const QR_GRID_SHIFTS: [(i8, i8); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+/// Что о символе QR известно до семплинга: finder'ы, рамка, счёт таймингов и тихая
+/// зона; после первого чтения — ещё и калибровка сетки для повторов.
+struct QrFrame<'f> {
+    finders: &'f [finder::PointF],
+    geometry: sample::QrGeometry,
+    timing_n: Option<usize>,
+    quiet: bool,
+    calibration: Cell<Option<sample::GridCalibration>>,
+}
+
+impl QrFrame<'_> {
+    /// Калибровка несдвинутой сетки для повторов (поправки формата, сдвиги сетки):
+    /// сохранённая первым чтением, а если его не было или сетка читалась без
+    /// калибровки (осевая синтетика) — подобранная один раз.
+    fn calibration(
+        &self,
+        img: &GrayImage<'_>,
+        opts: &QrOptions,
+    ) -> Option<sample::GridCalibration> {
+        if self.calibration.get().is_none() {
+            self.calibration
+                .set(sample::calibrate_qr_v1_grid(img, opts, self.finders));
+        }
+        self.calibration.get()
+    }
+}
+
+/// Насколько снижается уверенность символа, прочитанного без тихой зоны
+/// (см. [`QuietZone::Relaxed`]).
+const QUIET_ZONE_PENALTY: f32 = 0.15;
+
+/// Насколько снижается уверенность 1D-символа с несошедшейся контрольной суммой
+/// (см. [`ChecksumPolicy`]): цифры могут быть неверны.
+const CHECKSUM_PENALTY: f32 = 0.4;
+
+/// 1D-результат → `DecodedSymbol` (симвология — по `b.format`, строка кладётся в extras как "row",
+/// положение в ней — как `x_start`/`x_end` (px, конец не включительно),
+/// ширина символа — как `modules` и `module_px`, пикселей на модуль).
+/// Для EAN-13/UPC-A добавляются `ean.number_system`, нормализованный `ean.ean13`,
+/// `gtin14` (см. [`one_d::to_gtin14`]),
+/// `ean.parity_recovered` (если первая цифра восстановлена) и, для книжных
+/// префиксов, ISBN/ISSN/ISMN (см. [`one_d::bookland`]).
+/// Им же соответствует [`SymbolMeta::Ean`].
+/// Без тихой зоны (см. [`QuietZone::Relaxed`]) — `quiet_zone = "missing"`,
+/// с несошедшейся контрольной суммой (см. [`ChecksumPolicy`]) — `checksum = "failed"`.
+/// `bytes` — ASCII-байты текста (у Code128 их заменяют точные, см. [`code128_symbol`]).
+fn symbol_from_barcode(b: one_d::Barcode, model: &dyn ConfidenceModel) -> DecodedSymbol {
+    let symbology = Symbology::from(b.format);
+    let mut extras = DecodedExtras::new()
+        .with("row", b.row.to_string())
+        .with("x_start", b.x.to_string())
+        .with("x_end", (b.x + b.width_px).to_string())
+        .with("modules", b.modules.to_string())
+        .with("module_px", alloc::format!("{:.2}", b.module_px()));
+    #[cfg(feature = "debug-capture")]
+    if let Some(d) = &b.debug {
+        let runs: Vec<String> = d.runs.iter().map(ToString::to_string).collect();
+        extras = extras
+            .with("debug.scan_row", d.scan_row.to_string())
+            .with("debug.runs", runs.join(" "));
+    }
+    let mut meta = None;
+    if matches!(
+        b.format,
+        one_d::BarcodeFormat::EAN13 | one_d::BarcodeFormat::UPCA
+    ) {
+        if b.recovered {
+            extras = extras.with("ean.parity_recovered", "true");
+        }
+        if let Some(ns) = one_d::ean13::number_system(&b.text) {
+            extras = extras.with("ean.number_system", ns);
+        }
+        if let Some(&d) = b.text.as_bytes().first().filter(|d| d.is_ascii_digit()) {
+            meta = Some(SymbolMeta::Ean {
+                number_system: d - b'0',
+                addon: None,
+            });
+        }
+        if let Some(full) = one_d::ean13::to_ean13(&b.text) {
+            // ISBN/ISSN/ISMN для книжных и периодических префиксов.
+            for (key, id) in one_d::bookland::bookland_ids(&full) {
+                extras = extras.with(key, id);
+            }
+            if let Some(gtin) = one_d::ean13::to_gtin14(&full) {
+                extras = extras.with("gtin14", gtin);
+            }
+            extras = extras.with("ean.ean13", full);
+        }
+    }
+    if b.quiet_zone_missing {
+        extras = extras.with("quiet_zone", "missing");
+    }
+    if b.checksum_failed {
+        extras = extras.with("checksum", "failed");
+    }
+    let confidence = model.one_d(&OneDEvidence {
+        symbology,
+        recovered: b.recovered,
+        quiet_zone_missing: b.quiet_zone_missing,
+        checksum_failed: b.checksum_failed,
+    });
+    let bytes = b.text.clone().into_bytes();
+    let orientation = b.orientation();
+    let mut sym = DecodedSymbol::new(symbology, b.text)
+        .with_confidence(confidence)
+        .with_orientation(orientation)
+        .with_bytes(bytes)
+        .with_extras(extras);
+    sym.meta = meta;
+    sym
+}
+
+/// EAN-13/UPC-A → 13 цифр (см. [`PipelineOptions::normalize_gtin13`]): текст и `bytes`
+/// берутся из `ean.ean13`, прочитанный текст — в `raw`. Без `ean.ean13` (цифры не
+/// сложились в номер) символ не меняется.
+fn gtin13_symbol(mut sym: DecodedSymbol) -> DecodedSymbol {
+    let Some(full) = sym.extras.get_str("ean.ean13").map(String::from) else {
+        return sym;
+    };
+    let raw = core::mem::replace(&mut sym.text, full);
+    sym.bytes = Some(sym.text.clone().into_bytes());
+    sym.extras = sym.extras.with("raw", raw);
+    sym
+}
+
+/// Поля внутримагазинного кода по `ean.ean13` (см. [`PipelineOptions::parse_in_store_price`]);
+/// у прочих префиксов символ не меняется.
+fn in_store_symbol(mut sym: DecodedSymbol) -> DecodedSymbol {
+    let Some(price) = sym
+        .extras
+        .get_str("ean.ean13")
+        .and_then(one_d::in_store::parse_in_store_price)
+    else {
+        return sym;
+    };
+    let check = if price.price_check_ok { "ok" } else { "failed" };
+    sym.extras = sym
+        .extras
+        .with("ean.in_store_item", price.item)
+        .with("ean.in_store_price", price.price.to_string())
+        .with("ean.in_store_price_check", check);
+    sym
+}
+
+/// Code128: точные байты в `bytes`, позиции FNC — в `code128.fnc`
+/// (`"0:FNC1,7:FNC1"`; индекс — позиция в `bytes`, перед которой стоит маркер),
+/// `c128.gs1 = "true"` — FNC1 сразу после старта (GS1-128).
+fn code128_symbol(
+    b: one_d::Barcode,
+    data: one_d::code128::Code128Bytes,
+    model: &dyn ConfidenceModel,
+) -> DecodedSymbol {
+    let mut sym = symbol_from_barcode(b, model);
+    if !data.fnc.is_empty() {
+        let marks: Vec<String> = data
+            .fnc
+            .iter()
+            .map(|&(at, f)| {
+                let name = match f {
+                    one_d::code128::Fnc::Fnc1 => "FNC1",
+                    one_d::code128::Fnc::Fnc2 => "FNC2",
+                    one_d::code128::Fnc::Fnc3 => "FNC3",
+                    one_d::code128::Fnc::Fnc4 => "FNC4",
+                };
+                alloc::format!("{at}:{name}")
+            })
+            .collect();
+        sym.extras = sym.extras.with("code128.fnc", marks.join(","));
+    }
+    if data.gs1 {
+        sym.extras = sym.extras.with("c128.gs1", "true");
+    }
+    sym.with_meta(SymbolMeta::Code128 {
+        start_set: data.start_set,
+        fnc1_gs1: data.gs1,
+    })
+    .with_bytes(data.bytes)
+}
+
+/// Aztec → `DecodedSymbol`: в extras `aztec.layers`, `aztec.data_words`,
+/// `aztec.rs_corrected_words`, `aztec.charset` и `aztec.module_px`.
+/// Уверенность — [`ConfidenceModel::aztec`].
+fn aztec_symbol(code: AztecCode, model: &dyn ConfidenceModel) -> DecodedSymbol {
+    let extras = DecodedExtras::new()
+        .with("aztec.layers", code.layers.to_string())
+        .with("aztec.data_words", code.data_words.to_string())
+        .with("aztec.rs_corrected_words", code.corrected_words.to_string())
+        .with("aztec.charset", code.charset.as_str())
+        .with("aztec.module_px", alloc::format!("{:.2}", code.module_px));
+    let confidence = model.aztec(&AztecEvidence {
+        layers: code.layers,
+        corrected_words: code.corrected_words,
+        module_px: code.module_px,
+    });
+    DecodedSymbol::new(Symbology::Aztec, code.text)
+        .with_confidence(confidence)
+        .with_bytes(code.bytes)
+        .with_quad(code.corners.to_quad())
+        .with_extras(extras)
 }
 
 /// Снять маску `mask_id` (0..7) — вернёт новую матрицу 21×21 с XOR маской.
@@ -341,22 +2064,6 @@ fn unmask_matrix_v1(matrix: &[Vec<bool>], mask_id: u8) -> Vec<Vec<bool>> {
     out
 }
 
-/// Дедупликация по (Symbology, text).
-fn dedup_by_sym_and_text(mut items: Vec<DecodedSymbol>) -> Vec<DecodedSymbol> {
-    use std::collections::HashSet;
-    let mut seen: HashSet<(Symbology, String)> = HashSet::new();
-    items.retain(|s| {
-        let key = (s.symbology, s.text.clone());
-        if seen.contains(&key) {
-            false
-        } else {
-            seen.insert(key);
-            true
-        }
-    });
-    items
-}
-
 #[inline]
 fn ec_level_to_str(l: format::EcLevel) -> &'static str {
     match l {
@@ -382,7 +2089,7 @@ fn bytes_to_bits_msb(bytes: &[u8]) -> Vec<bool> {
 fn hex_bytes(bs: &[u8]) -> String {
     let mut s = String::with_capacity(bs.len() * 2);
     for b in bs {
-        use std::fmt::Write as _;
+        use core::fmt::Write as _;
         let _ = write!(&mut s, "{:02X}", b);
     }
     s
@@ -1,4 +1,5 @@
 use std::env;
+use ultracode::prelude::Symbology;
 use ultracode::{decode_any, synthesize_row_code128, DecodeOptions, GrayImage};
 
 fn main() {
@@ -50,7 +51,14 @@ fn main() {
         }
     }
 
-    let row = synthesize_row_code128(&text, set, unit);
+    let row =
+        match synthesize_row_code128(&text, set, Symbology::Code128.standard_quiet_zone(), unit) {
+            Ok(row) => row,
+            Err(e) => {
+                eprintln!("Нельзя закодировать: {e}");
+                std::process::exit(2);
+            }
+        };
     let width = row.len();
     let mut img_buf = Vec::with_capacity(width * height);
     for _ in 0..height {
@@ -1,12 +1,17 @@
-use std::{
-    env, fs,
-    io::{self, Read},
+use std::{env, fs};
+use ultracode::core::pgm::{read_pgm, PgmError};
+use ultracode::prelude::{DecodedSymbol, LumaImage, Symbology};
+use ultracode::{
+    decode_formats, format_requested, pipeline_for_formats, BarcodeFormat, DecodeOptions,
+    GrayImage, DEFAULT_FORMATS,
 };
-use ultracode::{decode_any, DecodeOptions, GrayImage};
 
 fn main() {
     let mut path: Option<String> = None;
     let mut scan_rows: Option<usize> = None;
+    let mut formats: Vec<BarcodeFormat> = DEFAULT_FORMATS.to_vec();
+    let mut max_pixels = GrayImage::DEFAULT_MAX_PIXELS;
+    let mut csv = false;
 
     let mut args = env::args().skip(1);
     while let Some(a) = args.next() {
@@ -16,6 +21,28 @@ fn main() {
                     scan_rows = Some(v.parse().unwrap_or(15));
                 }
             }
+            "--max-pixels" => match args.next().and_then(|v| v.parse().ok()) {
+                Some(n) => max_pixels = n,
+                None => {
+                    print_help();
+                    std::process::exit(2);
+                }
+            },
+            "--formats" => {
+                let Some(v) = args.next() else {
+                    print_help();
+                    std::process::exit(2);
+                };
+                formats = match parse_formats(&v) {
+                    Some(f) => f,
+                    None => {
+                        eprintln!("Неизвестная симвология в --formats: {v}");
+                        print_help();
+                        std::process::exit(2);
+                    }
+                };
+            }
+            "--csv" => csv = true,
             "--help" | "-h" => {
                 print_help();
                 return;
@@ -40,8 +67,8 @@ fn main() {
         }
     };
 
-    let (width, height, data) = match read_pgm_p5(&path) {
-        Ok(t) => t,
+    let luma = match read_pgm_file(&path, max_pixels) {
+        Ok(img) => img,
         Err(e) => {
             eprintln!("Не удалось прочитать PGM: {e}");
             std::process::exit(1);
@@ -52,12 +79,12 @@ fn main() {
     if let Some(r) = scan_rows {
         opts.scan_rows = r;
     }
-    let img = GrayImage {
-        width,
-        height,
-        data: &data,
-    };
-    let results = decode_any(img, opts);
+    let img = luma.as_gray();
+    if csv {
+        print_csv(img, opts, &formats);
+        return;
+    }
+    let results = decode_formats(img, opts, &formats);
 
     if results.is_empty() {
         println!("Ничего не распознано.");
@@ -71,92 +98,107 @@ fn main() {
 fn print_help() {
     eprintln!(
         r#"Использование:
-  cargo run --bin scan_pgm -- <path.pgm> [--rows <N>]
+  cargo run --bin scan_pgm -- <path.pgm> [--rows <N>] [--formats <список>] [--max-pixels <N>] [--csv]
+
+  --rows <N>          сколько строк сканировать 1D-декодерами (по умолчанию 15)
+  --formats <список>  через запятую: ean13, upca, code128, code93, msi, qr, aztec
+                      (по умолчанию всё, кроме msi и aztec)
+  --max-pixels <N>    предел width*height из заголовка (по умолчанию 268435456)
+  --csv               вывод CSV: symbology,text,confidence,row,x_start,x_end
+                      (row — у 1D; x_start/x_end — у 1D по строке, у 2D по углам)
 
 Требуется PGM P5 (8-бит, maxval=255).
 Примеры:
   cargo run --bin scan_pgm -- ./test.pgm
   cargo run --bin scan_pgm -- ./test.pgm --rows 25
+  cargo run --bin scan_pgm -- ./test.pgm --formats code128,msi
+  cargo run --bin scan_pgm -- ./test.pgm --csv > results.csv
 "#
     );
 }
 
-// Минимальный парсер PGM (P5, 8-бит)
-fn read_pgm_p5(path: &str) -> io::Result<(usize, usize, Vec<u8>)> {
-    let mut file = fs::File::open(path)?;
-    let mut buf = Vec::new();
-    file.read_to_end(&mut buf)?;
-
-    // читаем ascii-хедер "P5\n<width> <height>\n<maxval>\n"
-    let mut i = 0usize;
-
-    fn read_token(buf: &[u8], i: &mut usize) -> Option<String> {
-        while *i < buf.len() {
-            let c = buf[*i];
-            if c == b'#' {
-                while *i < buf.len() && buf[*i] != b'\n' {
-                    *i += 1;
-                }
-            } else if c.is_ascii_whitespace() {
-                *i += 1;
-            } else {
-                break;
-            }
-        }
-        if *i >= buf.len() {
-            return None;
+// --csv: тот же набор символогий через Pipeline — у его символов есть уверенность
+// и углы. Пустое поле — значения нет (row у 2D, x у символов без углов и положения в строке).
+fn print_csv(img: GrayImage<'_>, opts: DecodeOptions, formats: &[BarcodeFormat]) {
+    let pipe = pipeline_for_formats(opts, formats).build();
+    println!("symbology,text,confidence,row,x_start,x_end");
+    for sym in pipe.decode_all_gray(&img) {
+        // Тот же отбор, что в decode_formats (UPC-A приходит и с ean13).
+        if format_requested(BarcodeFormat::from(sym.symbology), formats) {
+            println!("{}", csv_row(&sym));
         }
-        let start = *i;
-        while *i < buf.len() && !buf[*i].is_ascii_whitespace() {
-            *i += 1;
-        }
-        Some(String::from_utf8_lossy(&buf[start..*i]).to_string())
     }
+}
 
-    let magic = read_token(&buf, &mut i)
-        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "PGM: нет магической сигнатуры"))?;
-    if magic != "P5" {
-        return Err(io::Error::new(
-            io::ErrorKind::InvalidData,
-            "PGM: поддерживается только P5 (binary)",
-        ));
-    }
-    let width: usize = read_token(&buf, &mut i)
-        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "PGM: нет width"))?
-        .parse()
-        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "PGM: неверный width"))?;
-    let height: usize = read_token(&buf, &mut i)
-        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "PGM: нет height"))?
-        .parse()
-        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "PGM: неверный height"))?;
-    let maxval: usize = read_token(&buf, &mut i)
-        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "PGM: нет maxval"))?
-        .parse()
-        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "PGM: неверный maxval"))?;
-    if maxval != 255 {
-        return Err(io::Error::new(
-            io::ErrorKind::InvalidData,
-            "PGM: поддерживается только maxval=255",
-        ));
-    }
+fn csv_row(sym: &DecodedSymbol) -> String {
+    // у 2D — по углам, у 1D — по положению символа в строке
+    let (x_start, x_end) = match sym.quad.map(|q| q.bounding_box()) {
+        Some((min, max)) => (min.x.to_string(), max.x.to_string()),
+        None => (
+            sym.extras
+                .get_str("x_start")
+                .unwrap_or_default()
+                .to_string(),
+            sym.extras.get_str("x_end").unwrap_or_default().to_string(),
+        ),
+    };
+    let fields = [
+        symbology_name(sym.symbology).to_string(),
+        csv_field(&sym.text),
+        format!("{:.2}", sym.confidence),
+        sym.extras.get_str("row").unwrap_or_default().to_string(),
+        x_start,
+        x_end,
+    ];
+    fields.join(",")
+}
 
-    if i >= buf.len() {
-        return Err(io::Error::new(
-            io::ErrorKind::UnexpectedEof,
-            "PGM: нет данных изображения",
-        ));
-    }
-    if buf[i] == b'\n' {
-        i += 1;
+// Имена как в --formats, чтобы вывод можно было скормить обратно.
+fn symbology_name(s: Symbology) -> &'static str {
+    match s {
+        Symbology::QR => "qr",
+        Symbology::Code128 => "code128",
+        Symbology::Code93 => "code93",
+        Symbology::Ean13 => "ean13",
+        Symbology::UpcA => "upca",
+        Symbology::Msi => "msi",
+        Symbology::Aztec => "aztec",
+        Symbology::Custom(name) => name,
+        _ => "unknown",
     }
-    let expected = width.checked_mul(height)
-        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "PGM: переполнение размера"))?;
-    if buf.len() - i < expected {
-        return Err(io::Error::new(
-            io::ErrorKind::UnexpectedEof,
-            "PGM: мало байтов данных",
-        ));
+}
+
+// Поле по RFC 4180: в кавычках, если есть запятая, кавычка или перевод строки;
+// кавычки внутри удваиваются.
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
     }
-    let data = buf[i..i + expected].to_vec();
-    Ok((width, height, data))
+}
+
+// Список символогий для --formats ("ean13,code128"); None — если есть незнакомое имя.
+fn parse_formats(list: &str) -> Option<Vec<BarcodeFormat>> {
+    list.split(',')
+        .map(|name| match name.trim().to_ascii_lowercase().as_str() {
+            "ean13" => Some(BarcodeFormat::EAN13),
+            "upca" => Some(BarcodeFormat::UPCA),
+            "code128" => Some(BarcodeFormat::Code128),
+            "code93" => Some(BarcodeFormat::Code93),
+            "msi" => Some(BarcodeFormat::Msi),
+            "qr" => Some(BarcodeFormat::QR),
+            "aztec" => Some(BarcodeFormat::Aztec),
+            _ => None,
+        })
+        .collect()
+}
+
+// Кадр из PGM-файла; ошибки разбора (см. `PgmError`) — текстом для stderr.
+fn read_pgm_file(path: &str, max_pixels: usize) -> Result<LumaImage, String> {
+    let bytes = fs::read(path).map_err(|e| e.to_string())?;
+    read_pgm(&bytes, max_pixels).map_err(|e| match e {
+        PgmError::BadDimension { .. } => format!("{e} (--max-pixels {max_pixels})"),
+        _ => e.to_string(),
+    })
 }
@@ -1,4 +1,6 @@
 use std::env;
+use ultracode::one_d::{synthesize_ean13_row, synthesize_upca_row};
+use ultracode::prelude::Symbology;
 use ultracode::{decode_any, DecodeOptions, GrayImage};
 
 fn main() {
@@ -42,7 +44,20 @@ fn main() {
         }
     }
 
-    let row = synthesize_ean_row(&code, unit);
+    // 11–12 цифр — UPC-A, иначе EAN-13
+    let quiet_zone = Symbology::Ean13.standard_quiet_zone();
+    let row = if code.len() <= 12 {
+        synthesize_upca_row(&code, quiet_zone, unit)
+    } else {
+        synthesize_ean13_row(&code, quiet_zone, unit)
+    };
+    let row = match row {
+        Ok(row) => row,
+        Err(e) => {
+            eprintln!("Нельзя закодировать {code:?}: {e}");
+            std::process::exit(2);
+        }
+    };
     let width = row.len();
     let mut img_buf = Vec::with_capacity(width * height);
     for _ in 0..height {
@@ -80,6 +95,8 @@ fn print_help() {
   cargo run --bin scan_synthetic -- [--code <digits>] [--unit <px>] [--height <px>] [--write-pgm <file.pgm>]
 
 По умолчанию генерируется EAN-13 5901234123457 с unit=2 и height=64.
+13 цифр — EAN-13, 12 — UPC-A (11 — UPC-A без контрольной, она дописывается);
+переданная контрольная цифра проверяется.
 
 Примеры:
   cargo run --bin scan_synthetic --
@@ -89,132 +106,6 @@ fn print_help() {
     );
 }
 
-// ======= Синтетический генератор EAN-13/UPC-A =======
-
-const A_PATTERNS: [(u8, u8, u8, u8); 10] = [
-    (3, 2, 1, 1),
-    (2, 2, 2, 1),
-    (2, 1, 2, 2),
-    (1, 4, 1, 1),
-    (1, 1, 3, 2),
-    (1, 2, 3, 1),
-    (1, 1, 1, 4),
-    (1, 3, 1, 2),
-    (1, 2, 1, 3),
-    (3, 1, 1, 2),
-];
-const B_PATTERNS: [(u8, u8, u8, u8); 10] = [
-    (1, 1, 2, 3),
-    (1, 2, 2, 2),
-    (2, 2, 1, 2),
-    (1, 1, 4, 1),
-    (2, 3, 1, 1),
-    (1, 3, 2, 1),
-    (4, 1, 1, 1),
-    (2, 1, 3, 1),
-    (3, 1, 2, 1),
-    (2, 1, 1, 3),
-];
-const C_PATTERNS: [(u8, u8, u8, u8); 10] = A_PATTERNS;
-
-const FIRST_DIGIT_MASKS: [(bool, bool, bool, bool, bool, bool); 10] = [
-    (false, false, false, false, false, false), // 0
-    (false, false, true, false, true, true),    // 1
-    (false, false, true, true, false, true),    // 2
-    (false, false, true, true, true, false),    // 3
-    (false, true, false, false, true, true),    // 4
-    (false, true, true, false, false, true),    // 5
-    (false, true, true, true, false, false),    // 6
-    (false, true, false, true, false, true),    // 7
-    (false, true, false, true, true, false),    // 8
-    (false, true, true, false, true, false),    // 9
-];
-
-fn synthesize_ean_row_from_digits(digits: &[u8], unit: usize) -> Vec<u8> {
-    let mut modules: Vec<u8> = Vec::new();
-    modules.push(9);
-    modules.extend_from_slice(&[1, 1, 1]);
-
-    let mut ean13: [u8; 13] = [0; 13];
-    match digits.len() {
-        12 => {
-            ean13[0] = 0;
-            for i in 0..12 {
-                ean13[i + 1] = digits[i];
-            }
-            let mut sum = 0u32;
-            for i in 0..12 {
-                let w = if i % 2 == 0 { 1 } else { 3 };
-                sum += ean13[i] as u32 * w;
-            }
-            ean13[12] = ((10 - (sum % 10)) % 10) as u8;
-        }
-        13 => {
-            for i in 0..13 {
-                ean13[i] = digits[i];
-            }
-        }
-        _ => panic!("Ожидались 12 (UPC-A) или 13 (EAN-13) цифр, получено {}", digits.len()),
-    }
-
-    let first = ean13[0] as usize;
-    let mask = FIRST_DIGIT_MASKS[first];
-
-    for i in 0..6 {
-        let d = ean13[1 + i] as usize;
-        let (a, b, c, dw) = if mask_at(mask, i) { B_PATTERNS[d] } else { A_PATTERNS[d] };
-        modules.extend_from_slice(&[a, b, c, dw]);
-    }
-
-    modules.extend_from_slice(&[1, 1, 1, 1, 1]);
-
-    for i in 0..6 {
-        let d = ean13[7 + i] as usize;
-        let (a, b, c, dw) = C_PATTERNS[d];
-        modules.extend_from_slice(&[a, b, c, dw]);
-    }
-
-    modules.extend_from_slice(&[1, 1, 1]);
-    modules.push(9);
-
-    let mut pix: Vec<u8> = Vec::new();
-    let mut black = false;
-    for m in modules {
-        let w = m as usize * unit;
-        let val = if black { 0u8 } else { 255u8 };
-        for _ in 0..w {
-            pix.push(val);
-        }
-        black = !black;
-    }
-    pix
-}
-
-fn synthesize_ean_row(code: &str, unit: usize) -> Vec<u8> {
-    let digits: Vec<u8> = code
-        .bytes()
-        .map(|c| {
-            if !c.is_ascii_digit() {
-                panic!("Код должен содержать только цифры");
-            }
-            c - b'0'
-        })
-        .collect();
-    synthesize_ean_row_from_digits(&digits, unit)
-}
-
-fn mask_at(mask: (bool, bool, bool, bool, bool, bool), idx: usize) -> bool {
-    match idx {
-        0 => mask.0,
-        1 => mask.1,
-        2 => mask.2,
-        3 => mask.3,
-        4 => mask.4,
-        5 => mask.5,
-        _ => false,
-    }
-}
-
 fn write_pgm_p5(path: &str, width: usize, height: usize, data: &[u8]) -> std::io::Result<()> {
     use std::io::Write;
     let mut f = std::fs::File::create(path)?;
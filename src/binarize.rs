@@ -10,9 +10,22 @@
 //! - `binarize_row_adaptive(&[u8]) -> Vec<bool>`
 //! - `runs(&[bool]) -> Vec<usize>`
 //! - `normalize_modules(&[bool], &[usize]) -> (Vec<u8>, bool)`
+//! - `normalize_modules_windowed(&[bool], &[usize], usize) -> (Vec<u8>, bool)`
+//! - `runs_subpixel(&[u8]) -> Vec<f32>` + `normalize_runs_f32(&[f32], usize) -> Vec<u8>`
+//!   (дробные ширины по субпиксельным фронтам — для модулей в 1–2 пикселя)
+//!
+//! Целый кадр (для своей разметки и 2D): `binarize_image(&GrayImage, BinarizeMethod) -> Vec<bool>`.
+
+use crate::core::cast::{f32_to_usize, usize_to_f32};
+#[cfg(not(feature = "std"))]
+use crate::core::math::FloatExt;
+use crate::core::types::GrayImage;
+use alloc::vec;
+use alloc::vec::Vec;
 
 /// Простой «Otsu-like» порог: среднее и середина (min+max)/2.
 #[inline]
+#[must_use]
 pub fn otsu_like_threshold(row: &[u8]) -> u8 {
     if row.is_empty() {
         return 0;
@@ -34,6 +47,7 @@ pub fn otsu_like_threshold(row: &[u8]) -> u8 {
 }
 
 /// Глобальная бинаризация строки: true = чёрный, false = белый.
+#[must_use]
 pub fn binarize_row(row: &[u8]) -> Vec<bool> {
     let t = otsu_like_threshold(row);
     row.iter().map(|&v| v < t).collect()
@@ -42,6 +56,7 @@ pub fn binarize_row(row: &[u8]) -> Vec<bool> {
 /// Адаптивная бинаризация по скользящему среднему.
 /// Окно подбирается от width/32 и ограничивается в [8..64],
 /// небольшой `bias` смещает порог в «чёрную» сторону.
+#[must_use]
 pub fn binarize_row_adaptive(row: &[u8]) -> Vec<bool> {
     let n = row.len();
     if n == 0 {
@@ -77,7 +92,135 @@ pub fn binarize_row_adaptive(row: &[u8]) -> Vec<bool> {
     out
 }
 
+/// Способ бинаризации целого кадра (см. [`binarize_image`]).
+/// `radius == 0` — окно как у [`binarize_row_adaptive`]: `max(width, height) / 32`
+/// в пределах [8..64].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinarizeMethod {
+    /// Один порог на кадр — [`otsu_like_threshold`] по всем пикселям.
+    Global,
+    /// Среднее в квадратном окне `±radius` минус `bias`: как
+    /// [`binarize_row_adaptive`] (там `bias = 5`), но по площади.
+    AdaptiveMean { radius: usize, bias: i32 },
+    /// Sauvola: порог `m · (1 + k · (s / 128 − 1))` по среднему `m` и
+    /// стандартному отклонению `s` в окне `±radius`. На тексте и бликах
+    /// чище скользящего среднего; обычно `k` около 0.2–0.5.
+    Sauvola { radius: usize, k: f32 },
+}
+
+/// Бинаризовать кадр целиком: `width * height` значений построчно, true = чёрный.
+/// Окна считаются по интегральным суммам — время не зависит от `radius`;
+/// у краёв окно обрезается кадром.
+#[must_use]
+pub fn binarize_image(img: &GrayImage<'_>, method: BinarizeMethod) -> Vec<bool> {
+    let (width, height) = (img.width, img.height);
+    if width == 0 || height == 0 {
+        return Vec::new();
+    }
+    let auto = |radius: usize| {
+        if radius == 0 {
+            (width.max(height) / 32).clamp(8, 64)
+        } else {
+            radius
+        }
+    };
+    match method {
+        BinarizeMethod::Global => {
+            let threshold = otsu_like_threshold(&img.data[..width * height]);
+            img.data[..width * height]
+                .iter()
+                .map(|&v| v < threshold)
+                .collect()
+        }
+        BinarizeMethod::AdaptiveMean { radius, bias } => {
+            let integral = Integral::new(img, false);
+            let r = auto(radius);
+            let mut out = Vec::with_capacity(width * height);
+            for y in 0..height {
+                for (x, &v) in img.row(y).iter().enumerate() {
+                    let (sum, _, n) = integral.window(x, y, r);
+                    out.push(i32::from(v) < i32::try_from(sum / n).unwrap_or(i32::MAX) - bias);
+                }
+            }
+            out
+        }
+        BinarizeMethod::Sauvola { radius, k } => {
+            let integral = Integral::new(img, true);
+            let r = auto(radius);
+            let mut out = Vec::with_capacity(width * height);
+            for y in 0..height {
+                for (x, &v) in img.row(y).iter().enumerate() {
+                    let (sum, sq, n) = integral.window(x, y, r);
+                    let mean = sum as f32 / n as f32;
+                    let var = (sq as f32 / n as f32 - mean * mean).max(0.0);
+                    let threshold = mean * (1.0 + k * (var.sqrt() / 128.0 - 1.0));
+                    out.push(f32::from(v) < threshold);
+                }
+            }
+            out
+        }
+    }
+}
+
+/// Интегральные суммы яркостей (и, по запросу, их квадратов) с нулевой
+/// строкой и столбцом спереди: сумма по окну — за четыре чтения.
+struct Integral {
+    width: usize,
+    height: usize,
+    sum: Vec<u64>,
+    sq: Vec<u64>,
+}
+
+impl Integral {
+    fn new(img: &GrayImage<'_>, squares: bool) -> Self {
+        let (w, h) = (img.width, img.height);
+        let stride = w + 1;
+        let mut sum = vec![0u64; stride * (h + 1)];
+        let mut sq = if squares {
+            vec![0u64; stride * (h + 1)]
+        } else {
+            Vec::new()
+        };
+        for y in 0..h {
+            let (mut row_sum, mut row_sq) = (0u64, 0u64);
+            for (x, &v) in img.row(y).iter().enumerate() {
+                let v = u64::from(v);
+                row_sum += v;
+                sum[(y + 1) * stride + x + 1] = sum[y * stride + x + 1] + row_sum;
+                if squares {
+                    row_sq += v * v;
+                    sq[(y + 1) * stride + x + 1] = sq[y * stride + x + 1] + row_sq;
+                }
+            }
+        }
+        Self {
+            width: w,
+            height: h,
+            sum,
+            sq,
+        }
+    }
+
+    /// `(сумма, сумма квадратов, пикселей)` в окне `±r` вокруг `(x, y)`;
+    /// без квадратов вторая — 0.
+    fn window(&self, x: usize, y: usize, r: usize) -> (u64, u64, u64) {
+        let stride = self.width + 1;
+        let (x0, x1) = (x.saturating_sub(r), (x + r + 1).min(self.width));
+        let (y0, y1) = (y.saturating_sub(r), (y + r + 1).min(self.height));
+        let area = |t: &[u64]| {
+            t[y1 * stride + x1] + t[y0 * stride + x0] - t[y0 * stride + x1] - t[y1 * stride + x0]
+        };
+        let sq = if self.sq.is_empty() {
+            0
+        } else {
+            area(&self.sq)
+        };
+        (area(&self.sum), sq, ((x1 - x0) * (y1 - y0)) as u64)
+    }
+}
+
 /// Превратить бинарную строку (true=чёрный) в run-lengths (ширины подряд идущих баров/пробелов).
+#[must_use]
 pub fn runs(row_bin: &[bool]) -> Vec<usize> {
     if row_bin.is_empty() {
         return Vec::new();
@@ -98,44 +241,150 @@ pub fn runs(row_bin: &[bool]) -> Vec<usize> {
     v
 }
 
+/// Дробные run-lengths по субпиксельным фронтам.
+///
+/// Порог — середина между локальными min и max (окно как у [`binarize_row_adaptive`]):
+/// в отличие от скользящего среднего, он не уезжает к белому у тихой зоны, и фронт
+/// оказывается ровно на полпути яркостного перепада. На каждой смене класса между
+/// пикселями `i-1` и `i` позиция фронта уточняется линейной интерполяцией точки,
+/// где яркость пересекает порог (центр пикселя `i` — координата `i + 0.5`).
+/// Сумма ширин равна длине строки.
+#[must_use]
+pub fn runs_subpixel(row: &[u8]) -> Vec<f32> {
+    let th = midrange_thresholds(row);
+    let f: Vec<f32> = row
+        .iter()
+        .zip(&th)
+        .map(|(&v, &t)| f32::from(v) - t)
+        .collect();
+    let mut out = Vec::new();
+    let mut last_edge = 0.0f32;
+    for i in 1..f.len() {
+        let (a, b) = (f[i - 1], f[i]);
+        if (a < 0.0) == (b < 0.0) {
+            continue;
+        }
+        // a и b разных знаков (или одно из них 0) — доля пути от центра i-1 к центру i
+        let step = a - b;
+        let frac = if step.abs() < f32::EPSILON {
+            0.5
+        } else {
+            (a / step).clamp(0.0, 1.0)
+        };
+        let edge = usize_to_f32(i) - 0.5 + frac;
+        out.push(edge - last_edge);
+        last_edge = edge;
+    }
+    if !row.is_empty() {
+        out.push(usize_to_f32(row.len()) - last_edge);
+    }
+    out
+}
+
+/// Порог (min + max) / 2 в скользящем окне.
+fn midrange_thresholds(row: &[u8]) -> Vec<f32> {
+    let n = row.len();
+    let win = (n / 32).clamp(8, 64);
+    (0..n)
+        .map(|i| {
+            let w = &row[i.saturating_sub(win)..(i + win + 1).min(n)];
+            let lo = w.iter().copied().min().unwrap_or(0);
+            let hi = w.iter().copied().max().unwrap_or(0);
+            (f32::from(lo) + f32::from(hi)) * 0.5
+        })
+        .collect()
+}
+
+/// Квантование дробных run'ов в модули 1..4 (база — как в [`normalize_modules_windowed`];
+/// `window == 0` — одна оценка на всю строку).
+#[must_use]
+pub fn normalize_runs_f32(rl: &[f32], window: usize) -> Vec<u8> {
+    if window == 0 {
+        let base = thin_median(rl.iter().copied());
+        return rl.iter().map(|&w| quantize(w, base)).collect();
+    }
+    (0..rl.len())
+        .map(|i| {
+            let lo = i.saturating_sub(window);
+            let hi = (i + window + 1).min(rl.len());
+            quantize(rl[i], thin_median(rl[lo..hi].iter().copied()))
+        })
+        .collect()
+}
+
 /// Нормализовать run-lengths в условные «модули» (1..4).
 /// Возвращает `(вектор_модулей, starts_black)`.
 ///
 /// Алгоритм:
 /// 1) Оценить базовый модуль как медиану нижней половины run-ов (устойчив к «толстой лапе»).
 /// 2) Квантовать каждую ширину в 1..4 округлением к ближайшему целому.
+#[must_use]
 pub fn normalize_modules(row_bin: &[bool], rl: &[usize]) -> (Vec<u8>, bool) {
     if rl.is_empty() {
         return (Vec::new(), false);
     }
 
-    // Базовый модуль — «тонкие» полосы (нижняя половина).
-    let mut sorted = rl.to_vec();
-    sorted.sort_unstable();
-    let thin_slice = &sorted[..(sorted.len().max(1) + 1) / 2];
-    let base = {
-        let mid = thin_slice.len() / 2;
-        if thin_slice.is_empty() {
-            1.0f32
-        } else if thin_slice.len() % 2 == 1 {
-            thin_slice[mid] as f32
-        } else {
-            (thin_slice[mid - 1] as f32 + thin_slice[mid] as f32) * 0.5
-        }
-        .max(1.0)
-    };
+    let base = thin_median(rl.iter().copied().map(usize_to_f32));
+    let mods: Vec<u8> = rl
+        .iter()
+        .map(|&w| quantize(usize_to_f32(w), base))
+        .collect();
+
+    let starts_black = row_bin.first().copied().unwrap_or(false);
+    (mods, starts_black)
+}
 
-    let mut mods: Vec<u8> = Vec::with_capacity(rl.len());
-    for &w in rl {
-        let q = (w as f32 / base).round() as i32;
-        let q = q.clamp(1, 4) as u8;
-        mods.push(q);
+/// Как [`normalize_modules`], но базовый модуль оценивается для каждого run'а
+/// отдельно — по соседям в окне `±window` run'ов. Медленнее (сортировка окна на
+/// каждый run), зато широкие полосы фона вдали от штрихкода не сдвигают оценку.
+/// `window == 0` — то же, что глобальная оценка.
+#[must_use]
+pub fn normalize_modules_windowed(
+    row_bin: &[bool],
+    rl: &[usize],
+    window: usize,
+) -> (Vec<u8>, bool) {
+    if window == 0 {
+        return normalize_modules(row_bin, rl);
     }
+    let mods: Vec<u8> = (0..rl.len())
+        .map(|i| {
+            let lo = i.saturating_sub(window);
+            let hi = (i + window + 1).min(rl.len());
+            quantize(
+                usize_to_f32(rl[i]),
+                thin_median(rl[lo..hi].iter().copied().map(usize_to_f32)),
+            )
+        })
+        .collect();
 
     let starts_black = row_bin.first().copied().unwrap_or(false);
     (mods, starts_black)
 }
 
+/// Базовый модуль — медиана «тонких» полос (нижней половины run'ов), не меньше 1.
+fn thin_median(rl: impl Iterator<Item = f32>) -> f32 {
+    let mut sorted: Vec<f32> = rl.collect();
+    sorted.sort_unstable_by(f32::total_cmp);
+    let thin_slice = &sorted[..sorted.len().max(1).div_ceil(2)];
+    let mid = thin_slice.len() / 2;
+    if thin_slice.is_empty() {
+        1.0f32
+    } else if thin_slice.len() % 2 == 1 {
+        thin_slice[mid]
+    } else {
+        (thin_slice[mid - 1] + thin_slice[mid]) * 0.5
+    }
+    .max(1.0)
+}
+
+/// Ширина в модулях 1..4 округлением к ближайшему целому.
+#[inline]
+fn quantize(w: f32, base: f32) -> u8 {
+    let q = f32_to_usize((w / base).round()).clamp(1, 4);
+    u8::try_from(q).unwrap_or(4)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -163,4 +412,60 @@ mod tests {
         assert_eq!(mods.len(), rl.len());
         assert_eq!(starts_black, true);
     }
+
+    #[test]
+    fn windowed_base_ignores_distant_wide_runs() {
+        // Тонкий код (модуль 2 px), справа — много широких полос фона (8 px).
+        let mut rl = vec![2usize, 4, 2, 6, 2, 2, 4, 2];
+        rl.resize(rl.len() + 24, 8);
+        let row_bin: Vec<bool> = (0..rl.len()).map(|i| i % 2 == 0).collect();
+
+        let (global, _) = normalize_modules(&row_bin, &rl);
+        assert_ne!(&global[..8], &[1, 2, 1, 3, 1, 1, 2, 1]);
+
+        let (local, starts_black) = normalize_modules_windowed(&row_bin, &rl, 6);
+        assert_eq!(&local[..8], &[1, 2, 1, 3, 1, 1, 2, 1]);
+        assert!(starts_black);
+        assert_eq!(normalize_modules_windowed(&row_bin, &rl, 0).0, global);
+    }
+
+    #[test]
+    fn image_binarization_matches_rows_on_uniform_image() {
+        // Одна строка, повторённая по высоте: глобальный порог и среднее по
+        // квадрату совпадают с построчными (вертикаль усреднению ничего не добавляет).
+        let row: Vec<u8> = (0..160)
+            .map(|i| if (i / 5) % 3 == 0 { 30 } else { 220 })
+            .collect();
+        let height = 12;
+        let data = row.repeat(height);
+        let img = GrayImage {
+            width: row.len(),
+            height,
+            data: &data,
+        };
+
+        let global = binarize_image(&img, BinarizeMethod::Global);
+        assert_eq!(global, binarize_row(&row).repeat(height));
+        let adaptive = binarize_image(&img, BinarizeMethod::AdaptiveMean { radius: 0, bias: 5 });
+        assert_eq!(adaptive, binarize_row_adaptive(&row).repeat(height));
+        let sauvola = binarize_image(&img, BinarizeMethod::Sauvola { radius: 0, k: 0.2 });
+        assert_eq!(sauvola, global);
+
+        // Однотонный кадр — ни одного чёрного пикселя ни одним способом.
+        let flat = vec![128u8; 40 * 30];
+        let img = GrayImage {
+            width: 40,
+            height: 30,
+            data: &flat,
+        };
+        for method in [
+            BinarizeMethod::Global,
+            BinarizeMethod::AdaptiveMean { radius: 3, bias: 5 },
+            BinarizeMethod::Sauvola { radius: 3, k: 0.3 },
+        ] {
+            let map = binarize_image(&img, method);
+            assert_eq!(map.len(), 40 * 30);
+            assert!(map.iter().all(|&b| !b), "{method:?}");
+        }
+    }
 }
@@ -2,41 +2,127 @@
 //! Совместимость со старым API (бинарники scan_*).
 //! Используем новый Pipeline и маппим результат в one_d::Barcode.
 
-use crate::api::Pipeline;
+use crate::api::PipelineBuilder;
+use crate::core::cast::{f32_to_usize, usize_to_f32};
+#[cfg(not(feature = "std"))]
+use crate::core::math::FloatExt;
 use crate::one_d::{Barcode, BarcodeFormat, DecodeOptions};
-use crate::prelude::{DecodedSymbol, GrayImage, LumaImage, Symbology};
+use crate::prelude::{DecodedSymbol, GrayImage, Orientation};
+use alloc::vec::Vec;
+
+/// Набор символогий по умолчанию — как у `Pipeline::default()` (Code 93, MSI и
+/// Aztec выключены).
+pub const DEFAULT_FORMATS: &[BarcodeFormat] = &[
+    BarcodeFormat::EAN13,
+    BarcodeFormat::UPCA,
+    BarcodeFormat::Code128,
+    BarcodeFormat::QR,
+];
 
 /// Старый вход из бинарников: GrayImage<'_> + DecodeOptions → Vec<one_d::Barcode>.
-/// Конвертируем GrayImage во «владельческий» LumaImage и запускаем новый пайплайн.
-/// Теперь поддерживаем и QR через добавленный вариант BarcodeFormat::QR.
-pub fn decode_any(img: GrayImage<'_>, _opts: DecodeOptions) -> Vec<Barcode> {
-    let pipeline = Pipeline::default();
+/// Запускаем новый пайплайн прямо по заимствованному кадру (без копии)
+/// с [`DEFAULT_FORMATS`]; `opts` (`scan_rows` и пр.) уходят в 1D-декодеры,
+/// `opts.progress` — в пайплайн (шаги — строки стадий и QR), `opts.quiet_zone` —
+/// и в QR.
+/// Симвология маппится через `From<Symbology> for BarcodeFormat` без потерь (включая QR и UPC-A).
+#[must_use]
+pub fn decode_any(img: GrayImage<'_>, opts: DecodeOptions) -> Vec<Barcode> {
+    decode_formats(img, opts, DEFAULT_FORMATS)
+}
+
+/// Пайплайн, который [`decode_formats`] собирает под `formats`: включены только
+/// нужные декодеры, `opts` уходят в 1D, `opts.quiet_zone` — и в QR,
+/// `opts.progress` — в пайплайн. Для вызывающих, которым нужны сами
+/// [`DecodedSymbol`] (уверенность, углы); отбор прочтений — [`format_requested`].
+/// Служебное: общее у [`decode_formats`] и `scan_pgm --csv`.
+#[doc(hidden)]
+#[must_use]
+pub fn pipeline_for_formats(opts: DecodeOptions, formats: &[BarcodeFormat]) -> PipelineBuilder {
+    let has = |f: BarcodeFormat| formats.contains(&f);
+    let progress = opts.progress.clone();
+    let quiet_zone = opts.quiet_zone;
+    let mut builder = PipelineBuilder::new()
+        .enable_ean13_upca(has(BarcodeFormat::EAN13) || has(BarcodeFormat::UPCA))
+        .enable_code128(has(BarcodeFormat::Code128))
+        .enable_code93(has(BarcodeFormat::Code93))
+        .enable_msi(has(BarcodeFormat::Msi))
+        .enable_qr(has(BarcodeFormat::QR))
+        .enable_aztec(has(BarcodeFormat::Aztec))
+        .one_d_options(opts)
+        .quiet_zone(quiet_zone);
+    if let Some(p) = progress {
+        builder = builder.progress(move |done, total| p.report(done, total));
+    }
+    builder
+}
 
-    let owned: LumaImage = img.into();
+/// Оставлять ли прочтение формата `format` при запрошенных `formats`.
+/// EAN-13 и UPC-A включаются одним флагом: UPC-A приходит и при `EAN13`,
+/// а EAN-13 отбрасывается, если запрошен только `UPCA`. Служебное, как и
+/// [`pipeline_for_formats`].
+#[doc(hidden)]
+#[must_use]
+pub fn format_requested(format: BarcodeFormat, formats: &[BarcodeFormat]) -> bool {
+    format != BarcodeFormat::EAN13 || formats.contains(&BarcodeFormat::EAN13)
+}
+
+/// Как [`decode_any`], но запускаются только декодеры из `formats`.
+/// EAN-13 и UPC-A читает один декодер: UPC-A — частный случай EAN-13 (с ведущим
+/// `0`), поэтому с `EAN13` приходят и UPC-A (с меткой `UPCA`), а при одном `UPCA`
+/// прочтения EAN-13 отбрасываются.
+#[must_use]
+pub fn decode_formats(
+    img: GrayImage<'_>,
+    opts: DecodeOptions,
+    formats: &[BarcodeFormat],
+) -> Vec<Barcode> {
+    let pipeline = pipeline_for_formats(opts, formats).build();
 
-    let decoded: Vec<DecodedSymbol> = pipeline.decode_all(&owned);
+    let decoded: Vec<DecodedSymbol> = pipeline.decode_all_gray(&img);
 
     let mut out = Vec::with_capacity(decoded.len());
     for s in decoded {
-        let format = match s.symbology {
-            Symbology::Code128 => BarcodeFormat::Code128,
-            Symbology::Ean13 => BarcodeFormat::EAN13,
-            Symbology::QR => BarcodeFormat::QR,
-        };
+        let format = BarcodeFormat::from(s.symbology);
+        if !format_requested(format, formats) {
+            continue;
+        }
 
         // Попробуем вытащить y-координату строки, если она была положена в extras (для 1D).
-        let row = s
-            .extras
-            .properties
-            .get("row")
-            .and_then(|v| v.parse::<usize>().ok())
-            .unwrap_or(0);
+        let row = s.extras.get_parsed::<usize>("row").unwrap_or(0);
+
+        let recovered = s.extras.get_str("ean.parity_recovered").is_some();
+        let quiet_zone_missing = s.extras.get_str("quiet_zone") == Some("missing");
+        let checksum_failed = s.extras.get_str("checksum") == Some("failed");
+        let modules = s.extras.get_parsed::<usize>("modules").unwrap_or(0);
+        let module_px = s.extras.get_parsed::<f32>("module_px").unwrap_or(0.0);
 
         out.push(Barcode {
             format,
             text: s.text,
             row,
+            recovered,
+            quiet_zone_missing,
+            checksum_failed,
+            modules,
+            width_px: f32_to_usize((module_px * usize_to_f32(modules)).round()),
+            x: s.extras.get_parsed::<usize>("x_start").unwrap_or(0),
+            reversed: s.orientation == Some(Orientation::Rot180),
+            #[cfg(feature = "debug-capture")]
+            debug: scan_debug(&s.extras),
         });
     }
     out
 }
+
+/// [`ScanDebug`](crate::one_d::ScanDebug) обратно из extras `debug.*` (см. `symbol_from_barcode`).
+#[cfg(feature = "debug-capture")]
+fn scan_debug(extras: &crate::prelude::DecodedExtras) -> Option<crate::one_d::ScanDebug> {
+    let scan_row = extras.get_parsed::<usize>("debug.scan_row")?;
+    let runs = extras
+        .get_str("debug.runs")?
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<_, _>>()
+        .ok()?;
+    Some(crate::one_d::ScanDebug { scan_row, runs })
+}
@@ -0,0 +1,44 @@
+// src/core/cast.rs
+//
+// Преобразования между пиксельными счётчиками (`usize`) и координатами (`f32`).
+// Каждое `as` между ними — предупреждение clippy (pedantic); здесь оно одно на
+// направление, а поведение на краях записано явно.
+
+/// Счётчик, длина или координата в пикселях → `f32`. Целые до 2^24 представимы
+/// точно — кадров такого размера не бывает.
+#[inline]
+#[allow(clippy::cast_precision_loss)]
+pub(crate) fn usize_to_f32(n: usize) -> f32 {
+    n as f32
+}
+
+/// `f32` → `usize` с отбрасыванием дробной части: отрицательные и NaN дают 0,
+/// слишком большие — `usize::MAX`.
+#[inline]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub(crate) fn f32_to_usize(x: f32) -> usize {
+    x as usize
+}
+
+/// `f32` → `i32` с отбрасыванием дробной части и насыщением; NaN даёт 0.
+#[inline]
+#[allow(clippy::cast_possible_truncation)]
+pub(crate) fn f32_to_i32(x: f32) -> i32 {
+    x as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn float_to_int_saturates_at_the_edges() {
+        assert_eq!(f32_to_usize(-3.7), 0);
+        assert_eq!(f32_to_usize(f32::NAN), 0);
+        assert_eq!(f32_to_usize(41.9), 41);
+        assert_eq!(f32_to_usize(f32::INFINITY), usize::MAX);
+        assert_eq!(f32_to_i32(-3.7), -3);
+        assert_eq!(f32_to_i32(1e12), i32::MAX);
+        assert_eq!(usize_to_f32(1 << 20).to_bits(), 1_048_576f32.to_bits());
+    }
+}
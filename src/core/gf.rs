@@ -0,0 +1,636 @@
+// src/core/gf.rs
+//! Конечные поля и Reed–Solomon над ними.
+//!
+//! [`GaloisField`] — GF(2^m) с произвольным примитивным полиномом: QR
+//! (GF(256)/0x11D, см. [`crate::qr::rs`]), Aztec (GF(16), GF(64), GF(256)/0x12D).
+//! [`ReedSolomon`] параметризован полем через трейт [`Field`], так что код
+//! над простым полем (PDF417: GF(929)) ляжет на тот же декодер.
+//!
+//! Соглашения:
+//! - многочлены внутри — по ВОЗРАСТАНИЮ степени (`p[i] == coef(x^i)`);
+//! - кодворды — high-degree-first (индекс 0 — старшая степень);
+//! - корни генератора `α^1..α^{ec_len}`, `α` — [`Field::alpha`].
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Почему [`ReedSolomon::correct`] не смог исправить блок.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RsError {
+    /// Длина блока не подходит коду: нет слов коррекции, одна коррекция без данных
+    /// или блок длиннее `size() − 1` слов.
+    InvalidLength,
+    /// Степень локатора ошибок больше `ec_len / 2` — ошибок больше, чем код исправляет.
+    TooManyErrors,
+    /// Локатор не раскладывается на корни по позициям блока (поиск Чиена нашёл
+    /// меньше корней, чем его степень, или корень кратный) — ошибки вне блока
+    /// или их больше, чем кажется.
+    NoErrorLocations,
+    /// После исправления синдромы не обнулились.
+    ResidualSyndrome,
+}
+
+impl RsError {
+    /// Короткое имя для логов и extras (`qr.rs_error`).
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RsError::InvalidLength => "invalid_length",
+            RsError::TooManyErrors => "too_many_errors",
+            RsError::NoErrorLocations => "no_error_locations",
+            RsError::ResidualSyndrome => "residual_syndrome",
+        }
+    }
+}
+
+/// Конечное поле для [`ReedSolomon`]: элементы — `u16` в `0..size()`.
+/// Для GF(2^m) сложение и вычитание — XOR; для простых полей — по модулю.
+pub trait Field: Copy {
+    /// Число элементов поля.
+    fn size(self) -> usize;
+    /// Примитивный элемент `α`.
+    fn alpha(self) -> u16;
+    fn add(self, a: u16, b: u16) -> u16;
+    /// `−a`.
+    fn neg(self, a: u16) -> u16;
+    fn mul(self, a: u16, b: u16) -> u16;
+    /// `a^e`; отрицательная степень — через порядок мультипликативной группы.
+    fn pow(self, a: u16, e: i32) -> u16;
+    fn inv(self, a: u16) -> u16;
+    /// `k·a` — сумма `k` копий `a` (для формальной производной).
+    fn times(self, a: u16, k: usize) -> u16;
+
+    #[inline]
+    fn sub(self, a: u16, b: u16) -> u16 {
+        self.add(a, self.neg(b))
+    }
+}
+
+/// GF(2^`bits`) по примитивному полиному `prim` (со старшим битом, например 0x13 = x^4+x+1).
+/// Элементы — `u16` в `0..2^bits`, `α = 2`. Умножение — сдвигами, без таблиц.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GaloisField {
+    bits: u8,
+    prim: u16,
+}
+
+impl GaloisField {
+    /// Поле степени `bits` (2..=15) по полиному `prim` степени `bits`.
+    #[must_use]
+    pub const fn new(bits: u8, prim: u16) -> Self {
+        Self { bits, prim }
+    }
+
+    /// Число элементов поля, `2^bits`.
+    #[inline]
+    #[must_use]
+    pub fn size(self) -> usize {
+        1usize << self.bits
+    }
+
+    /// Порядок мультипликативной группы, `2^bits − 1`.
+    #[inline]
+    fn order(self) -> i32 {
+        (1i32 << self.bits) - 1
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn mul(self, a: u16, b: u16) -> u16 {
+        let top = 1u16 << self.bits;
+        let mut aa = a;
+        let mut bb = b;
+        let mut r = 0u16;
+        while bb != 0 {
+            if (bb & 1) != 0 {
+                r ^= aa;
+            }
+            aa <<= 1;
+            if (aa & top) != 0 {
+                aa ^= self.prim;
+            }
+            bb >>= 1;
+        }
+        r
+    }
+
+    /// `a^e`; отрицательная степень — через порядок группы.
+    #[must_use]
+    pub fn pow(self, a: u16, e: i32) -> u16 {
+        if e == 0 {
+            return 1;
+        }
+        if a == 0 {
+            return 0;
+        }
+        let mut exp = e.rem_euclid(self.order());
+        let mut base = a;
+        let mut acc = 1u16;
+        while exp > 0 {
+            if (exp & 1) != 0 {
+                acc = self.mul(acc, base);
+            }
+            base = self.mul(base, base);
+            exp >>= 1;
+        }
+        acc
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn inv(self, a: u16) -> u16 {
+        debug_assert!(a != 0);
+        self.pow(a, self.order() - 1)
+    }
+}
+
+impl Field for GaloisField {
+    #[inline]
+    fn size(self) -> usize {
+        GaloisField::size(self)
+    }
+    #[inline]
+    fn alpha(self) -> u16 {
+        2
+    }
+    #[inline]
+    fn add(self, a: u16, b: u16) -> u16 {
+        a ^ b
+    }
+    #[inline]
+    fn neg(self, a: u16) -> u16 {
+        a
+    }
+    #[inline]
+    fn mul(self, a: u16, b: u16) -> u16 {
+        GaloisField::mul(self, a, b)
+    }
+    #[inline]
+    fn pow(self, a: u16, e: i32) -> u16 {
+        GaloisField::pow(self, a, e)
+    }
+    #[inline]
+    fn inv(self, a: u16) -> u16 {
+        GaloisField::inv(self, a)
+    }
+    #[inline]
+    fn times(self, a: u16, k: usize) -> u16 {
+        // характеристика 2: чётное число копий гасится
+        if k % 2 == 1 {
+            a
+        } else {
+            0
+        }
+    }
+}
+
+/// Reed–Solomon (узкий смысл, корни `α^1..α^{ec_len}`) над полем `F`:
+/// систематическое кодирование и исправление ошибок (Берлекэмп–Мэсси, Чиен, Форни).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReedSolomon<F> {
+    field: F,
+}
+
+impl<F: Field> ReedSolomon<F> {
+    pub const fn new(field: F) -> Self {
+        Self { field }
+    }
+
+    #[inline]
+    pub fn field(&self) -> F {
+        self.field
+    }
+
+    /// ECC для `data` (систематический RS): `ec_len` слов, дописываемых в конец `data`.
+    pub fn encode(&self, data: &[u16], ec_len: usize) -> Vec<u16> {
+        let f = self.field;
+        if ec_len == 0 {
+            return Vec::new();
+        }
+        let mut g_rev = self.generator_poly(ec_len);
+        g_rev.reverse(); // high-degree-first, g_rev[0] == 1
+
+        // M(x)·x^{ec_len} mod g(x); кодворд — M(x)·x^{ec_len} − остаток.
+        let mut rem: Vec<u16> = data.to_vec();
+        rem.resize(data.len() + ec_len, 0);
+        for i in 0..data.len() {
+            let coef = rem[i];
+            if coef != 0 {
+                for (j, &g) in g_rev.iter().enumerate() {
+                    rem[i + j] = f.sub(rem[i + j], f.mul(coef, g));
+                }
+            }
+        }
+        rem.split_off(data.len())
+            .into_iter()
+            .map(|r| f.neg(r))
+            .collect()
+    }
+
+    /// Исправить ошибки в блоке `codewords` (данные + `ec_len` слов коррекции) на месте.
+    /// `Ok(число исправленных слов)`.
+    ///
+    /// # Errors
+    ///
+    /// [`RsError`] — неверная длина блока или ошибок больше, чем исправимо; блок
+    /// при этом может остаться частично изменённым.
+    pub fn correct(&self, codewords: &mut [u16], ec_len: usize) -> Result<usize, RsError> {
+        timed!("rs_correct", self.correct_block(codewords, ec_len))
+    }
+
+    fn correct_block(&self, codewords: &mut [u16], ec_len: usize) -> Result<usize, RsError> {
+        let f = self.field;
+        let n = codewords.len();
+        if ec_len == 0 || ec_len >= n || n > f.size() - 1 {
+            return Err(RsError::InvalidLength);
+        }
+
+        let synd = self.syndromes(codewords, ec_len);
+        if synd.iter().all(|&s| s == 0) {
+            return Ok(0);
+        }
+
+        let (sigma, omega) = self.berlekamp_massey(&synd);
+        let errors = sigma.len() - 1;
+        if errors > ec_len / 2 {
+            return Err(RsError::TooManyErrors);
+        }
+
+        // Чиен: правые индексы i (0 — последний кодворд), где σ(α^{-i}) = 0.
+        let err_pos: Vec<usize> = (0..n)
+            .filter(|&i| self.poly_eval(&sigma, f.pow(f.alpha(), -exponent(i))) == 0)
+            .collect();
+        if err_pos.len() != errors {
+            return Err(RsError::NoErrorLocations);
+        }
+
+        // Форни: ошибка e = −ω(X⁻¹)/σ'(X⁻¹), исправленное слово — r − e.
+        let sigma_der = self.poly_derivative(&sigma);
+        for &i in &err_pos {
+            let x_inv = f.pow(f.alpha(), -exponent(i));
+            let den = self.poly_eval(&sigma_der, x_inv);
+            if den == 0 {
+                return Err(RsError::NoErrorLocations);
+            }
+            let e = f.mul(self.poly_eval(&omega, x_inv), f.inv(den));
+            codewords[n - 1 - i] = f.add(codewords[n - 1 - i], e);
+        }
+
+        if self.syndromes(codewords, ec_len).iter().any(|&s| s != 0) {
+            return Err(RsError::ResidualSyndrome);
+        }
+        Ok(errors)
+    }
+
+    // ---------------- poly helpers (ascending-degree representation) ----------------
+    //
+    // Нулевой многочлен всегда `[0]`; пустой срез на входе трактуется как ноль.
+
+    fn poly_add(&self, a: &[u16], b: &[u16]) -> Vec<u16> {
+        let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+        let mut out = long.to_vec();
+        for (o, &c) in out.iter_mut().zip(short) {
+            *o = self.field.add(*o, c);
+        }
+        trim_high_zeros(&mut out);
+        out
+    }
+
+    fn poly_scale(&self, p: &[u16], s: u16) -> Vec<u16> {
+        if s == 0 || p.is_empty() {
+            return vec![0];
+        }
+        let mut out: Vec<u16> = p.iter().map(|&c| self.field.mul(c, s)).collect();
+        trim_high_zeros(&mut out);
+        out
+    }
+
+    fn poly_mul(&self, a: &[u16], b: &[u16]) -> Vec<u16> {
+        if a.iter().all(|&c| c == 0) || b.iter().all(|&c| c == 0) {
+            return vec![0];
+        }
+        let mut out = vec![0u16; a.len() + b.len() - 1];
+        for (i, &ai) in a.iter().enumerate() {
+            if ai == 0 {
+                continue;
+            }
+            for (j, &bj) in b.iter().enumerate() {
+                out[i + j] = self.field.add(out[i + j], self.field.mul(ai, bj));
+            }
+        }
+        trim_high_zeros(&mut out);
+        out
+    }
+
+    /// Формальная производная: `d/dx x^k = k·x^{k-1}` (в GF(2^m) остаются нечётные `k`).
+    fn poly_derivative(&self, p: &[u16]) -> Vec<u16> {
+        if p.len() <= 1 {
+            return vec![0];
+        }
+        let mut out: Vec<u16> = p
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(k, &c)| self.field.times(c, k))
+            .collect();
+        trim_high_zeros(&mut out);
+        out
+    }
+
+    fn poly_eval(&self, p: &[u16], x: u16) -> u16 {
+        p.iter()
+            .rev()
+            .fold(0, |y, &coef| self.field.add(self.field.mul(y, x), coef))
+    }
+
+    /// Генератор степени `ec_len` `∏(x − α^i)`, ascending, со старшей 1.
+    fn generator_poly(&self, ec_len: usize) -> Vec<u16> {
+        let f = self.field;
+        let mut g = vec![1u16];
+        for i in 1..=ec_len {
+            g = self.poly_mul(&g, &[f.neg(f.pow(f.alpha(), exponent(i))), 1]);
+        }
+        g
+    }
+
+    // ---------------- internal: syndromes, BM ----------------
+
+    /// Синдромы `S_k = C(α^k)`, `k = 1..=ec_len`.
+    fn syndromes(&self, codewords: &[u16], ec_len: usize) -> Vec<u16> {
+        let f = self.field;
+        (1..=ec_len)
+            .map(|k| {
+                let a_k = f.pow(f.alpha(), exponent(k));
+                codewords
+                    .iter()
+                    .fold(0, |acc, &c| f.add(f.mul(acc, a_k), c))
+            })
+            .collect()
+    }
+
+    /// Берлекэмп–Мэсси: (σ(x), ω(x)) по возрастанию степени, σ[0] = 1.
+    fn berlekamp_massey(&self, synd: &[u16]) -> (Vec<u16>, Vec<u16>) {
+        let f = self.field;
+        let mut sigma = vec![1u16];
+        let mut b = vec![1u16];
+        let mut l = 0usize;
+        let mut m = 1usize;
+
+        for n in 0..synd.len() {
+            let mut delta = synd[n];
+            for i in 1..=l.min(sigma.len() - 1) {
+                delta = f.add(delta, f.mul(sigma[i], synd[n - i]));
+            }
+            if delta == 0 {
+                m += 1;
+                continue;
+            }
+            let t = sigma.clone();
+            // σ − δ·x^m·B
+            let mut upd = vec![0u16; m];
+            upd.extend(self.poly_scale(&b, f.neg(delta)));
+            sigma = self.poly_add(&sigma, &upd);
+            if 2 * l <= n {
+                l = n + 1 - l;
+                b = self.poly_scale(&t, f.inv(delta));
+                m = 1;
+            } else {
+                m += 1;
+            }
+        }
+
+        let mut omega = self.poly_mul(&sigma, synd);
+        omega.truncate(l.max(1));
+        trim_high_zeros(&mut omega);
+        (sigma, omega)
+    }
+}
+
+/// Индекс или степень как показатель для [`Field::pow`]: они не больше размера
+/// поля (`correct_block` это проверяет), так что в `i32` помещаются.
+fn exponent(i: usize) -> i32 {
+    i32::try_from(i).unwrap_or(i32::MAX)
+}
+
+/// Нулевой многочлен — `[0]`; старшие нули срезаются.
+fn trim_high_zeros(v: &mut Vec<u16>) {
+    while v.len() > 1 && v.last() == Some(&0) {
+        v.pop();
+    }
+    if v.is_empty() {
+        v.push(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIELDS: [GaloisField; 4] = [
+        GaloisField::new(4, 0x13),
+        GaloisField::new(6, 0x43),
+        GaloisField::new(8, 0x12D),
+        GaloisField::new(8, 0x11D),
+    ];
+
+    /// GF(929) с `α = 3` — поле PDF417: проверяет, что декодер не опирается на XOR.
+    #[derive(Clone, Copy, Debug)]
+    struct Prime929;
+
+    impl Field for Prime929 {
+        fn size(self) -> usize {
+            929
+        }
+        fn alpha(self) -> u16 {
+            3
+        }
+        fn add(self, a: u16, b: u16) -> u16 {
+            (a + b) % 929
+        }
+        fn neg(self, a: u16) -> u16 {
+            (929 - a) % 929
+        }
+        fn mul(self, a: u16, b: u16) -> u16 {
+            (u32::from(a) * u32::from(b) % 929) as u16
+        }
+        fn pow(self, a: u16, e: i32) -> u16 {
+            let mut exp = e.rem_euclid(928);
+            let (mut base, mut acc) = (a, 1u16);
+            while exp > 0 {
+                if exp & 1 == 1 {
+                    acc = self.mul(acc, base);
+                }
+                base = self.mul(base, base);
+                exp >>= 1;
+            }
+            acc
+        }
+        fn inv(self, a: u16) -> u16 {
+            self.pow(a, 927)
+        }
+        fn times(self, a: u16, k: usize) -> u16 {
+            (u32::from(a) * (k % 929) as u32 % 929) as u16
+        }
+    }
+
+    #[test]
+    fn alpha_generates_whole_field() {
+        for gf in FIELDS {
+            let order = gf.size() - 1;
+            let mut seen = vec![false; gf.size()];
+            for e in 0..order {
+                seen[usize::from(gf.pow(2, e as i32))] = true;
+            }
+            assert_eq!(seen.iter().filter(|&&s| s).count(), order, "{gf:?}");
+            for a in 1..gf.size() as u16 {
+                assert_eq!(gf.mul(a, gf.inv(a)), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn corrects_up_to_half_of_ec_words() {
+        for gf in FIELDS {
+            let rs = ReedSolomon::new(gf);
+            let max = gf.size() as u16 - 1;
+            let data: Vec<u16> = (0..6u16).map(|i| (i * 7 + 3) % max + 1).collect();
+            let ec_len = 6;
+            let mut cw = data.clone();
+            cw.extend(rs.encode(&data, ec_len));
+            assert_eq!(rs.correct(&mut cw.clone(), ec_len), Ok(0));
+
+            let mut bad = cw.clone();
+            bad[0] ^= 1;
+            bad[4] ^= max;
+            bad[10] ^= 5 % max;
+            assert_eq!(rs.correct(&mut bad, ec_len), Ok(3), "{gf:?}");
+            assert_eq!(bad, cw);
+
+            let mut hopeless = cw.clone();
+            for w in &mut hopeless[..5] {
+                *w ^= 1;
+            }
+            assert_eq!(
+                rs.correct(&mut hopeless, ec_len),
+                Err(RsError::NoErrorLocations),
+                "{gf:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn correction_failures_report_reason() {
+        let rs = ReedSolomon::new(GaloisField::new(8, 0x11D));
+        let data: Vec<u16> = (0..19u16).map(|i| i * 13 + 1).collect();
+        let mut cw = data.clone();
+        cw.extend(rs.encode(&data, 7));
+
+        assert_eq!(rs.correct(&mut cw.clone(), 0), Err(RsError::InvalidLength));
+        assert_eq!(
+            rs.correct(&mut cw[..7].to_vec(), 7),
+            Err(RsError::InvalidLength)
+        );
+        assert_eq!(
+            rs.correct(&mut vec![0; 300], 7),
+            Err(RsError::InvalidLength)
+        );
+
+        let mut hopeless = cw.clone();
+        for (w, delta) in hopeless.iter_mut().step_by(3).zip([1, 38, 75, 112, 149]) {
+            *w ^= delta;
+        }
+        assert_eq!(rs.correct(&mut hopeless, 7), Err(RsError::TooManyErrors));
+
+        // Синдромы одиночной ошибки в позиции 36 — за пределами блока из 26 слов:
+        // `encode` от x^{29} даёт x^{36} mod g. Локатор степени 1, корня в блоке нет.
+        let mut unit = vec![0u16; 30];
+        unit[0] = 1;
+        let mut outside = cw.clone();
+        for (w, r) in outside[19..].iter_mut().zip(rs.encode(&unit, 7)) {
+            *w ^= r;
+        }
+        assert_eq!(rs.correct(&mut outside, 7), Err(RsError::NoErrorLocations));
+    }
+
+    #[test]
+    fn prime_field_code_corrects_errors() {
+        let rs = ReedSolomon::new(Prime929);
+        let data: Vec<u16> = (0..20u16).map(|i| (i * 131 + 7) % 929).collect();
+        let ec_len = 8;
+        let mut cw = data.clone();
+        cw.extend(rs.encode(&data, ec_len));
+        assert_eq!(rs.correct(&mut cw.clone(), ec_len), Ok(0));
+
+        let mut bad = cw.clone();
+        for (at, delta) in [(0, 1), (9, 500), (17, 928), (27, 42)] {
+            bad[at] = (bad[at] + delta) % 929;
+        }
+        assert_eq!(rs.correct(&mut bad, ec_len), Ok(4));
+        assert_eq!(bad, cw);
+    }
+
+    #[test]
+    fn gf_mul_reduces_by_0x11d() {
+        let gf = GaloisField::new(8, 0x11D);
+        assert_eq!(gf.mul(0x80, 2), 0x1D);
+        assert_eq!(gf.mul(0x53, 0xCA), 0x8F);
+        assert_eq!(gf.mul(gf.inv(0x53), 0x53), 1);
+    }
+
+    #[test]
+    fn poly_add_aligns_by_degree() {
+        let rs = ReedSolomon::new(GaloisField::new(8, 0x11D));
+        // (1 + 2x + 3x^2) + (1 + 2x + 3x^2) = 0
+        assert_eq!(rs.poly_add(&[1, 2, 3], &[1, 2, 3]), vec![0]);
+        // короткий + длинный и наоборот: старшие члены длинного сохраняются
+        assert_eq!(rs.poly_add(&[7], &[1, 0, 5]), vec![6, 0, 5]);
+        assert_eq!(rs.poly_add(&[1, 0, 5], &[7]), vec![6, 0, 5]);
+        assert_eq!(rs.poly_add(&[1, 2], &[4, 8, 16, 32]), vec![5, 10, 16, 32]);
+        // сокращение старших членов
+        assert_eq!(rs.poly_add(&[5, 7, 9], &[1, 7, 9]), vec![4]);
+        // пустые/нулевые
+        assert_eq!(rs.poly_add(&[], &[]), vec![0]);
+        assert_eq!(rs.poly_add(&[], &[3, 4]), vec![3, 4]);
+        assert_eq!(rs.poly_add(&[0], &[0, 0]), vec![0]);
+    }
+
+    #[test]
+    fn poly_mul_known_products() {
+        let rs = ReedSolomon::new(GaloisField::new(8, 0x11D));
+        // (2 + x)(4 + x) = 8 + 6x + x^2
+        assert_eq!(rs.poly_mul(&[2, 1], &[4, 1]), vec![8, 6, 1]);
+        // (α + x)(α^2 + x)(α^3 + x) = α^6 + (α^3+α^4+α^5)x + (α+α^2+α^3)x^2 + x^3
+        let g = rs.poly_mul(&rs.poly_mul(&[2, 1], &[4, 1]), &[8, 1]);
+        assert_eq!(g, vec![64, 56, 14, 1]);
+        assert_eq!(rs.generator_poly(3), g);
+        // с редукцией: 0x80 · 2 = 0x1D
+        assert_eq!(rs.poly_mul(&[0x80], &[0, 2]), vec![0, 0x1D]);
+        // единица, ноль, пустой
+        assert_eq!(rs.poly_mul(&[1], &[9, 8, 7]), vec![9, 8, 7]);
+        assert_eq!(rs.poly_mul(&[0], &[9, 8, 7]), vec![0]);
+        assert_eq!(rs.poly_mul(&[], &[9, 8, 7]), vec![0]);
+        assert_eq!(rs.poly_mul(&[], &[]), vec![0]);
+        assert_eq!(rs.poly_mul(&[0, 0, 3], &[5]), vec![0, 0, 15]);
+    }
+
+    #[test]
+    fn poly_scale_and_derivative() {
+        let rs = ReedSolomon::new(GaloisField::new(8, 0x11D));
+        assert_eq!(rs.poly_scale(&[1, 0x80], 2), vec![2, 0x1D]);
+        assert_eq!(rs.poly_scale(&[1, 2, 3], 0), vec![0]);
+        assert_eq!(rs.poly_scale(&[], 5), vec![0]);
+        assert_eq!(rs.poly_scale(&[7], 1), vec![7]);
+
+        // d/dx (a0 + a1 x + a2 x^2 + a3 x^3) = a1 + a3 x^2 (в GF(2) чётные члены гаснут)
+        assert_eq!(rs.poly_derivative(&[9, 8, 7, 6]), vec![8, 0, 6]);
+        assert_eq!(rs.poly_derivative(&[1, 0, 1]), vec![0]);
+        assert_eq!(rs.poly_derivative(&[7]), vec![0]);
+        assert_eq!(rs.poly_derivative(&[]), vec![0]);
+        assert_eq!(rs.poly_derivative(&[3, 5]), vec![5]);
+        // в простом поле коэффициент умножается на степень: 3·6 = 18
+        assert_eq!(
+            ReedSolomon::new(Prime929).poly_derivative(&[9, 8, 7, 6]),
+            vec![8, 14, 18]
+        );
+    }
+}
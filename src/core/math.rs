@@ -0,0 +1,111 @@
+// src/core/math.rs
+//
+// Замена `f32::{floor, ceil, round, sqrt}` для сборки без `std` (в `core` их нет).
+// С `std` методы берутся из стандартной библиотеки, а модуль компилируется только
+// для тестов. Точности хватает для пиксельных координат и яркостей.
+
+/// Числа с модулем от 2^23 у f32 уже целые.
+const F32_INT_LIMIT: f32 = 8_388_608.0;
+
+/// Округление вниз.
+#[inline]
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)] // |x| < 2^23 проверено выше
+pub(crate) fn floor_f32(x: f32) -> f32 {
+    if x.is_nan() || x.abs() >= F32_INT_LIMIT {
+        return x;
+    }
+    let t = x as i32 as f32; // отсечение к нулю
+    if t > x {
+        t - 1.0
+    } else {
+        t
+    }
+}
+
+/// Округление вверх.
+#[inline]
+pub(crate) fn ceil_f32(x: f32) -> f32 {
+    -floor_f32(-x)
+}
+
+/// Округление к ближайшему, половина — от нуля (как `f32::round`).
+#[inline]
+pub(crate) fn round_f32(x: f32) -> f32 {
+    if x.is_nan() || x.abs() >= F32_INT_LIMIT {
+        return x;
+    }
+    if x >= 0.0 {
+        floor_f32(x + 0.5)
+    } else {
+        -floor_f32(-x + 0.5)
+    }
+}
+
+/// Квадратный корень: начальное приближение по битам экспоненты + Ньютон.
+#[inline]
+pub(crate) fn sqrt_f32(x: f32) -> f32 {
+    if x.is_nan() || x < 0.0 {
+        return f32::NAN;
+    }
+    if x == 0.0 || x.is_infinite() {
+        return x;
+    }
+    let mut y = f32::from_bits((x.to_bits() >> 1) + 0x1FC0_0000);
+    for _ in 0..4 {
+        y = 0.5 * (y + x / y);
+    }
+    y
+}
+
+/// Методы `floor`/`ceil`/`round`/`sqrt` для `f32` без `std`.
+/// Импортируется под `#[cfg(not(feature = "std"))]`, с `std` работают встроенные.
+#[allow(dead_code)]
+pub(crate) trait FloatExt {
+    fn floor(self) -> Self;
+    fn ceil(self) -> Self;
+    fn round(self) -> Self;
+    fn sqrt(self) -> Self;
+}
+
+impl FloatExt for f32 {
+    #[inline]
+    fn floor(self) -> f32 {
+        floor_f32(self)
+    }
+
+    #[inline]
+    fn ceil(self) -> f32 {
+        ceil_f32(self)
+    }
+
+    #[inline]
+    fn round(self) -> f32 {
+        round_f32(self)
+    }
+
+    #[inline]
+    fn sqrt(self) -> f32 {
+        sqrt_f32(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn matches_std_on_typical_values() {
+        for &x in &[
+            -3.5f32, -2.5, -1.2, -0.5, -0.0, 0.0, 0.49, 0.5, 1.5, 2.5, 17.99, 1e7,
+        ] {
+            assert_eq!(floor_f32(x), x.floor(), "floor({x})");
+            assert_eq!(ceil_f32(x), x.ceil(), "ceil({x})");
+            assert_eq!(round_f32(x), x.round(), "round({x})");
+        }
+        for &x in &[0.0f32, 1e-4, 0.25, 2.0, 16.0, 441.0, 12345.678] {
+            let rel = (sqrt_f32(x) - x.sqrt()).abs() / x.sqrt().max(1e-6);
+            assert!(rel < 1e-6, "sqrt({x}) rel err {rel}");
+        }
+    }
+}
@@ -1,4 +1,17 @@
 // src/core/mod.rs
 pub mod types;
 
+// Конечные поля (GF(2^m) с произвольным полиномом) и Reed–Solomon над ними (QR, Aztec).
+pub mod gf;
+
+// Разбор PGM P5 из байтов (загрузчики бинарников и тестов).
+pub mod pgm;
+
+// Преобразования usize/f32 без `as` по месту.
+pub(crate) mod cast;
+
+// Замена f32::{floor, round, sqrt} без std.
+#[cfg(any(not(feature = "std"), test))]
+pub(crate) mod math;
+
 pub use types::*;
@@ -0,0 +1,166 @@
+// src/core/pgm.rs
+//
+// Разбор PGM P5 (8 бит) из байтов: заголовок "P5 <width> <height> <maxval>"
+// (пробельные символы и комментарии `#` между полями), затем пиксели построчно.
+// Файловый I/O остаётся за вызывающим — модуль работает и без `std`.
+
+use crate::core::types::{GrayImage, LumaImage};
+
+/// Почему байты не разобрались как PGM (см. [`read_pgm`]).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PgmError {
+    /// Нет сигнатуры `P5` (текстовый `P2` и прочие форматы не поддерживаются).
+    BadMagic,
+    /// Поле заголовка (`"width"`, `"height"` или `"maxval"`) отсутствует или не число.
+    MissingField(&'static str),
+    /// Размер `width×height` переполняет `usize` или больше предела пикселей.
+    BadDimension { width: usize, height: usize },
+    /// Поддерживается только `maxval = 255` (8 бит на пиксель).
+    UnsupportedMaxval(usize),
+    /// Пикселей меньше `width·height`: `expected` байт, в файле `found`.
+    DataTooShort { expected: usize, found: usize },
+}
+
+impl core::fmt::Display for PgmError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match *self {
+            PgmError::BadMagic => f.write_str("PGM: поддерживается только P5 (binary)"),
+            PgmError::MissingField(field) => write!(f, "PGM: нет или неверное поле {field}"),
+            PgmError::BadDimension { width, height } => {
+                write!(
+                    f,
+                    "PGM: размер {width}x{height} переполняет или больше предела пикселей"
+                )
+            }
+            PgmError::UnsupportedMaxval(maxval) => {
+                write!(f, "PGM: maxval={maxval}, поддерживается только 255")
+            }
+            PgmError::DataTooShort { expected, found } => {
+                write!(f, "PGM: мало байтов данных ({found} из {expected})")
+            }
+        }
+    }
+}
+
+/// Кадр из байтов PGM P5; кадр больше `max_pixels` отвергается до выделения
+/// буфера (см. [`GrayImage::checked_len`]). Хвост после `width·height` байт
+/// пикселей отбрасывается.
+///
+/// # Errors
+///
+/// [`PgmError`] — что именно не так с заголовком или данными.
+pub fn read_pgm(bytes: &[u8], max_pixels: usize) -> Result<LumaImage, PgmError> {
+    let mut i = 0usize;
+    if next_token(bytes, &mut i) != Some(&b"P5"[..]) {
+        return Err(PgmError::BadMagic);
+    }
+    let width = read_field(bytes, &mut i, "width")?;
+    let height = read_field(bytes, &mut i, "height")?;
+    let maxval = read_field(bytes, &mut i, "maxval")?;
+    if maxval != 255 {
+        return Err(PgmError::UnsupportedMaxval(maxval));
+    }
+    // ровно один пробельный символ между maxval и пикселями
+    if bytes.get(i).is_some_and(u8::is_ascii_whitespace) {
+        i += 1;
+    }
+    let expected = GrayImage::checked_len(width, height, max_pixels)
+        .map_err(|_| PgmError::BadDimension { width, height })?;
+    let pixels = &bytes[i..];
+    if pixels.len() < expected {
+        return Err(PgmError::DataTooShort {
+            expected,
+            found: pixels.len(),
+        });
+    }
+    Ok(LumaImage {
+        data: pixels[..expected].to_vec(),
+        width,
+        height,
+    })
+}
+
+/// Числовое поле заголовка.
+fn read_field(bytes: &[u8], i: &mut usize, name: &'static str) -> Result<usize, PgmError> {
+    next_token(bytes, i)
+        .and_then(|t| core::str::from_utf8(t).ok())
+        .and_then(|t| t.parse().ok())
+        .ok_or(PgmError::MissingField(name))
+}
+
+/// Следующий токен заголовка: пропускаем пробельные символы и комментарии `#…\n`.
+fn next_token<'b>(bytes: &'b [u8], i: &mut usize) -> Option<&'b [u8]> {
+    while let Some(&c) = bytes.get(*i) {
+        if c == b'#' {
+            while bytes.get(*i).is_some_and(|&c| c != b'\n') {
+                *i += 1;
+            }
+        } else if c.is_ascii_whitespace() {
+            *i += 1;
+        } else {
+            break;
+        }
+    }
+    let start = *i;
+    while bytes.get(*i).is_some_and(|c| !c.is_ascii_whitespace()) {
+        *i += 1;
+    }
+    (*i > start).then(|| &bytes[start..*i])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pgm(header: &str, pixels: &[u8]) -> Vec<u8> {
+        let mut out = header.as_bytes().to_vec();
+        out.extend_from_slice(pixels);
+        out
+    }
+
+    #[test]
+    fn reads_header_with_comments_and_reports_each_failure() {
+        let max = GrayImage::DEFAULT_MAX_PIXELS;
+        let img = read_pgm(
+            &pgm("P5\n# сканер\n3 2\n255\n", &[0, 1, 2, 3, 4, 5, 9]),
+            max,
+        )
+        .expect("pgm");
+        assert_eq!(
+            (img.width, img.height, img.data.as_slice()),
+            (3, 2, &[0, 1, 2, 3, 4, 5][..])
+        );
+
+        assert_eq!(
+            read_pgm(b"P2\n3 2\n255\n", max).unwrap_err(),
+            PgmError::BadMagic
+        );
+        assert_eq!(read_pgm(b"", max).unwrap_err(), PgmError::BadMagic);
+        assert_eq!(
+            read_pgm(b"P5\n3", max).unwrap_err(),
+            PgmError::MissingField("height")
+        );
+        assert_eq!(
+            read_pgm(b"P5\nx 2\n255\n", max).unwrap_err(),
+            PgmError::MissingField("width")
+        );
+        assert_eq!(
+            read_pgm(b"P5\n3 2\n65535\n", max).unwrap_err(),
+            PgmError::UnsupportedMaxval(65535)
+        );
+        assert_eq!(
+            read_pgm(b"P5\n3 2\n255\n", 5).unwrap_err(),
+            PgmError::BadDimension {
+                width: 3,
+                height: 2
+            }
+        );
+        assert_eq!(
+            read_pgm(&pgm("P5\n3 2\n255\n", &[0; 4]), max).unwrap_err(),
+            PgmError::DataTooShort {
+                expected: 6,
+                found: 4
+            }
+        );
+    }
+}
@@ -2,10 +2,30 @@
 //
 // Общие типы и утилиты, независимые от конкретных декодеров.
 
-use std::collections::BTreeMap;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::str::FromStr;
+
+use crate::core::cast::usize_to_f32;
+#[cfg(not(feature = "std"))]
+use crate::core::math::FloatExt;
 
 /// Простое представление градаций серого.
 /// Буфер `data` — построчно, по строкам (row-major), 8 бит на пиксель.
+///
+/// Кадр только заимствует буфер: `GrayImage` поверх отображённого в память файла
+/// или чужого кадрового буфера проходит весь [`crate::api::Pipeline`] (1D, QR, Aztec,
+/// свои декодеры) через `*_gray`-методы без копии в [`LumaImage`]. Контракт:
+/// - строки идут подряд без отступов (шаг строки — ровно `width`), в `data`
+///   не меньше `width·height` байт — иначе декодеры паникуют на выходе за срез;
+///   [`GrayImage::from_slice`] проверяет это (и предел размера) при создании;
+/// - буфер не должен меняться, пока жив `GrayImage` и итераторы от него
+///   ([`crate::api::DecodeIter`] держит то же время жизни);
+/// - свои копии пайплайн делает только там, где без них нельзя: второй проход
+///   `auto_contrast`, выровненный кадр при `DecodeOptions::deskew` и фрагменты
+///   `decode_tiled`.
 #[derive(Clone, Copy, Debug)]
 pub struct GrayImage<'a> {
     pub data: &'a [u8],
@@ -14,7 +34,63 @@ pub struct GrayImage<'a> {
 }
 
 impl<'a> GrayImage<'a> {
+    /// Предел [`GrayImage::from_slice`]: 2²⁸ пикселей (`16384×16384`, 256 МБ) —
+    /// с запасом для любых снимков, но не даёт испорченному заголовку заказать
+    /// гигабайты.
+    pub const DEFAULT_MAX_PIXELS: usize = 1 << 28;
+
+    /// Число пикселей кадра `width×height`, если оно не переполняет `usize` и
+    /// не больше `max_pixels`. Загрузчики проверяют размеры из заголовка этим
+    /// до того, как выделять буфер.
+    ///
+    /// # Errors
+    ///
+    /// [`DecodeError::InvalidFormat`] при переполнении или превышении предела.
+    pub fn checked_len(
+        width: usize,
+        height: usize,
+        max_pixels: usize,
+    ) -> Result<usize, DecodeError> {
+        width
+            .checked_mul(height)
+            .filter(|&n| n <= max_pixels)
+            .ok_or(DecodeError::InvalidFormat)
+    }
+
+    /// Кадр `width×height` поверх `data` с проверкой размеров (предел —
+    /// [`GrayImage::DEFAULT_MAX_PIXELS`]); хвост буфера сверх `width·height`
+    /// отбрасывается.
+    ///
+    /// # Errors
+    ///
+    /// [`DecodeError::InvalidFormat`], если размер больше предела или `data`
+    /// короче `width·height`.
+    pub fn from_slice(data: &'a [u8], width: usize, height: usize) -> Result<Self, DecodeError> {
+        Self::from_slice_limited(data, width, height, Self::DEFAULT_MAX_PIXELS)
+    }
+
+    /// [`GrayImage::from_slice`] со своим пределом `max_pixels`.
+    ///
+    /// # Errors
+    ///
+    /// Как у [`GrayImage::from_slice`].
+    pub fn from_slice_limited(
+        data: &'a [u8],
+        width: usize,
+        height: usize,
+        max_pixels: usize,
+    ) -> Result<Self, DecodeError> {
+        let len = Self::checked_len(width, height, max_pixels)?;
+        let data = data.get(..len).ok_or(DecodeError::InvalidFormat)?;
+        Ok(Self {
+            data,
+            width,
+            height,
+        })
+    }
+
     #[inline]
+    #[must_use]
     pub fn row(&self, y: usize) -> &'a [u8] {
         let start = y * self.width;
         &self.data[start..start + self.width]
@@ -30,6 +106,69 @@ impl<'a> GrayImage<'a> {
         &buf[..]
     }
 
+    /// Растяжение гистограммы: 1-й и 99-й перцентили яркости уходят в 0 и 255,
+    /// всё между — линейно, хвосты обрезаются. Для тусклых/низкоконтрастных снимков,
+    /// на которых и глобальный, и адаптивный пороги проваливаются. Однотонное
+    /// изображение возвращается как есть.
+    #[must_use]
+    pub fn normalize_contrast(&self) -> LumaImage {
+        let mut hist = [0usize; 256];
+        for &v in self.data {
+            hist[usize::from(v)] += 1;
+        }
+        let n = self.data.len();
+        let clip = n / 100;
+        let percentile = |rank: usize| -> u8 {
+            let mut acc = 0usize;
+            for (v, &c) in (0..=255u8).zip(hist.iter()) {
+                acc += c;
+                if acc > rank {
+                    return v;
+                }
+            }
+            255
+        };
+        let lo = percentile(clip);
+        let hi = percentile(n.saturating_sub(clip + 1));
+        if hi <= lo {
+            return (*self).into();
+        }
+        let (lo, span) = (u32::from(lo), u32::from(hi) - u32::from(lo));
+        let lut: Vec<u8> = (0..=255u32)
+            .map(|v| (v.saturating_sub(lo) * 255 / span).min(255) as u8)
+            .collect();
+        LumaImage {
+            data: self.data.iter().map(|&v| lut[usize::from(v)]).collect(),
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    /// Яркостная сводка кадра за один проход: min, max, среднее и грубая гистограмма
+    /// (см. [`ImageStats`]). Пустой кадр — все нули.
+    #[must_use]
+    pub fn stats(&self) -> ImageStats {
+        let mut st = ImageStats {
+            min: 255,
+            max: 0,
+            mean: 0.0,
+            histogram: [0; ImageStats::BINS],
+        };
+        let mut sum = 0usize;
+        for &v in self.data {
+            st.min = st.min.min(v);
+            st.max = st.max.max(v);
+            sum += usize::from(v);
+            st.histogram[usize::from(v) / ImageStats::BIN_WIDTH] += 1;
+        }
+        if self.data.is_empty() {
+            st.min = 0;
+        } else {
+            st.mean = usize_to_f32(sum) / usize_to_f32(self.data.len());
+        }
+        st
+    }
+
     fn threshold_row_mean<'b>(&self, y: usize, window: usize, out: &'b mut Vec<u8>) -> &'b [u8] {
         let row = self.row(y);
         let w = self.width;
@@ -117,6 +256,7 @@ pub struct LumaImage {
 
 impl LumaImage {
     #[inline]
+    #[must_use]
     pub fn as_gray(&self) -> GrayImage<'_> {
         GrayImage {
             data: &self.data,
@@ -126,6 +266,7 @@ impl LumaImage {
     }
 
     #[inline]
+    #[must_use]
     pub fn row(&self, y: usize) -> &[u8] {
         let start = y * self.width;
         &self.data[start..start + self.width]
@@ -146,12 +287,71 @@ impl LumaImage {
         self.as_gray().threshold_row_mean(y, window, out)
     }
 
+    /// Яркостная сводка (см. [`GrayImage::stats`]).
+    #[inline]
+    #[must_use]
+    pub fn stats(&self) -> ImageStats {
+        self.as_gray().stats()
+    }
+
+    /// Растяжение гистограммы (см. [`GrayImage::normalize_contrast`]).
+    #[inline]
+    #[must_use]
+    pub fn normalize_contrast(&self) -> LumaImage {
+        self.as_gray().normalize_contrast()
+    }
+
+    /// Копия прямоугольника `w×h` с левым верхним углом (x, y); выход за границы обрезается.
+    #[must_use]
+    pub fn crop(&self, x: usize, y: usize, w: usize, h: usize) -> LumaImage {
+        let x0 = x.min(self.width);
+        let y0 = y.min(self.height);
+        let x1 = x.saturating_add(w).min(self.width);
+        let y1 = y.saturating_add(h).min(self.height);
+        let mut data = Vec::with_capacity((x1 - x0) * (y1 - y0));
+        for yy in y0..y1 {
+            data.extend_from_slice(&self.row(yy)[x0..x1]);
+        }
+        LumaImage {
+            data,
+            width: x1 - x0,
+            height: y1 - y0,
+        }
+    }
+
     #[inline]
     pub fn threshold_col_mean<'b>(&self, x: usize, window: usize, out: &'b mut Vec<u8>) -> &'b [u8] {
         self.as_gray().threshold_col_mean(x, window, out)
     }
 }
 
+/// Яркостная сводка кадра ([`GrayImage::stats`]): по ней решают, нужна ли
+/// предобработка (растяжение контраста), и разбираются, почему кадр не читается —
+/// например, узкий динамический диапазон.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageStats {
+    pub min: u8,
+    pub max: u8,
+    pub mean: f32,
+    /// Число пикселей по корзинам шириной [`ImageStats::BIN_WIDTH`] уровней:
+    /// яркость `v` попадает в `histogram[v / BIN_WIDTH]`.
+    pub histogram: [usize; ImageStats::BINS],
+}
+
+impl ImageStats {
+    /// Корзин в гистограмме.
+    pub const BINS: usize = 16;
+    /// Уровней яркости на корзину.
+    pub const BIN_WIDTH: usize = 256 / Self::BINS;
+
+    /// Динамический диапазон `max - min`; `0` — однотонный кадр.
+    #[inline]
+    #[must_use]
+    pub fn dynamic_range(&self) -> u8 {
+        self.max - self.min
+    }
+}
+
 /// Позволяем делать `.into()` из GrayImage в LumaImage (копия буфера).
 impl<'a> From<GrayImage<'a>> for LumaImage {
     #[inline]
@@ -171,6 +371,8 @@ pub struct Point {
     pub y: i32,
 }
 
+/// Четырёхугольник символа: углы по часовой стрелке, начиная с верхнего левого
+/// (для QR: p0 — угол у TL-finder'а, p1 — у TR, p2 — противоположный, p3 — у BL).
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub struct Quad {
     pub p0: Point,
@@ -179,6 +381,116 @@ pub struct Quad {
     pub p3: Point,
 }
 
+impl Quad {
+    /// Осевой ограничивающий прямоугольник: (min, max) включительно.
+    #[must_use]
+    pub fn bounding_box(&self) -> (Point, Point) {
+        let ps = [self.p0, self.p1, self.p2, self.p3];
+        let min = Point {
+            x: ps.iter().map(|p| p.x).min().unwrap_or(0),
+            y: ps.iter().map(|p| p.y).min().unwrap_or(0),
+        };
+        let max = Point {
+            x: ps.iter().map(|p| p.x).max().unwrap_or(0),
+            y: ps.iter().map(|p| p.y).max().unwrap_or(0),
+        };
+        (min, max)
+    }
+
+    /// Сдвиг всех углов на (dx, dy) — например, из координат фрагмента в глобальные.
+    #[must_use]
+    pub fn translated(self, dx: i32, dy: i32) -> Quad {
+        let t = |p: Point| Point {
+            x: p.x + dx,
+            y: p.y + dy,
+        };
+        Quad {
+            p0: t(self.p0),
+            p1: t(self.p1),
+            p2: t(self.p2),
+            p3: t(self.p3),
+        }
+    }
+}
+
+/// Точка с субпиксельной точностью (центры finder'ов, углы QR).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PointF {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl PointF {
+    #[inline]
+    #[must_use]
+    pub fn dist2(self, other: PointF) -> f32 {
+        let dx = self.x - other.x;
+        let dy = self.y - other.y;
+        dx * dx + dy * dy
+    }
+
+    /// В целочисленную точку (округление к ближайшему, с насыщением по i32).
+    #[inline]
+    #[allow(clippy::cast_possible_truncation)]
+    #[must_use]
+    pub fn to_point(self) -> Point {
+        Point {
+            x: self.x.round() as i32,
+            y: self.y.round() as i32,
+        }
+    }
+}
+
+impl From<Point> for PointF {
+    #[inline]
+    #[allow(clippy::cast_precision_loss)]
+    fn from(p: Point) -> Self {
+        Self {
+            x: p.x as f32,
+            y: p.y as f32,
+        }
+    }
+}
+
+/// `Quad` в f32: тот же порядок углов. Внутри декодеров геометрия живёт в `QuadF`,
+/// в `Quad` переводится только на границе API (в `DecodedSymbol`).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct QuadF {
+    pub p0: PointF,
+    pub p1: PointF,
+    pub p2: PointF,
+    pub p3: PointF,
+}
+
+impl QuadF {
+    /// Округлить углы до пикселей.
+    #[inline]
+    #[must_use]
+    pub fn to_quad(&self) -> Quad {
+        Quad {
+            p0: self.p0.to_point(),
+            p1: self.p1.to_point(),
+            p2: self.p2.to_point(),
+            p3: self.p3.to_point(),
+        }
+    }
+
+    /// Осевой ограничивающий прямоугольник: (min, max).
+    #[must_use]
+    pub fn bounding_box(&self) -> (PointF, PointF) {
+        let ps = [self.p0, self.p1, self.p2, self.p3];
+        let mut min = ps[0];
+        let mut max = ps[0];
+        for p in &ps[1..] {
+            min.x = min.x.min(p.x);
+            min.y = min.y.min(p.y);
+            max.x = max.x.max(p.x);
+            max.y = max.y.max(p.y);
+        }
+        (min, max)
+    }
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum Orientation {
     Rot0,
@@ -190,11 +502,132 @@ pub enum Orientation {
 }
 
 /// Тип распознанного символа.
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+///
+/// Взаимно однозначно соответствует `one_d::BarcodeFormat` (`From` в обе стороны).
+/// Список растёт с новыми символиками — в `match` нужна ветка `_`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+#[non_exhaustive]
 pub enum Symbology {
     QR,
     Code128,
+    Code93,
     Ean13,
+    /// UPC-A: EAN-13 с ведущим 0, отданный 12 цифрами
+    /// (см. `DecodeOptions::ean13_leading_zero_as_upca`).
+    UpcA,
+    Msi,
+    /// Aztec Code (пока только компактный, см. [`crate::two_d::aztec`]).
+    Aztec,
+    /// Своя симвология пользовательского декодера (см. [`crate::api::ImageDecoder`]);
+    /// строка — её имя.
+    Custom(&'static str),
+}
+
+impl Symbology {
+    /// Стандартная тихая зона символики, в модулях с каждой стороны — её рисуют
+    /// синтезаторы в тестах и демо (параметр `quiet_zone`). Aztec и свои
+    /// символики тихой зоны не требуют.
+    #[must_use]
+    pub const fn standard_quiet_zone(self) -> usize {
+        match self {
+            Symbology::QR => 4,
+            Symbology::Ean13 | Symbology::UpcA => 9,
+            Symbology::Code128 | Symbology::Code93 => 10,
+            Symbology::Msi => 12,
+            Symbology::Aztec | Symbology::Custom(_) => 0,
+        }
+    }
+}
+
+/// Почему символ нельзя синтезировать — общая ошибка синтезаторов всех
+/// символик (`one_d::*::synthesize_*`, [`crate::qr::encode`], [`crate::two_d::aztec`]).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SynthError {
+    /// Ширина модуля `unit` должна быть ≥ 1 px.
+    ZeroUnit,
+    /// EAN/UPC, MSI: не цифра `ch` на позиции `index` (в символах).
+    NonDigit { ch: char, index: usize },
+    /// Code 93: символ `ch` на позиции `index` (в символах) вне ASCII 0..127.
+    NonAscii { ch: char, index: usize },
+    /// Code128: символ `ch` на позиции `index` (в символах) не входит в набор
+    /// `set`: A — ASCII 0..95, B — ASCII 32..127, C — только цифры.
+    InvalidCharForSet { ch: char, index: usize, set: char },
+    /// Code128: набор C кодирует пары цифр — нужна чётная длина (передана `len`).
+    OddDigitCount(usize),
+    /// Code128: набор не 'A'/'B'/'C' (регистр не важен).
+    UnknownSet(char),
+    /// Code128: значение символа `value` на позиции `index` недопустимо — первым
+    /// идёт старт-код 103..=105, за ним значения 0..=102.
+    InvalidValue { value: u8, index: usize },
+    /// MSI: контрольная mod 11 для этих цифр равна 10 — такой код невалиден.
+    Mod11CheckIsTen,
+    /// EAN/UPC: неверное число цифр (передано `len`): EAN-13 — 12 или 13,
+    /// UPC-A — 11 или 12.
+    WrongLength(usize),
+    /// EAN/UPC: переданная контрольная цифра `found` не сходится с вычисленной `expected`.
+    CheckDigit { expected: u8, found: u8 },
+    /// QR: payload `len` байт не помещается в `capacity` байт уровня EC
+    /// (см. [`crate::qr::encode::v1_byte_capacity`]).
+    PayloadTooLong { len: usize, capacity: usize },
+    /// QR: маска вне 0..=7.
+    InvalidMask(u8),
+    /// QR: передано `len` data-кодвордов, а уровню EC нужно ровно `expected`.
+    DataCodewordCount { len: usize, expected: usize },
+    /// Aztec: пустой текст — символу нужен хотя бы один кодворд данных.
+    EmptyPayload,
+    /// Aztec: `len` байт не помещаются в компактный символ из
+    /// [`crate::two_d::aztec::MAX_COMPACT_LAYERS`] слоёв.
+    AztecOverflow { len: usize },
+}
+
+impl core::fmt::Display for SynthError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match *self {
+            SynthError::ZeroUnit => f.write_str("ширина модуля должна быть ≥ 1 px"),
+            SynthError::NonDigit { ch, index } => {
+                write!(f, "символ {ch:?} (позиция {index}) — не цифра")
+            }
+            SynthError::NonAscii { ch, index } => {
+                write!(f, "символ {ch:?} (позиция {index}) вне ASCII 0..127")
+            }
+            SynthError::InvalidCharForSet { ch, index, set } => {
+                write!(
+                    f,
+                    "символ {ch:?} (позиция {index}) не кодируется в наборе {set}"
+                )
+            }
+            SynthError::OddDigitCount(len) => write!(f, "набор C: нечётное число цифр ({len})"),
+            SynthError::UnknownSet(set) => {
+                write!(f, "неизвестный набор {set:?} (нужен A, B или C)")
+            }
+            SynthError::InvalidValue { value, index } => {
+                write!(
+                    f,
+                    "значение {value} (позиция {index}) недопустимо в Code128"
+                )
+            }
+            SynthError::Mod11CheckIsTen => f.write_str("контрольная mod 11 = 10, код невалиден"),
+            SynthError::WrongLength(len) => write!(f, "неверное число цифр ({len})"),
+            SynthError::CheckDigit { expected, found } => {
+                write!(f, "контрольная цифра {found}, ожидалась {expected}")
+            }
+            SynthError::PayloadTooLong { len, capacity } => {
+                write!(
+                    f,
+                    "{len} байт не помещаются в QR v1 (до {capacity} на этом уровне EC)"
+                )
+            }
+            SynthError::InvalidMask(m) => write!(f, "маска {m} вне 0..=7"),
+            SynthError::DataCodewordCount { len, expected } => {
+                write!(f, "{len} data-кодвордов, а уровню EC нужно {expected}")
+            }
+            SynthError::EmptyPayload => f.write_str("пустой текст не кодируется в Aztec"),
+            SynthError::AztecOverflow { len } => {
+                write!(f, "{len} байт не помещаются в компактный Aztec")
+            }
+        }
+    }
 }
 
 /// Ошибки распознавания верхнего уровня.
@@ -210,6 +643,76 @@ pub enum DecodeError {
     Internal(String),
 }
 
+/// Колбэк прогресса сканирования: `(сделано, всего)` шагов (строк или фрагментов).
+/// Вернуть `false` — остановить сканирование; уже найденное остаётся в результате.
+#[derive(Clone)]
+pub struct Progress(Arc<dyn Fn(usize, usize) -> bool + Send + Sync>);
+
+impl Progress {
+    pub fn new(f: impl Fn(usize, usize) -> bool + Send + Sync + 'static) -> Self {
+        Progress(Arc::new(f))
+    }
+
+    /// Сообщить о `done` из `total` шагов; `false` — просят остановиться.
+    #[inline]
+    pub(crate) fn report(&self, done: usize, total: usize) -> bool {
+        (self.0)(done, total)
+    }
+}
+
+impl core::fmt::Debug for Progress {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("Progress(..)")
+    }
+}
+
+/// Как относиться к тихим зонам — белым полям вокруг символа (1D и QR).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuietZone {
+    /// Символ без тихих зон не читается.
+    Strict,
+    /// Сначала ищем символ с тихими зонами; если такого нет, но остальная структура
+    /// верна — читаем, помечаем `extras["quiet_zone"] = "missing"` и снижаем уверенность.
+    Relaxed,
+    /// Тихие зоны не проверяются и не сообщаются.
+    Ignore,
+}
+
+/// Как относиться к несошедшейся контрольной сумме 1D-кода (EAN/UPC, Code128,
+/// Code 93, MSI). Прочтение без сошедшейся суммы помечается
+/// `extras["checksum"] = "failed"` и получает меньшую уверенность.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChecksumPolicy {
+    /// Код с несошедшейся контрольной суммой не читается.
+    #[default]
+    Require,
+    /// Сначала ищем прочтение с сошедшейся суммой (в обе стороны строки и по всем
+    /// строкам); если на том же месте строки (по x) такого нет, но остальная
+    /// структура верна — отдаём несошедшееся.
+    Prefer,
+    /// Сумма не влияет на выбор: отдаётся первое структурно верное прочтение
+    /// (для спасения цифр с повреждённых этикеток).
+    Ignore,
+}
+
+/// Какой бинаризацией 1D-декодеры (EAN/UPC, Code128, Code 93, MSI) делят строку
+/// на штрихи и пробелы. Субпиксельный путь (`subpixel_edges`) порог ищет сам
+/// и от настройки не зависит.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Binarization {
+    /// Адаптивный порог, а если run'ов на символ не набирается (засвет, слабый
+    /// контраст) — глобальный.
+    #[default]
+    Auto,
+    /// Только адаптивный порог (скользящее среднее): устойчив к неравномерной засветке.
+    Adaptive,
+    /// Только глобальный порог по всей строке: чище на ровном фоне.
+    Global,
+    /// Обе: прочтение берётся из той, где сошлась сумма (и есть тихие зоны), при
+    /// равенстве — из адаптивной. Строка бинаризуется дважды.
+    Both,
+}
+
 /// Дополнительная произвольная мета-информация о распознавании.
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct DecodedExtras {
@@ -218,6 +721,7 @@ pub struct DecodedExtras {
 
 impl DecodedExtras {
     #[inline]
+    #[must_use]
     pub fn new() -> Self {
         Self {
             properties: BTreeMap::new(),
@@ -225,10 +729,58 @@ impl DecodedExtras {
     }
 
     #[inline]
+    #[must_use]
     pub fn with(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
         self.properties.insert(key.into(), value.into());
         self
     }
+
+    /// Значение ключа как строка.
+    #[inline]
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.properties.get(key).map(String::as_str)
+    }
+
+    /// Значение ключа, разобранное через [`FromStr`] (`row` → `usize`, `qr.mask` → `u8`).
+    /// `None`, если ключа нет или значение не разбирается.
+    #[inline]
+    #[must_use]
+    pub fn get_parsed<T: FromStr>(&self, key: &str) -> Option<T> {
+        self.get_str(key)?.parse().ok()
+    }
+
+    /// Переносит все пары из `other`; при совпадении ключей побеждает `other`.
+    pub fn merge(&mut self, other: DecodedExtras) {
+        self.properties.extend(other.properties);
+    }
+}
+
+/// Типизированные метаданные символа: то же, что встроенные декодеры кладут в
+/// [`DecodedExtras`] строками, но с проверкой типов при компиляции.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SymbolMeta {
+    Qr {
+        ec: crate::qr::format::EcLevel,
+        mask: u8,
+        /// Сколько кодвордов исправил Reed–Solomon.
+        rs_corrected: usize,
+        version: u8,
+    },
+    Code128 {
+        /// Набор старт-кода: `'A'`, `'B'` или `'C'`.
+        start_set: char,
+        /// FNC1 сразу после старта — GS1-128.
+        fnc1_gs1: bool,
+    },
+    Ean {
+        /// Цифра системы нумерации (0–9): первая цифра UPC-A или EAN-13 (у EAN-13
+        /// она задана чётностью левой половины). Трёхзначный префикс GS1 —
+        /// в `extras["ean.number_system"]`.
+        number_system: u8,
+        /// Дополнение EAN-2/EAN-5; пока не читается — всегда `None`.
+        addon: Option<String>,
+    },
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -238,8 +790,15 @@ pub struct DecodedSymbol {
     pub confidence: f32, // 0..=1
     pub quad: Option<Quad>,
     pub orientation: Option<Orientation>,
+    /// Байты полезной нагрузки без потерь: у QR/Aztec и Code128 — как
+    /// закодированы (управляющие символы, FNC-маркеры GS1 — см. extras), у
+    /// EAN/UPC, Code 93 и MSI — ASCII-байты `text`. `text` — лучшая попытка строки.
+    /// QR, чьи данные разборщик не понял (`qr.parse = "failed"`), отдаётся с пустым
+    /// `text` и исправленными data-кодвордами целиком.
     pub bytes: Option<Vec<u8>>,
     pub extras: DecodedExtras,
+    /// Метаданные QR, Code128 и EAN/UPC (см. [`SymbolMeta`]); у прочих — `None`.
+    pub meta: Option<SymbolMeta>,
 }
 
 impl DecodedSymbol {
@@ -253,34 +812,46 @@ impl DecodedSymbol {
             orientation: None,
             bytes: None,
             extras: DecodedExtras::new(),
+            meta: None,
         }
     }
 
     #[inline]
+    #[must_use]
     pub fn with_confidence(mut self, c: f32) -> Self {
         self.confidence = c;
         self
     }
     #[inline]
+    #[must_use]
     pub fn with_quad(mut self, q: Quad) -> Self {
         self.quad = Some(q);
         self
     }
     #[inline]
+    #[must_use]
     pub fn with_orientation(mut self, o: Orientation) -> Self {
         self.orientation = Some(o);
         self
     }
     #[inline]
+    #[must_use]
     pub fn with_bytes(mut self, b: Vec<u8>) -> Self {
         self.bytes = Some(b);
         self
     }
     #[inline]
+    #[must_use]
     pub fn with_extras(mut self, extras: DecodedExtras) -> Self {
         self.extras = extras;
         self
     }
+    #[inline]
+    #[must_use]
+    pub fn with_meta(mut self, meta: SymbolMeta) -> Self {
+        self.meta = Some(meta);
+        self
+    }
 }
 
 /// Утилиты для GrayImage с корректными lifetime.
@@ -335,3 +906,108 @@ pub trait LumaImageExt {
     fn threshold_row_mean<'b>(&self, y: usize, window: usize, out: &'b mut Vec<u8>) -> &'b [u8];
     fn threshold_col_mean<'b>(&self, x: usize, window: usize, out: &'b mut Vec<u8>) -> &'b [u8];
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_stats_summarize_known_buffer() {
+        // 2×4: уровни 0, 15, 16, 100, 100, 200, 250, 255.
+        let img = LumaImage {
+            data: alloc::vec![0, 15, 16, 100, 100, 200, 250, 255],
+            width: 4,
+            height: 2,
+        };
+        let st = img.stats();
+        assert_eq!((st.min, st.max, st.dynamic_range()), (0, 255, 255));
+        assert!((st.mean - 117.0).abs() < 1e-4, "mean = {}", st.mean);
+        let mut expected = [0usize; ImageStats::BINS];
+        expected[0] = 2; // 0, 15
+        expected[1] = 1; // 16
+        expected[6] = 2; // 100
+        expected[12] = 1; // 200
+        expected[15] = 2; // 250, 255
+        assert_eq!(st.histogram, expected);
+        assert_eq!(st.histogram.iter().sum::<usize>(), img.data.len());
+        assert_eq!(img.as_gray().stats(), st);
+
+        let flat = LumaImage {
+            data: alloc::vec![128; 12],
+            width: 4,
+            height: 3,
+        };
+        let st = flat.stats();
+        assert_eq!((st.min, st.max, st.dynamic_range()), (128, 128, 0));
+        assert_eq!(st.histogram[8], 12);
+
+        let empty = LumaImage {
+            data: Vec::new(),
+            width: 0,
+            height: 0,
+        };
+        assert_eq!(
+            empty.stats(),
+            ImageStats {
+                min: 0,
+                max: 0,
+                mean: 0.0,
+                histogram: [0; ImageStats::BINS]
+            }
+        );
+    }
+
+    #[test]
+    fn decoded_extras_typed_accessors_and_merge() {
+        let mut extras = DecodedExtras::new()
+            .with("row", "42")
+            .with("qr.ec", "M")
+            .with("qr.mask", "x");
+        assert_eq!(extras.get_str("qr.ec"), Some("M"));
+        assert_eq!(extras.get_str("absent"), None);
+        assert_eq!(extras.get_parsed::<usize>("row"), Some(42));
+        // неразбираемое значение и отсутствующий ключ — одинаково None
+        assert_eq!(extras.get_parsed::<u8>("qr.mask"), None);
+        assert_eq!(extras.get_parsed::<u8>("absent"), None);
+
+        extras.merge(
+            DecodedExtras::new()
+                .with("qr.mask", "5")
+                .with("quiet_zone", "missing"),
+        );
+        assert_eq!(
+            extras.get_parsed::<u8>("qr.mask"),
+            Some(5),
+            "при совпадении ключа побеждает other"
+        );
+        assert_eq!(extras.get_str("quiet_zone"), Some("missing"));
+        assert_eq!(extras.get_str("row"), Some("42"));
+        let keys: Vec<&str> = extras.properties.keys().map(String::as_str).collect();
+        assert_eq!(keys, ["qr.ec", "qr.mask", "quiet_zone", "row"]);
+    }
+
+    #[test]
+    fn gray_image_from_slice_checks_dimensions() {
+        let data = alloc::vec![255u8; 12];
+        let img = GrayImage::from_slice(&data, 3, 3).expect("fits");
+        assert_eq!((img.width, img.height, img.data.len()), (3, 3, 9));
+
+        // буфер короче заголовка, переполнение `width·height`, предел пикселей
+        assert_eq!(
+            GrayImage::from_slice(&data, 4, 4).err(),
+            Some(DecodeError::InvalidFormat)
+        );
+        assert_eq!(
+            GrayImage::from_slice(&data, usize::MAX, 2).err(),
+            Some(DecodeError::InvalidFormat)
+        );
+        assert_eq!(
+            GrayImage::from_slice_limited(&data, 3, 3, 8).err(),
+            Some(DecodeError::InvalidFormat)
+        );
+        assert_eq!(
+            GrayImage::checked_len(1 << 15, 1 << 15, GrayImage::DEFAULT_MAX_PIXELS),
+            Err(DecodeError::InvalidFormat)
+        );
+    }
+}
@@ -0,0 +1,264 @@
+// src/debug.rs
+//! Отладочная SVG-разметка распознанных символов (фича `debug-overlay`).
+//!
+//! [`svg_overlay`] рисует поверх исходного кадра четырёхугольники символов
+//! (`DecodedSymbol::quad`) и подписи — для баг-репортов вместо дампов `diag!`.
+//! Кадр встраивается в SVG как PNG (data URI); PNG собирается здесь же, без
+//! сжатия и без внешних зависимостей. 1D-символы без `quad` отмечаются линией
+//! по строке скана (`extras["row"]`).
+
+use crate::core::types::{DecodedSymbol, LumaImage, Point};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+/// Цвет рамок и подписей.
+const STROKE: &str = "#00e676";
+/// Кегль подписи, px (не меньше — иначе на мелких кадрах не прочесть).
+const MIN_FONT_PX: usize = 10;
+
+/// SVG размером с кадр: кадр фоном (`<image>`), поверх — по рамке и подписи
+/// `"<симвология>: <текст>"` на символ. Символ без `quad` и без `extras["row"]`
+/// пропускается. Текст экранируется для XML, управляющие байты (FNC1 → GS и т.п.)
+/// показываются как `\xNN`.
+#[must_use]
+pub fn svg_overlay(img: &LumaImage, symbols: &[DecodedSymbol]) -> String {
+    let (w, h) = (img.width, img.height);
+    let font = (h / 40).max(MIN_FONT_PX);
+    let mut svg = String::new();
+    // `write!` в String не падает
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#
+    );
+    let _ = writeln!(
+        svg,
+        r#"<image width="{w}" height="{h}" style="image-rendering:pixelated" href="data:image/png;base64,{}"/>"#,
+        base64(&png_gray(img))
+    );
+    let _ = writeln!(
+        svg,
+        r#"<g fill="none" stroke="{STROKE}" stroke-width="{}" font-family="monospace" font-size="{font}">"#,
+        (font / 5).max(1)
+    );
+    for s in symbols {
+        let label = xml_escape(&format!("{:?}: {}", s.symbology, s.text));
+        let anchor = if let Some(q) = s.quad {
+            let pts = [q.p0, q.p1, q.p2, q.p3];
+            let list: Vec<String> = pts.iter().map(|p| format!("{},{}", p.x, p.y)).collect();
+            let _ = writeln!(svg, r#"<polygon points="{}"/>"#, list.join(" "));
+            // подпись над верхней-левой точкой рамки
+            let top = pts
+                .iter()
+                .min_by_key(|p| (p.y, p.x))
+                .copied()
+                .unwrap_or(q.p0);
+            Point {
+                x: top.x,
+                y: top.y - 3,
+            }
+        } else if let Some(row) = s.extras.get_parsed::<i32>("row") {
+            let _ = writeln!(
+                svg,
+                r#"<line x1="0" y1="{row}" x2="{w}" y2="{row}" stroke-dasharray="6 3"/>"#
+            );
+            Point { x: 2, y: row - 3 }
+        } else {
+            continue;
+        };
+        let y = anchor.y.max(i32::try_from(font).unwrap_or(i32::MAX));
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{y}" fill="{STROKE}" stroke="none">{label}</text>"#,
+            anchor.x
+        );
+    }
+    svg.push_str("</g>\n</svg>\n");
+    svg
+}
+
+/// Экранирование для текста и атрибутов XML; управляющие символы (в XML 1.0
+/// запрещены) — как `\xNN`.
+fn xml_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\x{:02X}", u32::from(c));
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// ---------------------------------------------------------------------------
+// PNG без сжатия: 8-битный серый, IDAT — zlib из stored-блоков
+// ---------------------------------------------------------------------------
+
+/// Максимум данных в одном stored-блоке deflate.
+const STORED_BLOCK: usize = 0xFFFF;
+
+/// Кадр → PNG (серый, 8 бит, без фильтров и сжатия).
+fn png_gray(img: &LumaImage) -> Vec<u8> {
+    // строки с байтом фильтра 0 (None)
+    let mut raw = Vec::with_capacity((img.width + 1) * img.height);
+    for y in 0..img.height {
+        raw.push(0);
+        raw.extend_from_slice(&img.data[y * img.width..(y + 1) * img.width]);
+    }
+
+    let mut zlib = alloc::vec![0x78, 0x01];
+    let mut blocks = raw.chunks(STORED_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        zlib.extend([1, 0, 0, 0xFF, 0xFF]); // пустой финальный блок
+    }
+    while let Some(block) = blocks.next() {
+        let len = u16::try_from(block.len()).unwrap_or(u16::MAX); // блоки не длиннее STORED_BLOCK
+        zlib.push(u8::from(blocks.peek().is_none()));
+        zlib.extend(len.to_le_bytes());
+        zlib.extend((!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend(adler32(&raw).to_be_bytes());
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend(u32::try_from(img.width).unwrap_or(u32::MAX).to_be_bytes());
+    ihdr.extend(u32::try_from(img.height).unwrap_or(u32::MAX).to_be_bytes());
+    ihdr.extend([8, 0, 0, 0, 0]); // 8 бит, серый, deflate, фильтры 0, без interlace
+
+    let mut png = alloc::vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+    png_chunk(&mut png, *b"IHDR", &ihdr);
+    png_chunk(&mut png, *b"IDAT", &zlib);
+    png_chunk(&mut png, *b"IEND", &[]);
+    png
+}
+
+/// Чанк PNG: длина, тип, данные, CRC-32 по типу и данным.
+fn png_chunk(out: &mut Vec<u8>, kind: [u8; 4], data: &[u8]) {
+    out.extend(u32::try_from(data.len()).unwrap_or(u32::MAX).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(&kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend(crc.to_be_bytes());
+}
+
+/// CRC-32 (IEEE, отражённый полином 0xEDB88320), побитово — кадры отладочные.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &b in data {
+        crc ^= u32::from(b);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Adler-32 (контрольная сумма zlib).
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &x in data {
+        a = (a + u32::from(x)) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+/// Base64 (RFC 4648, с `=`-дополнением).
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, &b)| acc | u32::from(b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(char::from(ALPHABET[(n >> (18 - 6 * i)) as usize & 63]));
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::{Quad, Symbology};
+
+    #[test]
+    fn checksums_and_base64_match_references() {
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn png_has_valid_structure_for_multi_block_frames() {
+        // 300×300 с байтами фильтров — больше одного stored-блока
+        let img = LumaImage {
+            width: 300,
+            height: 300,
+            data: (0..90_000u32).map(|i| (i % 251) as u8).collect(),
+        };
+        let png = png_gray(&img);
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[16..24], &[0, 0, 1, 44, 0, 0, 1, 44]);
+        let idat_len = u32::from_be_bytes(png[33..37].try_into().unwrap()) as usize;
+        assert_eq!(&png[37..41], b"IDAT");
+        let zlib = &png[41..41 + idat_len];
+        // два stored-блока: 65535 + (90300 − 65535), второй — финальный
+        assert_eq!(&zlib[..3], &[0x78, 0x01, 0]);
+        assert_eq!(zlib[2 + 5 + STORED_BLOCK], 1);
+        assert_eq!(idat_len, 2 + 2 * 5 + 300 * 301 + 4);
+        assert!(png.ends_with(&[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82]));
+    }
+
+    #[test]
+    fn overlay_draws_quads_rows_and_escaped_labels() {
+        let img = LumaImage {
+            width: 40,
+            height: 30,
+            data: alloc::vec![200; 1200],
+        };
+        let p = |x, y| Point { x, y };
+        let qr = DecodedSymbol::new(Symbology::QR, "a<b & \"c\"").with_quad(Quad {
+            p0: p(5, 6),
+            p1: p(30, 6),
+            p2: p(30, 28),
+            p3: p(5, 28),
+        });
+        let mut ean = DecodedSymbol::new(Symbology::Code128, "]C1\u{1d}01");
+        ean.extras = ean.extras.with("row", "17");
+        let lost = DecodedSymbol::new(Symbology::Msi, "123");
+
+        let svg = svg_overlay(&img, &[qr, ean, lost]);
+        assert!(svg.starts_with("<svg ") && svg.ends_with("</svg>\n"));
+        assert!(svg.contains(r#"width="40" height="30""#));
+        assert!(svg.contains(r#"<polygon points="5,6 30,6 30,28 5,28"/>"#));
+        assert!(svg.contains("QR: a&lt;b &amp; &quot;c&quot;</text>"));
+        assert!(svg.contains(r#"<line x1="0" y1="17" x2="40" y2="17""#));
+        assert!(svg.contains("Code128: ]C1\\x1D01</text>"));
+        assert!(!svg.contains("Msi"));
+        assert!(svg.contains("data:image/png;base64,iVBORw0KGgo"));
+    }
+}
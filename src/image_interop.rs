@@ -0,0 +1,72 @@
+// src/image_interop.rs
+//
+// Мост к крейту `image` (feature `image`): кадры, уже загруженные как
+// `image::GrayImage` / `DynamicImage`, идут в пайплайн без ручного разбора буферов.
+
+use crate::api::PipelineBuilder;
+use crate::core::types::{DecodedSymbol, GrayImage, LumaImage};
+use alloc::vec::Vec;
+
+impl From<&image::GrayImage> for LumaImage {
+    /// Копия пикселей 8-битного кадра яркости.
+    fn from(img: &image::GrayImage) -> Self {
+        Self {
+            data: img.as_raw().clone(),
+            width: img.width() as usize,
+            height: img.height() as usize,
+        }
+    }
+}
+
+/// Распознать кадр любого формата `image` пайплайном по умолчанию.
+///
+/// `Luma8` читается на месте, остальные форматы сначала переводятся в яркость
+/// (`DynamicImage::to_luma8`; альфа-канал отбрасывается). Порядок результата —
+/// как у [`crate::api::Pipeline::decode_all`].
+#[must_use]
+pub fn decode_dynamic_image(img: &image::DynamicImage) -> Vec<DecodedSymbol> {
+    let pipeline = PipelineBuilder::new().build();
+    match img.as_luma8() {
+        Some(luma) => pipeline.decode_all_gray(&GrayImage {
+            data: luma.as_raw(),
+            width: luma.width() as usize,
+            height: luma.height() as usize,
+        }),
+        None => pipeline.decode_all(&LumaImage::from(&img.to_luma8())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::one_d::ean13::synthesize_ean13_row;
+
+    #[test]
+    fn image_crate_frames_decode_like_luma_buffers() {
+        let row = synthesize_ean13_row("5901234123457", 9, 2).unwrap();
+        let img = LumaImage {
+            width: row.len(),
+            height: 8,
+            data: row.repeat(8),
+        };
+        let gray =
+            image::GrayImage::from_raw(img.width as u32, img.height as u32, img.data.clone())
+                .expect("buffer");
+
+        let luma = LumaImage::from(&gray);
+        assert_eq!(
+            (luma.width, luma.height, &luma.data),
+            (img.width, img.height, &img.data)
+        );
+
+        let expected = PipelineBuilder::new().build().decode_all(&img);
+        assert_eq!(expected[0].text, "5901234123457");
+        let dynamic = image::DynamicImage::ImageLuma8(gray);
+        assert_eq!(decode_dynamic_image(&dynamic), expected);
+        // цветной кадр переводится в яркость
+        assert_eq!(
+            decode_dynamic_image(&image::DynamicImage::ImageRgb8(dynamic.to_rgb8())),
+            expected
+        );
+    }
+}
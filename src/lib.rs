@@ -1,18 +1,51 @@
-#![forbid(unsafe_code)]
+#![cfg_attr(not(feature = "std"), no_std)]
+// wasm-bindgen генерирует unsafe-обёртки: под фичей `wasm` запрет снимается
+// только для модуля `wasm`.
+#![cfg_attr(not(feature = "wasm"), forbid(unsafe_code))]
+#![cfg_attr(feature = "wasm", deny(unsafe_code))]
 #![deny(clippy::all, clippy::pedantic)]
 #![allow(clippy::module_name_repetitions)]
 
+extern crate alloc;
+
+// Диагностика (макрос diag! + хук); объявлен первым, чтобы макрос был виден ниже.
+#[macro_use]
+pub mod log;
+// Замеры стадий (макрос timed! + хук, feature `profile`).
+#[macro_use]
+pub mod profile;
+
 // Публичные модули
-pub mod api;      // высокий уровень: пайплайн, трейт Decoder
-pub mod core;     // общие типы/утилиты (GrayImage и др.)
+pub mod api; // высокий уровень: пайплайн, трейты ImageDecoder/RowDecoder
+pub mod core; // общие типы/утилиты (GrayImage и др.)
 pub mod prelude;  // удобные re-export'ы
 
-pub mod one_d;    // 1D декодеры (ean13, code128)
-pub mod qr;       // утилиты QR (format и пр.)
-pub mod binarize; // быстрая бинаризация для 1D
+pub mod binarize;
+pub mod one_d; // 1D декодеры (ean13, code128)
+pub mod qr; // утилиты QR (format и пр.)
+pub mod two_d; // прочие 2D (Aztec) // быстрая бинаризация: строки для 1D и кадр целиком
+
+// Браузерная точка входа (wasm-bindgen)
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+// Конвертация из типов крейта `image`
+#[cfg(feature = "image")]
+pub mod image_interop;
+
+// SVG-разметка распознанных символов для баг-репортов
+#[cfg(feature = "debug-overlay")]
+pub mod debug;
+
+// Искажения синтетики для тестов устойчивости (в публичный API не входят)
+#[cfg(all(test, feature = "std"))]
+mod perturb;
 
 // Реэкспорт базового типа изображения в корень
-pub use crate::core::types::GrayImage;
+pub use crate::core::types::{
+    Binarization, ChecksumPolicy, DecodeError, GrayImage, ImageStats, Progress, QuietZone,
+    SynthError,
+};
 
 // Слой совместимости со старым API (decode_any и пр.)
 mod compat;
@@ -22,25 +55,31 @@ pub use compat::*;
 // Раньше они писали `use ultracode::{decode_any, DecodeOptions, GrayImage};` и т.п.
 // Чтобы ничего в них не менять — реэкспортируем здесь.
 pub use crate::one_d::DecodeOptions;
+#[cfg(feature = "debug-capture")]
+pub use crate::one_d::ScanDebug;
 pub use crate::one_d::{Barcode, BarcodeFormat};
 
 // Нужен также синтезатор для демо Code128:
 pub use crate::one_d::code128::synthesize_row_code128;
 
 // Быстрый «сахар»: функции, принимающие Pipeline и LumaImage.
-// (Сейчас Pipeline пустой — добавляй декодеры внутри Pipeline::decode_all)
+// (Свои декодеры подключаются через PipelineBuilder::add_decoder)
 use crate::api::Pipeline;
 use crate::core::types::{DecodedSymbol, LumaImage};
+use alloc::vec::Vec;
 
-/// Универсальный one-shot: прогоняет изображение через зарегистрированные декодеры.
-/// По умолчанию пайплайн пустой (ты добавляешь декодеры сам через Pipeline::add).
+/// Универсальный one-shot: прогоняет изображение через встроенные и
+/// зарегистрированные декодеры (свои добавляются через
+/// [`PipelineBuilder::add_decoder`](crate::api::PipelineBuilder::add_decoder)).
 #[inline]
+#[must_use]
 pub fn decode_all(img: &LumaImage, pipeline: &Pipeline) -> Vec<DecodedSymbol> {
     pipeline.decode_all(img)
 }
 
 /// Упроститель: вернуть первый найденный символ (если важна скорость TTF).
 #[inline]
+#[must_use]
 pub fn decode_first(img: &LumaImage, pipeline: &Pipeline) -> Option<DecodedSymbol> {
     pipeline.decode_first(img)
 }
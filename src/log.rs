@@ -0,0 +1,49 @@
+// src/log.rs
+//
+// Диагностический вывод декодеров (finder/sample/qr).
+//
+// Все сообщения идут через макрос `diag!` вместо разбросанных `println!`/`eprintln!`:
+// - с feature `std` по умолчанию печатаются в stderr, перехватываются через
+//   [`set_log_hook`] (например, чтобы заглушить или отправить в свой логгер);
+//   в wasm32 stderr нет — без хука сообщения отбрасываются;
+// - без `std` сообщения отбрасываются — форматирование не выполняется.
+
+/// Приёмник диагностических сообщений (одна строка без перевода строки).
+pub type LogHook = fn(&str);
+
+#[cfg(feature = "std")]
+static HOOK: std::sync::RwLock<Option<LogHook>> = std::sync::RwLock::new(None);
+
+/// Установить (или снять, передав `None`) приёмник диагностики.
+/// Без хука сообщения печатаются в stderr.
+#[cfg(feature = "std")]
+pub fn set_log_hook(hook: Option<LogHook>) {
+    if let Ok(mut h) = HOOK.write() {
+        *h = hook;
+    }
+}
+
+/// Точка выхода для `diag!`: хук, если установлен, иначе stderr.
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub fn emit(args: core::fmt::Arguments<'_>) {
+    let hook = HOOK.read().ok().and_then(|h| *h);
+    match hook {
+        Some(f) => f(&std::fmt::format(args)),
+        // в wasm32 stderr никуда не ведёт — без хука сообщения отбрасываются
+        #[cfg(not(target_arch = "wasm32"))]
+        None => std::eprintln!("{args}"),
+        #[cfg(target_arch = "wasm32")]
+        None => {}
+    }
+}
+
+/// Диагностическое сообщение в стиле `format!`.
+macro_rules! diag {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "std")]
+        $crate::log::emit(format_args!($($arg)*));
+        #[cfg(not(feature = "std"))]
+        let _ = format_args!($($arg)*);
+    }};
+}
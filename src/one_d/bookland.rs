@@ -0,0 +1,99 @@
+//! «Bookland»: ISBN/ISSN/ISMN поверх EAN-13.
+//!
+//! Чисто числовое преобразование уже декодированных 13 цифр:
+//! - `978`, `979` (кроме `979-0`) → ISBN-13 (и ISBN-10 для `978`);
+//! - `979-0` → ISMN (13-значный и старый `M`-формат);
+//! - `977` → ISSN (`NNNN-NNNC`, свой контрольный символ по mod 11).
+
+use crate::one_d::checks;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Идентификаторы издания для EAN-13 `ean13` в виде пар (ключ extras, значение).
+/// Ключи: `ean.isbn13`, `ean.isbn10`, `ean.ismn`, `ean.ismn10`, `ean.issn`.
+/// Для прочих префиксов (или не 13 цифр) — пусто.
+#[must_use]
+pub fn bookland_ids(ean13: &str) -> Vec<(&'static str, String)> {
+    let mut out = Vec::new();
+    if ean13.len() != 13 || !ean13.bytes().all(|c| c.is_ascii_digit()) {
+        return out;
+    }
+    let d = ean13.as_bytes();
+
+    match &ean13[..3] {
+        "979" if d[3] == b'0' => {
+            out.push(("ean.ismn", String::from(ean13)));
+            // Старый формат: «M» + 8 цифр + та же контрольная цифра, что у EAN-13.
+            let mut m = String::with_capacity(10);
+            m.push('M');
+            m.push_str(&ean13[4..]);
+            out.push(("ean.ismn10", m));
+        }
+        "978" => {
+            out.push(("ean.isbn13", String::from(ean13)));
+            out.push(("ean.isbn10", isbn10_from_body(&ean13[3..12])));
+        }
+        "979" => out.push(("ean.isbn13", String::from(ean13))),
+        "977" => out.push(("ean.issn", issn_from_body(&ean13[3..10]))),
+        _ => {}
+    }
+    out
+}
+
+/// ISBN-10 по 9 цифрам тела: контрольная по mod 11 с весами 10..2 (`X` = 10).
+fn isbn10_from_body(body: &str) -> String {
+    let mut s = String::with_capacity(10);
+    s.push_str(body);
+    s.push(mod11_check(body, 10));
+    s
+}
+
+/// ISSN по 7 цифрам тела: веса 8..2, mod 11 (`X` = 10), вывод `NNNN-NNNC`.
+fn issn_from_body(body: &str) -> String {
+    let mut s = String::with_capacity(9);
+    s.push_str(&body[..4]);
+    s.push('-');
+    s.push_str(&body[4..]);
+    s.push(mod11_check(body, 8));
+    s
+}
+
+/// Контрольный символ mod 11: веса убывают от `first_weight`, результат `0..9` или `X`.
+fn mod11_check(body: &str, first_weight: u32) -> char {
+    let digits: Vec<u8> = body.bytes().map(|c| c - b'0').collect();
+    char::from_digit(u32::from(checks::mod11(&digits, first_weight)), 10).unwrap_or('X')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get<'a>(ids: &'a [(&'static str, String)], key: &str) -> Option<&'a str> {
+        ids.iter().find(|(k, _)| *k == key).map(|(_, v)| v.as_str())
+    }
+
+    #[test]
+    fn isbn_issn_ismn_from_ean13() {
+        let book = bookland_ids("9780306406157");
+        assert_eq!(get(&book, "ean.isbn13"), Some("9780306406157"));
+        assert_eq!(get(&book, "ean.isbn10"), Some("0306406152"));
+
+        // контрольная `X`
+        let x = bookland_ids("9780804429573");
+        assert_eq!(get(&x, "ean.isbn10"), Some("080442957X"));
+
+        let isbn979 = bookland_ids("9791032300824");
+        assert_eq!(get(&isbn979, "ean.isbn13"), Some("9791032300824"));
+        assert_eq!(get(&isbn979, "ean.isbn10"), None);
+
+        let serial = bookland_ids("9770317847001");
+        assert_eq!(get(&serial, "ean.issn"), Some("0317-8471"));
+
+        let music = bookland_ids("9790260000438");
+        assert_eq!(get(&music, "ean.ismn"), Some("9790260000438"));
+        assert_eq!(get(&music, "ean.ismn10"), Some("M260000438"));
+
+        assert!(bookland_ids("4006381333931").is_empty());
+        assert!(bookland_ids("036000291452").is_empty());
+    }
+}
@@ -0,0 +1,96 @@
+//! Контрольные цифры по весам mod 10 / mod 11 — общие для EAN/UPC, MSI и
+//! книжных номеров (ISBN-10, ISSN); пригодны и для проверки ввода до синтеза.
+//!
+//! Все функции принимают значения цифр (`0..=9`, не ASCII) без контрольной
+//! и считают веса от самой правой цифры.
+
+/// Контрольная mod 10 с весами `weights`, повторяемыми по кругу справа налево:
+/// `(10 − Σ dᵢ·wᵢ mod 10) mod 10`. EAN-13/UPC-A/EAN-8 — `&[3, 1]`.
+#[must_use]
+pub fn mod10_weighted(digits: &[u8], weights: &[u32]) -> u8 {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .zip(weights.iter().cycle())
+        .map(|(&d, &w)| u32::from(d) * w)
+        .sum();
+    // < 10, так что усечение невозможно
+    u8::try_from((10 - sum % 10) % 10).unwrap_or(0)
+}
+
+/// Контрольная mod 11 с весами `2..=max_weight` справа налево (по кругу, если
+/// цифр больше): `(11 − Σ dᵢ·wᵢ mod 11) mod 11`. Результат `0..=10`: `10` — у ISBN-10
+/// и ISSN это `X`, у MSI такой код невалиден. MSI (IBM) — `7`, ISBN-10 — `10`, ISSN — `8`.
+#[must_use]
+pub fn mod11(digits: &[u8], max_weight: u32) -> u8 {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .zip((2..=max_weight.max(2)).cycle())
+        .map(|(&d, w)| u32::from(d) * w)
+        .sum();
+    // < 11, так что усечение невозможно
+    u8::try_from((11 - sum % 11) % 11).unwrap_or(0)
+}
+
+/// Контрольная Luhn (mod 10 с удвоением каждой второй цифры, начиная с самой
+/// правой; у произведений складываются цифры) — MSI mod 10, номера карт.
+#[must_use]
+pub fn luhn(digits: &[u8]) -> u8 {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(k, &d)| {
+            let v = u32::from(d) * if k % 2 == 0 { 2 } else { 1 };
+            v / 10 + v % 10
+        })
+        .sum();
+    u8::try_from((10 - sum % 10) % 10).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_check_digits() {
+        let ean: [(&[u8], u8); 4] = [
+            (&[4, 0, 0, 6, 3, 8, 1, 3, 3, 3, 9, 3], 1),
+            (&[5, 9, 0, 1, 2, 3, 4, 1, 2, 3, 4, 5], 7),
+            // UPC-A 036000291452
+            (&[0, 3, 6, 0, 0, 0, 2, 9, 1, 4, 5], 2),
+            // EAN-8 96385074
+            (&[9, 6, 3, 8, 5, 0, 7], 4),
+        ];
+        for (digits, check) in ean {
+            assert_eq!(mod10_weighted(digits, &[3, 1]), check, "{digits:?}");
+        }
+
+        let luhns: [(&[u8], u8); 4] = [
+            (&[7, 9, 9, 2, 7, 3, 9, 8, 7, 1], 3),
+            (&[1, 2, 3, 4, 5, 6, 7], 4),
+            (&[0], 0),
+            (&[], 0),
+        ];
+        for (digits, check) in luhns {
+            assert_eq!(luhn(digits), check, "{digits:?}");
+        }
+
+        let mod11s: [(&[u8], u32, u8); 5] = [
+            // MSI/IBM: 80523 -> 8
+            (&[8, 0, 5, 2, 3], 7, 8),
+            // ISBN-10 0-306-40615-2
+            (&[0, 3, 0, 6, 4, 0, 6, 1, 5], 10, 2),
+            // ISBN-10 0-8044-2957-X
+            (&[0, 8, 0, 4, 4, 2, 9, 5, 7], 10, 10),
+            // ISSN 0317-8471
+            (&[0, 3, 1, 7, 8, 4, 7], 8, 1),
+            // ISSN 2049-3630
+            (&[2, 0, 4, 9, 3, 6, 3], 8, 0),
+        ];
+        for (digits, max_weight, check) in mod11s {
+            assert_eq!(mod11(digits, max_weight), check, "{digits:?}");
+        }
+    }
+}
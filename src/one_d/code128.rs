@@ -2,15 +2,28 @@
 //!
 //! Поддержка:
 //! - Наборы A/B/C, коды CODE A/B/C, SHIFT, FNC1 (ASCII 29, GS).
+//! - Точный байтовый режим ([`decode_row_bytes`]): FNC1..4 отдельными маркерами,
+//!   расширение FNC4 (байты 128..255).
 //! - Проверка checksum (mod 103).
 //! - Детект всех трёх старт-кодов + STOP.
 //!
 //! Ключевая идея: ищем STOP-паттерн (7 run'ов, сумма 13), а затем
 //! идём НАЗАД по 6-run блокам до старт-кода. Это надёжно выравнивает
-//! поток и убирает двусмысленности «с какого run'а начинать».
-
-use crate::binarize::{binarize_row, binarize_row_adaptive, runs};
-use crate::one_d::DecodeOptions;
+//! поток и убирает двусмысленности «с какого run'а начинать». Тихие зоны
+//! (перед стартом и за STOP) — по `DecodeOptions::quiet_zone`.
+
+use crate::binarize::runs_subpixel;
+use crate::core::cast::{f32_to_usize, usize_to_f32};
+#[cfg(not(feature = "std"))]
+use crate::core::math::FloatExt;
+use crate::core::types::{ChecksumPolicy, SynthError};
+use crate::one_d::{
+    has_quiet_zone, pick_read, row_binarizations, subpixel_runs_look_like_barcode, DecodeOptions,
+    Extent, RowCandidate, RowRead,
+};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
 
 /// Паттерны 0..=105: по 6 чисел (bars/spaces), сумма 11.
 const CODE128_PATTERNS_STR: [&str; 106] = [
@@ -39,39 +52,202 @@ enum CodeSet {
     C,
 }
 
-/// Попытка декодировать один ряд в Code128. Успех -> строка.
+/// Попытка декодировать один ряд в Code128. Успех -> строка
+/// (FNC1 → GS, см. [`Code128Bytes::to_text`]).
+#[must_use]
 pub fn decode_row(row_gray: &[u8], opts: &DecodeOptions) -> Option<String> {
-    if row_gray.len() < opts.min_modules {
-        return None;
-    }
+    decode_row_bytes(row_gray, opts).map(|d| d.to_text())
+}
 
-    // 1) бинаризация (адаптивная -> фоллбэк) и run-lengths
-    let rb1 = binarize_row_adaptive(row_gray);
-    let rl1 = runs(&rb1);
-    let rl = if rl1.len() >= 24 {
-        rl1
-    } else {
-        let rb2 = binarize_row(row_gray);
-        let rl2 = runs(&rb2);
-        if rl2.len() < 24 {
-            return None;
+/// То же, что [`decode_row`], но ширины run'ов меряются по субпиксельным фронтам
+/// (см. [`crate::binarize::runs_subpixel`]) — для модулей шириной 1–2 пикселя.
+#[must_use]
+pub fn decode_row_subpixel(row_gray: &[u8], opts: &DecodeOptions) -> Option<String> {
+    decode_row_bytes_subpixel(row_gray, opts).map(|d| d.to_text())
+}
+
+/// Функциональные символы Code128 (не имеют байтового представления).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fnc {
+    /// FNC1: признак GS1-128 в начале, разделитель полей переменной длины внутри.
+    Fnc1,
+    Fnc2,
+    Fnc3,
+    Fnc4,
+}
+
+/// Точный результат Code128: байты данных без функциональных символов
+/// и позиции FNC-маркеров отдельно.
+///
+/// `(i, f)` в `fnc` означает, что маркер `f` стоит перед `bytes[i]`
+/// (`i == bytes.len()` — после последнего байта). В отличие от текста из
+/// [`decode_row`], где FNC1 превращается в GS (0x1D), литеральный GS из набора A
+/// здесь остаётся байтом 0x1D, а FNC1 — только маркером.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Code128Bytes {
+    pub bytes: Vec<u8>,
+    pub fnc: Vec<(usize, Fnc)>,
+    /// FNC1 сразу после старт-кода — символ GS1-128 (поля — AI GS1). FNC1 дальше
+    /// по тексту — разделители полей переменной длины, на флаг не влияют.
+    pub gs1: bool,
+    /// Набор старт-кода: `'A'`, `'B'` или `'C'`.
+    pub start_set: char,
+}
+
+impl Code128Bytes {
+    /// Текст как у [`decode_row`]: FNC1 → GS (0x1D), FNC2/3/4 опускаются.
+    #[must_use]
+    pub fn to_text(&self) -> String {
+        let mut out = String::with_capacity(self.bytes.len() + self.fnc.len());
+        let mut marks = self.fnc.iter().peekable();
+        for i in 0..=self.bytes.len() {
+            while let Some(&&(_, f)) = marks.peek().filter(|(at, _)| *at == i) {
+                if f == Fnc::Fnc1 {
+                    out.push('\u{1d}');
+                }
+                marks.next();
+            }
+            if let Some(&b) = self.bytes.get(i) {
+                out.push(char::from(b));
+            }
         }
-        rl2
-    };
+        out
+    }
+}
+
+/// Как [`decode_row`], но результат — байты + позиции FNC (см. [`Code128Bytes`]).
+/// FNC4 (расширенный набор) применяется: следующий символ данных получает +128,
+/// двойной FNC4 переключает это режимом до следующего двойного FNC4.
+#[must_use]
+pub fn decode_row_bytes(row_gray: &[u8], opts: &DecodeOptions) -> Option<Code128Bytes> {
+    read_row_bytes(row_gray, opts).map(|r| r.value)
+}
+
+/// [`decode_row_bytes`] по субпиксельным фронтам (см. [`crate::binarize::runs_subpixel`]).
+#[must_use]
+pub fn decode_row_bytes_subpixel(row_gray: &[u8], opts: &DecodeOptions) -> Option<Code128Bytes> {
+    read_row_bytes_subpixel(row_gray, opts).map(|r| r.value)
+}
 
+/// [`decode_row_bytes`] + флаг «без тихой зоны».
+pub(crate) fn read_row_bytes(
+    row_gray: &[u8],
+    opts: &DecodeOptions,
+) -> Option<RowRead<Code128Bytes>> {
+    let runs = row_runs(row_gray, opts);
+    pick_read(runs.iter().flat_map(|rl| stop_candidates(rl, opts)), opts)
+}
+
+/// 1) бинаризация (по `opts.binarization`, см. [`row_binarizations`]) и run-lengths.
+fn row_runs(row_gray: &[u8], opts: &DecodeOptions) -> Vec<Vec<f32>> {
+    if row_gray.len() < opts.min_modules {
+        return Vec::new();
+    }
+    row_binarizations(row_gray, opts, 24)
+        .into_iter()
+        .map(|(_, rl)| rl.iter().copied().map(usize_to_f32).collect())
+        .collect()
+}
+
+/// Все правдоподобные прочтения строки `(текст, расстояние, checksum_сошлась)`
+/// (см. [`crate::one_d::decode_row_candidates`]): по одному на каждый STOP-кандидат,
+/// от которого символы читаются до старта. Расстояние — сумма `patdist` STOP и
+/// всех символов до ближайших эталонов.
+pub(crate) fn row_candidates(row_gray: &[u8], opts: &DecodeOptions) -> Vec<(String, u32, bool)> {
     let patterns = get_patterns();
+    let runs = row_runs(row_gray, opts);
+    runs.iter()
+        .flat_map(|rl| {
+            (0..=rl.len().saturating_sub(7)).filter_map(|i| {
+                let stop_dist = patdist7(normalize7(&rl[i..i + 7]), CODE128_STOP);
+                if stop_dist > opts.code128_stop_tolerance {
+                    return None;
+                }
+                let read = decode_from_stop(rl, i, &patterns)?;
+                let bytes = decode_values_to_bytes(&read.values, read.set)?;
+                Some((bytes.to_text(), stop_dist + read.distance, read.checksum_ok))
+            })
+        })
+        .collect()
+}
 
-    // 2) ищем STOP: окно из 7 run'ов нормализуем к сумме 13 и сравниваем
-    let mut stop_pos: Option<usize> = None;
-    for i in 0..=rl.len().saturating_sub(7) {
-        let cand = normalize7(&rl[i..i + 7]);
-        if patdist7(cand, CODE128_STOP) <= 1 {
-            stop_pos = Some(i);
-            break;
-        }
+/// [`decode_row_bytes_subpixel`] + флаг «без тихой зоны».
+pub(crate) fn read_row_bytes_subpixel(
+    row_gray: &[u8],
+    opts: &DecodeOptions,
+) -> Option<RowRead<Code128Bytes>> {
+    if row_gray.len() < opts.min_modules {
+        return None;
+    }
+    let rl = runs_subpixel(row_gray);
+    if rl.len() < 24 || !subpixel_runs_look_like_barcode(&rl, opts) {
+        return None;
     }
-    let stop_i = stop_pos?; // индекс первого run'а STOP
+    pick_read(stop_candidates(&rl, opts), opts)
+}
+
+/// Общая часть: STOP, символы назад до старта, checksum — по (дробным) ширинам run'ов.
+/// Прочтения от каждого STOP-кандидата — для [`pick_read`].
+fn stop_candidates<'a>(
+    rl: &'a [f32],
+    opts: &'a DecodeOptions,
+) -> impl Iterator<Item = RowCandidate<Code128Bytes>> + 'a {
+    let patterns = get_patterns();
+
+    // 2) ищем STOP: окно из 7 run'ов (с завершающим штрихом в 2 модуля) нормализуем
+    //    к сумме 13 и сравниваем с допуском `code128_stop_tolerance`. Внутри данных
+    //    такое окно тоже встречается — кандидаты перебираем, пока символ не сойдётся;
+    //    символ с тихими зонами и сошедшейся суммой предпочтительнее (см. [`pick_read`]).
+    (0..=rl.len().saturating_sub(7))
+        .filter(|&i| {
+            patdist7(normalize7(&rl[i..i + 7]), CODE128_STOP) <= opts.code128_stop_tolerance
+        })
+        .filter_map(move |i| {
+            let StopRead {
+                values,
+                set,
+                start,
+                checksum_ok,
+                ..
+            } = decode_from_stop(rl, i, &patterns)?;
+            if !checksum_ok && opts.checksum == ChecksumPolicy::Require {
+                return None;
+            }
+            let bytes = decode_values_to_bytes(&values, set)?;
+            let stop_module = rl[i..i + 7].iter().sum::<f32>() / 13.0;
+            let start_module = rl[start..start + 6].iter().sum::<f32>() / 11.0;
+            let quiet = has_quiet_zone(rl[i + 7..].iter().copied(), stop_module)
+                && has_quiet_zone(rl[..start].iter().rev().copied(), start_module);
+            // старт, payload, checksum по 11 модулей + STOP 13
+            let extent = Extent::new(
+                11 * (values.len() + 2) + 13,
+                &rl[start..i + 7],
+                rl[..start].iter().sum(),
+            );
+            Some(RowCandidate {
+                value: bytes,
+                extent,
+                quiet,
+                checksum_ok,
+            })
+        })
+}
+
+/// Прочтение от одного STOP-кандидата (см. [`decode_from_stop`]).
+struct StopRead {
+    /// Значения payload (без checksum).
+    values: Vec<u8>,
+    /// Стартовый набор.
+    set: CodeSet,
+    /// Первый run старт-кода.
+    start: usize,
+    checksum_ok: bool,
+    /// Сумма расстояний символов (со стартом и checksum) до ближайших эталонов.
+    distance: u32,
+}
 
+/// Символы назад от STOP (run `stop_i`) до старт-кода и проверка checksum.
+fn decode_from_stop(rl: &[f32], stop_i: usize, patterns: &[[u8; 6]; 106]) -> Option<StopRead> {
     // 3) идём НАЗАД по 6-run символам, пока не встретим Start A/B/C
     let mut idx = stop_i; // текущая правая граница символа
     if idx < 6 {
@@ -80,13 +256,15 @@ pub fn decode_row(row_gray: &[u8], opts: &DecodeOptions) -> Option<String> {
 
     let mut vals_rev: Vec<u8> = Vec::new(); // чек + payload (в обратном порядке справа-налево)
     let mut start_set: Option<CodeSet> = None;
+    let mut distance = 0u32;
 
     while idx >= 6 {
         let pat6 = normalize6(&rl[idx - 6..idx]);
-        let (val, dist) = best_code_match(pat6, &patterns);
+        let (val, dist) = best_code_match(pat6, patterns);
         if dist > 1 || val > 105 {
             return None;
         }
+        distance += dist;
         if (103..=105).contains(&val) {
             start_set = Some(match val {
                 103 => CodeSet::A,
@@ -105,6 +283,11 @@ pub fn decode_row(row_gray: &[u8], opts: &DecodeOptions) -> Option<String> {
     }
 
     let start_set = start_set?;
+    let start_code = match start_set {
+        CodeSet::A => 103u8,
+        CodeSet::B => 104u8,
+        CodeSet::C => 105u8,
+    };
     if vals_rev.is_empty() {
         return None;
     } // нет даже checksum
@@ -115,50 +298,45 @@ pub fn decode_row(row_gray: &[u8], opts: &DecodeOptions) -> Option<String> {
 
     // 4) проверим checksum (ВАЖНО: считаем ТОЛЬКО по payload, без последнего символа)
     let n = values.len() - 1; // длина payload
-    let mut sum = match start_set {
-        CodeSet::A => 103u32,
-        CodeSet::B => 104u32,
-        CodeSet::C => 105u32,
-    };
-    for (i, &v) in values[..n].iter().enumerate() {
-        sum = sum.wrapping_add((v as u32) * ((i as u32) + 1));
-    }
-    let checksum_calc = sum % 103;
-    let checksum_from_code = values[n] as u32;
-    if checksum_calc != checksum_from_code {
-        return None;
-    }
-
-    // 5) декодируем payload (без checksum) начиная с ИСХОДНОГО старт-набора
-    let payload = &values[..n];
-    decode_values_to_text(payload, start_set)
+    let checksum_ok = code128_check_value(start_code, &values[..n]) == values[n];
+
+    // 5) payload (без checksum) декодируется начиная с ИСХОДНОГО старт-набора
+    let mut payload = values;
+    payload.truncate(n);
+    Some(StopRead {
+        values: payload,
+        set: start_set,
+        start: idx - 6,
+        checksum_ok,
+        distance,
+    })
 }
 
 // === Локальная нормализация символов ===
 
 #[inline]
-fn normalize6(slice: &[usize]) -> [u8; 6] {
+fn normalize6(slice: &[f32]) -> [u8; 6] {
     debug_assert!(slice.len() == 6);
-    let sum: usize = slice.iter().sum();
-    let scale = (sum as f32) / 11.0_f32;
+    let sum: f32 = slice.iter().sum();
+    let scale = sum / 11.0_f32;
     let mut out = [0u8; 6];
     for (k, &w) in slice.iter().enumerate() {
-        let v = ((w as f32) / scale).round() as i32;
-        out[k] = v.clamp(1, 4) as u8;
+        let v = f32_to_usize((w / scale).round()).clamp(1, 4);
+        out[k] = u8::try_from(v).unwrap_or(4);
     }
     adjust_sum_to(&mut out, 11);
     out
 }
 
 #[inline]
-fn normalize7(slice: &[usize]) -> [u8; 7] {
+fn normalize7(slice: &[f32]) -> [u8; 7] {
     debug_assert!(slice.len() == 7);
-    let sum: usize = slice.iter().sum();
-    let scale = (sum as f32) / 13.0_f32;
+    let sum: f32 = slice.iter().sum();
+    let scale = sum / 13.0_f32;
     let mut out = [0u8; 7];
     for (k, &w) in slice.iter().enumerate() {
-        let v = ((w as f32) / scale).round() as i32;
-        out[k] = v.clamp(1, 4) as u8; // в STOP максимум 3, но clamp(4) безопасен
+        let v = f32_to_usize((w / scale).round()).clamp(1, 4); // в STOP максимум 3, но clamp(4) безопасен
+        out[k] = u8::try_from(v).unwrap_or(4);
     }
     adjust_sum_to7(&mut out, 13);
     out
@@ -231,13 +409,25 @@ enum NextShift {
     B,
 }
 
-fn decode_values_to_text(vals: &[u8], mut set: CodeSet) -> Option<String> {
-    let mut out = String::new();
-    let mut i = 0usize;
+fn decode_values_to_bytes(vals: &[u8], mut set: CodeSet) -> Option<Code128Bytes> {
+    // FNC1 — значение 102 во всех трёх наборах
+    let start_set = match set {
+        CodeSet::A => 'A',
+        CodeSet::B => 'B',
+        CodeSet::C => 'C',
+    };
+    let mut out = Code128Bytes {
+        gs1: vals.first() == Some(&102),
+        start_set,
+        ..Code128Bytes::default()
+    };
     let mut shift: NextShift = NextShift::None;
+    // FNC4: `ext_once` — +128 к следующему символу данных, `ext_latch` — режим.
+    let mut ext_once = false;
+    let mut ext_latch = false;
 
-    while i < vals.len() {
-        let v = vals[i] as u32;
+    for &val in vals {
+        let v = u32::from(val);
 
         // SHIFT действует на один следующий символ
         let effective_set = match (set, shift) {
@@ -246,41 +436,64 @@ fn decode_values_to_text(vals: &[u8], mut set: CodeSet) -> Option<String> {
             _ => set,
         };
 
+        // символ данных (ASCII) или функциональный символ
+        let mut data: Option<u8> = None;
+        let mut fnc: Option<Fnc> = None;
         match effective_set {
             CodeSet::A => match v {
-                0..=95 => out.push(v as u8 as char), // ASCII 0..95
-                96 | 97 => {}                        // FNC3/FNC2 — пропустим
+                0..=63 => data = Some(val + 32),  // ASCII 32..95
+                64..=95 => data = Some(val - 64), // управляющие ASCII 0..31
+                96 => fnc = Some(Fnc::Fnc3),
+                97 => fnc = Some(Fnc::Fnc2),
                 98 => { /* SHIFT — применится к следующему */ }
                 99 => set = CodeSet::C,
                 100 => set = CodeSet::B,
-                101 => { /* остаёмся в A */ }
-                102 => out.push(29u8 as char), // FNC1 -> ASCII GS
+                101 => fnc = Some(Fnc::Fnc4),
+                102 => fnc = Some(Fnc::Fnc1),
                 _ => return None,
             },
             CodeSet::B => match v {
-                0..=95 => out.push((v as u8 + 32) as char), // ASCII 32..127
-                96 | 97 => {}
+                0..=95 => data = Some(val + 32), // ASCII 32..127
+                96 => fnc = Some(Fnc::Fnc3),
+                97 => fnc = Some(Fnc::Fnc2),
                 98 => { /* SHIFT — применится к следующему */ }
                 99 => set = CodeSet::C,
-                100 => { /* остаёмся в B */ }
+                100 => fnc = Some(Fnc::Fnc4),
                 101 => set = CodeSet::A,
-                102 => out.push(29u8 as char),
+                102 => fnc = Some(Fnc::Fnc1),
                 _ => return None,
             },
             CodeSet::C => match v {
-                99 => { /* CODE C — остаёмся в C */ }
-                0..=98 => {
-                    // две цифры за символ
-                    out.push(char::from(b'0' + (v / 10) as u8));
-                    out.push(char::from(b'0' + (v % 10) as u8));
+                0..=99 => {
+                    // две цифры за символ (FNC4 на набор C не действует)
+                    out.bytes.push(b'0' + val / 10);
+                    out.bytes.push(b'0' + val % 10);
                 }
                 100 => set = CodeSet::B,
                 101 => set = CodeSet::A,
-                102 => out.push(29u8 as char),
+                102 => fnc = Some(Fnc::Fnc1),
                 _ => return None,
             },
         }
 
+        if let Some(b) = data {
+            let hi = ext_latch != ext_once;
+            out.bytes.push(if hi { b | 0x80 } else { b });
+            ext_once = false;
+        }
+        if let Some(f) = fnc {
+            if f == Fnc::Fnc4 {
+                // двойной FNC4 переключает режим, одиночный — только следующий символ
+                if ext_once {
+                    ext_latch = !ext_latch;
+                    ext_once = false;
+                } else {
+                    ext_once = true;
+                }
+            }
+            out.fnc.push((out.bytes.len(), f));
+        }
+
         if shift != NextShift::None {
             shift = NextShift::None;
         } else if v == 98 {
@@ -290,8 +503,6 @@ fn decode_values_to_text(vals: &[u8], mut set: CodeSet) -> Option<String> {
                 CodeSet::C => NextShift::None, // в C shift не применим
             };
         }
-
-        i += 1;
     }
     Some(out)
 }
@@ -350,81 +561,135 @@ fn best_code_match(pat: [u8; 6], patterns: &[[u8; 6]; 106]) -> (usize, u32) {
     (best.1, best.0)
 }
 
+/// Контрольный символ Code128: `(start + Σ i·data[i-1]) mod 103`, где `start` —
+/// код старта (103/104/105 для A/B/C), `data` — значения символов после него
+/// (веса с 1), без самого контрольного символа и STOP.
+#[must_use]
+pub fn code128_check_value(start: u8, data: &[u8]) -> u8 {
+    let sum = data
+        .iter()
+        .zip(1u32..)
+        .fold(u32::from(start), |acc, (&v, w)| acc + u32::from(v) * w)
+        % 103;
+    // < 103, так что усечение невозможно
+    u8::try_from(sum).unwrap_or(0)
+}
+
 // === Синтезатор для тестов/демо ===
 
-/// Сгенерировать идеальный одномерный ряд (ч/б пиксели) для Code128.
-/// Поддержка наборов: 'A', 'B', 'C'.
-pub fn synthesize_row_code128(text: &str, set: char, unit: usize) -> Vec<u8> {
-    assert!(unit >= 1);
-    let patterns = get_patterns();
+/// Сгенерировать идеальный одномерный ряд (ч/б пиксели) для Code128: набор
+/// `set` — 'A', 'B' или 'C', тихая зона — `quiet_zone` модулей с каждой стороны
+/// (`0` — без неё, стандартная — `Symbology::Code128.standard_quiet_zone()`).
+///
+/// # Errors
+///
+/// [`SynthError`] — символ вне набора, нечётное число цифр в наборе C,
+/// неизвестный набор или `unit == 0`.
+pub fn synthesize_row_code128(
+    text: &str,
+    set: char,
+    quiet_zone: usize,
+    unit: usize,
+) -> Result<Vec<u8>, SynthError> {
+    if unit == 0 {
+        return Err(SynthError::ZeroUnit);
+    }
 
     // 1) собрать последовательность кодов (без checksum/stop)
-    let mut codes: Vec<usize> = Vec::new();
-    let set_cur = match set {
-        'A' | 'a' => CodeSet::A,
-        'B' | 'b' => CodeSet::B,
-        'C' | 'c' => CodeSet::C,
-        _ => CodeSet::B,
+    let (set_cur, start) = match set {
+        'A' | 'a' => (CodeSet::A, 103u8),
+        'B' | 'b' => (CodeSet::B, 104),
+        'C' | 'c' => (CodeSet::C, 105),
+        _ => return Err(SynthError::UnknownSet(set)),
+    };
+    let invalid = |(index, ch): (usize, char)| SynthError::InvalidCharForSet {
+        ch,
+        index,
+        set: set.to_ascii_uppercase(),
     };
 
-    match set_cur {
-        CodeSet::A => codes.push(103),
-        CodeSet::B => codes.push(104),
-        CodeSet::C => codes.push(105),
-    }
-
+    let mut values: Vec<u8> = vec![start];
     match set_cur {
         CodeSet::B => {
-            for ch in text.chars() {
-                let b = ch as u32;
-                assert!((32..=127).contains(&b), "Code128B: только ASCII 32..127");
-                codes.push((b - 32) as usize);
+            for (i, ch) in text.chars().enumerate() {
+                let b = u8::try_from(ch).ok().filter(|b| (32..=127).contains(b));
+                let b = b.ok_or_else(|| invalid((i, ch)))?;
+                values.push(b - 32);
             }
         }
         CodeSet::A => {
-            for ch in text.chars() {
-                let b = ch as u32;
-                assert!((0..=95).contains(&b), "Code128A: только ASCII 0..95");
-                codes.push(b as usize);
+            for (i, ch) in text.chars().enumerate() {
+                let b = u8::try_from(ch).ok().filter(|&b| b <= 95);
+                let b = b.ok_or_else(|| invalid((i, ch)))?;
+                // 32..95 -> 0..63, управляющие 0..31 -> 64..95
+                values.push(if b >= 32 { b - 32 } else { b + 64 });
             }
         }
         CodeSet::C => {
-            assert!(
-                text.len() % 2 == 0,
-                "Code128C: число цифр должно быть чётным"
-            );
-            let bytes = text.as_bytes();
-            for k in (0..bytes.len()).step_by(2) {
-                assert!(
-                    bytes[k].is_ascii_digit() && bytes[k + 1].is_ascii_digit(),
-                    "Code128C: только цифры"
-                );
-                let v = (bytes[k] - b'0') as usize * 10 + (bytes[k + 1] - b'0') as usize;
-                codes.push(v);
+            if let Some(bad) = text
+                .chars()
+                .enumerate()
+                .find(|(_, ch)| !ch.is_ascii_digit())
+            {
+                return Err(invalid(bad));
+            }
+            let digits = text.as_bytes();
+            if digits.len() % 2 != 0 {
+                return Err(SynthError::OddDigitCount(digits.len()));
+            }
+            for pair in digits.chunks_exact(2) {
+                values.push((pair[0] - b'0') * 10 + (pair[1] - b'0'));
             }
         }
     }
 
-    // 2) checksum
-    let mut sum = codes[0] as u32;
-    for (i, &v) in codes.iter().enumerate().skip(1) {
-        sum += (v as u32) * (i as u32);
+    synthesize_row_code128_values(&values, quiet_zone, unit)
+}
+
+/// Ряд Code128 из готовых значений символов: `values[0]` — старт-код (103..=105),
+/// далее payload как есть (в т.ч. FNC1..4, SHIFT, CODE x). Checksum и STOP добавляются;
+/// тихая зона — как у [`synthesize_row_code128`].
+///
+/// # Errors
+///
+/// [`SynthError::InvalidValue`] — нет старт-кода или значение payload > 102;
+/// [`SynthError::ZeroUnit`] — `unit == 0`.
+pub fn synthesize_row_code128_values(
+    values: &[u8],
+    quiet_zone: usize,
+    unit: usize,
+) -> Result<Vec<u8>, SynthError> {
+    if unit == 0 {
+        return Err(SynthError::ZeroUnit);
     }
-    let check = (sum % 103) as usize;
-    codes.push(check);
+    match values.first() {
+        Some(start) if (103..=105).contains(start) => {}
+        start => {
+            return Err(SynthError::InvalidValue {
+                value: start.copied().unwrap_or(0),
+                index: 0,
+            })
+        }
+    }
+    if let Some((index, &value)) = values.iter().enumerate().skip(1).find(|&(_, &v)| v > 102) {
+        return Err(SynthError::InvalidValue { value, index });
+    }
+    let patterns = get_patterns();
 
-    // 3) собрать модули: quiet(10) + символы + STOP + quiet(10)
+    // 2) checksum
+    let mut codes: Vec<usize> = values.iter().map(|&v| usize::from(v)).collect();
+    codes.push(usize::from(code128_check_value(values[0], &values[1..])));
+
+    // 3) собрать модули: символы + STOP (тихие зоны — при переводе в пиксели)
     let mut modules: Vec<u8> = Vec::new();
-    modules.push(10); // quiet белый
     for &code in &codes {
         modules.extend_from_slice(&patterns[code]);
     }
     modules.extend_from_slice(&CODE128_STOP);
-    modules.push(10); // quiet белый
 
-    // 4) модули -> пиксели (начинаем с белого — quiet)
-    let mut pix: Vec<u8> = Vec::new();
-    let mut black = false;
+    // 4) quiet + модули -> пиксели (первый модуль — чёрный) + quiet
+    let mut pix: Vec<u8> = vec![255; quiet_zone * unit];
+    let mut black = true;
     for m in modules {
         let w = (m as usize) * unit;
         let val = if black { 0 } else { 255 };
@@ -433,17 +698,28 @@ pub fn synthesize_row_code128(text: &str, set: char, unit: usize) -> Vec<u8> {
         }
         black = !black;
     }
-    pix
+    pix.resize(pix.len() + quiet_zone * unit, 255);
+    Ok(pix)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::types::QuietZone;
     use crate::GrayImage;
 
+    #[test]
+    fn check_value_known() {
+        // "PJJ123C" в наборе B: 879 mod 103
+        assert_eq!(code128_check_value(104, &[48, 42, 42, 17, 18, 19, 35]), 55);
+        // "123456" в наборе C
+        assert_eq!(code128_check_value(105, &[12, 34, 56]), 44);
+        assert_eq!(code128_check_value(103, &[]), 0);
+    }
+
     #[test]
     fn code128_b_simple() {
-        let row = synthesize_row_code128("HELLO-128", 'B', 2);
+        let row = synthesize_row_code128("HELLO-128", 'B', 10, 2).unwrap();
         let img = GrayImage {
             width: row.len(),
             height: 1,
@@ -457,7 +733,7 @@ mod tests {
 
     #[test]
     fn code128_c_digits() {
-        let row = synthesize_row_code128("0123456789", 'C', 2);
+        let row = synthesize_row_code128("0123456789", 'C', 10, 2).unwrap();
         let img = GrayImage {
             width: row.len(),
             height: 1,
@@ -471,7 +747,7 @@ mod tests {
 
     #[test]
     fn code128_b_ascii_span() {
-        let row = synthesize_row_code128("ABcd[]", 'B', 2);
+        let row = synthesize_row_code128("ABcd[]", 'B', 10, 2).unwrap();
         let img = GrayImage {
             width: row.len(),
             height: 1,
@@ -482,4 +758,229 @@ mod tests {
         assert!(!res.is_empty());
         assert_eq!(res[0].text, "ABcd[]");
     }
+
+    #[test]
+    fn bytes_separate_fnc1_from_literal_gs() {
+        // Start B, FNC1, "A", CODE A, GS (литерал, 29 + 64), "B"
+        let row = synthesize_row_code128_values(&[104, 102, 33, 101, 93, 34], 10, 2).unwrap();
+        let opts = DecodeOptions::default();
+
+        let d = decode_row_bytes(&row, &opts).expect("decode");
+        assert_eq!(d.bytes, b"A\x1dB");
+        assert_eq!(d.fnc, [(0, Fnc::Fnc1)]);
+
+        // В тексте оба превращаются в GS — отличить можно только по байтам.
+        assert_eq!(decode_row(&row, &opts).as_deref(), Some("\x1dA\x1dB"));
+        assert!(d.gs1);
+
+        // FNC1 не первым символом (Start B, "A", FNC1, "B") — разделитель, не GS1
+        let row = synthesize_row_code128_values(&[104, 33, 102, 34], 10, 2).unwrap();
+        let d = decode_row_bytes(&row, &opts).expect("decode");
+        assert_eq!(d.fnc, [(1, Fnc::Fnc1)]);
+        assert!(!d.gs1);
+    }
+
+    #[test]
+    fn bytes_apply_fnc4_extension() {
+        // Start B, FNC4 'A' (-> 0xC1), 'b', FNC4 FNC4 (режим) 'C' 'D', FNC4 FNC4 'e'
+        let row = synthesize_row_code128_values(
+            &[104, 100, 33, 66, 100, 100, 35, 36, 100, 100, 69],
+            10,
+            2,
+        )
+        .unwrap();
+        let d = decode_row_bytes(&row, &DecodeOptions::default()).expect("decode");
+        assert_eq!(d.bytes, [0xC1, b'b', 0xC3, 0xC4, b'e']);
+        assert!(d.fnc.iter().all(|&(_, f)| f == Fnc::Fnc4));
+        assert_eq!(d.to_text(), "Áb\u{c3}\u{c4}e");
+    }
+
+    #[test]
+    fn value_98_in_set_c_is_digits_not_shift() {
+        let opts = DecodeOptions::default();
+        // Start C: 12 98 34 — в C значение 98 это цифры «98», а не SHIFT.
+        let row = synthesize_row_code128_values(&[105, 12, 98, 34], 10, 2).unwrap();
+        assert_eq!(
+            decode_row_bytes(&row, &opts).expect("decode").bytes,
+            b"129834"
+        );
+        assert_eq!(synthesize_row_code128("129834", 'C', 10, 2).unwrap(), row);
+
+        // 98 последним в C, затем CODE B и 'a': «98» не оставляет висящий shift,
+        // и 'a' читается в B (в A значение 65 — управляющий 0x01).
+        let row = synthesize_row_code128_values(&[105, 98, 100, 65], 10, 2).unwrap();
+        assert_eq!(decode_row_bytes(&row, &opts).expect("decode").bytes, b"98a");
+
+        // То же через CODE A, затем SHIFT в A: 'a' из B, следом 65 снова в A — 0x01.
+        let row = synthesize_row_code128_values(&[105, 98, 101, 33, 98, 65, 65], 10, 2).unwrap();
+        assert_eq!(
+            decode_row_bytes(&row, &opts).expect("decode").bytes,
+            b"98Aa\x01"
+        );
+    }
+
+    #[test]
+    fn shift_switches_set_for_one_character() {
+        let opts = DecodeOptions::default();
+        // Start B: "ab", SHIFT, TAB (73 в A), "c" — после сдвинутого символа снова B.
+        let row = synthesize_row_code128_values(&[104, 65, 66, 98, 73, 67], 10, 2).unwrap();
+        assert_eq!(
+            decode_row_bytes(&row, &opts).expect("decode").bytes,
+            b"ab\tc"
+        );
+
+        // Start A: "AB", SHIFT, 'x' (88 в B; в A это 0x18), "C" и снова 88 — уже 0x18.
+        let row = synthesize_row_code128_values(&[103, 33, 34, 98, 88, 35, 88], 10, 2).unwrap();
+        assert_eq!(
+            decode_row_bytes(&row, &opts).expect("decode").bytes,
+            b"ABxC\x18"
+        );
+
+        // Два SHIFT подряд — по символу каждый, набор не защёлкивается.
+        let row = synthesize_row_code128_values(&[104, 98, 73, 98, 73, 65], 10, 2).unwrap();
+        assert_eq!(
+            decode_row_bytes(&row, &opts).expect("decode").bytes,
+            b"\t\ta"
+        );
+
+        // SHIFT и сразу CODE C: после сдвинутого символа защёлка на C работает как обычно.
+        let row = synthesize_row_code128_values(&[104, 65, 98, 73, 99, 12, 98], 10, 2).unwrap();
+        assert_eq!(
+            decode_row_bytes(&row, &opts).expect("decode").bytes,
+            b"a\t1298"
+        );
+
+        // FNC4 перед SHIFT: +128 достаётся сдвинутому символу ('A' в B → 0xC1 через A).
+        let row = synthesize_row_code128_values(&[104, 100, 98, 33, 65], 10, 2).unwrap();
+        let d = decode_row_bytes(&row, &opts).expect("decode");
+        assert_eq!(d.bytes, [0xC1, b'a']);
+        assert_eq!(d.fnc, [(0, Fnc::Fnc4)]);
+    }
+
+    #[test]
+    fn code128_a_control_chars() {
+        let row = synthesize_row_code128("AB\tC\x1d9", 'A', 10, 2).unwrap();
+        let d = decode_row_bytes(&row, &DecodeOptions::default()).expect("decode");
+        assert_eq!(d.bytes, b"AB\tC\x1d9");
+        assert!(d.fnc.is_empty());
+    }
+
+    #[test]
+    fn stop_inside_data_is_skipped() {
+        // Run'ы «!T» (и checksum) внутри данных складываются в окно 2331112 —
+        // раньше это окно принималось за STOP, и ряд не читался.
+        let row = synthesize_row_code128("!T", 'B', 10, 2).unwrap();
+        assert_eq!(
+            decode_row(&row, &DecodeOptions::default()).as_deref(),
+            Some("!T")
+        );
+
+        // Без тихой зоны за STOP (сразу следующий штрих) кандидат отвергается.
+        let mut crowded = row.clone();
+        let tail = crowded.len() - 10 * 2;
+        crowded.truncate(tail + 4);
+        crowded.extend_from_slice(&[0; 6]);
+        crowded.extend_from_slice(&[255; 40]);
+        assert_eq!(decode_row(&crowded, &DecodeOptions::default()), None);
+    }
+
+    #[test]
+    fn crowded_stop_reads_with_relaxed_quiet_zone() {
+        // Тот же «!T», что в `stop_inside_data_is_skipped`, со штрихом вплотную за
+        // STOP: Relaxed читает его с пометкой.
+        let mut crowded = synthesize_row_code128("!T", 'B', 10, 2).unwrap();
+        let tail = crowded.len() - 10 * 2;
+        crowded.truncate(tail + 4);
+        crowded.extend_from_slice(&[0; 6]);
+        crowded.extend_from_slice(&[255; 40]);
+        let relaxed = DecodeOptions {
+            quiet_zone: QuietZone::Relaxed,
+            ..DecodeOptions::default()
+        };
+        let read = read_row_bytes(&crowded, &relaxed).expect("relaxed");
+        assert_eq!(read.value.to_text(), "!T");
+        assert!(read.quiet_missing);
+    }
+
+    #[test]
+    fn stop_tolerance_accepts_smeared_stop() {
+        // Первый штрих STOP расплылся на модуль вправо: 2331112 -> 3231112 (расстояние 2).
+        let mut row = synthesize_row_code128("SMEAR", 'B', 10, 2).unwrap();
+        let stop = row.len() - 10 * 2 - 13 * 2;
+        row[stop + 4..stop + 6].fill(0);
+
+        assert_eq!(decode_row(&row, &DecodeOptions::default()), None);
+        let loose = DecodeOptions {
+            code128_stop_tolerance: 2,
+            ..DecodeOptions::default()
+        };
+        assert_eq!(decode_row(&row, &loose).as_deref(), Some("SMEAR"));
+    }
+
+    #[test]
+    fn synthesize_reports_invalid_input() {
+        assert_eq!(
+            synthesize_row_code128("ab", 'a', 10, 2),
+            Err(SynthError::InvalidCharForSet {
+                ch: 'a',
+                index: 0,
+                set: 'A'
+            })
+        );
+        assert_eq!(
+            synthesize_row_code128("OK\u{e9}", 'B', 10, 2),
+            Err(SynthError::InvalidCharForSet {
+                ch: '\u{e9}',
+                index: 2,
+                set: 'B'
+            })
+        );
+        assert_eq!(
+            synthesize_row_code128("12345", 'C', 10, 2),
+            Err(SynthError::OddDigitCount(5))
+        );
+        assert_eq!(
+            synthesize_row_code128("12a4", 'C', 10, 2),
+            Err(SynthError::InvalidCharForSet {
+                ch: 'a',
+                index: 2,
+                set: 'C'
+            })
+        );
+        assert_eq!(
+            synthesize_row_code128("X", 'D', 10, 2),
+            Err(SynthError::UnknownSet('D'))
+        );
+        assert_eq!(
+            synthesize_row_code128("X", 'B', 10, 0),
+            Err(SynthError::ZeroUnit)
+        );
+
+        // Готовые значения: первым — старт-код, дальше 0..=102.
+        assert_eq!(
+            synthesize_row_code128_values(&[], 10, 2),
+            Err(SynthError::InvalidValue { value: 0, index: 0 })
+        );
+        assert_eq!(
+            synthesize_row_code128_values(&[40, 41], 10, 2),
+            Err(SynthError::InvalidValue {
+                value: 40,
+                index: 0
+            })
+        );
+        assert_eq!(
+            synthesize_row_code128_values(&[104, 41, 106], 10, 2),
+            Err(SynthError::InvalidValue {
+                value: 106,
+                index: 2
+            })
+        );
+
+        // Тихая зона: `quiet_zone·unit` px белого с каждой стороны, `0` — ряд с штриха.
+        let bare = synthesize_row_code128("HELLO-128", 'B', 0, 2).unwrap();
+        let framed = synthesize_row_code128("HELLO-128", 'B', 10, 2).unwrap();
+        assert_eq!(bare[0], 0);
+        assert_eq!(framed.len(), bare.len() + 2 * 10 * 2);
+        assert_eq!(&framed[20..framed.len() - 20], &bare[..]);
+    }
 }
@@ -0,0 +1,415 @@
+//! Code 93: декодер по одной строке + синтезатор (для тестов/демо).
+//!
+//! Поддержка:
+//! - 47 символов (9 модулей, 3 бара + 3 пробела) + старт/стоп `*`
+//!   с завершающим баром в 1 модуль.
+//! - Две контрольные C и K (взвешенный mod 47), в текст не попадают.
+//! - Full ASCII через shift-символы `($)`, `(%)`, `(/)`, `(+)` —
+//!   раскрывается при `DecodeOptions::code93_full_ascii`, иначе
+//!   shift'ы остаются в тексте как есть, в скобках.
+//! - Тихие зоны перед стартом и за завершающим баром — по `DecodeOptions::quiet_zone`.
+
+use crate::core::cast::{f32_to_i32, f32_to_usize, usize_to_f32};
+#[cfg(not(feature = "std"))]
+use crate::core::math::FloatExt;
+use crate::core::types::{ChecksumPolicy, SynthError};
+use crate::one_d::{
+    has_quiet_zone, pick_read, row_binarizations, DecodeOptions, Extent, RowCandidate, RowRead,
+};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Паттерны 0..=47: по 6 чисел (bars/spaces), сумма 9.
+/// Порядок значений — как в `CODE93_ALPHABET`; 43..=46 — shift'ы, 47 — `*`.
+const CODE93_PATTERNS_STR: [&str; 48] = [
+    "131112", "111213", "111312", "111411", "121113", "121212", "121311", "111114", "131211",
+    "141111", "211113", "211212", "211311", "221112", "221211", "231111", "112113", "112212",
+    "112311", "122112", "132111", "111123", "111222", "111321", "121122", "131121", "212112",
+    "212211", "211122", "211221", "221121", "222111", "112122", "112221", "122121", "123111",
+    "121131", "311112", "311211", "321111", "112131", "113121", "211131", "121221", "312111",
+    "311121", "122211", "111141",
+];
+
+/// Символы для значений 0..=42 (shift'ы и `*` обрабатываются отдельно).
+const CODE93_ALPHABET: &[u8; 43] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ-. $/+%";
+
+/// Значения shift-символов `($)`, `(%)`, `(/)`, `(+)`.
+const SHIFT_DOLLAR: u8 = 43;
+const SHIFT_PERCENT: u8 = 44;
+const SHIFT_SLASH: u8 = 45;
+const SHIFT_PLUS: u8 = 46;
+/// Старт/стоп `*`.
+const START_STOP: u8 = 47;
+
+/// Минимум run'ов: `*` + 1 символ + C + K + `*` + завершающий бар.
+const MIN_RUNS: usize = 31;
+
+/// Попытка декодировать один ряд в Code 93. Успех -> строка.
+#[must_use]
+pub fn decode_row(row_gray: &[u8], opts: &DecodeOptions) -> Option<String> {
+    read_row(row_gray, opts).map(|r| r.value)
+}
+
+/// [`decode_row`] + флаг «без тихой зоны».
+pub(crate) fn read_row(row_gray: &[u8], opts: &DecodeOptions) -> Option<RowRead<String>> {
+    let runs = row_runs(row_gray, opts);
+    pick_read(
+        runs.iter()
+            .flat_map(|(rb, rl)| start_candidates(rb, rl, opts)),
+        opts,
+    )
+}
+
+/// 2) ищем старт `*` только на чёрных run'ах, затем читаем символы до стопа.
+fn start_candidates<'a>(
+    rb: &[bool],
+    rl: &'a [usize],
+    opts: &'a DecodeOptions,
+) -> impl Iterator<Item = RowCandidate<String>> + 'a {
+    let patterns = get_patterns();
+    let first_bar = usize::from(!rb[0]);
+    let widths: Vec<f32> = rl.iter().copied().map(usize_to_f32).collect();
+    (first_bar..rl.len().saturating_sub(5))
+        .step_by(2)
+        .filter(move |&i| normalize9(&rl[i..i + 6]) == patterns[START_STOP as usize])
+        .filter_map(move |i| {
+            let (values, term, checksum_ok) = read_symbols(rl, i + 6, &patterns)?;
+            if !checksum_ok && opts.checksum == ChecksumPolicy::Require {
+                return None;
+            }
+            let text = values_to_text(&values, opts.code93_full_ascii)?;
+            let module = |at: usize| widths[at..at + 6].iter().sum::<f32>() / 9.0;
+            let quiet = has_quiet_zone(widths[..i].iter().rev().copied(), module(i))
+                && has_quiet_zone(widths[term + 1..].iter().copied(), module(term - 6));
+            // старт, данные, C, K, стоп по 9 модулей + завершающий бар
+            let extent = Extent::new(
+                9 * (values.len() + 4) + 1,
+                &widths[i..=term],
+                widths[..i].iter().sum(),
+            );
+            Some(RowCandidate {
+                value: text,
+                extent,
+                quiet,
+                checksum_ok,
+            })
+        })
+}
+
+/// 1) бинаризация (по `opts.binarization`, см. [`row_binarizations`]) и run-lengths.
+fn row_runs(row_gray: &[u8], opts: &DecodeOptions) -> Vec<(Vec<bool>, Vec<usize>)> {
+    if row_gray.len() < opts.min_modules {
+        return Vec::new();
+    }
+    row_binarizations(row_gray, opts, MIN_RUNS)
+}
+
+/// Все правдоподобные прочтения строки `(текст, расстояние, C/K_сошлись)`
+/// (см. [`crate::one_d::decode_row_candidates`]): по одному на каждый старт `*`,
+/// от которого символы читаются до стопа. Паттерны сравниваются точно —
+/// расстояние всегда `0`.
+pub(crate) fn row_candidates(row_gray: &[u8], opts: &DecodeOptions) -> Vec<(String, u32, bool)> {
+    let patterns = get_patterns();
+    let runs = row_runs(row_gray, opts);
+    runs.iter()
+        .flat_map(|(rb, rl)| {
+            (usize::from(!rb[0])..rl.len().saturating_sub(5))
+                .step_by(2)
+                .filter(|&i| normalize9(&rl[i..i + 6]) == patterns[START_STOP as usize])
+                .filter_map(|i| {
+                    let (values, _, checksum_ok) = read_symbols(rl, i + 6, &patterns)?;
+                    Some((
+                        values_to_text(&values, opts.code93_full_ascii)?,
+                        0,
+                        checksum_ok,
+                    ))
+                })
+        })
+        .collect()
+}
+
+/// Значения символов между стартом (run `idx`) и стопом `*` + завершающий бар
+/// (второй элемент — его run). Контрольные C/K проверяются (третий элемент —
+/// сошлись ли обе) и отрезаются.
+fn read_symbols(
+    rl: &[usize],
+    mut idx: usize,
+    patterns: &[[u8; 6]; 48],
+) -> Option<(Vec<u8>, usize, bool)> {
+    let mut values: Vec<u8> = Vec::new();
+    loop {
+        if idx + 6 > rl.len() {
+            return None;
+        }
+        let pat = normalize9(&rl[idx..idx + 6]);
+        let val = patterns.iter().position(|p| *p == pat)?;
+        idx += 6;
+        if val == START_STOP as usize {
+            break;
+        }
+        values.push(u8::try_from(val).ok()?);
+    }
+
+    // завершающий бар: 1 модуль (≈ 1/9 ширины символа)
+    let term = *rl.get(idx)?;
+    let module = usize_to_f32(rl[idx - 6..idx].iter().sum::<usize>()) / 9.0;
+    if f32_to_i32((usize_to_f32(term) / module).round()) != 1 {
+        return None;
+    }
+
+    // хотя бы один символ данных + C + K
+    if values.len() < 3 {
+        return None;
+    }
+    let n = values.len() - 2;
+    let c = check_value(&values[..n], 20);
+    let k = check_value(&values[..=n], 15);
+    let checksum_ok = values[n] == c && values[n + 1] == k;
+    values.truncate(n);
+    Some((values, idx, checksum_ok))
+}
+
+/// Контрольный символ mod 47: веса 1..=`max_weight` циклически, справа налево.
+fn check_value(values: &[u8], max_weight: u32) -> u8 {
+    let sum: u32 = values
+        .iter()
+        .rev()
+        .zip((1..=max_weight).cycle())
+        .map(|(&v, w)| u32::from(v) * w)
+        .sum();
+    (sum % 47) as u8
+}
+
+/// Значения -> текст; при `full_ascii` раскрываем shift-пары.
+fn values_to_text(values: &[u8], full_ascii: bool) -> Option<String> {
+    let mut out = String::with_capacity(values.len());
+    let mut i = 0usize;
+    while i < values.len() {
+        let v = values[i];
+        if v < SHIFT_DOLLAR {
+            out.push(char::from(CODE93_ALPHABET[v as usize]));
+            i += 1;
+            continue;
+        }
+        if !full_ascii {
+            out.push_str(match v {
+                SHIFT_DOLLAR => "($)",
+                SHIFT_PERCENT => "(%)",
+                SHIFT_SLASH => "(/)",
+                _ => "(+)",
+            });
+            i += 1;
+            continue;
+        }
+        // shift действует на следующую букву
+        let next = *values.get(i + 1)?;
+        let c = *CODE93_ALPHABET.get(next as usize)?;
+        out.push(char::from(expand_shift(v, c)?));
+        i += 2;
+    }
+    Some(out)
+}
+
+/// Full ASCII: shift + буква -> байт ASCII (таблица как у Code 39).
+fn expand_shift(shift: u8, c: u8) -> Option<u8> {
+    match (shift, c) {
+        (SHIFT_PLUS, b'A'..=b'Z') => Some(c + 32),    // a..z
+        (SHIFT_DOLLAR, b'A'..=b'Z') => Some(c - 64),  // 0x01..0x1A
+        (SHIFT_PERCENT, b'A'..=b'E') => Some(c - 38), // ESC FS GS RS US
+        (SHIFT_PERCENT, b'F'..=b'J') => Some(c - 11), // ; < = > ?
+        (SHIFT_PERCENT, b'K'..=b'O') => Some(c + 16), // [ \ ] ^ _
+        (SHIFT_PERCENT, b'P'..=b'T') => Some(c + 43), // { | } ~ DEL
+        (SHIFT_PERCENT, b'U') => Some(0),             // NUL
+        (SHIFT_PERCENT, b'V') => Some(b'@'),
+        (SHIFT_PERCENT, b'W') => Some(b'`'),
+        (SHIFT_PERCENT, b'X'..=b'Z') => Some(127),  // DEL
+        (SHIFT_SLASH, b'A'..=b'O') => Some(c - 32), // ! .. /
+        (SHIFT_SLASH, b'Z') => Some(b':'),
+        _ => None,
+    }
+}
+
+/// Обратное к `expand_shift`: байт ASCII -> (значение shift'а или прямое значение, буква).
+/// None — байт вне ASCII 0..127.
+fn encode_ascii(b: u8) -> Option<(u8, Option<u8>)> {
+    if let Some(pos) = CODE93_ALPHABET.iter().position(|&c| c == b) {
+        return Some((u8::try_from(pos).ok()?, None));
+    }
+    for shift in SHIFT_DOLLAR..=SHIFT_PLUS {
+        for c in b'A'..=b'Z' {
+            if expand_shift(shift, c) == Some(b) {
+                let letter = u8::try_from(CODE93_ALPHABET.iter().position(|&x| x == c)?).ok()?;
+                return Some((shift, Some(letter)));
+            }
+        }
+    }
+    None
+}
+
+// === Локальная нормализация символа (6 run'ов, сумма 9) ===
+
+#[inline]
+fn normalize9(slice: &[usize]) -> [u8; 6] {
+    debug_assert!(slice.len() == 6);
+    let sum: usize = slice.iter().sum();
+    let scale = usize_to_f32(sum) / 9.0_f32;
+    let mut out = [0u8; 6];
+    for (k, &w) in slice.iter().enumerate() {
+        let v = f32_to_usize((usize_to_f32(w) / scale).round()).clamp(1, 4);
+        out[k] = u8::try_from(v).unwrap_or(4);
+    }
+    // подгоняем сумму к 9: правим самый широкий/узкий run
+    let mut total: i32 = out.iter().map(|&x| i32::from(x)).sum();
+    while total != 9 {
+        if total > 9 {
+            let (i, _) = out
+                .iter()
+                .enumerate()
+                .rev()
+                .max_by_key(|(_, &x)| x)
+                .unwrap();
+            if out[i] == 1 {
+                break;
+            }
+            out[i] -= 1;
+            total -= 1;
+        } else {
+            let (i, _) = out.iter().enumerate().min_by_key(|(_, &x)| x).unwrap();
+            if out[i] == 4 {
+                break;
+            }
+            out[i] += 1;
+            total += 1;
+        }
+    }
+    out
+}
+
+#[inline]
+fn get_patterns() -> [[u8; 6]; 48] {
+    let mut out = [[0u8; 6]; 48];
+    for (i, s) in CODE93_PATTERNS_STR.iter().enumerate() {
+        let b = s.as_bytes();
+        for k in 0..6 {
+            out[i][k] = b[k] - b'0';
+        }
+    }
+    out
+}
+
+// === Синтезатор для тестов/демо ===
+
+/// Сгенерировать идеальный одномерный ряд (ч/б пиксели) для Code 93: символы
+/// вне базового набора кодируются shift-парами (full ASCII), тихая зона —
+/// `quiet_zone` модулей с каждой стороны (`0` — без неё, стандартная —
+/// `Symbology::Code93.standard_quiet_zone()`).
+///
+/// # Errors
+///
+/// [`SynthError`] — символ вне ASCII или `unit == 0`.
+pub fn synthesize_row_code93(
+    text: &str,
+    quiet_zone: usize,
+    unit: usize,
+) -> Result<Vec<u8>, SynthError> {
+    if unit == 0 {
+        return Err(SynthError::ZeroUnit);
+    }
+    let patterns = get_patterns();
+
+    // 1) значения данных (+ shift'ы), затем C и K
+    let mut values: Vec<u8> = Vec::new();
+    for (index, ch) in text.chars().enumerate() {
+        let encoded = u8::try_from(ch).ok().and_then(encode_ascii);
+        let (v, letter) = encoded.ok_or(SynthError::NonAscii { ch, index })?;
+        values.push(v);
+        values.extend(letter);
+    }
+    values.push(check_value(&values, 20));
+    values.push(check_value(&values, 15));
+
+    // 2) модули: * + символы + * + завершающий бар (тихие зоны — при переводе в пиксели)
+    let mut modules: Vec<u8> = Vec::new();
+    modules.extend_from_slice(&patterns[START_STOP as usize]);
+    for &v in &values {
+        modules.extend_from_slice(&patterns[v as usize]);
+    }
+    modules.extend_from_slice(&patterns[START_STOP as usize]);
+    modules.push(1);
+
+    // 3) quiet + модули -> пиксели (первый модуль — чёрный) + quiet
+    let mut pix: Vec<u8> = vec![255; quiet_zone * unit];
+    let mut black = true;
+    for m in modules {
+        let w = (m as usize) * unit;
+        let val = if black { 0 } else { 255 };
+        for _ in 0..w {
+            pix.push(val);
+        }
+        black = !black;
+    }
+    pix.resize(pix.len() + quiet_zone * unit, 255);
+    Ok(pix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::one_d::decode_code93;
+    use crate::GrayImage;
+
+    #[test]
+    fn code93_basic_roundtrip() {
+        let row = synthesize_row_code93("TEST93", 10, 2).unwrap();
+        let img = GrayImage {
+            width: row.len(),
+            height: 1,
+            data: &row,
+        };
+        let opts = DecodeOptions::default();
+        let res = decode_code93(&img, &opts);
+        assert!(!res.is_empty());
+        assert_eq!(res[0].text, "TEST93");
+    }
+
+    #[test]
+    fn code93_check_chars_match_reference() {
+        // "TEST93" -> C = '+' (41), K = '6' (6) — пример из спецификации
+        let values: Vec<u8> = b"TEST93"
+            .iter()
+            .map(|&b| CODE93_ALPHABET.iter().position(|&c| c == b).unwrap() as u8)
+            .collect();
+        let c = check_value(&values, 20);
+        assert_eq!(c, 41);
+        let mut with_c = values.clone();
+        with_c.push(c);
+        assert_eq!(check_value(&with_c, 15), 6);
+    }
+
+    #[test]
+    fn code93_full_ascii_expansion() {
+        let row = synthesize_row_code93("Ab:c", 10, 2).unwrap();
+        let mut opts = DecodeOptions::default();
+        assert_eq!(decode_row(&row, &opts).as_deref(), Some("Ab:c"));
+
+        opts.code93_full_ascii = false;
+        assert_eq!(decode_row(&row, &opts).as_deref(), Some("A(+)B(/)Z(+)C"));
+    }
+
+    #[test]
+    fn synthesize_reports_invalid_input() {
+        assert_eq!(
+            synthesize_row_code93("AB\u{e9}", 10, 2),
+            Err(SynthError::NonAscii {
+                ch: '\u{e9}',
+                index: 2
+            })
+        );
+        assert_eq!(
+            synthesize_row_code93("AB", 10, 0),
+            Err(SynthError::ZeroUnit)
+        );
+        assert!(synthesize_row_code93("a\x7f", 10, 2).is_ok());
+    }
+}
@@ -1,14 +1,25 @@
 //! Декодер EAN-13/UPC-A по одной строке.
 //!
 //! Алгоритм (быстрый и без зависимостей):
-//! 1) Бинаризуем строку (адаптивно, с фоллбэком на глобально) и строим run-lengths.
+//! 1) Бинаризуем строку (по `DecodeOptions::binarization`) и строим run-lengths.
 //! 2) Нормализуем run'ы в модули (1..4).
 //! 3) Ищем стартовый guard (101), затем центральный (01010) и финальный (101).
 //! 4) Левую половину декодируем с учётом A/B (B = реверс A), правую — C.
 //! 5) Определяем первую цифру по маске A/B, проверяем контрольную сумму.
+//! 6) Тихие зоны за крайними guard'ами — по `DecodeOptions::quiet_zone`.
 
-use crate::binarize::{binarize_row, binarize_row_adaptive, normalize_modules, runs};
-use crate::one_d::DecodeOptions;
+use crate::binarize::{normalize_runs_f32, runs_subpixel};
+use crate::core::cast::{f32_to_usize, usize_to_f32};
+#[cfg(not(feature = "std"))]
+use crate::core::math::FloatExt;
+use crate::core::types::{ChecksumPolicy, SynthError};
+use crate::one_d::{
+    has_quiet_zone, normalize_row, pick_read, row_binarizations, subpixel_runs_look_like_barcode,
+    DecodeOptions, Extent, RowCandidate, RowRead,
+};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
 
 // A (L) — левые «A»-паттерны (bars/spaces), сумма = 7 модулей
 const A_PATTERNS: [(u8, u8, u8, u8); 10] = [
@@ -57,35 +68,226 @@ const FIRST_DIGIT_MASKS: [(bool, bool, bool, bool, bool, bool); 10] = [
 ];
 
 /// Попытка декодировать один ряд. Возвращает строку 13 цифр (EAN) или 12 (UPC-A) при успехе.
+/// Ведущий `0` снимается (UPC-A) только при `opts.ean13_leading_zero_as_upca`.
+#[must_use]
 pub fn decode_row(row_gray: &[u8], opts: &DecodeOptions) -> Option<String> {
+    read_row(row_gray, opts).map(|r| r.value.0)
+}
+
+/// То же, что [`decode_row`], но ширины run'ов меряются по субпиксельным фронтам
+/// (см. [`crate::binarize::runs_subpixel`]) — для модулей шириной 1–2 пикселя.
+/// Без глобального фоллбэка: фронты ищутся по адаптивному порогу.
+#[must_use]
+pub fn decode_row_subpixel(row_gray: &[u8], opts: &DecodeOptions) -> Option<String> {
+    read_row_subpixel(row_gray, opts).map(|r| r.value.0)
+}
+
+/// [`decode_row`] + флаг «первая цифра восстановлена по соседней маске A/B»
+/// (см. [`recover_first_digit`]) и флаг «без тихой зоны».
+pub(crate) fn read_row(row_gray: &[u8], opts: &DecodeOptions) -> Option<RowRead<(String, bool)>> {
+    let candidates = row_modules(row_gray, opts)
+        .into_iter()
+        .filter_map(|(rl, modules)| {
+            let widths: Vec<f32> = rl.iter().copied().map(usize_to_f32).collect();
+            decode_modules(&modules, &widths, opts)
+        });
+    pick_read(candidates, opts)
+}
+
+/// Все EAN-13/UPC-A строки подряд (несколько этикеток бок о бок), слева
+/// направо — как [`read_row`], но после каждого прочтения поиск продолжается за
+/// его финальным guard'ом. Из бинаризаций берётся та, что нашла больше кодов.
+pub(crate) fn read_row_all(row_gray: &[u8], opts: &DecodeOptions) -> Vec<RowRead<(String, bool)>> {
+    let mut best = Vec::new();
+    for (rl, modules) in row_modules(row_gray, opts) {
+        let widths: Vec<f32> = rl.iter().copied().map(usize_to_f32).collect();
+        let found = read_all(modules, &widths, opts, false);
+        if found.len() > best.len() {
+            best = found;
+        }
+    }
+    best
+}
+
+/// [`read_row_all`] по субпиксельным фронтам (см. [`read_row_subpixel`]).
+pub(crate) fn read_row_all_subpixel(
+    row_gray: &[u8],
+    opts: &DecodeOptions,
+) -> Vec<RowRead<(String, bool)>> {
+    match subpixel_modules(row_gray, opts) {
+        Some((rl, modules)) => read_all(modules, &rl, opts, true),
+        None => Vec::new(),
+    }
+}
+
+/// Прочтения подряд по модулям строки (`widths` — ширины тех же run'ов): после
+/// удачного поиск продолжается за его финальным guard'ом, после неудачного — со
+/// следующего run'а. `requantize` — цифры от очередного guard'а переквантовать
+/// по дробным ширинам (см. [`requantize_digits`]).
+fn read_all(
+    mut modules: Vec<u8>,
+    widths: &[f32],
+    opts: &DecodeOptions,
+    requantize: bool,
+) -> Vec<RowRead<(String, bool)>> {
+    let mut out = Vec::new();
+    let mut from = 0;
+    loop {
+        if requantize {
+            let Some((i, _)) = modules
+                .get(from..)
+                .and_then(|m| guard_start_candidates(m).next())
+            else {
+                break;
+            };
+            requantize_digits(&mut modules, widths, from + i + 3);
+        }
+        let Some(h) = read_halves_from(&modules, from) else {
+            break;
+        };
+        match pick_read(decode_halves(&h, widths, opts).into_iter(), opts) {
+            Some(read) => {
+                from = h.end + 3;
+                out.push(read);
+            }
+            None => from = h.start + 1,
+        }
+    }
+    out
+}
+
+/// Шаг 1 чтения: run'ы строки и они же в модулях — по одной паре на бинаризацию
+/// (см. [`row_binarizations`]).
+fn row_modules(row_gray: &[u8], opts: &DecodeOptions) -> Vec<(Vec<usize>, Vec<u8>)> {
+    if row_gray.len() < opts.min_modules {
+        return Vec::new();
+    }
+    row_binarizations(row_gray, opts, 40)
+        .into_iter()
+        .map(|(rb, rl)| {
+            let (m, _starts_black) = normalize_row(&rb, &rl, opts);
+            (rl, m)
+        })
+        .collect()
+}
+
+/// [`decode_row_subpixel`] + флаги восстановления первой цифры и «без тихой зоны».
+pub(crate) fn read_row_subpixel(
+    row_gray: &[u8],
+    opts: &DecodeOptions,
+) -> Option<RowRead<(String, bool)>> {
+    let (rl, mut modules) = subpixel_modules(row_gray, opts)?;
+    let start = guard_start_candidates(&modules).next()?.0 + 3;
+    requantize_digits(&mut modules, &rl, start);
+    pick_read(decode_modules(&modules, &rl, opts).into_iter(), opts)
+}
+
+/// Субпиксельные run'ы строки и они же в модулях; `None` — строка не похожа на код.
+fn subpixel_modules(row_gray: &[u8], opts: &DecodeOptions) -> Option<(Vec<f32>, Vec<u8>)> {
     if row_gray.len() < opts.min_modules {
         return None;
     }
+    let rl = runs_subpixel(row_gray);
+    if rl.len() < 40 || !subpixel_runs_look_like_barcode(&rl, opts) {
+        return None;
+    }
+    let modules = normalize_runs_f32(&rl, opts.module_window);
+    Some((rl, modules))
+}
+
+/// Цифры переквантуем по месту: 4 run'а каждой цифры — ровно 7 модулей.
+/// Так дробные ширины не зависят от оценки базового модуля по всей строке.
+/// `start` — первый run левой цифры.
+fn requantize_digits(modules: &mut [u8], rl: &[f32], start: usize) {
+    for d in 0..12 {
+        let idx = start + 4 * d + if d >= 6 { 5 } else { 0 };
+        if idx + 4 > rl.len() {
+            break;
+        }
+        modules[idx..idx + 4].copy_from_slice(&normalize_digit(&rl[idx..idx + 4]));
+    }
+}
 
-    // --- 1) Бинаризация: пробуем адаптивно, фоллбэк на глобальную
-    let (modules, _starts_black) = {
-        let rb = binarize_row_adaptive(row_gray);
-        let rl = runs(&rb);
-        if rl.len() >= 40 {
-            normalize_modules(&rb, &rl)
+/// 4 дробные ширины цифры → модули 1..4 с суммой 7 (правка по наибольшей ошибке округления).
+fn normalize_digit(w: &[f32]) -> [u8; 4] {
+    let unit = w.iter().sum::<f32>() / 7.0;
+    let exact: [f32; 4] = core::array::from_fn(|k| w[k] / unit.max(f32::EPSILON));
+    let mut out: [u8; 4] = core::array::from_fn(|k| {
+        u8::try_from(f32_to_usize(exact[k].round()).clamp(1, 4)).unwrap_or(4)
+    });
+    loop {
+        let sum: u8 = out.iter().sum();
+        let err = |k: usize| f32::from(out[k]) - exact[k];
+        let pick = match sum.cmp(&7) {
+            core::cmp::Ordering::Equal => return out,
+            core::cmp::Ordering::Greater => (0..4)
+                .filter(|&k| out[k] > 1)
+                .max_by(|&a, &b| err(a).total_cmp(&err(b))),
+            core::cmp::Ordering::Less => (0..4)
+                .filter(|&k| out[k] < 4)
+                .min_by(|&a, &b| err(a).total_cmp(&err(b))),
+        };
+        let Some(k) = pick else { return out };
+        if sum > 7 {
+            out[k] -= 1;
         } else {
-            let rb2 = binarize_row(row_gray);
-            let rl2 = runs(&rb2);
-            if rl2.len() < 40 {
-                return None;
-            }
-            normalize_modules(&rb2, &rl2)
+            out[k] += 1;
         }
-    };
+    }
+}
+
+/// Половины EAN-13 до выбора первой цифры (шаги 2–6 чтения).
+struct Halves {
+    /// Первый run стартового и финального guard'а.
+    start: usize,
+    end: usize,
+    /// Крайний штрих guard'а слился с соседним тёмным (обрезка вплотную):
+    /// run'ы `start` / `end + 2` шире модуля.
+    start_merged: bool,
+    end_merged: bool,
+    left_digits: [u8; 6],
+    /// Набор каждой левой цифры: `true` — B, `false` — A.
+    left_is_b: [bool; 6],
+    left_pats: [(u8, u8, u8, u8); 6],
+    right_digits: [u8; 6],
+    /// Сумма расстояний [`best_match`] по всем 12 цифрам.
+    distance: u32,
+}
 
-    // --- 2) Поиск стартового guard: первые подряд [1,1,1] в модулях ---
-    let i = find_guard_start(&modules)?;
+/// Guard'ы и 12 цифр по квантованным модулям: первый кандидат стартового guard'а
+/// (см. [`guard_start_candidates`]), от которого читается вся структура.
+fn read_halves(modules: &[u8]) -> Option<Halves> {
+    read_halves_from(modules, 0)
+}
+
+/// [`read_halves`] со стартовым guard'ом не раньше run'а `from`.
+fn read_halves_from(modules: &[u8], from: usize) -> Option<Halves> {
+    // --- 2) Поиск стартового guard: [1,1,1] (первый штрих может слиться) ---
+    // Слившийся финальный штрих — только если с узким не читается ни один кандидат.
+    let tail = modules.get(from..)?;
+    [false, true].into_iter().find_map(|merged_end| {
+        guard_start_candidates(tail).find_map(|(i, start_merged)| {
+            read_halves_at(modules, from + i, start_merged, merged_end)
+        })
+    })
+}
+
+/// Шаги 3–6 от стартового guard'а в run'е `i`; `merged_end` — допустить
+/// слившийся последний штрих финального guard'а (см. [`is_guard_end`]).
+fn read_halves_at(
+    modules: &[u8],
+    i: usize,
+    start_merged: bool,
+    merged_end: bool,
+) -> Option<Halves> {
     // сдвигаемся за 3 run-а старта
     let mut idx = i + 3;
+    let mut distance = 0u32;
 
     // --- 3) Левая половина: 6 цифр, каждая — 4 run'а ---
     let mut left_digits = [0u8; 6];
     let mut left_is_b = [false; 6];
+    let mut left_pats = [(0u8, 0u8, 0u8, 0u8); 6];
     for d in 0..6 {
         if idx + 3 >= modules.len() {
             return None;
@@ -96,20 +298,23 @@ pub fn decode_row(row_gray: &[u8], opts: &DecodeOptions) -> Option<String> {
             modules[idx + 2],
             modules[idx + 3],
         );
+        left_pats[d] = pat;
         let (digit_a, dist_a) = best_match(&pat, &A_PATTERNS);
         let (digit_b, dist_b) = best_match(&pat, &B_PATTERNS);
         if dist_a <= dist_b {
             left_digits[d] = digit_a;
             left_is_b[d] = false;
+            distance += dist_a;
         } else {
             left_digits[d] = digit_b;
             left_is_b[d] = true;
+            distance += dist_b;
         }
         idx += 4;
     }
 
     // --- 4) Центральный guard 01010 => 5 run'ов модулей ---
-    if !is_guard_center(&modules, idx) {
+    if !is_guard_center(modules, idx) {
         return None;
     }
     idx += 5;
@@ -126,48 +331,272 @@ pub fn decode_row(row_gray: &[u8], opts: &DecodeOptions) -> Option<String> {
             modules[idx + 2],
             modules[idx + 3],
         );
-        let (digit_c, _dist_c) = best_match(&pat, &C_PATTERNS);
+        let (digit_c, dist_c) = best_match(&pat, &C_PATTERNS);
         right_digits[d] = digit_c;
+        distance += dist_c;
         idx += 4;
     }
 
-    // --- 6) Финальный guard 101 ---
-    if !is_guard_end(&modules, idx) {
+    // --- 6) Финальный guard 101 (последний штрих может слиться) ---
+    if !is_guard_end(modules, idx, merged_end) {
         return None;
     }
+    let end_merged = modules[idx + 2] > 1;
+    Some(Halves {
+        start: i,
+        end: idx,
+        start_merged,
+        end_merged,
+        left_digits,
+        left_is_b,
+        left_pats,
+        right_digits,
+        distance,
+    })
+}
+
+/// Общая часть: guard'ы, цифры и контрольная сумма по уже квантованным модулям
+/// (`widths` — исходные ширины тех же run'ов, для тихих зон).
+/// Результат — кандидат `(текст, первая_цифра_восстановлена)` для [`pick_read`];
+/// восстановление — см. [`recover_first_digit`]. Несошедшиеся цифры отдаются только
+/// при `opts.checksum` ≠ `Require` и известной маске A/B: при `Ignore` — сразу, без
+/// восстановления, при `Prefer` — если восстановление не помогло.
+fn decode_modules(
+    modules: &[u8],
+    widths: &[f32],
+    opts: &DecodeOptions,
+) -> Option<RowCandidate<(String, bool)>> {
+    decode_halves(&read_halves(modules)?, widths, opts)
+}
+
+/// Шаги 7–8 [`decode_modules`] по уже прочитанным половинам.
+fn decode_halves(
+    h: &Halves,
+    widths: &[f32],
+    opts: &DecodeOptions,
+) -> Option<RowCandidate<(String, bool)>> {
+    // слившийся штрих guard'а считаем шириной в модуль — по двум соседним run'ам
+    let mut span = widths[h.start..h.end + 3].to_vec();
+    let last = span.len() - 1;
+    if h.start_merged {
+        span[0] = (span[1] + span[2]) / 2.0;
+    }
+    if h.end_merged {
+        span[last] = (span[last - 1] + span[last - 2]) / 2.0;
+    }
+    let guard_module = |at: usize| span[at..at + 3].iter().sum::<f32>() / 3.0;
+    // у слившегося штриха тихой зоны нет по определению
+    let quiet = !h.start_merged
+        && !h.end_merged
+        && has_quiet_zone(widths[..h.start].iter().rev().copied(), guard_module(0))
+        && has_quiet_zone(widths[h.end + 3..].iter().copied(), guard_module(last - 2));
 
     // --- 7) Первая цифра по маске типов A/B ---
-    let first = deduce_first_digit(&left_is_b)?;
+    let raw = deduce_first_digit(&h.left_is_b)
+        .map(|first| assemble_digits(first, h.left_digits, h.right_digits));
+
+    // --- 8) Контрольная сумма; при неудаче — повтор с соседними масками ---
+    let (digits, recovered, checksum_ok) = match raw {
+        Some(d) if check_ean13_checksum(&d) => (d, false, true),
+        Some(d) if opts.checksum == ChecksumPolicy::Ignore => (d, false, false),
+        _ => match recover_first_digit(h) {
+            Some(d) => (d, true, true),
+            None if opts.checksum == ChecksumPolicy::Prefer => (raw?, false, false),
+            None => return None,
+        },
+    };
+
+    Some(RowCandidate {
+        value: (digits_text(&digits, opts), recovered),
+        // слившийся штрих начинается внутри своего run'а
+        extent: Extent::new(95, &span, widths[..=h.start].iter().sum::<f32>() - span[0]),
+        quiet,
+        checksum_ok,
+    })
+}
+
+/// Текст по 13 цифрам: UPC-A — это EAN-13 с ведущим 0
+/// (снимается при `opts.ean13_leading_zero_as_upca`).
+fn digits_text(digits: &[u8; 13], opts: &DecodeOptions) -> String {
+    let from = usize::from(digits[0] == 0 && opts.ean13_leading_zero_as_upca);
+    digits[from..].iter().map(|d| (b'0' + *d) as char).collect()
+}
+
+/// 13 цифр: первая, 6 левых, 6 правых.
+fn assemble_digits(first: u8, left: [u8; 6], right: [u8; 6]) -> [u8; 13] {
     let mut digits = [0u8; 13];
     digits[0] = first;
-    for k in 0..6 {
-        digits[1 + k] = left_digits[k];
+    digits[1..7].copy_from_slice(&left);
+    digits[7..13].copy_from_slice(&right);
+    digits
+}
+
+/// Повтор при несошедшейся контрольной сумме (или маске A/B, которой нет в таблице).
+///
+/// Частая ошибка — одна левая цифра прочитана не из того набора (A вместо B или
+/// наоборот): маска чётности отличается от верной ровно в одной позиции. Перебираем
+/// варианты [`mask_alternatives`] и проверяем контрольную сумму.
+/// Если подходит больше одного варианта, чтение неоднозначно — `None`.
+fn recover_first_digit(h: &Halves) -> Option<[u8; 13]> {
+    let mut found: Option<[u8; 13]> = None;
+    for (digits, _) in mask_alternatives(h) {
+        if check_ean13_checksum(&digits) {
+            if found.is_some() {
+                return None;
+            }
+            found = Some(digits);
+        }
+    }
+    found
+}
+
+/// Прочтения с первой цифрой, чья маска отстоит от прочитанной на 1 (не больше
+/// 10 вариантов): спорная цифра перечитывается из «правильного» набора, только
+/// если её паттерн почти одинаково близок к обоим наборам. Второй элемент —
+/// сумма расстояний с перечитанной цифрой (как [`Halves::distance`]).
+fn mask_alternatives(h: &Halves) -> Vec<([u8; 13], u32)> {
+    let mut out = Vec::new();
+    for (first, &mask) in (0u8..).zip(&FIRST_DIGIT_MASKS) {
+        let mut diff = (0..6).filter(|&k| mask_at(mask, k) != h.left_is_b[k]);
+        let (Some(k), None) = (diff.next(), diff.next()) else {
+            continue;
+        };
+        let (alt, cur) = if mask_at(mask, k) {
+            (&B_PATTERNS, &A_PATTERNS)
+        } else {
+            (&A_PATTERNS, &B_PATTERNS)
+        };
+        let (digit, alt_dist) = best_match(&h.left_pats[k], alt);
+        let cur_dist = best_match(&h.left_pats[k], cur).1;
+        // Чистое чтение (расстояние 0) отстоит от другого набора минимум на 2:
+        // перечитываем только цифры, для которых оба набора почти равноправны.
+        if alt_dist > cur_dist + 1 {
+            continue;
+        }
+        let mut left = h.left_digits;
+        left[k] = digit;
+        out.push((
+            assemble_digits(first, left, h.right_digits),
+            h.distance - cur_dist + alt_dist,
+        ));
     }
-    for k in 0..6 {
-        digits[7 + k] = right_digits[k];
+    out
+}
+
+/// Все правдоподобные прочтения строки `(текст, расстояние, сумма_сошлась)`
+/// (см. [`crate::one_d::decode_row_candidates`]): прямое (если маска A/B есть
+/// в таблице) и варианты [`mask_alternatives`], с несошедшимися суммами включительно.
+pub(crate) fn row_candidates(row_gray: &[u8], opts: &DecodeOptions) -> Vec<(String, u32, bool)> {
+    let mut out = Vec::new();
+    for (_, modules) in row_modules(row_gray, opts) {
+        let Some(h) = read_halves(&modules) else {
+            continue;
+        };
+        let raw = deduce_first_digit(&h.left_is_b).map(|first| {
+            (
+                assemble_digits(first, h.left_digits, h.right_digits),
+                h.distance,
+            )
+        });
+        out.extend(
+            raw.into_iter()
+                .chain(mask_alternatives(&h))
+                .map(|(digits, distance)| {
+                    (
+                        digits_text(&digits, opts),
+                        distance,
+                        check_ean13_checksum(&digits),
+                    )
+                }),
+        );
     }
+    out
+}
 
-    // --- 8) Контрольная сумма ---
-    if !check_ean13_checksum(&digits) {
+/// Нормализованная 13-значная форма: UPC-A (12 цифр) дополняется ведущим `0`,
+/// EAN-13 возвращается как есть. Для прочих строк — `None`.
+#[must_use]
+pub fn to_ean13(text: &str) -> Option<String> {
+    if !text.bytes().all(|c| c.is_ascii_digit()) {
         return None;
     }
+    match text.len() {
+        12 => {
+            let mut s = String::with_capacity(13);
+            s.push('0');
+            s.push_str(text);
+            Some(s)
+        }
+        13 => Some(String::from(text)),
+        _ => None,
+    }
+}
 
-    // UPC-A — это EAN-13 с ведущим 0.
-    let text = if digits[0] == 0 {
-        digits[1..13]
-            .iter()
-            .map(|d| (b'0' + *d) as char)
-            .collect::<String>()
-    } else {
-        digits
-            .iter()
-            .map(|d| (b'0' + *d) as char)
-            .collect::<String>()
-    };
+/// GTIN-14 для ERP/логистики: EAN-8, UPC-A, EAN-13 (или уже GTIN-14) дополняются
+/// ведущими нулями до 14 цифр (индикатор упаковки `0`). Контрольная цифра
+/// пересчитывается по mod 10 с весами 3/1 — ведущие нули на неё не влияют, так
+/// что она совпадает с исходной. `None` — не цифры, другая длина или контрольная
+/// не сошлась.
+#[must_use]
+pub fn to_gtin14(code: &str) -> Option<String> {
+    if !code.bytes().all(|c| c.is_ascii_digit()) || ![8, 12, 13, 14].contains(&code.len()) {
+        return None;
+    }
+    let digits: Vec<u8> = code.bytes().map(|c| c - b'0').collect();
+    let (&check, body) = digits.split_last()?;
+    if crate::one_d::checks::mod10_weighted(body, &[3, 1]) != check {
+        return None;
+    }
+    let mut s = String::with_capacity(14);
+    s.extend(core::iter::repeat('0').take(14 - code.len()));
+    s.push_str(code);
+    Some(s)
+}
 
-    Some(text)
+/// Система нумерации: для UPC-A (12 цифр) — первая цифра,
+/// для EAN-13 — трёхзначный префикс GS1 (страна/Bookland `978`/`979`, in-store `2xx` и т.п.).
+#[must_use]
+pub fn number_system(text: &str) -> Option<&str> {
+    if !text.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    match text.len() {
+        12 => Some(&text[..1]),
+        13 => Some(&text[..3]),
+        _ => None,
+    }
 }
 
+/// Кандидаты стартового guard'а 101 по порядку: `(первый run, первый штрих слился)`.
+///
+/// Кандидат — два узких run'а, перед которыми штрих guard'а: узкий либо широкий
+/// (на обрезанных вплотную сканах он сливается с тёмным содержимым страницы).
+/// Проверка вперёд: за кандидатом шесть левых цифр ровно по 7 модулей (такие
+/// четвёрки — всегда A- или B-паттерны). Порядок: подтверждённые с узким штрихом;
+/// первая тройка `1,1,1` без проверки (цифры могут быть искажены); подтверждённые
+/// со слившимся штрихом — последними, чтобы широкий пробел тихой зоны не сдвинул
+/// чтение целого кода на run.
+fn guard_start_candidates(m: &[u8]) -> impl Iterator<Item = (usize, bool)> + '_ {
+    let leads_to_digits = move |i: usize| {
+        m[i + 1] == 1
+            && m[i + 2] == 1
+            && m.get(i + 3..i + 27)
+                .is_some_and(|left| left.chunks(4).all(|d| d.iter().sum::<u8>() == 7))
+    };
+    let starts = 0..m.len().saturating_sub(2);
+    let narrow = starts
+        .clone()
+        .filter(move |&i| m[i] == 1 && leads_to_digits(i))
+        .map(|i| (i, false));
+    let merged = starts
+        .filter(move |&i| m[i] > 1 && leads_to_digits(i))
+        .map(|i| (i, true));
+    narrow
+        .chain(find_guard_start(m).map(|i| (i, false)))
+        .chain(merged)
+}
+
+/// Первая тройка `1,1,1` в модулях.
 fn find_guard_start(m: &[u8]) -> Option<usize> {
     for i in 0..m.len().saturating_sub(2) {
         if m[i] == 1 && m[i + 1] == 1 && m[i + 2] == 1 {
@@ -177,12 +606,23 @@ fn find_guard_start(m: &[u8]) -> Option<usize> {
     None
 }
 
+/// Сколько «лишних» модулей допускаем в центральном guard'е: на сжатых сканах
+/// 01010 квантуется, например, в `1,1,1,1,2`.
+const CENTER_GUARD_SLACK: u8 = 1;
+
+/// Центральный guard 01010: пять run'ов по 1–2 модуля, в сумме не больше
+/// `5 + CENTER_GUARD_SLACK`. Чередование цветов гарантировано разбиением на run'ы.
 fn is_guard_center(m: &[u8], i: usize) -> bool {
-    i + 4 < m.len() && m[i] == 1 && m[i + 1] == 1 && m[i + 2] == 1 && m[i + 3] == 1 && m[i + 4] == 1
+    let Some(g) = m.get(i..i + 5) else {
+        return false;
+    };
+    g.iter().all(|&v| (1..=2).contains(&v)) && g.iter().sum::<u8>() <= 5 + CENTER_GUARD_SLACK
 }
 
-fn is_guard_end(m: &[u8], i: usize) -> bool {
-    i + 2 < m.len() && m[i] == 1 && m[i + 1] == 1 && m[i + 2] == 1
+/// Финальный guard 101. При `merged` последний штрих может быть широким — слиться
+/// с тёмным за краем символа (см. [`guard_start_candidates`]).
+fn is_guard_end(m: &[u8], i: usize, merged: bool) -> bool {
+    i + 2 < m.len() && m[i] == 1 && m[i + 1] == 1 && (merged || m[i + 2] == 1)
 }
 
 /// подобрать ближайшую цифру по паттерну ширин (манхэттенское расстояние)
@@ -222,44 +662,133 @@ fn deduce_first_digit(mask_b: &[bool; 6]) -> Option<u8> {
 }
 
 fn check_ean13_checksum(d: &[u8; 13]) -> bool {
-    let mut sum = 0u32;
-    for i in 0..12 {
-        let w = if i % 2 == 0 { 1 } else { 3 };
-        sum += d[i] as u32 * w;
+    let mut body = [0u8; 12];
+    body.copy_from_slice(&d[..12]);
+    ean13_check_digit(&body) == d[12]
+}
+
+/// Контрольная цифра EAN-13 по первым 12 цифрам (значения 0..=9):
+/// веса 1,3,1,3… слева, дополнение суммы до кратного 10.
+#[must_use]
+pub fn ean13_check_digit(digits: &[u8; 12]) -> u8 {
+    crate::one_d::checks::mod10_weighted(digits, &[3, 1])
+}
+
+/// Контрольная цифра UPC-A по первым 11 цифрам: это EAN-13 с ведущим `0`.
+#[must_use]
+pub fn upca_check_digit(digits: &[u8; 11]) -> u8 {
+    let mut ean = [0u8; 12];
+    ean[1..].copy_from_slice(digits);
+    ean13_check_digit(&ean)
+}
+
+/// Ряд EAN-13 (ч/б пиксели) с тихой зоной `quiet_zone` модулей с каждой стороны
+/// (`0` — без неё, стандартная — `Symbology::Ean13.standard_quiet_zone()`).
+///
+/// `digits` — 12 цифр (контрольная дописывается) или 13 (контрольная проверяется).
+/// UPC-A — те же 13 цифр с ведущим `0` (или [`synthesize_upca_row`]).
+///
+/// # Errors
+///
+/// [`SynthError`] — не цифры, неверная длина или контрольная, `unit == 0`.
+pub fn synthesize_ean13_row(
+    digits: &str,
+    quiet_zone: usize,
+    unit: usize,
+) -> Result<Vec<u8>, SynthError> {
+    let ds = parse_digits(digits, unit)?;
+    let mut ean13 = [0u8; 13];
+    match ds.len() {
+        12 | 13 => ean13[..ds.len()].copy_from_slice(&ds),
+        n => return Err(SynthError::WrongLength(n)),
+    }
+    let mut body = [0u8; 12];
+    body.copy_from_slice(&ean13[..12]);
+    let expected = ean13_check_digit(&body);
+    if ds.len() == 13 && ds[12] != expected {
+        return Err(SynthError::CheckDigit {
+            expected,
+            found: ds[12],
+        });
     }
-    let check = (10 - (sum % 10)) % 10;
-    check == d[12] as u32
+    ean13[12] = expected;
+    Ok(render_row(&ean13, quiet_zone, unit))
 }
 
-/// Вспомогательная функция для юнит-теста: синтез идеального ряда по строке цифр.
-#[cfg(test)]
-pub fn synthesize_ideal_row(digits: &str, unit: usize) -> Vec<u8> {
-    let mut modules: Vec<u8> = Vec::new();
-    modules.extend([9]); // quiet (белое)
-    modules.extend([1, 1, 1]); // старт 101
+/// Ряд UPC-A: EAN-13 с ведущим `0` (читается как 12-значный UPC-A), тихая
+/// зона — как у [`synthesize_ean13_row`].
+///
+/// `digits` — 11 цифр (контрольная дописывается) или 12 (контрольная проверяется).
+///
+/// # Errors
+///
+/// Как у [`synthesize_ean13_row`].
+pub fn synthesize_upca_row(
+    digits: &str,
+    quiet_zone: usize,
+    unit: usize,
+) -> Result<Vec<u8>, SynthError> {
+    let ds = parse_digits(digits, unit)?;
+    if !(11..=12).contains(&ds.len()) {
+        return Err(SynthError::WrongLength(ds.len()));
+    }
+    let mut ean = String::with_capacity(13);
+    ean.push('0');
+    ean.push_str(digits);
+    // Длина уже 12 или 13 — ошибкой может быть только контрольная цифра.
+    synthesize_ean13_row(&ean, quiet_zone, unit)
+}
+
+/// Цифры строки как значения 0..=9 (плюс проверка `unit`).
+fn parse_digits(digits: &str, unit: usize) -> Result<Vec<u8>, SynthError> {
+    if unit == 0 {
+        return Err(SynthError::ZeroUnit);
+    }
+    digits
+        .chars()
+        .enumerate()
+        .map(|(index, ch)| {
+            let digit = u8::try_from(ch).ok().filter(u8::is_ascii_digit);
+            digit
+                .map(|b| b - b'0')
+                .ok_or(SynthError::NonDigit { ch, index })
+        })
+        .collect()
+}
 
-    let ds: Vec<u8> = digits.bytes().map(|c| c - b'0').collect();
-    let is_upca = ds.len() == 12;
+/// Синтез идеального ряда по строке цифр (для тестов крейта): 13 цифр —
+/// EAN-13 как есть, даже с неверной контрольной; 12 — UPC-A (контрольная
+/// цифра пересчитывается). Тихая зона — как у [`synthesize_ean13_row`].
+///
+/// Паникует на недопустимом входе. Снаружи — [`synthesize_ean13_row`] /
+/// [`synthesize_upca_row`].
+#[cfg(test)]
+pub(crate) fn synthesize_ideal_row(digits: &str, quiet_zone: usize, unit: usize) -> Vec<u8> {
+    let ds: Vec<u8> = digits.bytes().map(|c| c.wrapping_sub(b'0')).collect();
+    assert!(
+        (ds.len() == 12 || ds.len() == 13) && ds.iter().all(|&d| d <= 9) && unit >= 1,
+        "EAN-13/UPC-A: нужно 12 или 13 цифр"
+    );
     let mut ean13 = [0u8; 13];
-    if is_upca {
-        ean13[0] = 0;
-        for i in 0..12 {
-            ean13[i + 1] = ds[i];
-        }
-        // пересчёт checksum
-        let mut sum = 0u32;
-        for i in 0..12 {
-            let w = if i % 2 == 0 { 1 } else { 3 };
-            sum += ean13[i] as u32 * w;
-        }
-        ean13[12] = ((10 - (sum % 10)) % 10) as u8;
+    if ds.len() == 12 {
+        // UPC-A: ведущий 0 и пересчёт checksum
+        ean13[1..].copy_from_slice(&ds);
+        let mut body = [0u8; 12];
+        body.copy_from_slice(&ean13[..12]);
+        ean13[12] = ean13_check_digit(&body);
     } else {
-        for i in 0..13 {
-            ean13[i] = ds[i];
-        }
+        ean13.copy_from_slice(&ds);
     }
-    let first = ean13[0] as usize;
-    let mask = super::ean13::FIRST_DIGIT_MASKS[first];
+    render_row(&ean13, quiet_zone, unit)
+}
+
+/// 13 цифр EAN-13 → пиксели: quiet (`quiet_zone` модулей), старт, левая половина
+/// A/B по маске первой цифры, центр, правая половина C, финал, quiet.
+fn render_row(ean13: &[u8; 13], quiet_zone: usize, unit: usize) -> Vec<u8> {
+    let mut modules: Vec<u8> = Vec::new();
+    modules.extend([1, 1, 1]); // старт 101
+
+    let mask = FIRST_DIGIT_MASKS[ean13[0] as usize];
 
     // левая половина: A/B
     for i in 0..6 {
@@ -279,13 +808,12 @@ pub fn synthesize_ideal_row(digits: &str, unit: usize) -> Vec<u8> {
         let (a, b, c, dw) = C_PATTERNS[d];
         modules.extend([a, b, c, dw]);
     }
-    // финал и quiet
+    // финал
     modules.extend([1, 1, 1]);
-    modules.extend([9]);
 
-    // В пиксели (чёрный=0, белый=255), начиная с белого
-    let mut pix: Vec<u8> = Vec::new();
-    let mut black = false;
+    // В пиксели (чёрный=0, белый=255): quiet, модули с чёрного, quiet
+    let mut pix: Vec<u8> = vec![255; quiet_zone * unit];
+    let mut black = true;
     for m in modules {
         let w = m as usize * unit;
         let val = if black { 0u8 } else { 255u8 };
@@ -294,10 +822,10 @@ pub fn synthesize_ideal_row(digits: &str, unit: usize) -> Vec<u8> {
         }
         black = !black;
     }
+    pix.resize(pix.len() + quiet_zone * unit, 255);
     pix
 }
 
-#[cfg(test)]
 fn mask_at(mask: (bool, bool, bool, bool, bool, bool), idx: usize) -> bool {
     match idx {
         0 => mask.0,
@@ -309,3 +837,318 @@ fn mask_at(mask: (bool, bool, bool, bool, bool, bool), idx: usize) -> bool {
         _ => false,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binarize::{binarize_row, runs};
+    use crate::core::types::QuietZone;
+
+    /// [`decode_modules`] без ширины символа: `(значение, тихие_зоны, сумма_сошлась)`.
+    fn decode_flat(
+        modules: &[u8],
+        widths: &[f32],
+        opts: &DecodeOptions,
+    ) -> Option<((String, bool), bool, bool)> {
+        decode_modules(modules, widths, opts).map(|c| (c.value, c.quiet, c.checksum_ok))
+    }
+
+    #[test]
+    fn check_digits_of_known_codes() {
+        assert_eq!(ean13_check_digit(&[4, 0, 0, 6, 3, 8, 1, 3, 3, 3, 9, 3]), 1);
+        assert_eq!(ean13_check_digit(&[5, 9, 0, 1, 2, 3, 4, 1, 2, 3, 4, 5]), 7);
+        assert_eq!(ean13_check_digit(&[9, 7, 8, 0, 3, 0, 6, 4, 0, 6, 1, 5]), 7);
+        assert_eq!(upca_check_digit(&[0, 3, 6, 0, 0, 0, 2, 9, 1, 4, 5]), 2);
+        assert_eq!(upca_check_digit(&[0; 11]), 0);
+    }
+
+    #[test]
+    fn single_parity_misread_is_recovered() {
+        // 4006381333931: первая цифра 4 → маска A B A A B B. Вторая левая цифра — «0»
+        // из набора B (1,1,2,3); читаем её как (1,1,3,3): до A[4] и до B[0] по 1,
+        // при равенстве выигрывает A — маска AAAABB, которой нет в таблице.
+        let row = synthesize_ideal_row("4006381333931", 9, 1);
+        let mut modules: Vec<u8> = runs(&binarize_row(&row)).iter().map(|&w| w as u8).collect();
+        let widths: Vec<f32> = modules.iter().map(|&m| f32::from(m)).collect();
+        let opts = DecodeOptions::default();
+        assert_eq!(
+            decode_flat(&modules, &widths, &opts),
+            Some((("4006381333931".into(), false), true, true))
+        );
+
+        // quiet(1 run) + старт(3) + первая цифра(4) → вторая цифра с индекса 8.
+        assert_eq!(&modules[8..12], &[1, 1, 2, 3]);
+        modules[10] = 3;
+        assert_eq!(
+            decode_flat(&modules, &widths, &opts),
+            Some((("4006381333931".into(), true), true, true))
+        );
+    }
+
+    #[test]
+    fn imperfect_center_guard_is_tolerated() {
+        let row = synthesize_ideal_row("5901234123457", 9, 1);
+        let modules: Vec<u8> = runs(&binarize_row(&row)).iter().map(|&w| w as u8).collect();
+        let widths: Vec<f32> = modules.iter().map(|&m| f32::from(m)).collect();
+        let opts = DecodeOptions::default();
+        let expect = Some((("5901234123457".into(), false), true, true));
+
+        // quiet(1) + старт(3) + 6 цифр × 4 → центральный guard с индекса 28.
+        assert_eq!(&modules[28..33], &[1, 1, 1, 1, 1]);
+        for k in 28..33 {
+            let mut m = modules.clone();
+            m[k] = 2;
+            assert_eq!(
+                decode_flat(&m, &widths, &opts),
+                expect,
+                "center run {k} = 2"
+            );
+        }
+        // два уширенных run'а или run в 3 модуля — уже не guard
+        let mut m = modules.clone();
+        m[28] = 2;
+        m[30] = 2;
+        assert_eq!(decode_flat(&m, &widths, &opts), None);
+        let mut m = modules;
+        m[32] = 3;
+        assert_eq!(decode_flat(&m, &widths, &opts), None);
+
+        // Пиксельный ряд: последний пробел guard'а шире на 3/4 модуля (≈ 2 после нормализации).
+        let unit = 4;
+        let mut row = synthesize_ideal_row("5901234123457", 9, unit);
+        let center_end = (9 + 3 + 42 + 5) * unit;
+        row.splice(center_end..center_end, core::iter::repeat(255).take(3));
+        assert_eq!(decode_row(&row, &opts).as_deref(), Some("5901234123457"));
+    }
+
+    #[test]
+    fn merged_guard_bars_are_located_by_look_ahead() {
+        let unit = 2;
+        let mut row = synthesize_ideal_row("5901234123457", 9, unit);
+        let quiet = 9 * unit;
+        // обрезка вплотную: слева «текст» страницы (узкие 1,1,1,1), затем тёмное поле,
+        // слившееся со стартовым штрихом; справа тёмное поле за финальным штрихом
+        row[..quiet]
+            .copy_from_slice(&[0, 0, 255, 255, 0, 0, 255, 255, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        let n = row.len();
+        row[n - quiet..].fill(0);
+
+        let mut opts = DecodeOptions {
+            quiet_zone: QuietZone::Relaxed,
+            ..DecodeOptions::default()
+        };
+        let read = read_row(&row, &opts).expect("merged guards are tolerated");
+        assert_eq!(read.value, ("5901234123457".into(), false));
+        assert!(read.quiet_missing);
+        // слившиеся штрихи считаются шириной в модуль
+        assert_eq!(read.extent.modules, 95);
+        assert!(
+            (read.extent.px - (95 * unit) as f32).abs() < 0.5,
+            "{}",
+            read.extent.px
+        );
+
+        opts.quiet_zone = QuietZone::Strict;
+        assert!(read_row(&row, &opts).is_none());
+    }
+
+    #[test]
+    fn wide_end_bar_only_on_merged_guard_path() {
+        assert!(is_guard_end(&[1, 1, 1], 0, false));
+        assert!(!is_guard_end(&[1, 1, 3], 0, false));
+        assert!(is_guard_end(&[1, 1, 3], 0, true));
+
+        // целый код читается по узкому штриху, без пометки слияния
+        let row = synthesize_ideal_row("5901234123457", 9, 1);
+        let h = read_halves(
+            &runs(&binarize_row(&row))
+                .iter()
+                .map(|&w| w as u8)
+                .collect::<Vec<_>>(),
+        )
+        .expect("halves");
+        assert!(!h.end_merged);
+    }
+
+    #[test]
+    fn checksum_policy_on_broken_code() {
+        // 5901234123457 с последней цифрой 8: структура верна, сумма — нет,
+        // и ни одна соседняя маска A/B её не чинит.
+        let row = synthesize_ideal_row("5901234123458", 9, 2);
+        let mut opts = DecodeOptions::default();
+        assert_eq!(opts.checksum, ChecksumPolicy::Require);
+        assert!(read_row(&row, &opts).is_none());
+
+        for policy in [ChecksumPolicy::Prefer, ChecksumPolicy::Ignore] {
+            opts.checksum = policy;
+            let read = read_row(&row, &opts).expect("digits are returned");
+            assert_eq!(read.value, ("5901234123458".into(), false), "{policy:?}");
+            assert!(read.checksum_failed, "{policy:?}");
+        }
+
+        // Маски AAAABB нет в таблице, сырых цифр нет — восстановление при любой политике.
+        let row = synthesize_ideal_row("4006381333931", 9, 1);
+        let mut modules: Vec<u8> = runs(&binarize_row(&row)).iter().map(|&w| w as u8).collect();
+        let widths: Vec<f32> = modules.iter().map(|&m| f32::from(m)).collect();
+        modules[10] = 3;
+        for policy in [ChecksumPolicy::Prefer, ChecksumPolicy::Ignore] {
+            opts.checksum = policy;
+            let expect = Some((("4006381333931".into(), true), true, true));
+            assert_eq!(decode_flat(&modules, &widths, &opts), expect, "{policy:?}");
+        }
+
+        // Целый код при любой политике читается без флага.
+        let row = synthesize_ideal_row("5901234123457", 9, 2);
+        for policy in [
+            ChecksumPolicy::Require,
+            ChecksumPolicy::Prefer,
+            ChecksumPolicy::Ignore,
+        ] {
+            opts.checksum = policy;
+            assert!(!read_row(&row, &opts).expect("valid code").checksum_failed);
+        }
+    }
+
+    #[test]
+    fn leading_zero_reported_per_flag() {
+        let row = synthesize_ideal_row("036000291452", 9, 3);
+        let mut opts = DecodeOptions::default();
+        assert_eq!(decode_row(&row, &opts).as_deref(), Some("036000291452"));
+
+        opts.ean13_leading_zero_as_upca = false;
+        assert_eq!(decode_row(&row, &opts).as_deref(), Some("0036000291452"));
+    }
+
+    #[test]
+    fn number_system_and_normalized_form() {
+        assert_eq!(number_system("036000291452"), Some("0"));
+        assert_eq!(to_ean13("036000291452").as_deref(), Some("0036000291452"));
+        assert_eq!(number_system("9780306406157"), Some("978"));
+        assert_eq!(to_ean13("9780306406157").as_deref(), Some("9780306406157"));
+        assert_eq!(number_system("12345"), None);
+        assert_eq!(to_ean13("03600029145X"), None);
+    }
+
+    #[test]
+    fn gtin14_pads_and_validates_check_digit() {
+        // EAN-13, UPC-A, EAN-8, уже GTIN-14
+        assert_eq!(
+            to_gtin14("5901234123457").as_deref(),
+            Some("05901234123457")
+        );
+        assert_eq!(to_gtin14("036000291452").as_deref(), Some("00036000291452"));
+        assert_eq!(to_gtin14("96385074").as_deref(), Some("00000096385074"));
+        assert_eq!(
+            to_gtin14("10614141000415").as_deref(),
+            Some("10614141000415")
+        );
+        // несошедшаяся контрольная, чужая длина, не цифры
+        assert_eq!(to_gtin14("5901234123458"), None);
+        assert_eq!(to_gtin14("96385075"), None);
+        assert_eq!(to_gtin14("1234567"), None);
+        assert_eq!(to_gtin14("03600029145X"), None);
+    }
+
+    #[test]
+    fn local_module_window_survives_wide_background() {
+        // Тонкий EAN (2 px/модуль), справа — широкие полосы фона (10 px):
+        // глобальная медиана «тонких» run'ов уезжает, локальная — нет.
+        let mut row = synthesize_ideal_row("5901234123457", 9, 2);
+        for k in 0..80 {
+            let v = if k % 2 == 0 { 0 } else { 255 };
+            row.extend(core::iter::repeat(v).take(10));
+        }
+        let mut opts = DecodeOptions::default();
+        assert_eq!(decode_row(&row, &opts), None);
+
+        opts.module_window = 16;
+        assert_eq!(decode_row(&row, &opts).as_deref(), Some("5901234123457"));
+    }
+
+    #[test]
+    fn public_synthesizers_validate_input() {
+        let opts = DecodeOptions::default();
+        // без контрольной цифры она дописывается; с ней — тот же ряд
+        let row = synthesize_ean13_row("400638133393", 9, 2).expect("ean13");
+        assert_eq!(
+            synthesize_ean13_row("4006381333931", 9, 2).as_ref(),
+            Ok(&row)
+        );
+        assert_eq!(decode_row(&row, &opts).as_deref(), Some("4006381333931"));
+
+        let upc = synthesize_upca_row("03600029145", 9, 2).expect("upca");
+        assert_eq!(upc, synthesize_ideal_row("036000291452", 9, 2));
+        assert_eq!(decode_row(&upc, &opts).as_deref(), Some("036000291452"));
+
+        assert_eq!(
+            synthesize_ean13_row("4006381333930", 9, 2),
+            Err(SynthError::CheckDigit {
+                expected: 1,
+                found: 0
+            })
+        );
+        assert_eq!(
+            synthesize_upca_row("036000291453", 9, 2),
+            Err(SynthError::CheckDigit {
+                expected: 2,
+                found: 3
+            })
+        );
+        assert_eq!(
+            synthesize_ean13_row("12345", 9, 2),
+            Err(SynthError::WrongLength(5))
+        );
+        assert_eq!(
+            synthesize_upca_row("4006381333931", 9, 2),
+            Err(SynthError::WrongLength(13))
+        );
+        assert_eq!(
+            synthesize_ean13_row("40063813-3931", 9, 2),
+            Err(SynthError::NonDigit { ch: '-', index: 8 })
+        );
+        assert_eq!(
+            synthesize_ean13_row("4006381333931", 9, 0),
+            Err(SynthError::ZeroUnit)
+        );
+    }
+
+    #[test]
+    fn side_by_side_codes_are_all_read_with_positions() {
+        let opts = DecodeOptions::default();
+        let first = synthesize_ean13_row("5901234123457", 9, 2).expect("ean13");
+        let second = synthesize_ean13_row("4006381333931", 9, 2).expect("ean13");
+        let mut row = first.clone();
+        row.extend_from_slice(&second);
+
+        // тихая зона 9 модулей, символ 95 модулей, по 2 пикселя на модуль
+        let q = 18.0;
+        let reads = |row: &[u8], opts: &DecodeOptions| -> Vec<(String, f32, f32)> {
+            read_row_all(row, opts)
+                .into_iter()
+                .map(|r| (r.value.0, r.extent.x, r.extent.px))
+                .collect()
+        };
+        let expected = [
+            ("5901234123457".to_string(), q, 190.0),
+            ("4006381333931".to_string(), first.len() as f32 + q, 190.0),
+        ];
+        assert_eq!(reads(&row, &opts), expected);
+        // одиночный декодер по-прежнему отдаёт первый код
+        assert_eq!(decode_row(&row, &opts).as_deref(), Some("5901234123457"));
+        assert_eq!(reads(&first, &opts), expected[..1]);
+        assert!(reads(&[255; 300], &opts).is_empty());
+
+        // по субпиксельным фронтам — те же коды и положения
+        let subpixel: Vec<(String, f32)> = read_row_all_subpixel(&row, &opts)
+            .into_iter()
+            .map(|r| (r.value.0, r.extent.x.round()))
+            .collect();
+        assert_eq!(
+            subpixel,
+            [
+                ("5901234123457".to_string(), q),
+                ("4006381333931".to_string(), first.len() as f32 + q)
+            ]
+        );
+    }
+}
@@ -0,0 +1,97 @@
+//! Внутримагазинные коды с ценой/весом: EAN-13 с префиксом `2`
+//! (`20`–`29`) и UPC-A с системой нумерации `2`.
+//!
+//! Чисто числовой разбор уже декодированных 13 цифр (UPC-A — с ведущим `0`) по
+//! самой распространённой раскладке: `2x IIIII C PPPP K` — два знака префикса,
+//! 5 цифр товара, контрольная цифра цены, 4 цифры цены и контрольная цифра EAN.
+//! У UPC-A (`0 2 IIIII C PPPP K`) поля стоят на тех же позициях.
+//!
+//! Раскладка не стандартизована до конца: в разных сетях и странах встречаются
+//! 5-значная цена без своей контрольной цифры, вес вместо цены, другие длины
+//! товарного номера. Разбор поэтому включается флагом
+//! (`PipelineOptions::parse_in_store_price`), а сошедшаяся контрольная цифра
+//! цены — единственный признак, что раскладка угадана.
+
+use alloc::string::String;
+
+/// Поля внутримагазинного кода (см. [`parse_in_store_price`]).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InStorePrice {
+    /// 5 цифр товара.
+    pub item: String,
+    /// Цена (или вес) в младших единицах: `0199` → `199`. Положение запятой
+    /// зависит от магазина.
+    pub price: u32,
+    /// Контрольная цифра цены сошлась (см. [`price_check_digit`]).
+    pub price_check_ok: bool,
+}
+
+/// Поля кода по 13 цифрам `ean13` (UPC-A — с ведущим `0`, как `ean.ean13`).
+/// `None` — не внутримагазинный префикс, не 13 цифр.
+pub fn parse_in_store_price(ean13: &str) -> Option<InStorePrice> {
+    let d = ean13.as_bytes();
+    if d.len() != 13 || !d.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    if d[0] != b'2' && !(d[0] == b'0' && d[1] == b'2') {
+        return None;
+    }
+    let price: [u8; 4] = core::array::from_fn(|k| d[8 + k] - b'0');
+    Some(InStorePrice {
+        item: String::from(&ean13[2..7]),
+        price: price.iter().fold(0, |acc, &p| acc * 10 + u32::from(p)),
+        price_check_ok: price_check_digit(&price) == d[7] - b'0',
+    })
+}
+
+/// Веса «2-» и «5-» (GS1): произведение на 2 или 5, из единиц вычитаются
+/// десятки (по mod 10).
+const WEIGHT_2_MINUS: [u8; 10] = [0, 2, 4, 6, 8, 9, 1, 3, 5, 7];
+const WEIGHT_5_MINUS: [u8; 10] = [0, 5, 9, 4, 8, 3, 7, 2, 6, 1];
+
+/// Контрольная цифра 4-значной цены (GS1): веса `2-, 2, 3, 5-`, сумма
+/// произведений умножается на 3, берутся единицы.
+#[must_use]
+pub fn price_check_digit(price: &[u8; 4]) -> u8 {
+    let sum = WEIGHT_2_MINUS[usize::from(price[0])]
+        + (2 * price[1]) % 10
+        + (3 * price[2]) % 10
+        + WEIGHT_5_MINUS[usize::from(price[3])];
+    (3 * sum) % 10
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn price_fields_of_ean_and_upc_forms() {
+        // пример GS1: цена 2875 → контрольная 2
+        assert_eq!(price_check_digit(&[2, 8, 7, 5]), 2);
+        assert_eq!(price_check_digit(&[0, 1, 9, 9]), 0);
+
+        let ean = parse_in_store_price("2112345001992").expect("prefix 21");
+        assert_eq!(
+            ean,
+            InStorePrice {
+                item: "12345".into(),
+                price: 199,
+                price_check_ok: true
+            }
+        );
+        // UPC-A с системой нумерации 2 в 13-значной форме
+        let upc = parse_in_store_price("0204321228757").expect("UPC-A NS 2");
+        assert_eq!(
+            (upc.item.as_str(), upc.price, upc.price_check_ok),
+            ("04321", 2875, true)
+        );
+        assert!(
+            !parse_in_store_price("2112345301992")
+                .expect("bad price check")
+                .price_check_ok
+        );
+
+        assert_eq!(parse_in_store_price("5901234123457"), None);
+        assert_eq!(parse_in_store_price("211234500199"), None);
+    }
+}
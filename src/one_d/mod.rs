@@ -1,14 +1,70 @@
+pub mod bookland;
+pub mod checks;
 pub mod code128;
+pub mod code93;
 pub mod ean13;
+pub mod in_store;
+pub mod msi;
+pub mod skew;
 
+pub use code128::code128_check_value;
+pub use ean13::{
+    ean13_check_digit, synthesize_ean13_row, synthesize_upca_row, to_gtin14, upca_check_digit,
+};
+
+use crate::binarize::{binarize_row, binarize_row_adaptive, runs};
+use crate::core::cast::{f32_to_usize, usize_to_f32};
+#[cfg(not(feature = "std"))]
+use crate::core::math::FloatExt;
+use crate::core::types::{
+    Binarization, ChecksumPolicy, Orientation, Progress, QuietZone, Symbology,
+};
 use crate::GrayImage;
+use alloc::string::String;
+use alloc::vec::Vec;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum BarcodeFormat {
     EAN13,
     UPCA,
     Code128,
+    Code93,
+    Msi,
     QR,
+    Aztec,
+    /// См. [`Symbology::Custom`].
+    Custom(&'static str),
+}
+
+impl From<Symbology> for BarcodeFormat {
+    fn from(s: Symbology) -> Self {
+        match s {
+            Symbology::Ean13 => BarcodeFormat::EAN13,
+            Symbology::UpcA => BarcodeFormat::UPCA,
+            Symbology::Code128 => BarcodeFormat::Code128,
+            Symbology::Code93 => BarcodeFormat::Code93,
+            Symbology::Msi => BarcodeFormat::Msi,
+            Symbology::QR => BarcodeFormat::QR,
+            Symbology::Aztec => BarcodeFormat::Aztec,
+            Symbology::Custom(name) => BarcodeFormat::Custom(name),
+        }
+    }
+}
+
+impl From<BarcodeFormat> for Symbology {
+    fn from(f: BarcodeFormat) -> Self {
+        match f {
+            BarcodeFormat::EAN13 => Symbology::Ean13,
+            BarcodeFormat::UPCA => Symbology::UpcA,
+            BarcodeFormat::Code128 => Symbology::Code128,
+            BarcodeFormat::Code93 => Symbology::Code93,
+            BarcodeFormat::Msi => Symbology::Msi,
+            BarcodeFormat::QR => Symbology::QR,
+            BarcodeFormat::Aztec => Symbology::Aztec,
+            BarcodeFormat::Custom(name) => Symbology::Custom(name),
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -17,14 +73,160 @@ pub struct Barcode {
     pub text: String,
     /// y-координата строки (для 1D сканирования).
     pub row: usize,
+    /// Чтение восстановлено эвристикой: для EAN-13/UPC-A — первая цифра подобрана
+    /// по соседней маске A/B после несошедшейся контрольной суммы.
+    pub recovered: bool,
+    /// Символ прочитан без тихой зоны хотя бы с одной стороны
+    /// (только при [`QuietZone::Relaxed`]).
+    pub quiet_zone_missing: bool,
+    /// Контрольная сумма не сошлась, текст отдан по [`ChecksumPolicy::Prefer`]
+    /// или [`ChecksumPolicy::Ignore`].
+    pub checksum_failed: bool,
+    /// Ширина символа в модулях — от первого до последнего штриха, без тихих зон
+    /// (EAN-13/UPC-A — 95).
+    pub modules: usize,
+    /// Та же ширина в пикселях строки (округлённо).
+    pub width_px: usize,
+    /// x первого штриха в строке, px: символ занимает `x..x + width_px`
+    /// (`0`, если ширина неизвестна).
+    pub x: usize,
+    /// Прочитано справа-налево: символ повёрнут на 180° (см. [`Barcode::orientation`]).
+    pub reversed: bool,
+    /// Снимок прочтения для повторного прогона (при [`DecodeOptions::capture_debug`]).
+    #[cfg(feature = "debug-capture")]
+    pub debug: Option<ScanDebug>,
+}
+
+/// Чем именно прочитан символ: строка и run'ы, на которых сработал декодер, —
+/// чтобы повторить удачное (или неверное) прочтение в отладчике или собрать
+/// регрессионную фикстуру из реального кадра.
+#[cfg(feature = "debug-capture")]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ScanDebug {
+    /// Строка, которую читал декодер. При [`DecodeOptions::deskew`] — строка
+    /// выровненного кадра, а не исходника (та — в [`Barcode::row`]).
+    pub scan_row: usize,
+    /// Ширины run'ов символа в пикселях, от первого штриха до последнего,
+    /// в порядке чтения (у прочтения справа налево — развёрнутой строки).
+    /// Дробные — при [`DecodeOptions::subpixel_edges`].
+    pub runs: Vec<f32>,
+}
+
+// Ширины — суммы пикселей или субпиксельные фронты: NaN не бывает.
+#[cfg(feature = "debug-capture")]
+impl Eq for ScanDebug {}
+
+impl Barcode {
+    /// Прочтение `text` формата `format` на строке `row`; остальные поля —
+    /// «ничего особенного»: без флагов, ширина неизвестна.
+    #[must_use]
+    pub fn new(format: BarcodeFormat, text: String, row: usize) -> Self {
+        Self {
+            format,
+            text,
+            row,
+            recovered: false,
+            quiet_zone_missing: false,
+            checksum_failed: false,
+            modules: 0,
+            width_px: 0,
+            x: 0,
+            reversed: false,
+            #[cfg(feature = "debug-capture")]
+            debug: None,
+        }
+    }
+
+    /// Отрезок строки под символом: `(x, x + width_px)`, конец не включительно.
+    pub(crate) fn x_span(&self) -> (usize, usize) {
+        (self.x, self.x + self.width_px)
+    }
+
+    /// Пикселей на модуль: `width_px / modules` (`0.0`, если ширина неизвестна).
+    /// Меньше ~2 — разрешения съёмки едва хватает, декодирование ненадёжно.
+    #[must_use]
+    pub fn module_px(&self) -> f32 {
+        if self.modules == 0 {
+            return 0.0;
+        }
+        usize_to_f32(self.width_px) / usize_to_f32(self.modules)
+    }
+
+    /// Ориентация символа на кадре по направлению чтения: [`Orientation::Rot0`] или
+    /// [`Orientation::Rot180`]. Штрихи по высоте одинаковы, поэтому отражение сверху
+    /// вниз строку не меняет (читается как `Rot0`), а отражение слева направо
+    /// неотличимо от поворота на 180°.
+    #[must_use]
+    pub fn orientation(&self) -> Orientation {
+        if self.reversed {
+            Orientation::Rot180
+        } else {
+            Orientation::Rot0
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct DecodeOptions {
-    /// Сколько строк сканировать (равномерно по высоте).
+    /// Сколько строк сканировать (равномерно по высоте; см. также
+    /// [`DecodeOptions::smart_row_selection`]).
     pub scan_rows: usize,
     /// Минимальная длина строки (в пикселях) для попытки распознавания.
     pub min_modules: usize,
+    /// EAN-13 с ведущим `0` отдавать как UPC-A (12 цифр, без нуля) — так их
+    /// читают кассовые системы. Если `false`, код остаётся 13-значным EAN-13.
+    pub ean13_leading_zero_as_upca: bool,
+    /// Code 93: раскрывать shift-пары full ASCII (`(+)A` -> `a` и т.п.).
+    /// Если `false`, shift'ы остаются в тексте как `($)`, `(%)`, `(/)`, `(+)`.
+    pub code93_full_ascii: bool,
+    /// Проверка контрольной цифры MSI Plessey (по умолчанию mod 10).
+    pub msi_check: msi::MsiCheck,
+    /// Оценивать базовый модуль локально, по `±module_window` соседним run'ам,
+    /// а не по медиане всей строки (EAN/UPC, MSI). Нужно, когда рядом с тонким
+    /// кодом есть широкие полосы фона. `0` — глобальная оценка (быстрый путь).
+    pub module_window: usize,
+    /// EAN/UPC и Code128: мерить ширины run'ов по субпиксельным фронтам
+    /// (`decode_row_subpixel`) вместо целых run'ов. Точнее на модулях в 1–2 пикселя,
+    /// но дороже и без фоллбэка на глобальный порог.
+    pub subpixel_edges: bool,
+    /// Оценивать наклон кода и сканировать строки вдоль его оси
+    /// (см. [`skew`]). Нужно для повёрнутых на несколько градусов кодов
+    /// с короткими штрихами; `row` в результатах — строка исходника.
+    pub deskew: bool,
+    /// Code128: допуск поиска STOP — сумма `|Δ|` по 7 run'ам окна, нормализованного
+    /// к 13 модулям. Суммы равны, поэтому расстояние чётное: `0`/`1` — точное
+    /// совпадение, `2` — один модуль «перетёк» между соседними run'ами (смаз, шум).
+    /// Каждый кандидат всё равно проверяется checksum'ом.
+    pub code128_stop_tolerance: u32,
+    /// Тихие зоны у EAN/UPC, Code128 и Code 93 (см. [`QuietZone`]); проверяется
+    /// не меньше [`MIN_QUIET_MODULES`] модулей. У MSI тихие зоны — часть разметки
+    /// (старт и стоп иначе не отличить от битов данных) и требуются всегда.
+    /// По умолчанию `Strict`.
+    pub quiet_zone: QuietZone,
+    /// Колбэк прогресса: вызывается после каждой строки сканирования с
+    /// `(строк_пройдено, строк_всего)`; `false` — прекратить сканирование.
+    pub progress: Option<Progress>,
+    /// Отсев строк, не похожих на штрих-код (см. [`row_looks_like_barcode`]):
+    /// меньше стольких run'ов между крайними штрихами — строку не декодируем.
+    /// Отсекает строки через подписи под кодом и пустой фон до запуска декодеров.
+    /// `0` — фильтр выключен.
+    pub min_run_count: usize,
+    /// Контрольные суммы EAN/UPC, Code128, Code 93 и MSI (см. [`ChecksumPolicy`]).
+    /// У MSI сумма проверяется по [`DecodeOptions::msi_check`]; при `MsiCheck::None`
+    /// проверять нечего и политика ни на что не влияет.
+    pub checksum: ChecksumPolicy,
+    /// Выбирать `scan_rows` строк по профилю плотности фронтов (см.
+    /// [`edge_density_rows`]) вместо равномерной сетки: строки уходят в полосы
+    /// со штрихами, а тонкий код между узлами сетки не теряется. Стоит один
+    /// проход по изображению. По умолчанию выключено.
+    pub smart_row_selection: bool,
+    /// Бинаризация строк (см. [`Binarization`]). По умолчанию адаптивная
+    /// с фоллбэком на глобальную, если run'ов на символ не хватает.
+    pub binarization: Binarization,
+    /// Прикладывать к 1D-результатам [`ScanDebug`] (строка и run'ы прочтения).
+    /// По умолчанию выключено: копия run'ов на каждый символ.
+    #[cfg(feature = "debug-capture")]
+    pub capture_debug: bool,
 }
 
 impl Default for DecodeOptions {
@@ -32,78 +234,1096 @@ impl Default for DecodeOptions {
         Self {
             scan_rows: 15,
             min_modules: 30,
+            ean13_leading_zero_as_upca: true,
+            code93_full_ascii: true,
+            msi_check: msi::MsiCheck::Mod10,
+            module_window: 0,
+            subpixel_edges: false,
+            deskew: false,
+            code128_stop_tolerance: 1,
+            quiet_zone: QuietZone::Strict,
+            progress: None,
+            min_run_count: 20,
+            checksum: ChecksumPolicy::Require,
+            smart_row_selection: false,
+            binarization: Binarization::Auto,
+            #[cfg(feature = "debug-capture")]
+            capture_debug: false,
         }
     }
 }
 
-/// Декодировать EAN-13/UPC-A сканированием нескольких строк.
-/// Пробуем каждую строку слева-направо и справа-налево (на случай разворота).
-pub fn decode_ean13_upca(img: &GrayImage<'_>, opts: &DecodeOptions) -> Vec<Barcode> {
-    let mut out = Vec::new();
-    let rows = opts.scan_rows.max(1).min(img.height);
-    for i in 0..rows {
-        let y = (i * (img.height - 1)) / (rows - 1).max(1);
-        let row = img.row(y);
-
-        if let Some(text) = ean13::decode_row(row, opts) {
-            let (format, normalized) = if text.len() == 12 {
-                (BarcodeFormat::UPCA, text)
+/// Минимальная тихая зона 1D, модулей: шире любого run'а внутри символов
+/// (≤ 4 модулей), но с запасом уже стандартных 7–10 — под плотную обрезку.
+pub const MIN_QUIET_MODULES: f32 = 5.0;
+
+/// Есть ли тихая зона: `runs` — ширины run'ов от края символа наружу, первый —
+/// пробел. Тёмные крапины уже полумодуля (шум) зону не прерывают; символ вплотную
+/// к краю строки или к чужому штриху — зоны нет.
+pub(crate) fn has_quiet_zone(runs: impl IntoIterator<Item = f32>, module: f32) -> bool {
+    let need = MIN_QUIET_MODULES * module;
+    let mut runs = runs.into_iter();
+    let mut quiet = 0.0f32;
+    while let Some(space) = runs.next() {
+        quiet += space;
+        if quiet >= need {
+            return true;
+        }
+        match runs.next() {
+            Some(speck) if speck < module * 0.5 => quiet += speck,
+            _ => return false,
+        }
+    }
+    false
+}
+
+/// Доля run'ов одной ширины, выше которой строка считается текстом, а не кодом:
+/// у штрихов и пробелов кода ширины 1–4 модуля, у строки через цифры подписи —
+/// почти одинаковая толщина штриха.
+pub const MAX_UNIFORM_RUN_SHARE: f32 = 0.85;
+
+/// Похожа ли строка на штрих-код: между крайними штрихами не меньше
+/// `opts.min_run_count` run'ов, и они не «одной ширины» (не больше
+/// [`MAX_UNIFORM_RUN_SHARE`] run'ов в пределах ±25% от самой частой ширины).
+/// Строка бинаризуется по `opts.binarization` — как в декодерах; достаточно,
+/// чтобы прошёл любой из вариантов. При `min_run_count == 0` — всегда `true`.
+///
+/// Декодеры строк проверяют то же самое по run'ам своей бинаризации (не
+/// прошедшая отбрасывается, как и с недобором run'ов) — отдельно звать эту
+/// функцию перед ними не нужно.
+#[must_use]
+pub fn row_looks_like_barcode(row: &[u8], opts: &DecodeOptions) -> bool {
+    opts.min_run_count == 0 || !row_binarizations(row, opts, 0).is_empty()
+}
+
+/// Фильтр [`row_looks_like_barcode`] по run'ам `rl` одной бинаризации `bin`.
+fn runs_look_like_barcode(bin: &[bool], rl: &[usize], opts: &DecodeOptions) -> bool {
+    if opts.min_run_count == 0 {
+        return true;
+    }
+    // фон до первого и после последнего штриха в счёт не идёт
+    let skip_head = usize::from(bin.first() == Some(&false));
+    let skip_tail = usize::from(bin.last() == Some(&false));
+    rl.get(skip_head..rl.len().saturating_sub(skip_tail))
+        .is_some_and(|inner| inner.len() >= opts.min_run_count && !is_uniform(inner))
+}
+
+/// Фильтр [`row_looks_like_barcode`] по дробным run'ам [`crate::binarize::runs_subpixel`]:
+/// цвет краёв неизвестен, поэтому крайние run'ы (обычно фон) не считаются всегда.
+pub(crate) fn subpixel_runs_look_like_barcode(rl: &[f32], opts: &DecodeOptions) -> bool {
+    if opts.min_run_count == 0 {
+        return true;
+    }
+    let inner: Vec<usize> = rl
+        .get(1..rl.len().saturating_sub(1))
+        .unwrap_or_default()
+        .iter()
+        .map(|w| f32_to_usize(w.round()))
+        .collect();
+    inner.len() >= opts.min_run_count && !is_uniform(&inner)
+}
+
+/// Доминирует ли в `rl` одна ширина (см. [`MAX_UNIFORM_RUN_SHARE`]).
+fn is_uniform(rl: &[usize]) -> bool {
+    let mut sorted = rl.to_vec();
+    sorted.sort_unstable();
+    // самая частая ширина — самый длинный отрезок равных в отсортированном списке
+    let (mut mode, mut best, mut cur) = (sorted[0], 0usize, 0usize);
+    for (i, &w) in sorted.iter().enumerate() {
+        cur = if i > 0 && sorted[i - 1] == w {
+            cur + 1
+        } else {
+            1
+        };
+        if cur > best {
+            (mode, best) = (w, cur);
+        }
+    }
+    let close = rl.iter().filter(|&&w| w.abs_diff(mode) <= mode / 4).count();
+    usize_to_f32(close) > MAX_UNIFORM_RUN_SHARE * usize_to_f32(rl.len())
+}
+
+/// Ширина символа в строке: модулей и пикселей (сумма ширин его run'ов),
+/// и где он начинается.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Extent {
+    pub modules: usize,
+    pub px: f32,
+    /// x первого штриха в прочитанной строке, px.
+    pub x: f32,
+    /// Сами run'ы — для [`ScanDebug`].
+    #[cfg(feature = "debug-capture")]
+    pub runs: Vec<f32>,
+}
+
+impl Extent {
+    /// Символ из run'ов `runs` (от первого штриха до последнего) шириной `modules`,
+    /// начиная с `x` px от начала строки.
+    pub fn new(modules: usize, runs: &[f32], x: f32) -> Self {
+        Self {
+            modules,
+            px: runs.iter().sum(),
+            x,
+            #[cfg(feature = "debug-capture")]
+            runs: runs.to_vec(),
+        }
+    }
+
+    /// [`ScanDebug`] прочтения на строке `y`, если его просили.
+    #[cfg(feature = "debug-capture")]
+    fn scan_debug(&self, y: usize, opts: &DecodeOptions) -> Option<ScanDebug> {
+        opts.capture_debug.then(|| ScanDebug {
+            scan_row: y,
+            runs: self.runs.clone(),
+        })
+    }
+}
+
+/// Бинаризации строки `(биты, run'ы)` по `opts.binarization`, в порядке перебора:
+/// только те, где набралось `min_runs` run'ов и которые прошли фильтр
+/// [`row_looks_like_barcode`]. При `Auto` — не больше одной (адаптивная, иначе
+/// глобальная), при `Both` — обе, если они различаются.
+pub(crate) fn row_binarizations(
+    row_gray: &[u8],
+    opts: &DecodeOptions,
+    min_runs: usize,
+) -> Vec<(Vec<bool>, Vec<usize>)> {
+    let binarize = |adaptive: bool| {
+        let rb = timed!(
+            "binarize_row",
+            if adaptive {
+                binarize_row_adaptive(row_gray)
             } else {
-                (BarcodeFormat::EAN13, text)
-            };
-            out.push(Barcode {
-                format,
-                text: normalized,
-                row: y,
-            });
+                binarize_row(row_gray)
+            }
+        );
+        let rl = runs(&rb);
+        (rl.len() >= min_runs && runs_look_like_barcode(&rb, &rl, opts)).then_some((rb, rl))
+    };
+    match opts.binarization {
+        Binarization::Auto => binarize(true)
+            .or_else(|| binarize(false))
+            .into_iter()
+            .collect(),
+        Binarization::Adaptive => binarize(true).into_iter().collect(),
+        Binarization::Global => binarize(false).into_iter().collect(),
+        Binarization::Both => {
+            let mut out: Vec<_> = binarize(true).into_iter().collect();
+            if let Some(global) = binarize(false).filter(|g| out.first() != Some(g)) {
+                out.push(global);
+            }
+            out
+        }
+    }
+}
+
+/// Прочитанное декодером строки значение, его ширина и флаги «без тихой зоны»,
+/// «контрольная сумма не сошлась».
+pub(crate) struct RowRead<T> {
+    pub value: T,
+    pub extent: Extent,
+    pub quiet_missing: bool,
+    pub checksum_failed: bool,
+    /// Прочитано по развёрнутой строке (ставит [`read_both_ways`]).
+    pub reversed: bool,
+}
+
+/// Прочтение-кандидат для [`pick_read`].
+pub(crate) struct RowCandidate<T> {
+    pub value: T,
+    pub extent: Extent,
+    /// Тихие зоны с обеих сторон есть.
+    pub quiet: bool,
+    pub checksum_ok: bool,
+}
+
+/// Выбрать прочтение среди кандидатов (см. [`RowCandidate`]) по политикам
+/// `opts.quiet_zone` и `opts.checksum`. Тихие зоны: первый с ними; при `Relaxed` —
+/// иначе первый без них (с флагом); при `Ignore` — просто первый.
+/// Сумма: при `Require` несошедшиеся отбрасываются, при `Prefer` идут после всех
+/// сошедшихся, при `Ignore` на выбор не влияют (только флаг). Кандидаты
+/// вычисляются лениво — до первого, лучше которого не бывает.
+pub(crate) fn pick_read<T>(
+    candidates: impl Iterator<Item = RowCandidate<T>>,
+    opts: &DecodeOptions,
+) -> Option<RowRead<T>> {
+    // ранг: 2 — сумма не сошлась (при `Prefer`), 1 — нет тихой зоны (при `Relaxed`)
+    let mut fallback: Option<(u8, RowRead<T>)> = None;
+    for RowCandidate {
+        value,
+        extent,
+        quiet,
+        checksum_ok,
+    } in candidates
+    {
+        if !checksum_ok && opts.checksum == ChecksumPolicy::Require {
+            continue;
+        }
+        let quiet_missing = !quiet && opts.quiet_zone != QuietZone::Ignore;
+        if quiet_missing && opts.quiet_zone == QuietZone::Strict {
             continue;
         }
+        let rank = 2 * u8::from(!checksum_ok && opts.checksum == ChecksumPolicy::Prefer)
+            + u8::from(quiet_missing);
+        let read = RowRead {
+            value,
+            extent,
+            quiet_missing,
+            checksum_failed: !checksum_ok,
+            reversed: false,
+        };
+        if rank == 0 {
+            return Some(read);
+        }
+        if fallback.as_ref().map_or(true, |(best, _)| rank < *best) {
+            fallback = Some((rank, read));
+        }
+    }
+    fallback.map(|(_, read)| read)
+}
 
-        // обратное направление
-        let mut rev = row.to_vec();
-        rev.reverse();
-        if let Some(text) = ean13::decode_row(&rev, opts) {
-            let (format, normalized) = if text.len() == 12 {
-                (BarcodeFormat::UPCA, text)
-            } else {
-                (BarcodeFormat::EAN13, text)
-            };
-            out.push(Barcode {
-                format,
-                text: normalized,
-                row: y,
-            });
+/// Прочитать строку `row` слева-направо, при неудаче — справа-налево.
+/// При [`ChecksumPolicy::Prefer`] прочтение с несошедшейся суммой не мешает
+/// попробовать обратное направление: сошедшееся в любую сторону важнее.
+fn read_both_ways<T>(
+    row: &[u8],
+    opts: &DecodeOptions,
+    read: impl Fn(&[u8], &DecodeOptions) -> Option<RowRead<T>>,
+) -> Option<RowRead<T>> {
+    let forward = read(row, opts);
+    if forward
+        .as_ref()
+        .is_some_and(|r| !r.checksum_failed || opts.checksum != ChecksumPolicy::Prefer)
+    {
+        return forward;
+    }
+    let mut rev = row.to_vec();
+    rev.reverse();
+    let backward = read(&rev, opts).map(|r| reversed_read(r, row.len()));
+    if backward.as_ref().is_some_and(|r| !r.checksum_failed) {
+        backward
+    } else {
+        forward.or(backward)
+    }
+}
+
+/// Прочтение развёрнутой строки длиной `len`: x — снова от левого края.
+fn reversed_read<T>(mut read: RowRead<T>, len: usize) -> RowRead<T> {
+    read.extent.x = (usize_to_f32(len) - read.extent.x - read.extent.px).max(0.0);
+    RowRead {
+        reversed: true,
+        ..read
+    }
+}
+
+/// [`read_both_ways`] для декодеров, читающих все символы строки: обратное
+/// направление — если прямое ничего не дало (или, при [`ChecksumPolicy::Prefer`],
+/// дало только несошедшиеся). Прочтения развёрнутой строки — тоже слева направо.
+fn read_all_both_ways<T>(
+    row: &[u8],
+    opts: &DecodeOptions,
+    read: impl Fn(&[u8], &DecodeOptions) -> Vec<RowRead<T>>,
+) -> Vec<RowRead<T>> {
+    let any_ok = |reads: &[RowRead<T>]| reads.iter().any(|r| !r.checksum_failed);
+    let forward = read(row, opts);
+    if any_ok(&forward) || (!forward.is_empty() && opts.checksum != ChecksumPolicy::Prefer) {
+        return forward;
+    }
+    let mut rev = row.to_vec();
+    rev.reverse();
+    let mut backward: Vec<RowRead<T>> = read(&rev, opts)
+        .into_iter()
+        .map(|r| reversed_read(r, row.len()))
+        .collect();
+    backward.reverse();
+    if any_ok(&backward) || forward.is_empty() {
+        backward
+    } else {
+        forward
+    }
+}
+
+/// Нормализация run'ов в модули с учётом `opts.module_window`.
+#[inline]
+pub(crate) fn normalize_row(
+    row_bin: &[bool],
+    rl: &[usize],
+    opts: &DecodeOptions,
+) -> (Vec<u8>, bool) {
+    crate::binarize::normalize_modules_windowed(row_bin, rl, opts.module_window)
+}
+
+/// Сколько строк реально сканировать: `scan_rows`, но не больше высоты.
+#[inline]
+pub(crate) fn scan_row_count(height: usize, opts: &DecodeOptions) -> usize {
+    opts.scan_rows.max(1).min(height)
+}
+
+/// y-координата `i`-й из `rows` строк, равномерно по высоте (сверху вниз).
+#[inline]
+pub(crate) fn scan_row_y(i: usize, rows: usize, height: usize) -> usize {
+    (i * (height - 1)) / (rows - 1).max(1)
+}
+
+/// y-координаты строк сканирования сверху вниз: равномерная сетка
+/// [`scan_row_y`] или, при `opts.smart_row_selection`, [`edge_density_rows`].
+pub(crate) fn scan_row_ys(img: &GrayImage<'_>, opts: &DecodeOptions) -> Vec<usize> {
+    let rows = scan_row_count(img.height, opts);
+    if opts.smart_row_selection {
+        let ys = edge_density_rows(img, rows);
+        if !ys.is_empty() {
+            return ys;
+        }
+    }
+    (0..rows).map(|i| scan_row_y(i, rows, img.height)).collect()
+}
+
+/// Минимальный перепад яркости строки (max − min), при котором в ней считаются
+/// фронты: ниже — шум ровного фона.
+const EDGE_DENSITY_MIN_CONTRAST: u8 = 48;
+
+/// Число переходов через середину диапазона яркости строки (0 — строка без
+/// контраста, см. [`EDGE_DENSITY_MIN_CONTRAST`]).
+fn row_edge_count(row: &[u8]) -> usize {
+    let (Some(&min), Some(&max)) = (row.iter().min(), row.iter().max()) else {
+        return 0;
+    };
+    if max - min < EDGE_DENSITY_MIN_CONTRAST {
+        return 0;
+    }
+    let mid = min + (max - min) / 2;
+    row.windows(2)
+        .filter(|p| (p[0] < mid) != (p[1] < mid))
+        .count()
+}
+
+/// До `rows` строк с наибольшей плотностью фронтов, сверху вниз.
+///
+/// Строки берутся жадно по убыванию числа переходов, не ближе
+/// `height / (2 * rows)` к уже выбранным — чтобы одна плотная полоса (подпись,
+/// крупный код) не забрала все строки. Строки без фронтов не берутся: пустой
+/// результат — на изображении нет ни одного штриха.
+#[must_use]
+pub fn edge_density_rows(img: &GrayImage<'_>, rows: usize) -> Vec<usize> {
+    let mut profile: Vec<(usize, usize)> = (0..img.height)
+        .map(|y| (row_edge_count(img.row(y)), y))
+        .filter(|&(edges, _)| edges > 0)
+        .collect();
+    // по убыванию плотности, при равенстве — верхняя строка раньше
+    profile.sort_unstable_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    let gap = (img.height / (2 * rows.max(1))).max(1);
+    let mut picked: Vec<usize> = Vec::with_capacity(rows);
+    for (_, y) in profile {
+        if picked.len() == rows {
+            break;
+        }
+        if picked.iter().all(|&p| p.abs_diff(y) >= gap) {
+            picked.push(y);
         }
     }
+    picked.sort_unstable();
+    picked
+}
+
+/// Пересекаются ли отрезки `(начало, конец)` (конец не включительно).
+pub(crate) fn spans_overlap(a: (usize, usize), b: (usize, usize)) -> bool {
+    a.0 < b.1 && b.0 < a.1
+}
+
+/// Прогнать `decode_row` (прочтение строки или все её символы) по `scan_rows`
+/// строкам — исходника или, при `opts.deskew`, выровненного изображения
+/// (строки пересчитываются в исходные).
+fn scan_rows<R: IntoIterator<Item = Barcode>>(
+    img: &GrayImage<'_>,
+    opts: &DecodeOptions,
+    decode_row: fn(&GrayImage<'_>, usize, &DecodeOptions) -> R,
+) -> Vec<Barcode> {
+    let aligned = skew::deskewed(img, opts);
+    let img = aligned.as_ref().map_or(*img, |d| d.image.as_gray());
+    let ys = scan_row_ys(&img, opts);
+    let rows = ys.len();
+    let mut out = Vec::new();
+    for (i, &y) in ys.iter().enumerate() {
+        for mut b in decode_row(&img, y, opts) {
+            if let Some(d) = &aligned {
+                b.row = d.source_row(b.row);
+            }
+            out.push(b);
+        }
+        if opts
+            .progress
+            .as_ref()
+            .is_some_and(|p| !p.report(i + 1, rows))
+        {
+            break;
+        }
+    }
+    // `Prefer`: несошедшееся прочтение нужно, только если на его месте в строке
+    // (по x) нет сошедшегося — другой, повреждённый код рядом остаётся.
+    if opts.checksum == ChecksumPolicy::Prefer {
+        let ok: Vec<(usize, usize)> = out
+            .iter()
+            .filter(|b| !b.checksum_failed)
+            .map(Barcode::x_span)
+            .collect();
+        out.retain(|b| {
+            !b.checksum_failed || !ok.iter().any(|&span| spans_overlap(span, b.x_span()))
+        });
+    }
     out
 }
 
+/// Одно правдоподобное прочтение строки (см. [`decode_row_candidates`]).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Candidate {
+    pub format: BarcodeFormat,
+    pub text: String,
+    /// Сумма манхэттенских расстояний (в модулях) прочитанных паттернов до
+    /// выбранных эталонов; `0` — все символы совпали точно. Code 93 и MSI
+    /// сравнивают паттерны только точно — у них всегда `0`.
+    pub distance: u32,
+    /// Контрольная сумма сошлась (у MSI — по `msi_check`).
+    pub checksum_ok: bool,
+    /// Прочитано справа-налево (строка развёрнута).
+    pub reversed: bool,
+}
+
+/// Кандидаты одного декодера по строке: `(текст, расстояние, сумма_сошлась)`.
+type RowCandidatesFn = fn(&[u8], &DecodeOptions) -> Vec<(String, u32, bool)>;
+
+/// Все правдоподобные прочтения строки `row` всеми 1D-декодерами в обе стороны —
+/// для разбора, почему этикетка читается неверно. В отличие от `decode_*_row`,
+/// ничего не отбрасывается и не сворачивается в лучшее: несошедшиеся суммы,
+/// EAN-варианты с перечитанной по соседней маске A/B цифрой, каждый STOP Code128
+/// и каждый старт Code 93/MSI, от которых символ читается целиком.
+///
+/// Порядок: направление (прямое, затем обратное), внутри — EAN/UPC, Code128,
+/// Code 93, MSI, дальше — по позиции в строке. Параметры `opts` те же, что у
+/// декодеров (`min_modules`, `module_window`, `code128_stop_tolerance`, ...),
+/// но `checksum`, `quiet_zone`, `min_run_count` и `subpixel_edges` не влияют:
+/// кандидаты строятся по целым run'ам, без тихих зон и фильтра строк. При
+/// [`Binarization::Both`] строка читается по обеим бинаризациям, и одно и то же
+/// прочтение может встретиться дважды.
+#[must_use]
+pub fn decode_row_candidates(row: &[u8], opts: &DecodeOptions) -> Vec<Candidate> {
+    // фильтр строк не применяется — см. выше
+    let opts = &DecodeOptions {
+        min_run_count: 0,
+        ..opts.clone()
+    };
+    let mut rev = row.to_vec();
+    rev.reverse();
+    let mut out = Vec::new();
+    for (row, reversed) in [(row, false), (rev.as_slice(), true)] {
+        let decoders: [(RowCandidatesFn, BarcodeFormat); 4] = [
+            (ean13::row_candidates, BarcodeFormat::EAN13),
+            (code128::row_candidates, BarcodeFormat::Code128),
+            (code93::row_candidates, BarcodeFormat::Code93),
+            (msi::row_candidates, BarcodeFormat::Msi),
+        ];
+        for (read, format) in decoders {
+            for (text, distance, checksum_ok) in read(row, opts) {
+                // EAN-13 с ведущим 0 уже отдан как 12-значный UPC-A
+                let format = if format == BarcodeFormat::EAN13 && text.len() == 12 {
+                    BarcodeFormat::UPCA
+                } else {
+                    format
+                };
+                out.push(Candidate {
+                    format,
+                    text,
+                    distance,
+                    checksum_ok,
+                    reversed,
+                });
+            }
+        }
+    }
+    out
+}
+
+/// Декодировать EAN-13/UPC-A сканированием нескольких строк.
+/// Пробуем каждую строку слева-направо и справа-налево (на случай разворота);
+/// с одной строки берутся все коды (см. [`decode_ean13_upca_row_all`]).
+pub fn decode_ean13_upca(img: &GrayImage<'_>, opts: &DecodeOptions) -> Vec<Barcode> {
+    scan_rows(img, opts, decode_ean13_upca_row_all)
+}
+
+/// EAN-13/UPC-A на одной строке `y` (в обе стороны): первый код строки.
+#[must_use]
+pub fn decode_ean13_upca_row(
+    img: &GrayImage<'_>,
+    y: usize,
+    opts: &DecodeOptions,
+) -> Option<Barcode> {
+    let row = img.row(y);
+
+    let read = if opts.subpixel_edges {
+        ean13::read_row_subpixel
+    } else {
+        ean13::read_row
+    };
+    read_both_ways(row, opts, read).map(|read| ean_barcode(read, y, opts))
+}
+
+/// Все EAN-13/UPC-A на строке `y`, слева направо — несколько этикеток бок о бок
+/// (положение каждой — [`Barcode::x`]). Как у [`decode_ean13_upca_row`], строка
+/// читается справа-налево, если слева-направо ничего не прочиталось.
+#[must_use]
+pub fn decode_ean13_upca_row_all(
+    img: &GrayImage<'_>,
+    y: usize,
+    opts: &DecodeOptions,
+) -> Vec<Barcode> {
+    let row = img.row(y);
+
+    let read = if opts.subpixel_edges {
+        ean13::read_row_all_subpixel
+    } else {
+        ean13::read_row_all
+    };
+    read_all_both_ways(row, opts, read)
+        .into_iter()
+        .map(|read| ean_barcode(read, y, opts))
+        .collect()
+}
+
+/// [`Barcode`] EAN-13/UPC-A по прочтению строки `y`: 12 цифр — UPC-A.
+#[cfg_attr(not(feature = "debug-capture"), allow(unused_variables))]
+fn ean_barcode(read: RowRead<(String, bool)>, y: usize, opts: &DecodeOptions) -> Barcode {
+    let (text, recovered) = read.value;
+    let format = if text.len() == 12 {
+        BarcodeFormat::UPCA
+    } else {
+        BarcodeFormat::EAN13
+    };
+    Barcode {
+        format,
+        text,
+        row: y,
+        recovered,
+        quiet_zone_missing: read.quiet_missing,
+        checksum_failed: read.checksum_failed,
+        modules: read.extent.modules,
+        width_px: f32_to_usize(read.extent.px.round()),
+        x: f32_to_usize(read.extent.x.round()),
+        reversed: read.reversed,
+        #[cfg(feature = "debug-capture")]
+        debug: read.extent.scan_debug(y, opts),
+    }
+}
+
 /// Декодировать Code128 сканированием нескольких строк (в обе стороны).
 pub fn decode_code128(img: &GrayImage<'_>, opts: &DecodeOptions) -> Vec<Barcode> {
-    let mut out = Vec::new();
-    let rows = opts.scan_rows.max(1).min(img.height);
-    for i in 0..rows {
-        let y = (i * (img.height - 1)) / (rows - 1).max(1);
-        let row = img.row(y);
-
-        if let Some(text) = code128::decode_row(row, opts) {
-            out.push(Barcode {
-                format: BarcodeFormat::Code128,
-                text,
-                row: y,
-            });
-            continue;
+    scan_rows(img, opts, decode_code128_row)
+}
+
+/// Code128 на одной строке `y` (в обе стороны).
+#[must_use]
+pub fn decode_code128_row(img: &GrayImage<'_>, y: usize, opts: &DecodeOptions) -> Option<Barcode> {
+    decode_code128_row_bytes(img, y, opts).map(|(b, _)| b)
+}
+
+/// Самый быстрый 1D-вход: как лазерный сканер, читаем только среднюю строку
+/// кадра — EAN-13/UPC-A, затем Code128 (каждый в обе стороны) — и отдаём
+/// первое прочтение. Без цикла `scan_rows`, выравнивания наклона и голосования
+/// по строкам: для потока кадров, где код держат по центру.
+#[must_use]
+pub fn decode_center_row(img: &GrayImage<'_>, opts: &DecodeOptions) -> Option<Barcode> {
+    if img.height == 0 {
+        return None;
+    }
+    let y = img.height / 2;
+    decode_ean13_upca_row(img, y, opts).or_else(|| decode_code128_row(img, y, opts))
+}
+
+/// Как [`decode_code128_row`], плюс точные байты и позиции FNC
+/// (см. [`code128::Code128Bytes`]) — для разбора GS1-128.
+#[must_use]
+pub fn decode_code128_row_bytes(
+    img: &GrayImage<'_>,
+    y: usize,
+    opts: &DecodeOptions,
+) -> Option<(Barcode, code128::Code128Bytes)> {
+    let row = img.row(y);
+
+    let decode = if opts.subpixel_edges {
+        code128::read_row_bytes_subpixel
+    } else {
+        code128::read_row_bytes
+    };
+    let read = read_both_ways(row, opts, decode)?;
+
+    let barcode = Barcode {
+        format: BarcodeFormat::Code128,
+        text: read.value.to_text(),
+        row: y,
+        recovered: false,
+        quiet_zone_missing: read.quiet_missing,
+        checksum_failed: read.checksum_failed,
+        modules: read.extent.modules,
+        width_px: f32_to_usize(read.extent.px.round()),
+        x: f32_to_usize(read.extent.x.round()),
+        reversed: read.reversed,
+        #[cfg(feature = "debug-capture")]
+        debug: read.extent.scan_debug(y, opts),
+    };
+    Some((barcode, read.value))
+}
+
+/// Декодировать Code 93 сканированием нескольких строк (в обе стороны).
+pub fn decode_code93(img: &GrayImage<'_>, opts: &DecodeOptions) -> Vec<Barcode> {
+    scan_rows(img, opts, decode_code93_row)
+}
+
+/// Code 93 на одной строке `y` (в обе стороны).
+pub fn decode_code93_row(img: &GrayImage<'_>, y: usize, opts: &DecodeOptions) -> Option<Barcode> {
+    let row = img.row(y);
+
+    let read = read_both_ways(row, opts, code93::read_row)?;
+
+    Some(Barcode {
+        format: BarcodeFormat::Code93,
+        text: read.value,
+        row: y,
+        recovered: false,
+        quiet_zone_missing: read.quiet_missing,
+        checksum_failed: read.checksum_failed,
+        modules: read.extent.modules,
+        width_px: f32_to_usize(read.extent.px.round()),
+        x: f32_to_usize(read.extent.x.round()),
+        reversed: read.reversed,
+        #[cfg(feature = "debug-capture")]
+        debug: read.extent.scan_debug(y, opts),
+    })
+}
+
+/// Декодировать MSI Plessey сканированием нескольких строк (в обе стороны).
+pub fn decode_msi(img: &GrayImage<'_>, opts: &DecodeOptions) -> Vec<Barcode> {
+    scan_rows(img, opts, decode_msi_row)
+}
+
+/// MSI Plessey на одной строке `y` (в обе стороны).
+pub fn decode_msi_row(img: &GrayImage<'_>, y: usize, opts: &DecodeOptions) -> Option<Barcode> {
+    let row = img.row(y);
+
+    let read = read_both_ways(row, opts, msi::read_row)?;
+
+    Some(Barcode {
+        format: BarcodeFormat::Msi,
+        text: read.value,
+        row: y,
+        recovered: false,
+        quiet_zone_missing: false,
+        checksum_failed: read.checksum_failed,
+        modules: read.extent.modules,
+        width_px: f32_to_usize(read.extent.px.round()),
+        x: f32_to_usize(read.extent.x.round()),
+        reversed: read.reversed,
+        #[cfg(feature = "debug-capture")]
+        debug: read.extent.scan_debug(y, opts),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_symbology_roundtrip_is_lossless() {
+        for f in [
+            BarcodeFormat::EAN13,
+            BarcodeFormat::UPCA,
+            BarcodeFormat::Code128,
+            BarcodeFormat::Code93,
+            BarcodeFormat::Msi,
+            BarcodeFormat::QR,
+            BarcodeFormat::Aztec,
+        ] {
+            assert_eq!(BarcodeFormat::from(Symbology::from(f)), f);
         }
+    }
 
-        let mut rev = row.to_vec();
-        rev.reverse();
-        if let Some(text) = code128::decode_row(&rev, opts) {
-            out.push(Barcode {
-                format: BarcodeFormat::Code128,
-                text,
-                row: y,
-            });
+    #[test]
+    fn every_ean_on_a_row_is_reported_in_both_directions() {
+        let ean = ean13::synthesize_ean13_row("5901234123457", 9, 2).expect("ean13");
+        let upca = ean13::synthesize_upca_row("036000291452", 9, 2).expect("upca");
+        let row = [ean.as_slice(), upca.as_slice()].concat();
+        let q = 9 * 2;
+        let reads =
+            |row: &[u8], opts: &DecodeOptions| -> Vec<(BarcodeFormat, String, usize, bool)> {
+                let img = GrayImage {
+                    data: row,
+                    width: row.len(),
+                    height: 1,
+                };
+                decode_ean13_upca_row_all(&img, 0, opts)
+                    .into_iter()
+                    .map(|b| (b.format, b.text, b.x, b.reversed))
+                    .collect()
+            };
+        for subpixel_edges in [false, true] {
+            let opts = DecodeOptions {
+                subpixel_edges,
+                ..DecodeOptions::default()
+            };
+            assert_eq!(
+                reads(&row, &opts),
+                [
+                    (BarcodeFormat::EAN13, "5901234123457".to_string(), q, false),
+                    (
+                        BarcodeFormat::UPCA,
+                        "036000291452".to_string(),
+                        ean.len() + q,
+                        false
+                    ),
+                ],
+                "subpixel_edges={subpixel_edges}"
+            );
+            // перевёрнутая этикетка: читается справа-налево, x — от левого края
+            let mut flipped = row.clone();
+            flipped.reverse();
+            assert_eq!(
+                reads(&flipped, &opts),
+                [
+                    (BarcodeFormat::UPCA, "036000291452".to_string(), q, true),
+                    (
+                        BarcodeFormat::EAN13,
+                        "5901234123457".to_string(),
+                        upca.len() + q,
+                        true
+                    ),
+                ],
+                "subpixel_edges={subpixel_edges}"
+            );
         }
     }
-    out
+
+    #[test]
+    fn smart_row_selection_finds_code_between_grid_rows() {
+        // тонкий код (строки 120..140) между узлами сетки 0, 99, 199, 299, 399
+        let row = ean13::synthesize_ideal_row("5901234123457", 9, 2);
+        let (width, height) = (row.len(), 400);
+        let mut data = vec![255u8; width * height];
+        for y in 120..140 {
+            data[y * width..(y + 1) * width].copy_from_slice(&row);
+        }
+        let img = GrayImage {
+            data: &data,
+            width,
+            height,
+        };
+        let mut opts = DecodeOptions {
+            scan_rows: 5,
+            ..DecodeOptions::default()
+        };
+        assert_eq!(scan_row_ys(&img, &opts), [0, 99, 199, 299, 399]);
+        assert!(decode_ean13_upca(&img, &opts).is_empty());
+
+        opts.smart_row_selection = true;
+        let ys = scan_row_ys(&img, &opts);
+        assert!(
+            !ys.is_empty() && ys.iter().all(|y| (120..140).contains(y)),
+            "{ys:?}"
+        );
+        let found = decode_ean13_upca(&img, &opts);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].text, "5901234123457");
+
+        // без единого штриха — обычная сетка
+        let blank = GrayImage {
+            data: &data[..width * 100],
+            width,
+            height: 100,
+        };
+        assert!(edge_density_rows(&blank, 5).is_empty());
+        assert_eq!(scan_row_ys(&blank, &opts), [0, 24, 49, 74, 99]);
+    }
+
+    #[test]
+    fn text_like_rows_are_skipped_before_decoding() {
+        let opts = DecodeOptions::default();
+        let rows = [
+            ean13::synthesize_ideal_row("5901234123457", 9, 2),
+            ean13::synthesize_ideal_row("036000291452", 9, 1),
+            code128::synthesize_row_code128("!T", 'B', 10, 2).unwrap(),
+            code93::synthesize_row_code93("A", 10, 2).unwrap(),
+            msi::synthesize_row_msi("000", msi::MsiCheck::Mod10, 12, 2).unwrap(),
+        ];
+        for row in &rows {
+            assert!(row_looks_like_barcode(row, &opts), "{row:?}");
+            assert!(
+                subpixel_runs_look_like_barcode(&crate::binarize::runs_subpixel(row), &opts),
+                "{row:?}"
+            );
+            for binarization in [
+                Binarization::Adaptive,
+                Binarization::Global,
+                Binarization::Both,
+            ] {
+                assert!(
+                    row_looks_like_barcode(
+                        row,
+                        &DecodeOptions {
+                            binarization,
+                            ..opts.clone()
+                        }
+                    ),
+                    "{row:?}"
+                );
+            }
+        }
+
+        // Строка через мелкую подпись: штрихи и просветы одной толщины.
+        let mut text = alloc::vec![255u8; 20];
+        for _ in 0..30 {
+            text.extend([0, 0, 0, 255, 255, 255]);
+        }
+        text.extend([255; 20]);
+        assert!(!row_looks_like_barcode(&text, &opts));
+        assert!(!subpixel_runs_look_like_barcode(
+            &crate::binarize::runs_subpixel(&text),
+            &opts
+        ));
+        // Мало run'ов (край этикетки, одиночные полосы) и пустой фон.
+        let sparse: Vec<u8> = [255u8, 0, 0, 0, 0, 255, 255, 0, 0, 255].repeat(4);
+        assert!(!row_looks_like_barcode(&sparse, &opts));
+        assert!(!row_looks_like_barcode(&[255; 200], &opts));
+
+        let img = GrayImage {
+            data: &text,
+            width: text.len(),
+            height: 1,
+        };
+        assert!(decode_code93_row(&img, 0, &opts).is_none());
+        let off = DecodeOptions {
+            min_run_count: 0,
+            ..DecodeOptions::default()
+        };
+        assert!(row_looks_like_barcode(&text, &off) && row_looks_like_barcode(&sparse, &off));
+    }
+
+    #[test]
+    fn center_row_reads_only_the_middle_line() {
+        let opts = DecodeOptions::default();
+        let blank = alloc::vec![255u8; 300];
+        let frame = |row: &[u8]| {
+            let mut data = Vec::new();
+            for y in 0..5 {
+                let mut line = blank.clone();
+                if y == 2 {
+                    line[..row.len()].copy_from_slice(row);
+                }
+                data.extend(line);
+            }
+            data
+        };
+
+        let ean = frame(&ean13::synthesize_ideal_row("5901234123457", 9, 2));
+        let img = GrayImage {
+            data: &ean,
+            width: 300,
+            height: 5,
+        };
+        let b = decode_center_row(&img, &opts).expect("EAN");
+        assert_eq!(
+            (b.format, b.text.as_str()),
+            (BarcodeFormat::EAN13, "5901234123457")
+        );
+
+        let mut c128 = code128::synthesize_row_code128("!T", 'B', 10, 2).unwrap();
+        c128.reverse();
+        let data = frame(&c128);
+        let img = GrayImage {
+            data: &data,
+            width: 300,
+            height: 5,
+        };
+        let b = decode_center_row(&img, &opts).expect("Code128");
+        assert_eq!((b.format, b.text.as_str()), (BarcodeFormat::Code128, "!T"));
+
+        // Код не на средней строке — не ищем.
+        let mut shifted = ean.clone();
+        shifted.rotate_left(300);
+        let img = GrayImage {
+            data: &shifted,
+            width: 300,
+            height: 5,
+        };
+        assert!(decode_center_row(&img, &opts).is_none());
+        assert!(decode_center_row(
+            &GrayImage {
+                data: &[],
+                width: 0,
+                height: 0
+            },
+            &opts
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn row_candidates_expose_checksum_and_alternatives() {
+        let opts = DecodeOptions::default();
+        let clean = Candidate {
+            format: BarcodeFormat::EAN13,
+            text: "5901234123457".into(),
+            distance: 0,
+            checksum_ok: true,
+            reversed: false,
+        };
+        let all = decode_row_candidates(&ean13::synthesize_ideal_row("5901234123457", 9, 2), &opts);
+        assert!(all.contains(&clean), "{all:?}");
+        assert!(all
+            .iter()
+            .filter(|c| c.reversed)
+            .all(|c| c.format != BarcodeFormat::EAN13));
+
+        // Битая контрольная: декодер молчит, кандидат есть.
+        let row = ean13::synthesize_ideal_row("5901234123458", 9, 2);
+        assert_eq!(ean13::decode_row(&row, &opts), None);
+        let all = decode_row_candidates(&row, &opts);
+        assert!(
+            all.iter()
+                .any(|c| c.text == "5901234123458" && !c.checksum_ok && c.distance == 0),
+            "{all:?}"
+        );
+
+        // Вторая левая цифра 4006381333931 («0» из набора B) уширена на модуль:
+        // маски AAAABB нет в таблице, прямого прочтения нет — только перечитанное
+        // по соседней маске, на модуль дальше от эталона.
+        let mut row = ean13::synthesize_ideal_row("4006381333931", 9, 1);
+        let at = crate::binarize::runs(&crate::binarize::binarize_row(&row))[..10]
+            .iter()
+            .sum::<usize>();
+        row.insert(at, row[at]);
+        let ean: Vec<Candidate> = decode_row_candidates(&row, &opts)
+            .into_iter()
+            .filter(|c| c.format == BarcodeFormat::EAN13 && !c.reversed)
+            .collect();
+        let recovered = Candidate {
+            text: "4006381333931".into(),
+            distance: 1,
+            ..clean
+        };
+        assert_eq!(ean, [recovered]);
+
+        // Code128: каждый STOP, от которого читается символ; чистый ряд — точно.
+        let row = code128::synthesize_row_code128("ABC-123", 'B', 10, 2).unwrap();
+        let all = decode_row_candidates(&row, &opts);
+        let code128 = Candidate {
+            format: BarcodeFormat::Code128,
+            text: "ABC-123".into(),
+            ..clean
+        };
+        assert!(all.contains(&code128), "{all:?}");
+    }
+
+    #[test]
+    fn barcode_reports_module_count_and_pixels_per_module() {
+        type RowFn = fn(&GrayImage<'_>, usize, &DecodeOptions) -> Option<Barcode>;
+        let opts = DecodeOptions::default();
+        let unit = 3;
+        let cases: [(Vec<u8>, RowFn, usize); 4] = [
+            (
+                ean13::synthesize_ideal_row("5901234123457", 9, unit),
+                decode_ean13_upca_row,
+                95,
+            ),
+            // старт + 7 символов + checksum по 11, STOP 13
+            (
+                code128::synthesize_row_code128("ABC-123", 'B', 10, unit).unwrap(),
+                decode_code128_row,
+                9 * 11 + 13,
+            ),
+            // старт, «A», C, K, стоп по 9 + завершающий бар
+            (
+                code93::synthesize_row_code93("A", 10, unit).unwrap(),
+                decode_code93_row,
+                5 * 9 + 1,
+            ),
+            // старт 3, четыре цифры по 12, стоп 4
+            (
+                msi::synthesize_row_msi("000", msi::MsiCheck::Mod10, 12, unit).unwrap(),
+                decode_msi_row,
+                3 + 4 * 12 + 4,
+            ),
+        ];
+        for (row, decode, modules) in cases {
+            let img = GrayImage {
+                data: &row,
+                width: row.len(),
+                height: 1,
+            };
+            let b = decode(&img, 0, &opts).expect("decodes");
+            assert_eq!(b.modules, modules, "{:?}", b.format);
+            assert_eq!(b.width_px, modules * unit, "{:?}", b.format);
+            assert!((b.module_px() - 3.0).abs() < 1e-6);
+        }
+    }
+
+    #[cfg(feature = "debug-capture")]
+    #[test]
+    fn scan_debug_is_captured_only_on_request() {
+        let row = code128::synthesize_row_code128("ABC-123", 'B', 10, 3).unwrap();
+        let img = GrayImage {
+            data: &row,
+            width: row.len(),
+            height: 1,
+        };
+        let mut opts = DecodeOptions::default();
+        assert_eq!(
+            decode_code128_row(&img, 0, &opts).expect("decodes").debug,
+            None
+        );
+
+        opts.capture_debug = true;
+        let b = decode_code128_row(&img, 0, &opts).expect("decodes");
+        let debug = b.debug.expect("captured");
+        assert_eq!(debug.scan_row, 0);
+        // 9 символов по 6 run'ов + STOP из 7
+        assert_eq!(debug.runs.len(), 9 * 6 + 7);
+        assert_eq!(debug.runs.iter().sum::<f32>() as usize, b.width_px);
+    }
+
+    #[test]
+    fn binarization_mode_can_be_forced_or_combined() {
+        let base = ean13::synthesize_ideal_row("5901234123457", 9, 4);
+        let n = base.len();
+        // низкий контраст: адаптивный порог дробит широкие штрихи, Auto его не отбрасывает
+        let flat: Vec<u8> = base
+            .iter()
+            .map(|&p| if p > 128 { 140 } else { 110 })
+            .collect();
+        // засветка справа: глобальный порог «съедает» левую половину
+        let ramp: Vec<u8> = base
+            .iter()
+            .enumerate()
+            .map(|(i, &p)| (f32::from(p) * (0.15 + 0.85 * i as f32 / n as f32)) as u8)
+            .collect();
+
+        let read = |row: &[u8], binarization| {
+            ean13::decode_row(
+                row,
+                &DecodeOptions {
+                    binarization,
+                    ..DecodeOptions::default()
+                },
+            )
+        };
+        let modes = [
+            Binarization::Auto,
+            Binarization::Adaptive,
+            Binarization::Global,
+            Binarization::Both,
+        ];
+        let flat_reads: Vec<bool> = modes.iter().map(|&b| read(&flat, b).is_some()).collect();
+        let ramp_reads: Vec<bool> = modes.iter().map(|&b| read(&ramp, b).is_some()).collect();
+        assert_eq!(flat_reads, [false, false, true, true]);
+        assert_eq!(ramp_reads, [true, true, false, true]);
+        assert_eq!(
+            read(&flat, Binarization::Both).as_deref(),
+            Some("5901234123457")
+        );
+    }
 }
@@ -0,0 +1,283 @@
+//! MSI Plessey: декодер по одной строке + синтезатор (для тестов/демо).
+//!
+//! Структура символа (в модулях, bar/space парами):
+//! - старт: бит `1` — широкий бар + узкий пробел (2,1);
+//! - каждая цифра — 4 бита BCD (старший первым), бит `1` = (2,1), бит `0` = (1,2);
+//! - стоп: узкий бар, широкий пробел, узкий бар (1,2,1), затем тихая зона.
+//!
+//! Контрольная цифра (mod 10 / mod 11) выбирается через `DecodeOptions::msi_check`
+//! и остаётся в возвращаемом тексте (как у EAN).
+
+use crate::core::cast::usize_to_f32;
+use crate::core::types::SynthError;
+use crate::one_d::{
+    checks, normalize_row, pick_read, row_binarizations, DecodeOptions, Extent, RowCandidate,
+    RowRead,
+};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Проверка контрольной цифры MSI.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MsiCheck {
+    /// Без проверки: все цифры — данные.
+    None,
+    /// Последняя цифра — mod 10 (Luhn).
+    Mod10,
+    /// Последняя цифра — mod 11 (веса 2..7 справа, IBM).
+    Mod11,
+}
+
+/// Минимум run'ов: тихая зона + старт (2) + одна цифра (8) + стоп (3).
+const MIN_RUNS: usize = 14;
+
+/// Попытка декодировать один ряд в MSI. Успех -> строка цифр (с контрольной).
+#[must_use]
+pub fn decode_row(row_gray: &[u8], opts: &DecodeOptions) -> Option<String> {
+    read_row(row_gray, opts).map(|r| r.value)
+}
+
+/// [`decode_row`] + флаг «контрольная не сошлась» (тихие зоны у MSI обязательны,
+/// `quiet_missing` всегда `false`).
+pub(crate) fn read_row(row_gray: &[u8], opts: &DecodeOptions) -> Option<RowRead<String>> {
+    pick_read(starts(row_gray, opts).into_iter(), opts)
+}
+
+/// Все правдоподобные прочтения строки `(текст, расстояние, контрольная_сошлась)`
+/// (см. [`crate::one_d::decode_row_candidates`]). Паттерны битов сравниваются
+/// точно — расстояние всегда `0`.
+pub(crate) fn row_candidates(row_gray: &[u8], opts: &DecodeOptions) -> Vec<(String, u32, bool)> {
+    starts(row_gray, opts)
+        .into_iter()
+        .map(|c| (c.value, 0, c.checksum_ok))
+        .collect()
+}
+
+/// Прочтения от каждого старта строки, слева направо, по каждой бинаризации
+/// (тихие зоны — часть разметки, поэтому `quiet` всегда `true`).
+fn starts(row_gray: &[u8], opts: &DecodeOptions) -> Vec<RowCandidate<String>> {
+    if row_gray.len() < opts.min_modules {
+        return Vec::new();
+    }
+
+    // 1) бинаризация (по `opts.binarization`) и нормализация в модули
+    let mut out = Vec::new();
+    for (rb, rl) in row_binarizations(row_gray, opts, MIN_RUNS) {
+        let (modules, starts_black) = normalize_row(&rb, &rl, opts);
+        let widths: Vec<f32> = rl.iter().copied().map(usize_to_f32).collect();
+
+        // 2) старт: чёрный run после тихой зоны, паттерн (2,1)
+        // чётные run'ы чёрные, если строка начинается с чёрного (только чёрные run'ы)
+        let first = if starts_black { 2 } else { 1 };
+        let reads = (first..modules.len().saturating_sub(1))
+            .step_by(2)
+            .filter(|&i| modules[i - 1] >= 3 && modules[i] == 2 && modules[i + 1] == 1)
+            .filter_map(|i| {
+                let (digits, stop) = decode_from(&modules, i + 2)?;
+                let checksum_ok = verify_check(&digits, opts.msi_check);
+                // старт 3 модуля, цифра 12, стоп 4
+                let extent = Extent::new(
+                    3 + 12 * digits.len() + 4,
+                    &widths[i..stop + 3],
+                    widths[..i].iter().sum(),
+                );
+                let value = digits.iter().map(|&d| char::from(b'0' + d)).collect();
+                Some(RowCandidate {
+                    value,
+                    extent,
+                    quiet: true,
+                    checksum_ok,
+                })
+            });
+        out.extend(reads);
+    }
+    out
+}
+
+/// Читает цифры, начиная с run'а `idx` (сразу после старта), до стопа;
+/// второй элемент — первый run стопа.
+fn decode_from(m: &[u8], mut idx: usize) -> Option<(Vec<u8>, usize)> {
+    let mut digits = Vec::new();
+    loop {
+        if is_stop(m, idx) {
+            return if digits.is_empty() {
+                None
+            } else {
+                Some((digits, idx))
+            };
+        }
+        if idx + 8 > m.len() {
+            return None;
+        }
+        let mut d = 0u8;
+        for bit in 0..4 {
+            let (bar, space) = (m[idx + 2 * bit], m[idx + 2 * bit + 1]);
+            d = (d << 1)
+                | match (bar, space) {
+                    (2, 1) => 1,
+                    (1, 2) => 0,
+                    _ => return None,
+                };
+        }
+        if d > 9 {
+            return None;
+        }
+        digits.push(d);
+        idx += 8;
+    }
+}
+
+/// Стоп (1,2,1) с тихой зоной (или концом строки) справа.
+fn is_stop(m: &[u8], i: usize) -> bool {
+    i + 2 < m.len()
+        && m[i] == 1
+        && m[i + 1] == 2
+        && m[i + 2] == 1
+        && !matches!(m.get(i + 3), Some(&q) if q < 3)
+}
+
+fn verify_check(digits: &[u8], check: MsiCheck) -> bool {
+    match check {
+        MsiCheck::None => true,
+        MsiCheck::Mod10 | MsiCheck::Mod11 => {
+            let Some((&last, payload)) = digits.split_last() else {
+                return false;
+            };
+            !payload.is_empty() && check_digit(payload, check) == Some(last)
+        }
+    }
+}
+
+/// Контрольная цифра для `payload` (None, если mod 11 даёт 10 — такой код невалиден).
+fn check_digit(payload: &[u8], check: MsiCheck) -> Option<u8> {
+    match check {
+        MsiCheck::None => None,
+        MsiCheck::Mod10 => Some(checks::luhn(payload)),
+        // веса 2..7 справа (IBM)
+        MsiCheck::Mod11 => Some(checks::mod11(payload, 7)).filter(|&c| c < 10),
+    }
+}
+
+// === Синтезатор для тестов/демо ===
+
+/// Сгенерировать идеальный одномерный ряд (ч/б пиксели) для MSI: к цифрам
+/// `digits` дописывается контрольная цифра по `check`, тихая зона — `quiet_zone`
+/// модулей с каждой стороны (`0` — без неё, стандартная —
+/// `Symbology::Msi.standard_quiet_zone()`).
+///
+/// # Errors
+///
+/// [`SynthError`] — не цифры, `unit == 0` или контрольная mod 11 = 10.
+pub fn synthesize_row_msi(
+    digits: &str,
+    check: MsiCheck,
+    quiet_zone: usize,
+    unit: usize,
+) -> Result<Vec<u8>, SynthError> {
+    if unit == 0 {
+        return Err(SynthError::ZeroUnit);
+    }
+    let mut ds: Vec<u8> = digits
+        .chars()
+        .enumerate()
+        .map(|(index, ch)| match u8::try_from(ch) {
+            Ok(b) if b.is_ascii_digit() => Ok(b - b'0'),
+            _ => Err(SynthError::NonDigit { ch, index }),
+        })
+        .collect::<Result<_, _>>()?;
+    if check != MsiCheck::None {
+        ds.push(check_digit(&ds, check).ok_or(SynthError::Mod11CheckIsTen)?);
+    }
+
+    // старт + цифры + стоп (тихие зоны — при переводе в пиксели)
+    let mut modules: Vec<u8> = Vec::new();
+    modules.extend([2, 1]);
+    for d in ds {
+        for bit in (0..4).rev() {
+            if (d >> bit) & 1 == 1 {
+                modules.extend([2, 1]);
+            } else {
+                modules.extend([1, 2]);
+            }
+        }
+    }
+    modules.extend([1, 2, 1]);
+
+    // quiet + модули -> пиксели (первый модуль — чёрный) + quiet
+    let mut pix: Vec<u8> = vec![255; quiet_zone * unit];
+    let mut black = true;
+    for m in modules {
+        let w = (m as usize) * unit;
+        let val = if black { 0 } else { 255 };
+        for _ in 0..w {
+            pix.push(val);
+        }
+        black = !black;
+    }
+    pix.resize(pix.len() + quiet_zone * unit, 255);
+    Ok(pix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::one_d::decode_msi;
+    use crate::GrayImage;
+
+    #[test]
+    fn msi_mod10_roundtrip() {
+        let row = synthesize_row_msi("1234567", MsiCheck::Mod10, 12, 3).unwrap();
+        let img = GrayImage {
+            width: row.len(),
+            height: 1,
+            data: &row,
+        };
+        let opts = DecodeOptions::default();
+        let res = decode_msi(&img, &opts);
+        assert!(!res.is_empty());
+        assert_eq!(res[0].text, "12345674");
+    }
+
+    #[test]
+    fn msi_mod11_and_wrong_policy() {
+        let row = synthesize_row_msi("80523", MsiCheck::Mod11, 12, 2).unwrap();
+        let mut opts = DecodeOptions {
+            msi_check: MsiCheck::Mod11,
+            ..DecodeOptions::default()
+        };
+        let text = decode_row(&row, &opts).expect("mod 11");
+        assert_eq!(text, "805238");
+
+        opts.msi_check = MsiCheck::None;
+        assert_eq!(decode_row(&row, &opts), Some(text.clone()));
+
+        // контрольная mod 11 для 80523 (=8) не совпадает с Luhn (=4)
+        opts.msi_check = MsiCheck::Mod10;
+        assert_eq!(decode_row(&row, &opts), None);
+    }
+
+    #[test]
+    fn synthesize_reports_invalid_input() {
+        assert_eq!(
+            synthesize_row_msi("12a4", MsiCheck::Mod10, 12, 2),
+            Err(SynthError::NonDigit { ch: 'a', index: 2 })
+        );
+        // 6·2 = 12 ≡ 1 (mod 11) → контрольная 10
+        assert_eq!(
+            synthesize_row_msi("6", MsiCheck::Mod11, 12, 2),
+            Err(SynthError::Mod11CheckIsTen)
+        );
+        assert_eq!(
+            synthesize_row_msi("6", MsiCheck::Mod10, 12, 0),
+            Err(SynthError::ZeroUnit)
+        );
+
+        // Тихая зона: `quiet_zone·unit` px белого с каждой стороны, `0` — ряд с штриха.
+        let bare = synthesize_row_msi("1234", MsiCheck::Mod10, 0, 2).unwrap();
+        let framed = synthesize_row_msi("1234", MsiCheck::Mod10, 12, 2).unwrap();
+        assert_eq!(bare[0], 0);
+        assert_eq!(framed.len(), bare.len() + 2 * 12 * 2);
+        assert_eq!(&framed[24..framed.len() - 24], &bare[..]);
+    }
+}
@@ -0,0 +1,220 @@
+//! Оценка наклона 1D-кода и выравнивание перед сканированием строк.
+//!
+//! Повёрнутый на несколько градусов код с короткими штрихами строка скана
+//! пересекает лишь частично: она «съезжает» через верхний/нижний край кода.
+//! Наклон ищем перебором углов: вертикальный сдвиг столбцов `y - x·k` с верным
+//! `k = tg θ` кладёт ось кода на строки, и энергия горизонтальных перепадов
+//! (фронты штрихов) собирается в самую узкую полосу строк — сумма квадратов
+//! построчных энергий максимальна. Ширины модулей при этом сохраняются
+//! с точностью до `1/cos θ`.
+
+// Пиксельные координаты и наклоны в f32: размеры изображений далеко от 2^23.
+#![allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+
+#[cfg(not(feature = "std"))]
+use crate::core::math::FloatExt;
+use crate::core::types::{GrayImage, LumaImage};
+use crate::one_d::DecodeOptions;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Перебираемые наклоны `k = i·SKEW_STEP`, `|i| <= SKEW_STEPS`: шаг ≈ 0.5°, до ≈ 10°.
+const SKEW_STEP: f32 = 0.0087;
+const SKEW_STEPS: i32 = 20;
+
+/// Выигрыш резкости над нулевым углом, ниже которого наклон не исправляем.
+const MIN_GAIN: f32 = 1.05;
+
+/// Не больше стольких строк на оценку (остальные пропускаются с шагом).
+const MAX_SAMPLE_ROWS: usize = 256;
+
+/// Выровненное изображение и отображение его строк обратно в исходник.
+#[derive(Clone, Debug)]
+pub struct Deskewed {
+    pub image: LumaImage,
+    /// Наклон оси кода `dy/dx` (тангенс угла) в исходном изображении.
+    pub slope: f32,
+    /// Сдвиг, добавленный к строкам, чтобы индексы оставались неотрицательными.
+    offset: f32,
+    source_height: usize,
+}
+
+impl Deskewed {
+    /// Строка исходного изображения, через которую проходит строка `y`
+    /// выровненного в середине ширины.
+    #[must_use]
+    pub fn source_row(&self, y: usize) -> usize {
+        let mid = (self.image.width.saturating_sub(1)) as f32 * 0.5;
+        let src = (y as f32 - self.offset + mid * self.slope).round();
+        (src.max(0.0) as usize).min(self.source_height - 1)
+    }
+}
+
+/// Перепад яркости, ниже которого пиксель не считается фронтом (шум, фон).
+const MIN_EDGE: u8 = 24;
+
+/// Оценить наклон оси кода `dy/dx`. `None` — код уже горизонтален
+/// (или в изображении нет выраженных штрихов).
+#[must_use]
+pub fn estimate_skew(img: &GrayImage<'_>) -> Option<f32> {
+    if img.width < 2 || img.height < 2 {
+        return None;
+    }
+    let step_y = img.height.div_ceil(MAX_SAMPLE_ROWS);
+
+    // фронты: (x, y, |I(x+1,y) - I(x,y)|)
+    let mut edges: Vec<(usize, usize, u32)> = Vec::new();
+    for y in (0..img.height).step_by(step_y) {
+        for (x, p) in img.row(y).windows(2).enumerate() {
+            let e = p[0].abs_diff(p[1]);
+            if e >= MIN_EDGE {
+                edges.push((x, y, u32::from(e)));
+            }
+        }
+    }
+    if edges.is_empty() {
+        return None;
+    }
+
+    let flat = band_concentration(&edges, img, 0.0);
+    let (best_k, best) = (-SKEW_STEPS..=SKEW_STEPS)
+        .filter(|&i| i != 0)
+        .map(|i| {
+            let k = i as f32 * SKEW_STEP;
+            (k, band_concentration(&edges, img, k))
+        })
+        .fold((0.0f32, flat), |acc, c| if c.1 > acc.1 { c } else { acc });
+
+    if best_k == 0.0 || best < flat * MIN_GAIN {
+        return None;
+    }
+    diag!(
+        "[skew] slope={:.4} concentration {:.3e} -> {:.3e}",
+        best_k,
+        flat,
+        best
+    );
+    Some(best_k)
+}
+
+/// Сумма квадратов построчных энергий фронтов после сдвига `y - x·k`.
+fn band_concentration(edges: &[(usize, usize, u32)], img: &GrayImage<'_>, k: f32) -> f32 {
+    let span = (k.abs() * (img.width - 1) as f32) as usize + 1;
+    let offset = if k > 0.0 { span as f32 } else { 0.0 };
+
+    let mut rows = vec![0u64; img.height + span + 1];
+    for &(x, y, e) in edges {
+        let idx = (y as f32 + offset - x as f32 * k).round() as usize;
+        rows[idx] += u64::from(e);
+    }
+    rows.iter().map(|&r| (r as f32) * (r as f32)).sum()
+}
+
+/// Вертикальный сдвиг столбцов на `-x·slope`: ось кода с наклоном `slope`
+/// ложится горизонтально. Высота растёт на `|slope|·(w-1)`, поля — белые.
+#[must_use]
+pub fn deskew(img: &GrayImage<'_>, slope: f32) -> Deskewed {
+    let (w, h) = (img.width, img.height);
+    let span = (slope.abs() * (w.saturating_sub(1)) as f32) as usize + 1;
+    let offset = if slope > 0.0 { span as f32 } else { 0.0 };
+    let nh = h + span;
+
+    let mut data = vec![255u8; w * nh];
+    for x in 0..w {
+        let dy = x as f32 * slope - offset;
+        for y in 0..nh {
+            // линейная интерполяция между соседними строками исходника
+            let fy = y as f32 + dy;
+            if fy < 0.0 || fy > (h - 1) as f32 {
+                continue;
+            }
+            let y0 = fy.floor() as usize;
+            let t = fy - y0 as f32;
+            let above = f32::from(img.data[y0 * w + x]);
+            let below = f32::from(img.data[(y0 + 1).min(h - 1) * w + x]);
+            data[y * w + x] = (above + (below - above) * t).round() as u8;
+        }
+    }
+
+    Deskewed {
+        image: LumaImage {
+            data,
+            width: w,
+            height: nh,
+        },
+        slope,
+        offset,
+        source_height: h,
+    }
+}
+
+/// Выровненное изображение для сканирования строк, если `opts.deskew`
+/// и наклон заметен; иначе `None` (сканируем исходник).
+#[must_use]
+pub fn deskewed(img: &GrayImage<'_>, opts: &DecodeOptions) -> Option<Deskewed> {
+    if !opts.deskew {
+        return None;
+    }
+    estimate_skew(img).map(|k| deskew(img, k))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Полоса штрихов высотой `band` вдоль оси с наклоном `k` через центр:
+    /// точка тёмная, если внутри полосы и `u / 4` нечётно (`u` — вдоль оси).
+    fn slanted_band(w: usize, h: usize, band: f32, k: f32) -> LumaImage {
+        let (cos, sin) = (1.0 / (1.0 + k * k).sqrt(), k / (1.0 + k * k).sqrt());
+        let (cx, cy) = (w as f32 * 0.5, h as f32 * 0.5);
+        let mut data = vec![255u8; w * h];
+        for y in 0..h {
+            for x in 0..w {
+                let (dx, dy) = (x as f32 - cx, y as f32 - cy);
+                let along = dx * cos + dy * sin + cx;
+                let across = dy * cos - dx * sin;
+                if across.abs() < band * 0.5 && (along as usize / 4) % 2 == 1 {
+                    data[y * w + x] = 0;
+                }
+            }
+        }
+        LumaImage {
+            data,
+            width: w,
+            height: h,
+        }
+    }
+
+    #[test]
+    fn estimates_slant_and_leaves_level_code_alone() {
+        let level = slanted_band(160, 60, 8.0, 0.0);
+        assert_eq!(estimate_skew(&level.as_gray()), None);
+
+        for deg in [3.0f32, -4.0] {
+            let k = deg.to_radians().tan();
+            let slanted = slanted_band(160, 60, 8.0, k);
+            let est = estimate_skew(&slanted.as_gray()).expect("наклон найден");
+            assert!((est - k).abs() < SKEW_STEP, "est={est}, k={k}");
+        }
+    }
+
+    #[test]
+    fn deskew_maps_rows_back_to_source() {
+        let img = LumaImage {
+            data: vec![128; 100 * 20],
+            width: 100,
+            height: 20,
+        };
+        let d = deskew(&img.as_gray(), 0.1);
+        assert_eq!((d.image.width, d.image.height), (100, 20 + 10));
+        // строка посередине исходника остаётся посередине
+        let mid_aligned = (0..d.image.height)
+            .find(|&y| d.source_row(y) == 10)
+            .unwrap();
+        assert_eq!(d.image.row(mid_aligned)[50], 128);
+    }
+}
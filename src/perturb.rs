@@ -0,0 +1,585 @@
+// src/perturb.rs
+//
+// Тестовые искажения синтетических изображений (только под `cfg(test)`):
+// размытие по Гауссу, аддитивный шум, градиент яркости, небольшое масштабирование,
+// поворот.
+// ГПСЧ детерминированный, так что падения воспроизводимы.
+
+// Пиксельная арифметика в f32: размеры тестовых картинок малы, касты безопасны.
+#![allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_possible_wrap,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+// Параметризованные тесты ниже прогоняют синтезаторы EAN/Code128/Code93/QR через
+// эти искажения и проверяют, что декодеры всё ещё читают коды — так регрессии
+// в бинаризации/семплинге ловятся в CI, а не на реальных сканах.
+
+use crate::core::types::LumaImage;
+use alloc::vec::Vec;
+
+/// Детерминированный ГПСЧ (xorshift32) — тесты должны быть воспроизводимы.
+pub(crate) struct Rng(u32);
+
+impl Rng {
+    pub(crate) fn new(seed: u32) -> Self {
+        Self(seed.max(1))
+    }
+
+    pub(crate) fn next_u32(&mut self) -> u32 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.0 = x;
+        x
+    }
+
+    /// Равномерно в [-1, 1).
+    pub(crate) fn next_signed(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1u32 << 23) as f32 - 1.0
+    }
+
+    /// Приблизительно N(0, 1): сумма 4 равномерных (центральная предельная).
+    pub(crate) fn next_gaussian(&mut self) -> f32 {
+        let s: f32 = (0..4).map(|_| self.next_signed()).sum();
+        s * (3.0f32 / 4.0).sqrt()
+    }
+}
+
+/// Размножить одну строку 1D-кода в изображение высотой `height`.
+pub(crate) fn image_from_row(row: &[u8], height: usize) -> LumaImage {
+    let mut data = Vec::with_capacity(row.len() * height);
+    for _ in 0..height {
+        data.extend_from_slice(row);
+    }
+    LumaImage {
+        data,
+        width: row.len(),
+        height,
+    }
+}
+
+#[inline]
+fn clamp_u8(v: f32) -> u8 {
+    v.round().clamp(0.0, 255.0) as u8
+}
+
+/// Размытие по Гауссу (сепарабельное, ядро радиуса ⌈3σ⌉, края — clamp).
+pub(crate) fn gaussian_blur(img: &LumaImage, sigma: f32) -> LumaImage {
+    if sigma <= 0.0 {
+        return img.clone();
+    }
+    let r = (3.0 * sigma).ceil() as isize;
+    let kernel: Vec<f32> = (-r..=r)
+        .map(|i| (-((i * i) as f32) / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let norm: f32 = kernel.iter().sum();
+
+    let (w, h) = (img.width as isize, img.height as isize);
+    let pass = |src: &[f32], horizontal: bool| -> Vec<f32> {
+        let mut out = alloc::vec![0.0f32; src.len()];
+        for y in 0..h {
+            for x in 0..w {
+                let mut acc = 0.0;
+                for (k, &kv) in kernel.iter().enumerate() {
+                    let d = k as isize - r;
+                    let (sx, sy) = if horizontal {
+                        ((x + d).clamp(0, w - 1), y)
+                    } else {
+                        (x, (y + d).clamp(0, h - 1))
+                    };
+                    acc += kv * src[(sy * w + sx) as usize];
+                }
+                out[(y * w + x) as usize] = acc / norm;
+            }
+        }
+        out
+    };
+
+    let src: Vec<f32> = img.data.iter().map(|&v| f32::from(v)).collect();
+    let tmp = pass(&src, true);
+    let out = if img.height > 1 {
+        pass(&tmp, false)
+    } else {
+        tmp
+    };
+    LumaImage {
+        data: out.into_iter().map(clamp_u8).collect(),
+        width: img.width,
+        height: img.height,
+    }
+}
+
+/// Аддитивный гауссов шум со стандартным отклонением `sigma` (в уровнях яркости).
+pub(crate) fn add_noise(img: &LumaImage, sigma: f32, seed: u32) -> LumaImage {
+    let mut rng = Rng::new(seed);
+    LumaImage {
+        data: img
+            .data
+            .iter()
+            .map(|&v| clamp_u8(f32::from(v) + sigma * rng.next_gaussian()))
+            .collect(),
+        width: img.width,
+        height: img.height,
+    }
+}
+
+/// Линейный градиент яркости слева направо: контраст сжимается к `[lo, hi]`,
+/// а затем вся картинка сдвигается на `shift_left..shift_right`
+/// (имитация неравномерной засветки).
+pub(crate) fn brightness_gradient(
+    img: &LumaImage,
+    lo: u8,
+    hi: u8,
+    shift_left: f32,
+    shift_right: f32,
+) -> LumaImage {
+    let span = f32::from(hi) - f32::from(lo);
+    let denom = (img.width.max(2) - 1) as f32;
+    let mut data = Vec::with_capacity(img.data.len());
+    for y in 0..img.height {
+        for x in 0..img.width {
+            let t = x as f32 / denom;
+            let v = f32::from(img.data[y * img.width + x]) / 255.0;
+            let shift = shift_left + (shift_right - shift_left) * t;
+            data.push(clamp_u8(f32::from(lo) + v * span + shift));
+        }
+    }
+    LumaImage {
+        data,
+        width: img.width,
+        height: img.height,
+    }
+}
+
+/// Масштабирование с билинейной интерполяцией (`sx`, `sy` — коэффициенты по осям).
+pub(crate) fn scale(img: &LumaImage, sx: f32, sy: f32) -> LumaImage {
+    let nw = ((img.width as f32 * sx).round() as usize).max(1);
+    let nh = ((img.height as f32 * sy).round() as usize).max(1);
+    let (w, h) = (img.width, img.height);
+    let at = |x: usize, y: usize| f32::from(img.data[y.min(h - 1) * w + x.min(w - 1)]);
+
+    let mut data = Vec::with_capacity(nw * nh);
+    for y in 0..nh {
+        let fy = ((y as f32 + 0.5) / sy - 0.5).max(0.0);
+        let (y0, ty) = (fy.floor() as usize, fy - fy.floor());
+        for x in 0..nw {
+            let fx = ((x as f32 + 0.5) / sx - 0.5).max(0.0);
+            let (x0, tx) = (fx.floor() as usize, fx - fx.floor());
+            let top = at(x0, y0) * (1.0 - tx) + at(x0 + 1, y0) * tx;
+            let bot = at(x0, y0 + 1) * (1.0 - tx) + at(x0 + 1, y0 + 1) * tx;
+            data.push(clamp_u8(top * (1.0 - ty) + bot * ty));
+        }
+    }
+    LumaImage {
+        data,
+        width: nw,
+        height: nh,
+    }
+}
+
+/// Поворот на `deg` градусов (по часовой при y вниз) вокруг центра, билинейно;
+/// пиксели снаружи исходника — белые. Размер не меняется.
+pub(crate) fn rotate(img: &LumaImage, deg: f32) -> LumaImage {
+    let (w, h) = (img.width, img.height);
+    let (sin, cos) = deg.to_radians().sin_cos();
+    let (cx, cy) = ((w as f32 - 1.0) * 0.5, (h as f32 - 1.0) * 0.5);
+    let at = |x: i64, y: i64| {
+        if x < 0 || y < 0 || x >= w as i64 || y >= h as i64 {
+            255.0
+        } else {
+            f32::from(img.data[y as usize * w + x as usize])
+        }
+    };
+
+    let mut data = Vec::with_capacity(w * h);
+    for y in 0..h {
+        for x in 0..w {
+            // обратное отображение: точка результата -> исходник
+            let (dx, dy) = (x as f32 - cx, y as f32 - cy);
+            let fx = cx + dx * cos + dy * sin;
+            let fy = cy - dx * sin + dy * cos;
+            let (x0, y0) = (fx.floor() as i64, fy.floor() as i64);
+            let (tx, ty) = (fx - fx.floor(), fy - fy.floor());
+            let top = at(x0, y0) * (1.0 - tx) + at(x0 + 1, y0) * tx;
+            let bot = at(x0, y0 + 1) * (1.0 - tx) + at(x0 + 1, y0 + 1) * tx;
+            data.push(clamp_u8(top * (1.0 - ty) + bot * ty));
+        }
+    }
+    LumaImage {
+        data,
+        width: w,
+        height: h,
+    }
+}
+
+/// Уменьшение строки в `f` раз (`f < 1`) усреднением по площади — как сенсор,
+/// у которого пиксель накрывает дробное число модулей.
+pub(crate) fn box_downscale_row(row: &[u8], f: f32) -> Vec<u8> {
+    let n = ((row.len() as f32 * f) as usize).max(1);
+    (0..n)
+        .map(|i| {
+            let (from, to) = (i as f32 / f, (i + 1) as f32 / f);
+            let mut acc = 0.0f32;
+            let mut x = from;
+            while x < to - 1e-4 {
+                let next = (x.floor() + 1.0).min(to);
+                acc += (next - x) * f32::from(row[(x as usize).min(row.len() - 1)]);
+                x = next;
+            }
+            clamp_u8(acc / (to - from))
+        })
+        .collect()
+}
+
+/// Именованное искажение для параметризованных тестов.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Perturbation {
+    Blur(f32),
+    Noise(f32, u32),
+    Gradient {
+        lo: u8,
+        hi: u8,
+        shift_left: f32,
+        shift_right: f32,
+    },
+    Scale(f32, f32),
+}
+
+impl Perturbation {
+    pub(crate) fn apply(self, img: &LumaImage) -> LumaImage {
+        match self {
+            Perturbation::Blur(sigma) => gaussian_blur(img, sigma),
+            Perturbation::Noise(sigma, seed) => add_noise(img, sigma, seed),
+            Perturbation::Gradient {
+                lo,
+                hi,
+                shift_left,
+                shift_right,
+            } => brightness_gradient(img, lo, hi, shift_left, shift_right),
+            Perturbation::Scale(sx, sy) => scale(img, sx, sy),
+        }
+    }
+}
+
+/// Искажения «в пределах допуска» для 1D-декодеров (модуль 3 px).
+/// Границы подобраны по текущей устойчивости: сильнее размытие (σ ≥ 0.8)
+/// и сжатие < 0.95 уже ломают нормализацию ширин.
+pub(crate) const ONE_D_TOLERATED: &[Perturbation] = &[
+    Perturbation::Blur(0.4),
+    Perturbation::Blur(0.6),
+    Perturbation::Noise(12.0, 1),
+    Perturbation::Noise(20.0, 7),
+    Perturbation::Gradient {
+        lo: 40,
+        hi: 220,
+        shift_left: -30.0,
+        shift_right: 30.0,
+    },
+    Perturbation::Gradient {
+        lo: 60,
+        hi: 200,
+        shift_left: 40.0,
+        shift_right: -40.0,
+    },
+    Perturbation::Scale(0.95, 1.0),
+    Perturbation::Scale(1.05, 1.0),
+    Perturbation::Scale(1.13, 1.0),
+    Perturbation::Scale(2.0, 2.0),
+];
+
+/// Искажения «в пределах допуска» для QR v1 (модуль 6 px).
+/// Набор уже, чем для 1D: поиск finder'ов по окнам 1:1:3:1:1 пока не
+/// переносит заметный шум и дробный масштаб — расширять по мере улучшения.
+pub(crate) const QR_TOLERATED: &[Perturbation] = &[
+    Perturbation::Blur(0.6),
+    Perturbation::Blur(1.0),
+    Perturbation::Noise(4.0, 1),
+    Perturbation::Gradient {
+        lo: 40,
+        hi: 220,
+        shift_left: -30.0,
+        shift_right: 30.0,
+    },
+    Perturbation::Gradient {
+        lo: 60,
+        hi: 200,
+        shift_left: 40.0,
+        shift_right: -40.0,
+    },
+    Perturbation::Scale(1.5, 1.5),
+    Perturbation::Scale(2.0, 2.0),
+];
+
+mod tests {
+    use super::*;
+    use crate::api::{Pipeline, PipelineBuilder};
+    use crate::core::types::Symbology;
+    use crate::one_d::{code128, code93, ean13};
+    use crate::qr::encode::encode_qr_v1;
+    use crate::qr::format::EcLevel;
+    use alloc::string::String;
+
+    /// Прогнать изображение через все допустимые искажения и проверить, что
+    /// `pipeline` находит `(sym, expected)`; в сообщении — какое искажение сломало.
+    fn assert_survives(
+        pipeline: &Pipeline,
+        base: &LumaImage,
+        tolerated: &[Perturbation],
+        sym: Symbology,
+        expected: &str,
+    ) {
+        let mut failed: Vec<String> = Vec::new();
+        for &p in tolerated {
+            let img = p.apply(base);
+            let ok = pipeline
+                .decode_iter(&img)
+                .any(|s| s.symbology == sym && s.text == expected);
+            if !ok {
+                failed.push(alloc::format!("{p:?}"));
+            }
+        }
+        assert!(
+            failed.is_empty(),
+            "{sym:?} {expected:?} не пережил: {failed:?}"
+        );
+    }
+
+    fn one_d_pipeline() -> Pipeline {
+        PipelineBuilder::new()
+            .enable_code93(true)
+            .enable_qr(false)
+            .build()
+    }
+
+    #[test]
+    fn ean13_survives_perturbations() {
+        let row = ean13::synthesize_ideal_row("4006381333931", 9, 3);
+        let base = image_from_row(&row, 24);
+        let sym = Symbology::Ean13;
+        assert_survives(
+            &one_d_pipeline(),
+            &base,
+            ONE_D_TOLERATED,
+            sym,
+            "4006381333931",
+        );
+    }
+
+    #[test]
+    fn upca_survives_perturbations() {
+        let row = ean13::synthesize_ideal_row("036000291452", 9, 3);
+        let base = image_from_row(&row, 24);
+        let sym = Symbology::UpcA;
+        assert_survives(
+            &one_d_pipeline(),
+            &base,
+            ONE_D_TOLERATED,
+            sym,
+            "036000291452",
+        );
+    }
+
+    #[test]
+    fn code128_survives_perturbations() {
+        for (text, set) in [("PERTURB-128", 'B'), ("0123456789", 'C')] {
+            let row = code128::synthesize_row_code128(text, set, 10, 3).unwrap();
+            let base = image_from_row(&row, 24);
+            assert_survives(
+                &one_d_pipeline(),
+                &base,
+                ONE_D_TOLERATED,
+                Symbology::Code128,
+                text,
+            );
+        }
+    }
+
+    #[test]
+    fn code93_survives_perturbations() {
+        let row = code93::synthesize_row_code93("CODE-93", 10, 3).unwrap();
+        let base = image_from_row(&row, 24);
+        let sym = Symbology::Code93;
+        assert_survives(&one_d_pipeline(), &base, ONE_D_TOLERATED, sym, "CODE-93");
+    }
+
+    #[test]
+    fn qr_survives_perturbations() {
+        let gray = encode_qr_v1(b"HELLO", EcLevel::L, 3, 4, 6).unwrap();
+        let base: LumaImage = gray.into();
+        let pipeline = PipelineBuilder::new()
+            .enable_ean13_upca(false)
+            .enable_code128(false)
+            .enable_code93(false)
+            .build();
+        assert_survives(&pipeline, &base, QR_TOLERATED, Symbology::QR, "HELLO");
+    }
+
+    #[test]
+    fn aztec_survives_perturbations_and_rotation() {
+        let text = "TICKET 0042 zone B";
+        let sym = crate::two_d::encode_aztec_compact(text, 12, 5).unwrap();
+        let pipeline = PipelineBuilder::new()
+            .enable_ean13_upca(false)
+            .enable_code128(false)
+            .enable_code93(false)
+            .enable_qr(false)
+            .enable_aztec(true)
+            .build();
+        assert_survives(&pipeline, &sym, QR_TOLERATED, Symbology::Aztec, text);
+        for deg in [10.0f32, 30.0, 45.0, 160.0, 250.0] {
+            let img = rotate(&sym, deg);
+            let found = pipeline
+                .decode_iter(&img)
+                .any(|s| s.symbology == Symbology::Aztec && s.text == text);
+            assert!(found, "{deg}°");
+        }
+    }
+
+    #[test]
+    fn qr_decodes_at_arbitrary_rotation() {
+        // Символ (модуль 6 px) на белом холсте с запасом, чтобы углы не срезались при повороте.
+        let (unit, pad) = (6usize, 100usize);
+        let gray = encode_qr_v1(b"HELLO", EcLevel::L, 3, 4, unit).unwrap();
+        let w = gray.width + 2 * pad;
+        let mut data = alloc::vec![255u8; w * w];
+        for y in 0..gray.height {
+            let dst = (y + pad) * w + pad;
+            data[dst..dst + gray.width].copy_from_slice(gray.row(y));
+        }
+        let base = LumaImage {
+            data,
+            width: w,
+            height: w,
+        };
+        let pipeline = PipelineBuilder::new()
+            .enable_ean13_upca(false)
+            .enable_code128(false)
+            .enable_code93(false)
+            .build();
+
+        // Центры finder'ов в исходнике (пиксельные координаты): TL, TR, BL.
+        let at = |m: f32| pad as f32 + m * unit as f32 - 0.5;
+        let src = [(at(7.5), at(7.5)), (at(21.5), at(7.5)), (at(7.5), at(21.5))];
+        let c = (w as f32 - 1.0) * 0.5;
+        // Эти углы без диагональных сканов теряли finder'ы.
+        for deg in [30.0f32, 45.0, 60.0, 220.0, 225.0] {
+            let img = rotate(&base, deg);
+            let (sin, cos) = deg.to_radians().sin_cos();
+            let finders = crate::qr::finder::find_finder_patterns(
+                &img.as_gray(),
+                &crate::qr::QrOptions::default(),
+            );
+            assert_eq!(finders.len(), 3, "{deg}°");
+            for (sx, sy) in src {
+                let (dx, dy) = (sx - c, sy - c);
+                let (ex, ey) = (c + dx * cos - dy * sin, c + dx * sin + dy * cos);
+                assert!(
+                    finders
+                        .iter()
+                        .any(|f| (f.x - ex).hypot(f.y - ey) < 1.5 * unit as f32),
+                    "{deg}°: нет finder'а у ({ex:.1},{ey:.1}) среди {finders:?}"
+                );
+            }
+            let text = pipeline.decode_first(&img).map(|s| s.text);
+            assert_eq!(text.as_deref(), Some("HELLO"), "{deg}°");
+        }
+    }
+
+    #[test]
+    fn perturbations_are_deterministic_and_shape_preserving() {
+        let row = code128::synthesize_row_code128("X", 'B', 10, 2).unwrap();
+        let base = image_from_row(&row, 4);
+        for &p in ONE_D_TOLERATED.iter().chain(QR_TOLERATED) {
+            let a = p.apply(&base);
+            let b = p.apply(&base);
+            assert_eq!(a.data, b.data, "{p:?} не детерминирован");
+            if !matches!(p, Perturbation::Scale(..)) {
+                assert_eq!((a.width, a.height), (base.width, base.height));
+            }
+        }
+        let up = scale(&base, 2.0, 1.0);
+        assert_eq!((up.width, up.height), (base.width * 2, base.height));
+    }
+
+    #[test]
+    fn deskew_reads_rotated_short_ean() {
+        // EAN с короткими (10 px) штрихами, повёрнутый на ±3°: на длине кода ось
+        // уходит на ~18 px, и ни одна строка скана не проходит его целиком.
+        let row = ean13::synthesize_ideal_row("4006381333931", 9, 3);
+        let (w, h, top, bar_h) = (row.len(), 60, 25, 10);
+        let mut level = LumaImage {
+            data: alloc::vec![255; w * h],
+            width: w,
+            height: h,
+        };
+        for y in top..top + bar_h {
+            level.data[y * w..(y + 1) * w].copy_from_slice(&row);
+        }
+
+        let deskew = PipelineBuilder::new()
+            .enable_qr(false)
+            .one_d_options(crate::one_d::DecodeOptions {
+                deskew: true,
+                ..Default::default()
+            })
+            .build();
+        for deg in [3.0f32, -3.0] {
+            let img = rotate(&level, deg);
+            let found = |p: &Pipeline| p.decode_iter(&img).find(|s| s.text == "4006381333931");
+            assert!(
+                found(&one_d_pipeline()).is_none(),
+                "{deg}°: читается и без выравнивания"
+            );
+
+            let sym = found(&deskew).unwrap_or_else(|| panic!("{deg}°: не прочитан"));
+            let y: usize = sym.extras.properties["row"].parse().unwrap();
+            assert!((top..top + bar_h).contains(&y), "{deg}°: row={y} вне кода");
+        }
+    }
+
+    #[test]
+    fn subpixel_edges_read_narrow_modules() {
+        // Модуль 4 px, уменьшенный усреднением до 1.6..2.6 px: целые run'ы
+        // теряют точность, субпиксельные фронты — нет.
+        let ean = ean13::synthesize_ideal_row("5901234123457", 9, 4);
+        let c128 = code128::synthesize_row_code128("SUBPX-42", 'B', 10, 4).unwrap();
+        let opts = crate::one_d::DecodeOptions::default();
+        for f in [0.4f32, 0.46, 0.55, 0.65] {
+            let e = box_downscale_row(&ean, f);
+            let c = box_downscale_row(&c128, f);
+            assert_eq!(
+                ean13::decode_row_subpixel(&e, &opts).as_deref(),
+                Some("5901234123457"),
+                "ean, f={f}"
+            );
+            assert_eq!(
+                code128::decode_row_subpixel(&c, &opts).as_deref(),
+                Some("SUBPX-42"),
+                "code128, f={f}"
+            );
+        }
+
+        // 1.84 px/модуль: целочисленный путь не читает ни один из кодов.
+        let (e, c) = (
+            box_downscale_row(&ean, 0.46),
+            box_downscale_row(&c128, 0.46),
+        );
+        assert_eq!(ean13::decode_row(&e, &opts), None);
+        assert_eq!(code128::decode_row(&c, &opts), None);
+
+        // Через пайплайн — по флагу `subpixel_edges`.
+        let pipe = PipelineBuilder::new()
+            .one_d_options(crate::one_d::DecodeOptions {
+                subpixel_edges: true,
+                ..crate::one_d::DecodeOptions::default()
+            })
+            .build();
+        let found = pipe.decode_all(&image_from_row(&c, 8));
+        assert!(found
+            .iter()
+            .any(|s| s.symbology == Symbology::Code128 && s.text == "SUBPX-42"));
+    }
+}
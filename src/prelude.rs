@@ -3,6 +3,7 @@
 // Удобные реэкспорты, чтобы подключать всё нужное одной строкой.
 
 pub use crate::core::types::{
-    DecodedExtras, DecodedSymbol, GrayImage, GrayImageExt, LumaImage, LumaImageExt, Orientation,
-    Point, Quad, Symbology,
+    Binarization, ChecksumPolicy, DecodeError, DecodedExtras, DecodedSymbol, GrayImage,
+    GrayImageExt, ImageStats, LumaImage, LumaImageExt, Orientation, Point, PointF, Progress, Quad,
+    QuadF, QuietZone, SymbolMeta, Symbology, SynthError,
 };
@@ -0,0 +1,113 @@
+// src/profile.rs
+//
+// Замеры времени горячих стадий для профилирования (feature `profile`).
+//
+// В отличие от `diag!` (текст для человека) здесь — числа: стадия и её длительность,
+// из которых собираются сводки «где уходит время» по большому прогону.
+// - с feature `profile` каждая обёрнутая `timed!` стадия отдаёт [`TimingEvent`]
+//   в хук [`set_timing_hook`]; без хука часы не запрашиваются;
+// - без feature `timed!` просто вычисляет выражение — сборка по умолчанию не меняется.
+//
+// События (имя — `TimingEvent::name`):
+// - `binarize_row` — бинаризация одной 1D-строки одним способом;
+// - `finder_scan` — поиск finder patterns QR по всему кадру;
+// - `qr_sample` — семплинг сетки модулей QR;
+// - `rs_correct` — исправление одного блока Reed–Solomon (QR и Aztec).
+//
+// `std::time::Instant` в wasm32-unknown-unknown не работает — там feature не включать.
+
+/// Одна замеренная стадия.
+#[cfg(feature = "profile")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimingEvent {
+    /// Имя стадии (см. список событий в описании модуля).
+    pub name: &'static str,
+    pub duration: std::time::Duration,
+}
+
+/// Приёмник замеров. Вызывается из потока, где шла стадия (в том числе из
+/// потоков `decode_batch`), так что агрегировать стоит потокобезопасно.
+#[cfg(feature = "profile")]
+pub type TimingHook = fn(&TimingEvent);
+
+#[cfg(feature = "profile")]
+static HOOK: std::sync::RwLock<Option<TimingHook>> = std::sync::RwLock::new(None);
+
+/// Установить (или снять, передав `None`) приёмник замеров.
+#[cfg(feature = "profile")]
+pub fn set_timing_hook(hook: Option<TimingHook>) {
+    if let Ok(mut h) = HOOK.write() {
+        *h = hook;
+    }
+}
+
+/// Начало замера для `timed!`: `None`, если хука нет (часы не трогаем).
+#[cfg(feature = "profile")]
+#[doc(hidden)]
+pub fn start() -> Option<(TimingHook, std::time::Instant)> {
+    let hook = HOOK.read().ok().and_then(|h| *h)?;
+    Some((hook, std::time::Instant::now()))
+}
+
+/// Конец замера для `timed!`.
+#[cfg(feature = "profile")]
+#[doc(hidden)]
+pub fn finish(name: &'static str, started: Option<(TimingHook, std::time::Instant)>) {
+    if let Some((hook, at)) = started {
+        hook(&TimingEvent {
+            name,
+            duration: at.elapsed(),
+        });
+    }
+}
+
+/// Вычислить выражение, замерив его как стадию `name` (см. описание модуля).
+macro_rules! timed {
+    ($name:literal, $body:expr) => {{
+        #[cfg(feature = "profile")]
+        let started = $crate::profile::start();
+        let out = $body;
+        #[cfg(feature = "profile")]
+        $crate::profile::finish($name, started);
+        out
+    }};
+}
+
+#[cfg(all(test, feature = "profile"))]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+    use std::sync::Mutex;
+
+    static EVENTS: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+
+    fn hook(e: &TimingEvent) {
+        EVENTS.lock().unwrap().push(e.name);
+    }
+
+    #[test]
+    fn hot_stages_report_timings() {
+        let img =
+            crate::qr::encode::encode_qr_v1(b"PROFILE", crate::qr::format::EcLevel::M, 2, 4, 4)
+                .unwrap();
+        set_timing_hook(Some(hook));
+        let found = crate::api::Pipeline::new().decode_first(&img);
+        let row = crate::one_d::ean13::synthesize_ideal_row("5901234123457", 9, 2);
+        let line = crate::GrayImage {
+            data: &row,
+            width: row.len(),
+            height: 1,
+        };
+        let ean = crate::one_d::decode_center_row(&line, &crate::one_d::DecodeOptions::default());
+        set_timing_hook(None);
+
+        assert_eq!(found.map(|s| s.text).as_deref(), Some("PROFILE"));
+        assert!(ean.is_some());
+        let events = EVENTS.lock().unwrap();
+        for name in ["binarize_row", "finder_scan", "qr_sample", "rs_correct"] {
+            assert!(events.contains(&name), "{name}: {events:?}");
+        }
+        // Без хука `timed!` — просто выражение.
+        assert_eq!(timed!("noop", 2 + 2), 4);
+    }
+}
@@ -1,6 +1,10 @@
 //! Биты ↔ байты и разбор Byte mode для QR v1-L.
 
+use alloc::string::String;
+use alloc::vec::Vec;
+
 /// Упаковать 208 бит (MSB-first) в 26 байт (codewords).
+#[must_use]
 pub fn bits_to_bytes_v1(bits: &[bool]) -> Vec<u8> {
     let mut out = Vec::with_capacity((bits.len()+7)/8);
     let mut cur: u8 = 0;
@@ -19,36 +23,104 @@ pub fn bits_to_bytes_v1(bits: &[bool]) -> Vec<u8> {
     out
 }
 
+/// Кодировка, в которой удалось прочитать payload Byte mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Charset {
+    Utf8,
+    /// ISO-8859-1: каждый байт — символ U+0000..U+00FF.
+    Latin1,
+}
+
+impl Charset {
+    /// Имя для `DecodedExtras` (`"qr.charset"`).
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Charset::Utf8 => "UTF-8",
+            Charset::Latin1 => "ISO-8859-1",
+        }
+    }
+}
+
+/// Текст из payload Byte mode: сначала UTF-8 (так пишет большинство генераторов),
+/// при неудаче — ISO-8859-1, кодировка Byte mode по умолчанию без ECI.
+/// Latin-1 отображает любой байт, поэтому результат есть всегда.
+#[must_use]
+pub fn decode_byte_payload(bytes: &[u8]) -> (String, Charset) {
+    match core::str::from_utf8(bytes) {
+        Ok(s) => (String::from(s), Charset::Utf8),
+        Err(_) => (
+            bytes.iter().map(|&b| char::from(b)).collect(),
+            Charset::Latin1,
+        ),
+    }
+}
+
+/// Сколько байт Byte mode вмещают `data_bits` бит данных v1 за вычетом заголовка
+/// (4 бита режима, 8 бит длины). Для 152/128/104/72 бит (L/M/Q/H) — 17/14/11/7,
+/// как [`capacity`](super::capacity::capacity). Длину сверх этого парсеры
+/// отвергают сразу, не читая байты.
+fn byte_capacity_v1(data_bits: usize) -> usize {
+    data_bits.saturating_sub(12) / 8
+}
+
 /// Разобрать Byte mode для v1-L (19 data bytes, 7 ec) **напрямую из битового потока**.
 /// Считаем первые 19×8=152 бита данных (остальное — EC), порядок бит MSB-first.
 /// Формат: 4 бита mode=0100, 8 бит length, затем `length` байтов данных.
+/// Текст — по [`decode_byte_payload`].
+#[must_use]
 pub fn parse_byte_mode_bits_v1_l(bits: &[bool]) -> Option<String> {
+    parse_byte_mode_payload_v1_l(bits).map(|b| decode_byte_payload(&b).0)
+}
+
+/// Как [`parse_byte_mode_bits_v1_l`], но возвращает сырые байты payload'а без декодирования текста.
+#[must_use]
+pub fn parse_byte_mode_payload_v1_l(bits: &[bool]) -> Option<Vec<u8>> {
     let data_bits = 19 * 8;
     if bits.len() < data_bits { return None; }
     parse_byte_mode_bits_v1_l_from_offset(bits, 0)
 }
 
+/// Сырые байты payload'а Byte mode из data-бит любого уровня EC v1
+/// (`bits` — ровно data-кодворды: 152/128/104/72 бита для L/M/Q/H).
+/// Payload обязан целиком уместиться в data-часть.
+#[must_use]
+pub fn parse_byte_mode_payload_v1(bits: &[bool]) -> Option<Vec<u8>> {
+    if bits.len() < 12 || bits.len() > 19 * 8 {
+        return None;
+    }
+    read_byte_segment(bits, 0)
+}
+
 /// «Умный» relaxed-парсер: сканирует заголовок `0100` с произвольного смещения
 /// в пределах первых 19 байт данных. Полезно, если поток оказался сдвинут.
+#[must_use]
 pub fn parse_byte_mode_bits_v1_l_relaxed(bits: &[bool]) -> Option<String> {
     let data_bits = 19 * 8;
     if bits.len() < data_bits { return None; }
     let s = &bits[..data_bits];
 
     // Можно сканировать с шагом 1 бита. Чтобы не ловить ложные срабатывания,
-    // проверяем: len<=17, хватает бит до конца, payload валиден как UTF-8.
+    // проверяем: len<=17, хватает бит до конца, payload валиден как UTF-8
+    // (без фоллбэка на Latin-1 — он принял бы мусор с любого смещения).
     for offset in 0..=(data_bits.saturating_sub(12)) {
-        if let Some(txt) = parse_byte_mode_bits_v1_l_from_offset(s, offset) {
+        if let Some(txt) =
+            parse_byte_mode_bits_v1_l_from_offset(s, offset).and_then(|b| String::from_utf8(b).ok())
+        {
             return Some(txt);
         }
     }
     None
 }
 
-fn parse_byte_mode_bits_v1_l_from_offset(bits: &[bool], offset: usize) -> Option<String> {
+fn parse_byte_mode_bits_v1_l_from_offset(bits: &[bool], offset: usize) -> Option<Vec<u8>> {
     let data_bits = 19 * 8;
     if bits.len() < data_bits || offset + 12 > data_bits { return None; }
+    read_byte_segment(&bits[..data_bits], offset)
+}
 
+/// Сегмент Byte mode (`0100`, длина 8 бит, байты) с позиции `offset` в пределах `bits`.
+fn read_byte_segment(bits: &[bool], offset: usize) -> Option<Vec<u8>> {
     struct R<'a> { b: &'a [bool], i: usize }
     impl<'a> R<'a> {
         fn new(b: &'a [bool], start: usize) -> Self { Self { b, i: start } }
@@ -64,11 +136,13 @@ fn parse_byte_mode_bits_v1_l_from_offset(bits: &[bool], offset: usize) -> Option
         }
     }
 
-    let mut r = R::new(&bits[..data_bits], offset);
+    let mut r = R::new(bits, offset);
     let mode = r.get(4)? as u8;
     if mode != 0b0100 { return None; }
     let len = r.get(8)? as usize;
-    if len > 17 { return None; }
+    if len > byte_capacity_v1(bits.len()) {
+        return None;
+    }
     if r.left() < len * 8 { return None; }
 
     let mut bytes = Vec::with_capacity(len);
@@ -76,11 +150,12 @@ fn parse_byte_mode_bits_v1_l_from_offset(bits: &[bool], offset: usize) -> Option
         let b = r.get(8)? as u8;
         bytes.push(b);
     }
-    String::from_utf8(bytes).ok()
+    Some(bytes)
 }
 
 /// Старый парсер по codewords — оставляем для API и тестов совместимости.
 /// Разобрать Byte mode из первых 19 data codewords.
+#[must_use]
 pub fn parse_byte_mode_v1_l(data_cw: &[u8]) -> Option<String> {
     if data_cw.len() < 1 { return None; }
 
@@ -105,9 +180,72 @@ pub fn parse_byte_mode_v1_l(data_cw: &[u8]) -> Option<String> {
     let mode = r.get(4)? as u8;
     if mode != 0b0100 { return None; }
     let len = r.get(8)? as usize;
+    // payload обязан уместиться в 19 data codewords — EC-часть за ними не читаем
+    if len > byte_capacity_v1(data_cw.len().min(19) * 8) {
+        return None;
+    }
     let mut bytes = Vec::with_capacity(len);
     for _ in 0..len {
         bytes.push(r.get(8)? as u8);
     }
-    String::from_utf8(bytes).ok()
+    Some(decode_byte_payload(&bytes).0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payload_falls_back_to_latin1() {
+        let (s, cs) = decode_byte_payload("café".as_bytes());
+        assert_eq!((s.as_str(), cs), ("café", Charset::Utf8));
+
+        // 0xE9 одиночным байтом — не UTF-8, но «é» в ISO-8859-1.
+        let (s, cs) = decode_byte_payload(b"caf\xe9");
+        assert_eq!((s.as_str(), cs), ("café", Charset::Latin1));
+        assert_eq!(cs.as_str(), "ISO-8859-1");
+    }
+
+    #[test]
+    fn byte_capacity_matches_capacity_table() {
+        use crate::qr::capacity::{capacity, codeword_split, QrMode};
+        use crate::qr::format::EcLevel;
+        for ec in [EcLevel::L, EcLevel::M, EcLevel::Q, EcLevel::H] {
+            let (data, _) = codeword_split(1, ec).unwrap();
+            assert_eq!(
+                byte_capacity_v1(data * 8),
+                capacity(1, ec, QrMode::Byte),
+                "{ec:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn bogus_length_byte_is_rejected_up_front() {
+        // Заголовок 0100 + длина, дальше — байты "A" до конца потока.
+        let stream = |len: u8, total: usize| {
+            let mut cw = vec![0x40 | (len >> 4), (len << 4) | 0x04];
+            cw.resize(total, 0x11);
+            cw
+        };
+        let bits = |cw: &[u8]| {
+            cw.iter()
+                .flat_map(|&b| (0..8).rev().map(move |i| b >> i & 1 == 1))
+                .collect::<Vec<_>>()
+        };
+
+        // v1-L: 19 data + 7 EC codewords; длина 20 раньше дочитывала байты из EC.
+        assert!(parse_byte_mode_v1_l(&stream(17, 26)).is_some());
+        assert_eq!(parse_byte_mode_v1_l(&stream(20, 26)), None);
+        assert_eq!(parse_byte_mode_v1_l(&stream(0xFF, 26)), None);
+        assert!(parse_byte_mode_payload_v1_l(&bits(&stream(17, 19))).is_some());
+        assert_eq!(parse_byte_mode_payload_v1_l(&bits(&stream(0xFF, 19))), None);
+
+        // v1-M: 16 data codewords — 14 байт, а не 17, как у L.
+        assert_eq!(
+            parse_byte_mode_payload_v1(&bits(&stream(14, 16))).map(|b| b.len()),
+            Some(14)
+        );
+        assert_eq!(parse_byte_mode_payload_v1(&bits(&stream(15, 16))), None);
+    }
 }
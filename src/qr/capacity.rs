@@ -0,0 +1,252 @@
+//! Ёмкость QR по версии, уровню EC и режиму данных (ISO/IEC 18004, табл. 7 и 9).
+//!
+//! Таблицы блоков коррекции — на все 40 версий: декодер пока читает только v1,
+//! но энкодер и валидация опираются на одни и те же числа.
+
+use super::format::EcLevel;
+
+/// Режим сегмента данных: от него зависят длина поля счётчика и плотность упаковки.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QrMode {
+    /// Цифры 0–9: 3 символа в 10 бит.
+    Numeric,
+    /// 45 символов (цифры, A–Z, пробел, `$%*+-./:`): 2 символа в 11 бит.
+    Alphanumeric,
+    /// Произвольные байты: 8 бит на символ.
+    Byte,
+    /// Shift JIS: 13 бит на символ.
+    Kanji,
+}
+
+/// EC-кодвордов на блок, по версиям 1..=40; столбцы — L, M, Q, H.
+const EC_CODEWORDS_PER_BLOCK: [[u8; 4]; 40] = [
+    [7, 10, 13, 17],
+    [10, 16, 22, 28],
+    [15, 26, 18, 22],
+    [20, 18, 26, 16],
+    [26, 24, 18, 22],
+    [18, 16, 24, 28],
+    [20, 18, 18, 26],
+    [24, 22, 22, 26],
+    [30, 22, 20, 24],
+    [18, 26, 24, 28],
+    [20, 30, 28, 24],
+    [24, 22, 26, 28],
+    [26, 22, 24, 22],
+    [30, 24, 20, 24],
+    [22, 24, 30, 24],
+    [24, 28, 24, 30],
+    [28, 28, 28, 28],
+    [30, 26, 28, 28],
+    [28, 26, 26, 26],
+    [28, 26, 30, 28],
+    [28, 26, 28, 30],
+    [28, 28, 30, 24],
+    [30, 28, 30, 30],
+    [30, 28, 30, 30],
+    [26, 28, 30, 30],
+    [28, 28, 28, 30],
+    [30, 28, 30, 30],
+    [30, 28, 30, 30],
+    [30, 28, 30, 30],
+    [30, 28, 30, 30],
+    [30, 28, 30, 30],
+    [30, 28, 30, 30],
+    [30, 28, 30, 30],
+    [30, 28, 30, 30],
+    [30, 28, 30, 30],
+    [30, 28, 30, 30],
+    [30, 28, 30, 30],
+    [30, 28, 30, 30],
+    [30, 28, 30, 30],
+    [30, 28, 30, 30],
+];
+
+/// Число блоков коррекции, по версиям 1..=40; столбцы — L, M, Q, H.
+const EC_BLOCKS: [[u8; 4]; 40] = [
+    [1, 1, 1, 1],
+    [1, 1, 1, 1],
+    [1, 1, 2, 2],
+    [1, 2, 2, 4],
+    [1, 2, 4, 4],
+    [2, 4, 4, 4],
+    [2, 4, 6, 5],
+    [2, 4, 6, 6],
+    [2, 5, 8, 8],
+    [4, 5, 8, 8],
+    [4, 5, 8, 11],
+    [4, 8, 10, 11],
+    [4, 9, 12, 16],
+    [4, 9, 16, 16],
+    [6, 10, 12, 18],
+    [6, 10, 17, 16],
+    [6, 11, 16, 19],
+    [6, 13, 18, 21],
+    [7, 14, 21, 25],
+    [8, 16, 20, 25],
+    [8, 17, 23, 25],
+    [9, 17, 23, 34],
+    [9, 18, 25, 30],
+    [10, 20, 27, 32],
+    [12, 21, 29, 35],
+    [12, 23, 34, 37],
+    [12, 25, 34, 40],
+    [13, 26, 35, 42],
+    [14, 28, 38, 45],
+    [15, 29, 40, 48],
+    [16, 31, 43, 51],
+    [17, 33, 45, 54],
+    [18, 35, 48, 57],
+    [19, 37, 51, 60],
+    [19, 38, 53, 63],
+    [20, 40, 56, 66],
+    [21, 43, 59, 70],
+    [22, 45, 62, 74],
+    [24, 47, 65, 77],
+    [25, 49, 68, 81],
+];
+
+#[inline]
+fn ec_column(ec: EcLevel) -> usize {
+    match ec {
+        EcLevel::L => 0,
+        EcLevel::M => 1,
+        EcLevel::Q => 2,
+        EcLevel::H => 3,
+    }
+}
+
+/// Всего кодвордов символа версии `version` (1..=40): модули матрицы минус
+/// функциональные (finder'ы, тайминги, выравнивающие узоры, формат и версия), делённые на 8.
+/// `None` для версии вне диапазона.
+#[must_use]
+pub fn total_codewords(version: u8) -> Option<usize> {
+    if !(1..=40).contains(&version) {
+        return None;
+    }
+    let v = usize::from(version);
+    let mut modules = (16 * v + 128) * v + 64;
+    if v >= 2 {
+        let align = v / 7 + 2;
+        modules -= (25 * align - 10) * align - 55;
+        if v >= 7 {
+            modules -= 36;
+        }
+    }
+    Some(modules / 8)
+}
+
+/// `(data, ec)` — суммарные числа кодвордов данных и коррекции на уровне `ec`.
+/// `None` для версии вне 1..=40.
+#[must_use]
+pub fn codeword_split(version: u8, ec: EcLevel) -> Option<(usize, usize)> {
+    let total = total_codewords(version)?;
+    let row = usize::from(version - 1);
+    let col = ec_column(ec);
+    let ec_total = usize::from(EC_CODEWORDS_PER_BLOCK[row][col]) * usize::from(EC_BLOCKS[row][col]);
+    Some((total - ec_total, ec_total))
+}
+
+/// Длина поля счётчика символов в битах (зависит от группы версий 1–9, 10–26, 27–40).
+fn count_bits(version: u8, mode: QrMode) -> usize {
+    let group = match version {
+        0..=9 => 0,
+        10..=26 => 1,
+        _ => 2,
+    };
+    let bits: [usize; 3] = match mode {
+        QrMode::Numeric => [10, 12, 14],
+        QrMode::Alphanumeric => [9, 11, 13],
+        QrMode::Byte => [8, 16, 16],
+        QrMode::Kanji => [8, 10, 12],
+    };
+    bits[group]
+}
+
+/// Сколько символов режима `mode` помещается одним сегментом в QR версии
+/// `version` на уровне `ec` (для [`QrMode::Byte`] — байт). Учитывается заголовок
+/// сегмента: 4 бита режима и поле счётчика. `0` для версии вне 1..=40.
+///
+/// Например, v1-L: 41 цифра, 25 алфавитно-цифровых символов, 17 байт, 10 кандзи.
+#[must_use]
+pub fn capacity(version: u8, ec: EcLevel, mode: QrMode) -> usize {
+    let Some((data, _)) = codeword_split(version, ec) else {
+        return 0;
+    };
+    let cc = count_bits(version, mode);
+    let bits = (data * 8).saturating_sub(4 + cc);
+    let chars = match mode {
+        QrMode::Numeric => {
+            // остаток: 4 бита — ещё одна цифра, 7 бит — две
+            let rem = bits % 10;
+            3 * (bits / 10) + usize::from(rem >= 4) + usize::from(rem >= 7)
+        }
+        QrMode::Alphanumeric => 2 * (bits / 11) + usize::from(bits % 11 >= 6),
+        QrMode::Byte => bits / 8,
+        QrMode::Kanji => bits / 13,
+    };
+    chars.min((1 << cc) - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEVELS: [EcLevel; 4] = [EcLevel::L, EcLevel::M, EcLevel::Q, EcLevel::H];
+
+    #[test]
+    fn v1_capacities_match_standard() {
+        // ISO/IEC 18004, табл. 7: цифры, буквенно-цифровые, байты, кандзи.
+        let expect = [
+            [41, 25, 17, 10],
+            [34, 20, 14, 8],
+            [27, 16, 11, 7],
+            [17, 10, 7, 4],
+        ];
+        for (ec, row) in LEVELS.into_iter().zip(expect) {
+            let got = [
+                QrMode::Numeric,
+                QrMode::Alphanumeric,
+                QrMode::Byte,
+                QrMode::Kanji,
+            ]
+            .map(|mode| capacity(1, ec, mode));
+            assert_eq!(got, row, "{ec:?}");
+        }
+        assert_eq!(codeword_split(1, EcLevel::L), Some((19, 7)));
+        assert_eq!(codeword_split(1, EcLevel::H), Some((9, 17)));
+    }
+
+    #[test]
+    fn larger_versions_match_standard() {
+        assert_eq!(capacity(10, EcLevel::M, QrMode::Byte), 213);
+        assert_eq!(capacity(10, EcLevel::M, QrMode::Numeric), 513);
+        assert_eq!(capacity(40, EcLevel::L, QrMode::Numeric), 7089);
+        assert_eq!(capacity(40, EcLevel::L, QrMode::Alphanumeric), 4296);
+        assert_eq!(capacity(40, EcLevel::L, QrMode::Byte), 2953);
+        assert_eq!(capacity(40, EcLevel::L, QrMode::Kanji), 1817);
+        assert_eq!(capacity(40, EcLevel::H, QrMode::Byte), 1273);
+        assert_eq!(total_codewords(7), Some(196));
+        assert_eq!(total_codewords(40), Some(3706));
+    }
+
+    #[test]
+    fn tables_are_consistent() {
+        for v in 1..=40u8 {
+            let total = total_codewords(v).unwrap();
+            let mut prev = usize::MAX;
+            for ec in LEVELS {
+                let (data, ec_cw) = codeword_split(v, ec).unwrap();
+                assert_eq!(data + ec_cw, total);
+                assert!(
+                    data < prev,
+                    "v{v} {ec:?}: ёмкость должна падать с ростом EC"
+                );
+                prev = data;
+            }
+        }
+        assert_eq!(capacity(0, EcLevel::L, QrMode::Byte), 0);
+        assert_eq!(capacity(41, EcLevel::L, QrMode::Byte), 0);
+        assert_eq!(codeword_split(41, EcLevel::L), None);
+    }
+}
@@ -6,11 +6,20 @@
 //! 2) [`walk_pairs_v1`] — правильный маршрут чтения модулей для извлечения бит:
 //!    идём парами колонок (x, x-1) справа налево, «змейкой» по y. Колонку x=6
 //!    (timing) пропускаем как пару — т.е. после x=8,7 сразу x=5,4.
-//! 3) [`extract_data_bits_v1`] — снимаем только data-модули (ровно 208 бит для v1).
+//! 3) [`extract_data_bits_v1`] — снимаем только data-модули (ровно 208 бит для v1);
+//!    [`fit_data_bits_v1`] страхует от промаха длины.
+//!
+//! Плюс самопроверка семплинга: [`structure_score_v1`] сверяет с сеткой то, что
+//! в любом символе v1 одинаково (finder'ы, сепараторы, тёмный модуль).
+
+use alloc::vec::Vec;
 
 /// Размер сетки для версии 1.
 pub const N1: usize = 21;
 
+/// Число data-бит QR v1: 26 кодвордов (data + EC) по 8 бит.
+pub const DATA_BITS_V1: usize = 208;
+
 /// Является ли модуль служебным (не data/ECC) для QR v1.
 ///
 /// Покрываем:
@@ -21,6 +30,7 @@ pub const N1: usize = 21;
 /// - «Тёмный модуль» v1 оказывается в левом нижнем прямоугольнике, отдельно
 ///   его помечать не нужно.
 #[inline]
+#[must_use]
 pub fn is_function_v1(x: usize, y: usize) -> bool {
     debug_assert!(x < N1 && y < N1);
 
@@ -41,12 +51,54 @@ pub fn is_function_v1(x: usize, y: usize) -> bool {
     false
 }
 
+/// Ожидаемый цвет структурного модуля v1 (`true` — тёмный): три finder'а 7×7,
+/// белые сепараторы вокруг них и тёмный модуль (8, 13). `None` — модуль от
+/// содержимого символа зависит (данные, формат) или в проверку не входит (тайминг).
+#[must_use]
+pub fn expected_structure_v1(x: usize, y: usize) -> Option<bool> {
+    for (ox, oy) in [(0, 0), (N1 - 7, 0), (0, N1 - 7)] {
+        let (dx, dy) = (x.wrapping_sub(ox), y.wrapping_sub(oy));
+        if dx < 7 && dy < 7 {
+            // рамка (кольцо 0) и ядро 3×3 тёмные, кольцо 1 светлое
+            return Some(dx.min(dy).min(6 - dx).min(6 - dy) != 1);
+        }
+    }
+    let separator = ((x <= 7 && y == 7) || (x == 7 && y <= 7))
+        || ((x >= N1 - 8 && y == 7) || (x == N1 - 8 && y <= 7))
+        || ((x <= 7 && y == N1 - 8) || (x == 7 && y >= N1 - 8));
+    if separator {
+        return Some(false);
+    }
+    ((x, y) == (8, N1 - 8)).then_some(true)
+}
+
+/// Структурная самопроверка семплинга: доля модулей из [`expected_structure_v1`]
+/// (193 штуки), совпавших с сеткой (flatten, `y * 21 + x`). `1.0` — finder'ы и
+/// сепараторы легли ровно; заметно меньше — сетка съехала, и тратить на неё
+/// Reed–Solomon бессмысленно.
+#[must_use]
+pub fn structure_score_v1(grid: &[bool]) -> f32 {
+    let (mut total, mut hits) = (0u16, 0u16);
+    for y in 0..N1 {
+        for x in 0..N1 {
+            if let Some(dark) = expected_structure_v1(x, y) {
+                total += 1;
+                if grid.get(y * N1 + x) == Some(&dark) {
+                    hits += 1;
+                }
+            }
+        }
+    }
+    f32::from(hits) / f32::from(total)
+}
+
 /// Маршрут обхода для выборки бит: пары колонок (x, x-1), справа налево,
 /// «змейкой» по y. Пару с x=6 (timing-колонка) пропускаем целиком: после
 /// пары (8,7) сразу идём на (5,4), затем (3,2), (1,0).
 ///
 /// Возвращает порядок координат модулей для **всей сетки, кроме x=6**.
 /// Колонка x=6 отсутствует намеренно (420 координат).
+#[must_use]
 pub fn walk_pairs_v1() -> Vec<(usize, usize)> {
     let mut out = Vec::with_capacity(N1 * N1);
 
@@ -89,22 +141,40 @@ pub fn walk_pairs_v1() -> Vec<(usize, usize)> {
 }
 
 /// Снять ровно 208 data-бит (без служебных) согласно маршруту [`walk_pairs_v1`].
+#[must_use]
 pub fn extract_data_bits_v1(grid: &[bool]) -> Vec<bool> {
     debug_assert_eq!(grid.len(), N1 * N1);
 
-    let mut bits = Vec::with_capacity(208);
+    let mut bits = Vec::with_capacity(DATA_BITS_V1);
     for (x, y) in walk_pairs_v1() {
         if is_function_v1(x, y) {
             continue;
         }
         bits.push(grid[y * N1 + x]);
-        if bits.len() == 208 {
+        if bits.len() == DATA_BITS_V1 {
             break;
         }
     }
     bits
 }
 
+/// Привести снятые биты ровно к [`DATA_BITS_V1`]: недостающие добиваются `false`,
+/// лишние отбрасываются. Маршрут v1 всегда даёт 208 бит, но промах на пару бит
+/// не должен обрывать декодирование — испорченный хвостовой кодворд исправит RS.
+/// Расхождение пишется в `diag!`.
+#[must_use]
+pub fn fit_data_bits_v1(mut bits: Vec<bool>) -> Vec<bool> {
+    if bits.len() != DATA_BITS_V1 {
+        diag!(
+            "[qr] unexpected data bits length: {} -> clamped to {}",
+            bits.len(),
+            DATA_BITS_V1
+        );
+        bits.resize(DATA_BITS_V1, false);
+    }
+    bits
+}
+
 /// Предикаты восьми масок из ISO/IEC 18004 (0..7).
 #[inline]
 pub(crate) fn mask_predicate(mask_id: u8, x: usize, y: usize) -> bool {
@@ -144,6 +214,39 @@ mod tests {
         assert_eq!(data, 208, "data modules count");
     }
 
+    #[test]
+    fn structure_score_tracks_finder_damage() {
+        let grid = crate::qr::encode::build_matrix_v1(b"HELLO", crate::qr::format::EcLevel::M, 3);
+        let structural = (0..N1 * N1)
+            .filter(|&i| expected_structure_v1(i % N1, i / N1).is_some())
+            .count();
+        assert_eq!(structural, 3 * 49 + 3 * 15 + 1);
+        assert!((structure_score_v1(&grid) - 1.0).abs() < 1e-6);
+        // Структурные модули — только служебные.
+        assert!((0..N1 * N1)
+            .all(|i| expected_structure_v1(i % N1, i / N1).is_none()
+                || is_function_v1(i % N1, i / N1)));
+
+        // Инвертированный левый верхний finder с сепаратором — 64 из 193 мимо.
+        let mut broken = grid.clone();
+        for y in 0..8 {
+            for x in 0..8 {
+                broken[y * N1 + x] = !broken[y * N1 + x];
+            }
+        }
+        assert!((structure_score_v1(&broken) - 129.0 / 193.0).abs() < 1e-6);
+
+        // Сетка, сдвинутая на модуль, структуру не держит.
+        let shifted: Vec<bool> = (0..N1 * N1)
+            .map(|i| i % N1 + 1 < N1 && grid[i + 1])
+            .collect();
+        assert!(
+            structure_score_v1(&shifted) < 0.8,
+            "{}",
+            structure_score_v1(&shifted)
+        );
+    }
+
     #[test]
     fn walk_pairs_basic_properties() {
         let path = walk_pairs_v1();
@@ -163,4 +266,32 @@ mod tests {
         // 3) правый нижний модуль идёт первым
         assert_eq!(path[0], (N1 - 1, N1 - 1));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn short_extraction_is_padded_and_corrected_by_rs() {
+        // v1-L: 19 data + 7 EC кодвордов; последний бит EC «потерян» обходом
+        let data: Vec<u8> = (0..19u8).map(|i| i.wrapping_mul(37) ^ 0xA5).collect();
+        let mut cw = data.clone();
+        cw.extend(crate::qr::rs::rs_ec_bytes(&data, 7));
+        let bits: Vec<bool> = cw
+            .iter()
+            .flat_map(|&b| (0..8).rev().map(move |i| (b >> i) & 1 != 0))
+            .collect();
+
+        let fitted = fit_data_bits_v1(bits[..DATA_BITS_V1 - 1].to_vec());
+        assert_eq!(fitted.len(), DATA_BITS_V1);
+        assert_eq!(&fitted[..DATA_BITS_V1 - 1], &bits[..DATA_BITS_V1 - 1]);
+        assert!(!fitted[DATA_BITS_V1 - 1]);
+
+        let mut read = crate::qr::bytes::bits_to_bytes_v1(&fitted);
+        let fixed = crate::qr::rs::rs_correct_codeword_block(&mut read, 19, 7)
+            .expect("RS corrects the tail");
+        assert!(fixed <= 1);
+        assert_eq!(read, cw);
+
+        // лишние биты отбрасываются
+        let mut long = bits.clone();
+        long.push(true);
+        assert_eq!(fit_data_bits_v1(long), bits);
+    }
+}
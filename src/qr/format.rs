@@ -4,7 +4,9 @@
 /// - перечисление уровня коррекции ошибок [`EcLevel`];
 /// - функция декодирования одного 15-битного слова [`decode_format_word`];
 /// - координаты двух дорожек чтения формата для версии 1
-///   [`FORMAT_READ_PATHS_V1`] (по 15 модулей каждая).
+///   [`FORMAT_READ_PATHS_V1`] (по 15 модулей каждая);
+/// - информация о версии (18 бит, BCH(18,6)) у версий от 7: [`decode_version_info`]
+///   и положение двух её блоков [`version_info_positions`].
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum EcLevel {
@@ -18,6 +20,7 @@ impl EcLevel {
     /// Два бита уровня EC по стандарту:
     /// L=01, M=00, Q=11, H=10
     #[inline]
+    #[must_use]
     pub fn to_bits2(self) -> u8 {
         match self {
             EcLevel::L => 0b01,
@@ -29,6 +32,7 @@ impl EcLevel {
 
     /// Обратное преобразование двух бит в уровень EC.
     #[allow(dead_code)]
+    #[must_use]
     pub fn from_bits2(b2: u8) -> Option<Self> {
         match b2 & 0b11 {
             0b01 => Some(EcLevel::L),
@@ -43,7 +47,7 @@ impl EcLevel {
 /// Генератор BCH(15,5): x^10 + x^8 + x^5 + x^4 + x^2 + x + 1
 const BCH15_5_GEN: u16 = 0b1_0100_1101_11; // 0x537
 /// Маска формата из стандарта
-const FORMAT_MASK: u16 = 0b1010_1000_0001_0010; // 0x5412
+const FORMAT_MASK: u16 = 0b0101_0100_0001_0010; // 0x5412
 
 /// Возвращает остаток при делении (data<<10) на генератор BCH по mod2.
 fn bch_remainder_15_5(mut v: u16) -> u16 {
@@ -79,6 +83,7 @@ fn hamming15(a: u16, b: u16) -> u32 {
 ///
 /// Возвращает Some(уровень, id маски, расстояние), если найден кандидат
 /// с расстоянием ≤ 3, иначе None.
+#[must_use]
 pub fn decode_format_word(word: u16) -> Option<(EcLevel, u8, u32)> {
     let mut best: Option<(EcLevel, u8, u32)> = None;
 
@@ -103,17 +108,85 @@ pub fn decode_format_word(word: u16) -> Option<(EcLevel, u8, u32)> {
     }
 }
 
+/// Множитель уверенности по расстоянию Хэмминга прочитанного формат-слова до
+/// ближайшего валидного (см. [`decode_format_word`]): 0 — полное доверие `1.0`,
+/// дальше всё меньше — до `0.85` на пределе исправления BCH (3 бита), где
+/// уровень EC и маска могли прочитаться чужими.
+#[must_use]
+pub fn format_distance_credit(distance: u32) -> f32 {
+    match distance {
+        0 => 1.0,
+        1 => 0.97,
+        2 => 0.92,
+        _ => 0.85,
+    }
+}
+
+/// Генератор BCH(18,6): x^12 + x^11 + x^10 + x^9 + x^8 + x^5 + x^2 + 1
+const BCH18_6_GEN: u32 = 0b1_1111_0010_0101; // 0x1F25
+
+/// Первая версия с блоками информации о версии.
+pub const VERSION_INFO_MIN: u8 = 7;
+
+/// 18-битное слово информации о версии: 6 бит версии, затем 12 бит BCH
+/// (маски нет). Для версий вне `7..=40` — `None`.
+#[must_use]
+pub fn encode_version_info(version: u8) -> Option<u32> {
+    if !(VERSION_INFO_MIN..=40).contains(&version) {
+        return None;
+    }
+    let payload = u32::from(version) << 12;
+    let mut rem = payload;
+    for shift in (12..=17).rev() {
+        if (rem >> shift) & 1 == 1 {
+            rem ^= BCH18_6_GEN << (shift - 12);
+        }
+    }
+    Some(payload | (rem & 0x0FFF))
+}
+
+/// Декодирование 18-битного слова версии (перебор по 34 валидным словам, как
+/// [`decode_format_word`]). Код исправляет до 3 ошибок: `Some(версия)`, если
+/// ближайшее слово не дальше 3 бит, иначе `None`.
+#[must_use]
+pub fn decode_version_info(word: u32) -> Option<u8> {
+    decode_version_info_with_distance(word).map(|(v, _)| v)
+}
+
+/// То же, что [`decode_version_info`], но вместе с расстоянием Хэмминга.
+#[must_use]
+pub fn decode_version_info_with_distance(word: u32) -> Option<(u8, u32)> {
+    let word = word & 0x3_FFFF;
+    let (version, d) = (VERSION_INFO_MIN..=40)
+        .filter_map(|v| encode_version_info(v).map(|valid| (v, (word ^ valid).count_ones())))
+        .min_by_key(|&(_, d)| d)?;
+    (d <= 3).then_some((version, d))
+}
+
+/// Модули двух блоков информации о версии символа `n`×`n` (`n = 17 + 4·version`):
+/// `[правый верхний, левый нижний]`, в каждом — `(x, y)` бита `i` слова (0 — младший).
+/// Правый верхний блок 6×3 — столбцы `n−11..n−8`, строки `0..6`; левый нижний —
+/// его транспонированная копия.
+#[must_use]
+pub fn version_info_positions(n: usize) -> [[(usize, usize); 18]; 2] {
+    let mut tr = [(0, 0); 18];
+    for (i, p) in tr.iter_mut().enumerate() {
+        *p = (n - 11 + i % 3, i / 3);
+    }
+    [tr, tr.map(|(x, y)| (y, x))]
+}
+
 /// Координаты чтения 15-битного формата (две копии) для QR v1 (21×21).
 ///
-/// Пары — это (x, y), где x — столбец, y — строка.
-/// Каждая дорожка содержит ровно 15 уникальных координат в пределах 0..=20.
-///
-/// Примечание: порядок соответствует распространённой раскладке:
-/// 1) около верхнего-левого угла (строка y=8 и столбец x=8);
-/// 2) «зеркальная» копия около правого-верхнего и левого-нижнего углов.
+/// Пары — это (x, y), где x — столбец, y — строка. Обе дорожки перечисляют
+/// биты в одном логическом порядке: от старшего (бит 14) к младшему (бит 0),
+/// как в ISO/IEC 18004 (рис. 25).
+/// 1) около верхнего-левого угла: строка y=8 слева направо (в обход тайминга x=6),
+///    затем столбец x=8 снизу вверх (в обход тайминга y=6);
+/// 2) копия: столбец x=8 у левого-нижнего finder'а снизу вверх (над ним — тёмный
+///    модуль (8,13), в формат не входит), затем строка y=8 у правого-верхнего слева направо.
 pub const FORMAT_READ_PATHS_V1: [[(usize, usize); 15]; 2] = [
-    // Дорожка 1 (вокруг топ-левого угла):
-    // y=8, x=0..5, затем x=7,8; далее столбец x=8, y=7..1
+    // Дорожка 1: y=8, x=0..5, 7, 8; далее x=8, y=7, 5..0
     [
         (0, 8),
         (1, 8),
@@ -124,37 +197,37 @@ pub const FORMAT_READ_PATHS_V1: [[(usize, usize); 15]; 2] = [
         (7, 8),
         (8, 8),
         (8, 7),
-        (8, 6),
         (8, 5),
         (8, 4),
         (8, 3),
         (8, 2),
         (8, 1),
+        (8, 0),
     ],
-    // Дорожка 2 (копия вокруг правого-верхнего и левого-нижнего углов):
-    // x=20, y=0..7; далее y=8, x=19..13
+    // Дорожка 2: x=8, y=20..14; далее y=8, x=13..20
     [
-        (20, 0),
-        (20, 1),
-        (20, 2),
-        (20, 3),
-        (20, 4),
-        (20, 5),
-        (20, 6),
-        (20, 7),
-        (19, 8),
-        (18, 8),
-        (17, 8),
-        (16, 8),
-        (15, 8),
-        (14, 8),
+        (8, 20),
+        (8, 19),
+        (8, 18),
+        (8, 17),
+        (8, 16),
+        (8, 15),
+        (8, 14),
         (13, 8),
+        (14, 8),
+        (15, 8),
+        (16, 8),
+        (17, 8),
+        (18, 8),
+        (19, 8),
+        (20, 8),
     ],
 ];
 
 /// Вспомогательная функция (оставлена для тестов), возвращает уже
 /// замаскированное слово формата для заданных параметров.
 #[allow(dead_code)]
+#[must_use]
 pub fn encode_format_bits_for_tests(ec: EcLevel, mask_id: u8) -> u16 {
     encode_format_word_masked(ec, mask_id)
 }
@@ -163,6 +236,17 @@ pub fn encode_format_bits_for_tests(ec: EcLevel, mask_id: u8) -> u16 {
 mod tests {
     use super::*;
 
+    #[test]
+    fn format_distance_credit_falls_with_distance() {
+        assert!((format_distance_credit(0) - 1.0).abs() < f32::EPSILON);
+        let credits: Vec<f32> = (0..=3).map(format_distance_credit).collect();
+        assert!(credits.windows(2).all(|w| w[1] < w[0]), "{credits:?}");
+        assert_eq!(
+            format_distance_credit(7).to_bits(),
+            format_distance_credit(3).to_bits()
+        );
+    }
+
     #[test]
     fn bch_roundtrip_examples() {
         // Несколько sanity-проверок: кодируем и проверяем,
@@ -177,6 +261,48 @@ mod tests {
         }
     }
 
+    #[test]
+    fn version_info_matches_standard_table_and_corrects_three_errors() {
+        // ISO/IEC 18004, табл. D.1.
+        assert_eq!(encode_version_info(7), Some(0x07C94));
+        assert_eq!(encode_version_info(21), Some(0x15683));
+        assert_eq!(encode_version_info(40), Some(0x28C69));
+        assert_eq!(encode_version_info(6), None);
+        for v in VERSION_INFO_MIN..=40 {
+            let w = encode_version_info(v).unwrap();
+            assert_eq!(decode_version_info(w), Some(v));
+            assert_eq!(
+                decode_version_info_with_distance(w ^ 0b10_0000_0100_0000_0001),
+                Some((v, 3))
+            );
+        }
+        // 4 ошибки — за пределами кода: либо отказ, либо чужая версия, но не «та же».
+        let w = encode_version_info(7).unwrap() ^ 0b1111;
+        assert_ne!(decode_version_info(w), Some(7));
+
+        let [tr, bl] = version_info_positions(45);
+        assert_eq!((tr[0], tr[17]), ((34, 0), (36, 5)));
+        assert_eq!((bl[0], bl[17]), ((0, 34), (5, 36)));
+    }
+
+    #[test]
+    fn format_paths_avoid_timing_and_finders() {
+        use crate::qr::data::is_function_v1;
+        for path in &FORMAT_READ_PATHS_V1 {
+            for &(x, y) in path {
+                // Формат живёт в зарезервированной зоне, но не на тайминге и не на finder'ах.
+                assert!(is_function_v1(x, y));
+                assert!(x != 6 && y != 6, "({x},{y}) is a timing module");
+                let in_finder =
+                    |fx: usize, fy: usize| (fx..fx + 7).contains(&x) && (fy..fy + 7).contains(&y);
+                assert!(
+                    !in_finder(0, 0) && !in_finder(14, 0) && !in_finder(0, 14),
+                    "({x},{y}) inside finder"
+                );
+            }
+        }
+    }
+
     #[test]
     fn format_paths_have_15_points_each_and_in_bounds() {
         for path in &FORMAT_READ_PATHS_V1 {
@@ -1,7 +1,7 @@
-
 //! Модуль QR (v1): формат-слово, извлечение data-битов и вспомогательные штуки.
 
 pub mod bytes;
+pub mod capacity;
 pub mod data;
 pub mod encode;
 pub mod finder;
@@ -9,19 +9,121 @@ pub mod format;
 pub mod rs;
 pub mod sample;
 
+pub use self::capacity::{capacity, QrMode};
+pub use self::encode::{encode_qr_v1, encode_qr_v1_data_codewords};
+
 use self::format::{decode_format_word, EcLevel, FORMAT_READ_PATHS_V1};
+use crate::core::types::{LumaImage, QuietZone};
+use alloc::vec::Vec;
+
+/// Версии, которые декодер умеет читать сейчас (от и до включительно).
+pub const SUPPORTED_VERSIONS: (u8, u8) = (1, 1);
 
 /// Опции пайплайна QR.
 #[derive(Clone, Copy, Debug)]
 pub struct QrOptions {
-    /// Количество линий для сканирования при поиске finder patterns.
+    /// Минимум линий на каждое направление (строки, столбцы, две диагонали)
+    /// при поиске finder patterns (см. `min_module_px`).
     pub scan_lines: usize,
+    /// Самый мелкий ожидаемый модуль в пикселях: на крупных кадрах, где
+    /// `scan_lines` линий идут реже, их добавляется столько, чтобы шаг был не больше
+    /// `2 × min_module_px` и finder (7 модулей) пересекали хотя бы три линии.
+    /// Меньше значение — выше recall на крупных снимках с мелким кодом, но поиск
+    /// дольше (линий пропорционально размеру кадра); больше — быстрее, но мелкие
+    /// коды можно проскочить. `0.0` — ровно `scan_lines` линий при любом размере.
+    /// По умолчанию `2.0`: на кадрах до ~250 px по стороне остаются `scan_lines`.
+    pub min_module_px: f32,
+    /// Порог кластеризации кандидатов finder'а как доля от `min(width, height)`.
+    /// Кандидаты ближе этого расстояния сливаются в один центр.
+    /// Для крошечных изображений имеет смысл увеличить, для плотных сцен
+    /// с несколькими кодами — уменьшить.
+    pub finder_cluster_fraction: f32,
+    /// Если найдено ровно 2 finder'а (третий закрыт или низкоконтрастен), достроить
+    /// третий по геометрии v1 (прямоугольный треугольник) и принять догадку, только
+    /// если тайминги семплированной сетки сходятся. По умолчанию выключено.
+    pub recover_two_finders: bool,
+    /// Какие версии искать: `(от, до)` включительно. Символ, чья версия выходит
+    /// за диапазон, отбрасывается до семплинга — если её подтвердили тайминги или
+    /// номер версии символа; одной оценке по разносу finder'ов (см.
+    /// [`sample::estimate_version`]) не верим. Диапазон без поддерживаемых версий
+    /// ([`SUPPORTED_VERSIONS`]) выключает поиск QR целиком. Сужение диапазона под
+    /// известный формат ускоряет детекцию и отсекает ложные срабатывания.
+    /// По умолчанию `(1, 1)`.
+    pub version_range: (u8, u8),
+    /// Тихая зона вокруг символа (см. [`QuietZone`],
+    /// [`sample::qr_v1_has_quiet_zone`]). По умолчанию `Relaxed`: символ без неё
+    /// читается (с пометкой), как и до появления проверки; `Strict` — отбрасывать.
+    pub quiet_zone: QuietZone,
+    /// Сетка суперсэмплинга модуля при чтении: `n×n` точек в пределах ±0.18 модуля
+    /// от центра, яркость усредняется. `1` — только центр (быстро, для чистых снимков
+    /// высокого разрешения), `5` — плотнее (устойчивее на размытых). Чётные значения
+    /// округляются вверх до нечётного, всё вне `1..=5` прижимается к границам.
+    /// По умолчанию `3`.
+    pub supersample: u8,
+    /// Порог яркости «тёмного» модуля при чтении сетки: модуль с усреднённой
+    /// яркостью ниже — чёрный. `None` — по самим модулям (Otsu-подобный порог по
+    /// их яркостям, см. [`crate::binarize::otsu_like_threshold`]): так читаются
+    /// тусклые и пересвеченные кадры, где символ не центрирован на 128.
+    /// По умолчанию `None`.
+    pub dark_threshold: Option<u8>,
+    /// Сколько раз перечитать сетку с возмущённой калибровкой (см.
+    /// [`sample::resample_qr_v1_grid`]), если формат не прочитался, прежде чем
+    /// бросить символ. Каждый повтор — ещё один семплинг, но только на неудачных
+    /// символах. Больше [`sample::MAX_FORMAT_RETRIES`] не бывает; `0` — без повторов.
+    /// По умолчанию `4`.
+    pub format_retries: u8,
+    /// Если сетка не прочиталась (формат или Reed–Solomon), перечитать её сдвинутой
+    /// на ±1 модуль по u и по v (см. [`sample::sample_qr_v1_grid_shifted`]): так
+    /// спасаются символы, где центры finder'ов ошиблись на целый модуль. До четырёх
+    /// лишних семплингов — только на неудачных символах. По умолчанию включено.
+    pub grid_shift_recovery: bool,
+    /// Нижняя граница структурной самопроверки сетки (см.
+    /// [`data::structure_score_v1`]: finder'ы, сепараторы и тёмный модуль). Ниже —
+    /// сетка перечитывается как при нечитаемом формате ([`QrOptions::format_retries`]),
+    /// а если не помогло, символ бросается до Reed–Solomon. `0.0` — не проверять.
+    /// По умолчанию `0.8`.
+    pub min_struct_score: f32,
+    /// Перебор масок: если формат прочитан на расстоянии Хэмминга не меньше этого
+    /// (BCH(15,5) исправляет до 3), маска из формата могла прочитаться неверно —
+    /// сетка снимается каждой из 8 масок, и берётся та, с которой Reed–Solomon
+    /// сходится с наименьшим числом исправлений (при равенстве — маска из формата).
+    /// До восьми лишних коррекций RS на каждый такой символ. `None` — доверять
+    /// маске из формата. По умолчанию `None`.
+    pub mask_search_distance: Option<u8>,
+    /// Увеличение мелких кодов: если символ не прочитался, а модуль по разносу
+    /// finder'ов меньше этого числа пикселей, область символа увеличивается
+    /// билинейно в целое число раз (до модуля не меньше порога, не больше
+    /// [`MAX_UPSCALE_FACTOR`], см. [`sample::upscale_qr_v1_roi`]), и поиск
+    /// finder'ов с семплингом повторяются на ней. Если трёх finder'ов не нашлось
+    /// (у мелкого кода они сливаются в один кластер), они ищутся на окне вокруг
+    /// сильнейшего кандидата ([`finder::strongest_candidate`]), а символ, если нужно,
+    /// так же увеличивается. Стоит памяти и времени только на мелких нечитаемых символах.
+    /// `None` — не увеличивать. По умолчанию `None`.
+    pub upscale_small_codes: Option<f32>,
 }
 
+/// Наибольший множитель [`QrOptions::upscale_small_codes`]: крупнее фрагмент
+/// растёт квадратично, а деталей билинейное увеличение не добавляет.
+pub const MAX_UPSCALE_FACTOR: usize = 4;
+
 impl Default for QrOptions {
     #[inline]
     fn default() -> Self {
-        Self { scan_lines: 64 }
+        Self {
+            scan_lines: 64,
+            min_module_px: 2.0,
+            finder_cluster_fraction: 0.05,
+            recover_two_finders: false,
+            version_range: SUPPORTED_VERSIONS,
+            quiet_zone: QuietZone::Relaxed,
+            supersample: 3,
+            dark_threshold: None,
+            format_retries: 4,
+            grid_shift_recovery: true,
+            min_struct_score: 0.8,
+            mask_search_distance: None,
+            upscale_small_codes: None,
+        }
     }
 }
 
@@ -60,10 +162,13 @@ fn read_15_from_path(matrix: &[Vec<bool>], path: &[(usize, usize); 15]) -> u16 {
 
 /// Основная функция: читает две 15-битные дорожки формата и пытается декодировать.
 ///
+/// Хватает одной читаемой дорожки: если вторая испорчена целиком (блик или
+/// перекрытый угол finder'а), слово берётся из уцелевшей. Какая дорожка
+/// использована, пишется в `diag!`.
+///
 /// Возвращает (EcLevel, mask_id, лучший_hamming_distance, индекс_дорожки_0_или_1).
-pub fn decode_v1_format_from_matrix(
-    matrix: &[Vec<bool>],
-) -> Option<(EcLevel, u8, u32, usize)> {
+#[must_use]
+pub fn decode_v1_format_from_matrix(matrix: &[Vec<bool>]) -> Option<(EcLevel, u8, u32, usize)> {
     // Две стандартные дорожки чтения формат-слова (каждая — 15 координат).
     let [path_a, path_b] = FORMAT_READ_PATHS_V1;
 
@@ -71,36 +176,85 @@ pub fn decode_v1_format_from_matrix(
     let raw_a = read_15_from_path(matrix, &path_a);
     let raw_b = read_15_from_path(matrix, &path_b);
 
-    // Каждое слово декодируем через BCH(15,5) и получаем кандидатов.
-    let mut candidates = Vec::with_capacity(2);
+    // Каждое слово декодируем через BCH(15,5); нечитаемая дорожка просто не даёт кандидата.
+    let candidates = [decode_format_word(raw_a), decode_format_word(raw_b)];
+    let distances = candidates.map(|c| c.map(|(_, _, dist)| dist));
 
-    if let Some((ec, mask_id, dist)) = decode_format_word(raw_a) {
-        candidates.push(FormatCandidate {
-            ec,
-            mask_id,
-            distance: dist,
-            source_idx: 0,
-        });
-    }
-    if let Some((ec, mask_id, dist)) = decode_format_word(raw_b) {
-        candidates.push(FormatCandidate {
-            ec,
-            mask_id,
-            distance: dist,
-            source_idx: 1,
-        });
+    // Выбрать наилучший (минимальное расстояние Хэмминга; при равенстве — дорожка A).
+    let best = candidates
+        .into_iter()
+        .enumerate()
+        .filter_map(|(source_idx, c)| {
+            c.map(|(ec, mask_id, distance)| FormatCandidate {
+                ec,
+                mask_id,
+                distance,
+                source_idx,
+            })
+        })
+        .min_by_key(|c| c.distance);
+
+    match best {
+        Some(c) => diag!(
+            "[qr] format track {} used (A: {:?}, B: {:?})",
+            if c.source_idx == 0 { 'A' } else { 'B' },
+            distances[0],
+            distances[1]
+        ),
+        None => diag!("[qr] format unreadable on both tracks (A: {raw_a:015b}, B: {raw_b:015b})"),
     }
+    best.map(|c| (c.ec, c.mask_id, c.distance, c.source_idx))
+}
 
-    // Если кандидатов нет — вернуть None.
-    if candidates.is_empty() {
-        return None;
+/// Матрица модулей (`matrix[y][x]`, true — чёрный) → изображение: `unit` px на
+/// модуль (`0` считается за 1) и стандартная тихая зона в 4 модуля, как у
+/// [`encode::encode_qr_v1`]. Для отладки: сетку семплера
+/// ([`sample::sample_qr_v1_grid`], разложенную по строкам) можно сохранить и
+/// сравнить с исходником попиксельно или снова прогнать через декодер.
+/// Короткие строки дополняются белым до квадрата по самой длинной стороне.
+pub fn matrix_to_luma(matrix: &[Vec<bool>], unit: usize) -> LumaImage {
+    let n = matrix
+        .iter()
+        .map(Vec::len)
+        .max()
+        .unwrap_or(0)
+        .max(matrix.len());
+    let mut grid = alloc::vec![false; n * n];
+    for (y, row) in matrix.iter().enumerate() {
+        grid[y * n..y * n + row.len()].copy_from_slice(row);
     }
+    encode::render_matrix(&grid, n, 4, unit.max(1))
+}
 
-    // Выбрать наилучший (минимальное расстояние Хэмминга).
-    candidates
-        .into_iter()
-        .min_by_key(|c| c.distance)
-        .map(|c| (c.ec, c.mask_id, c.distance, c.source_idx))
+/// Круговая проверка: `text` → [`encode::encode_qr_v1`] (unit=4, quiet=4) →
+/// [`crate::api::Pipeline`] (только QR) → текст. Всё в памяти, без файлов.
+///
+/// `true`, если найден ровно один QR с тем же текстом, уровнем EC и маской.
+/// Текст должен помещаться в ёмкость уровня (см. [`encode::encode_qr_v1`]).
+#[cfg(test)]
+pub(crate) fn roundtrip_check(text: &str, ec: EcLevel, mask: u8) -> bool {
+    let img = encode::encode_qr_v1(text.as_bytes(), ec, mask, 4, 4).unwrap();
+    let pipe = crate::api::PipelineBuilder::new()
+        .enable_ean13_upca(false)
+        .enable_code128(false)
+        .enable_code93(false)
+        .build();
+    let found = pipe.decode_all(&img);
+    let ec_name = match ec {
+        EcLevel::L => "L",
+        EcLevel::M => "M",
+        EcLevel::Q => "Q",
+        EcLevel::H => "H",
+    };
+    match found.as_slice() {
+        [s] => {
+            s.symbology == crate::core::types::Symbology::QR
+                && s.text == text
+                && s.extras.get_str("qr.ec") == Some(ec_name)
+                && s.extras.get_parsed::<u8>("qr.mask") == Some(mask)
+        }
+        _ => false,
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -127,6 +281,20 @@ mod tests {
         assert!(!bits2[0], "старший бит не должен быть установлен");
     }
 
+    #[test]
+    fn roundtrip_all_masks_and_ec_levels() {
+        // Текст укладывается в ёмкость H (7 байт), так что годится для всех уровней.
+        for ec in [EcLevel::L, EcLevel::M, EcLevel::Q, EcLevel::H] {
+            for mask in 0..8u8 {
+                assert!(
+                    roundtrip_check("HELLO", ec, mask),
+                    "roundtrip failed: ec={ec:?} mask={mask}"
+                );
+            }
+        }
+        assert!(roundtrip_check("HELLO, WORLD!!!!", EcLevel::L, 5));
+    }
+
     #[test]
     fn pack_bits_msb_basic() {
         let bits = [true, false, true, true]; // 1011b = 11
@@ -143,4 +311,128 @@ mod tests {
             assert!(x < 21 && y < 21, "({x},{y}) out of bounds");
         }
     }
-}
\ No newline at end of file
+
+    /// Матрица 21×21 с формат-словом `word`, записанным только в дорожку `track`.
+    fn matrix_with_format_on(track: usize, word: u16) -> Vec<Vec<bool>> {
+        let mut m = alloc::vec![alloc::vec![false; 21]; 21];
+        write_track(&mut m, track, word);
+        m
+    }
+
+    fn write_track(m: &mut [Vec<bool>], track: usize, word: u16) {
+        for (bit, &(x, y)) in u16_to_15bits_msb_first(word)
+            .iter()
+            .zip(&FORMAT_READ_PATHS_V1[track])
+        {
+            m[y][x] = *bit;
+        }
+    }
+
+    /// Слова, которые BCH-декодер отвергает (дальше 3 бит от любого валидного).
+    fn unreadable_words() -> Vec<u16> {
+        (0..1u16 << 15)
+            .filter(|&w| decode_format_word(w).is_none())
+            .collect()
+    }
+
+    #[test]
+    fn single_readable_track_is_enough() {
+        let garbage = unreadable_words();
+        assert!(garbage.len() > 100);
+        let word = format::encode_format_bits_for_tests(EcLevel::Q, 6);
+        for good in 0..2 {
+            for &bad in garbage.iter().step_by(97) {
+                let mut m = matrix_with_format_on(good, word ^ 0b100_0000_0000_0001);
+                write_track(&mut m, 1 - good, bad);
+                assert_eq!(
+                    decode_v1_format_from_matrix(&m),
+                    Some((EcLevel::Q, 6, 2, good)),
+                    "bad={bad:015b}"
+                );
+            }
+        }
+
+        // Обе дорожки нечитаемы — формата нет.
+        let mut m = matrix_with_format_on(0, garbage[0]);
+        write_track(&mut m, 1, garbage[garbage.len() / 2]);
+        assert_eq!(decode_v1_format_from_matrix(&m), None);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn format_track_choice_is_logged() {
+        static LOG: std::sync::Mutex<Vec<std::string::String>> = std::sync::Mutex::new(Vec::new());
+        fn hook(msg: &str) {
+            if msg.starts_with("[qr] format") {
+                LOG.lock().unwrap().push(msg.into());
+            }
+        }
+
+        let word = format::encode_format_bits_for_tests(EcLevel::L, 3);
+        let garbage = unreadable_words()[0];
+        let mut only_b = matrix_with_format_on(1, word);
+        write_track(&mut only_b, 0, garbage);
+        crate::log::set_log_hook(Some(hook));
+        let got = decode_v1_format_from_matrix(&only_b);
+        crate::log::set_log_hook(None);
+
+        assert_eq!(got, Some((EcLevel::L, 3, 0, 1)));
+        let log = LOG.lock().unwrap();
+        assert!(
+            log.iter()
+                .any(|l| l == "[qr] format track B used (A: None, B: Some(0))"),
+            "{log:?}"
+        );
+    }
+
+    #[test]
+    fn each_format_track_decodes_independently() {
+        for ec in [EcLevel::L, EcLevel::M, EcLevel::Q, EcLevel::H] {
+            for mask in 0..8u8 {
+                let word = format::encode_format_bits_for_tests(ec, mask);
+                for (track, path) in FORMAT_READ_PATHS_V1.iter().enumerate() {
+                    let m = matrix_with_format_on(track, word);
+                    assert_eq!(read_15_from_path(&m, path), word);
+                    let (got_ec, got_mask, dist, src) =
+                        decode_v1_format_from_matrix(&m).expect("format must decode");
+                    assert_eq!((got_ec, got_mask, dist, src), (ec, mask, 0, track));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sampled_matrix_renders_back_to_source() {
+        let src = encode::encode_qr_v1(b"MATRIX", EcLevel::Q, 4, 4, 3).unwrap();
+        let src = src.as_gray();
+        let opts = QrOptions::default();
+        let finders = finder::find_finder_patterns(&src, &opts);
+        let grid = sample::sample_qr_v1_grid(&src, &opts, &finders).expect("sample");
+        let matrix: Vec<Vec<bool>> = grid.chunks(data::N1).map(<[bool]>::to_vec).collect();
+
+        let img = matrix_to_luma(&matrix, 3);
+        assert_eq!((img.width, img.height), (src.width, src.height));
+        assert_eq!(img.data, src.data);
+
+        // Неполная матрица: квадрат по длинной стороне, недостающее — белое.
+        let ragged = matrix_to_luma(&[alloc::vec![true; 2], alloc::vec![true]], 0);
+        assert_eq!((ragged.width, ragged.height), (10, 10));
+        assert_eq!(&ragged.data[4 * 10 + 4..4 * 10 + 6], &[0, 0]);
+        assert_eq!(&ragged.data[5 * 10 + 4..5 * 10 + 6], &[0, 255]);
+    }
+
+    #[test]
+    fn format_track_bit_order_matches_iso_layout() {
+        // ISO/IEC 18004, прил. C: EC=M, маска 5 → 100000011001110.
+        let word = format::encode_format_bits_for_tests(EcLevel::M, 5);
+        assert_eq!(word, 0b100_0000_1100_1110);
+        let a = matrix_with_format_on(0, word);
+        let b = matrix_with_format_on(1, word);
+        // Старший бит: (x=0, y=8) и (x=8, y=20); младший: (8, 0) и (20, 8).
+        assert!(a[8][0] && b[20][8]);
+        assert!(!a[0][8] && !b[8][20]);
+        // Бит 7 (восьмой по порядку) — (8,8) и (13,8).
+        assert_eq!(a[8][8], (word >> 7) & 1 == 1);
+        assert_eq!(b[8][13], (word >> 7) & 1 == 1);
+    }
+}
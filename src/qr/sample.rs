@@ -4,13 +4,25 @@
 // - По ним получаем 4 внешних угла символа (координаты модулей 0..20).
 // - Гомография из [0..1]^2 всей матрицы в эти 4 угла (никакой экстраполяции).
 // - Лёгкая автокалибровка: анизотропные масштабы su/sv и сдвиги du/dv (в норм. коорд).
-// - Суперсэмплинг 3×3; скоринг по центральному участку таймингов (8..=12).
+// - Суперсэмплинг n×n (QrOptions::supersample, по умолчанию 3×3); скоринг по центральному участку таймингов (8..=12).
+// - Порог «тёмный модуль» — по яркостям самих модулей (QrOptions::dark_threshold),
+//   а не середина шкалы: символ на тусклом или пересвеченном кадре не центрирован на 128.
 //
 // Логи: углы, длины |ux|/|uy|, выбранные su/sv/du/dv, тайминги, 8×8 превью.
 
-use super::{finder::{self, PointF}, QrOptions};
-use crate::prelude::GrayImage;
 use super::data::N1;
+use super::{
+    finder::{self, PointF},
+    format, QrOptions,
+};
+use crate::binarize::otsu_like_threshold;
+use crate::core::cast::{f32_to_usize, usize_to_f32};
+#[cfg(not(feature = "std"))]
+use crate::core::math::FloatExt;
+use crate::prelude::{GrayImage, LumaImage, QuadF};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
 
 #[inline]
 fn sample_bilinear(img: &GrayImage<'_>, x: f32, y: f32) -> u8 {
@@ -41,13 +53,15 @@ fn sample_bilinear(img: &GrayImage<'_>, x: f32, y: f32) -> u8 {
     v.round() as u8
 }
 
-#[inline] fn is_dark(v: u8) -> bool { v < 128 }
+/// Порог модуля: [`QrOptions::dark_threshold`] или, если он `None`, Otsu-подобный
+/// порог по усреднённым яркостям модулей сетки.
+fn module_threshold(opts: &QrOptions, luma: &[u8]) -> u8 {
+    opts.dark_threshold
+        .unwrap_or_else(|| otsu_like_threshold(luma))
+}
 
 // ---------------- Гомография: unit square -> произвольный четырёхугольник ----------------
 
-#[derive(Clone, Copy)]
-struct Quad { p00: PointF, p10: PointF, p01: PointF, p11: PointF }
-
 #[derive(Clone, Copy)]
 struct ProjMap {
     x0: f32, x1: f32, x2: f32, x3: f32,
@@ -55,12 +69,13 @@ struct ProjMap {
     g: f32, h: f32,
 }
 
-fn build_projective(quad: Quad) -> ProjMap {
-    let (x0, y0) = (quad.p00.x, quad.p00.y);
-    let (x1, y1) = (quad.p10.x - quad.p00.x, quad.p10.y - quad.p00.y);
-    let (x2, y2) = (quad.p01.x - quad.p00.x, quad.p01.y - quad.p00.y);
-    let (x3, y3) = (quad.p11.x - quad.p10.x - quad.p01.x + quad.p00.x,
-                    quad.p11.y - quad.p10.y - quad.p01.y + quad.p00.y);
+/// Unit square → `quad`: (0,0)→p0, (1,0)→p1, (1,1)→p2, (0,1)→p3.
+fn build_projective(quad: QuadF) -> ProjMap {
+    let (p00, p10, p11, p01) = (quad.p0, quad.p1, quad.p2, quad.p3);
+    let (x0, y0) = (p00.x, p00.y);
+    let (x1, y1) = (p10.x - p00.x, p10.y - p00.y);
+    let (x2, y2) = (p01.x - p00.x, p01.y - p00.y);
+    let (x3, y3) = (p11.x - p10.x - p01.x + p00.x, p11.y - p10.y - p01.y + p00.y);
 
     let denom = x1 * y2 - y1 * x2;
     let (g, h) = if denom.abs() < 1e-6 { (0.0, 0.0) } else {
@@ -81,7 +96,10 @@ fn map_uv(pm: &ProjMap, u: f32, v: f32) -> PointF {
 
 // ------------------------- Осе-выровненный фоллбэк -------------------------
 
-fn sample_axis_aligned_qr_v1(img: &GrayImage<'_>) -> Option<Vec<bool>> {
+fn sample_axis_aligned_qr_v1(
+    img: &GrayImage<'_>,
+    opts: &QrOptions,
+) -> Option<(Vec<bool>, Vec<u8>)> {
     if img.width % 29 != 0 || img.height % 29 != 0 { return None; }
     let unit_x = (img.width as f32) / 29.0;
     let unit_y = (img.height as f32) / 29.0;
@@ -89,13 +107,12 @@ fn sample_axis_aligned_qr_v1(img: &GrayImage<'_>) -> Option<Vec<bool>> {
     let rx = unit_x * 0.35;
     let ry = unit_y * 0.35;
 
-    eprintln!(
+    diag!(
         "[sample/fallback] axis-aligned used: unit=({:.3},{:.3}) rx={:.2} ry={:.2}",
         unit_x, unit_y, rx, ry
     );
 
-    let mut out = vec![false; N1 * N1];
-    let mut preview = String::new();
+    let mut luma = vec![255u8; N1 * N1];
 
     for y in 0..N1 {
         for x in 0..N1 {
@@ -108,7 +125,6 @@ fn sample_axis_aligned_qr_v1(img: &GrayImage<'_>) -> Option<Vec<bool>> {
             let y1 = (cy + ry).floor().min((img.height - 1) as f32) as i32;
 
             if x1 < x0 || y1 < y0 {
-                out[y * N1 + x] = false;
                 continue;
             }
 
@@ -121,19 +137,32 @@ fn sample_axis_aligned_qr_v1(img: &GrayImage<'_>) -> Option<Vec<bool>> {
                     cnt += 1;
                 }
             }
-            let avg = (sum / cnt.max(1)) as u8;
-            let dark = avg < 128;
-            out[y * N1 + x] = dark;
-
-            if y < 8 && x < 8 {
-                preview.push(if dark { '1' } else { '0' });
-                if x == 7 { preview.push('\n'); }
-            }
+            luma[y * N1 + x] = u8::try_from(sum / cnt.max(1)).unwrap_or(u8::MAX);
         }
     }
 
-    eprintln!("[sample/fallback] preview 8x8:\n{}", preview);
-    Some(out)
+    let threshold = module_threshold(opts, &luma);
+    let out: Vec<bool> = luma.iter().map(|&l| l < threshold).collect();
+    diag!(
+        "[sample/fallback] threshold={} preview 8x8:\n{}",
+        threshold,
+        preview_8x8(&out)
+    );
+    Some((out, luma))
+}
+
+/// Смещения точек суперсэмплинга по одной оси: `n` равноотстоящих значений
+/// в `[-span, span]`, где `n` — `supersample`, приведённый к 1, 3 или 5
+/// (см. [`QrOptions::supersample`]).
+fn supersample_offsets(supersample: u8, span: f32) -> Vec<f32> {
+    let n = supersample.clamp(1, 5) | 1;
+    let half = i8::try_from(n / 2).unwrap_or(0);
+    let step = if half == 0 {
+        0.0
+    } else {
+        span / f32::from(half)
+    };
+    (-half..=half).map(|k| f32::from(k) * step).collect()
 }
 
 // ---------------------- «Почти осевой?» критерий ----------------------
@@ -193,103 +222,1095 @@ where
 
 // ---------------------------- ОСНОВНОЙ СЭМПЛЕР ----------------------------
 
-pub fn sample_qr_v1_grid(img: &GrayImage<'_>, _opts: &QrOptions, finders: &[PointF]) -> Option<Vec<bool>> {
+/// Внешние углы символа по центру TL и векторам модуля: центр finder'а — модуль 3.5,
+/// так что углы лежат на −3.5 и +17.5 модуля от него.
+fn symbol_corners(tl: PointF, ux: PointF, uy: PointF) -> QuadF {
+    let at = |a: f32, b: f32| PointF {
+        x: tl.x + a * ux.x + b * uy.x,
+        y: tl.y + a * ux.y + b * uy.y,
+    };
+    QuadF {
+        p0: at(-3.5, -3.5),
+        p1: at(17.5, -3.5),
+        p2: at(17.5, 17.5),
+        p3: at(-3.5, 17.5),
+    }
+}
+
+/// Внешние углы QR v1 по трём центрам finder'ов (в любом порядке):
+/// p0 — у TL, p1 — у TR, p2 — противоположный TL, p3 — у BL.
+#[must_use]
+pub fn qr_v1_corners(finders: &[PointF]) -> Option<QuadF> {
     if finders.len() < 3 {
-        eprintln!("[sample] ERROR: need 3 finders, got {}", finders.len());
         return None;
     }
+    let [bl, tl, tr] = finder::order_finders([finders[0], finders[1], finders[2]]);
+    let ux = PointF {
+        x: (tr.x - tl.x) / 14.0,
+        y: (tr.y - tl.y) / 14.0,
+    };
+    let uy = PointF {
+        x: (bl.x - tl.x) / 14.0,
+        y: (bl.y - tl.y) / 14.0,
+    };
+    Some(symbol_corners(tl, ux, uy))
+}
 
-    // Упорядочим как [BL, TL, TR]
+/// Геометрия символа по finder'ам — для диагностики разрешения съёмки
+/// (например, «меньше ~3 px на модуль — поднесите камеру ближе»).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QrGeometry {
+    /// Версия, для которой посчитана сетка.
+    pub version: u8,
+    /// Сторона сетки в модулях: `17 + 4·version`.
+    pub grid_n: usize,
+    /// Размер модуля в пикселях: среднее длин векторов модуля TL→TR и TL→BL.
+    pub module_px: f32,
+}
+
+/// [`QrGeometry`] по трём центрам finder'ов и версии (обычно из
+/// [`estimate_version`]): между центрами соседних finder'ов `grid_n − 7` модулей.
+#[must_use]
+pub fn qr_geometry(finders: &[PointF], version: u8) -> Option<QrGeometry> {
+    if finders.len() < 3 || version == 0 {
+        return None;
+    }
     let [bl, tl, tr] = finder::order_finders([finders[0], finders[1], finders[2]]);
+    let grid_n = 17 + 4 * usize::from(version);
+    let span = f32::from(10 + 4 * u16::from(version));
+    let module_px = (tl.dist2(tr).sqrt() + tl.dist2(bl).sqrt()) / (2.0 * span);
+    Some(QrGeometry {
+        version,
+        grid_n,
+        module_px,
+    })
+}
+
+/// Поля вокруг символа в [`upscale_qr_v1_roi`], модулей: тихая зона стандарта —
+/// finder'ам на увеличенном кадре нужен светлый фон за краем.
+const UPSCALE_MARGIN_MODULES: f32 = 4.0;
 
-    // Векторы модуля (из центров фиднеров)
+/// Множитель [`upscale_qr_v1_roi`] для модуля `module_px` (см.
+/// [`QrOptions::upscale_small_codes`]): наименьший, с которым модуль не мельче
+/// `threshold`, в пределах `2..=`[`super::MAX_UPSCALE_FACTOR`]. `None` — модуль
+/// и так не мельче порога.
+#[must_use]
+pub fn upscale_factor(module_px: f32, threshold: f32) -> Option<usize> {
+    if !(module_px > 0.0 && module_px < threshold) {
+        return None;
+    }
+    Some(f32_to_usize((threshold / module_px).ceil()).clamp(2, super::MAX_UPSCALE_FACTOR))
+}
+
+/// Область символа v1 по finder'ам (с полями [`UPSCALE_MARGIN_MODULES`], в пределах
+/// кадра), увеличенная билинейно в `factor` раз — для мелких кодов, на которых
+/// суперсэмплинг модуля в 1–2 пикселя ничего не даёт. Возвращает увеличенный
+/// фрагмент и его левый верхний угол в исходном кадре: точка `(x, y)` фрагмента —
+/// это `origin + (x, y) / factor`.
+#[must_use]
+pub fn upscale_qr_v1_roi(
+    img: &GrayImage<'_>,
+    finders: &[PointF],
+    factor: usize,
+) -> Option<(LumaImage, PointF)> {
+    let geometry = qr_geometry(finders, 1)?;
+    let (min, max) = qr_v1_corners(finders)?.bounding_box();
+    let margin = UPSCALE_MARGIN_MODULES * geometry.module_px;
+    let x0 = f32_to_usize((min.x - margin).floor());
+    let y0 = f32_to_usize((min.y - margin).floor());
+    let x1 = f32_to_usize((max.x + margin).ceil()).min(img.width);
+    let y1 = f32_to_usize((max.y + margin).ceil()).min(img.height);
+    if factor < 2 || x1 <= x0 || y1 <= y0 {
+        return None;
+    }
+    let (width, height) = ((x1 - x0) * factor, (y1 - y0) * factor);
+    let scale = usize_to_f32(factor);
+    let origin = PointF {
+        x: usize_to_f32(x0),
+        y: usize_to_f32(y0),
+    };
+    let mut data = Vec::with_capacity(width * height);
+    for y in 0..height {
+        // центр выходного пикселя → координаты исходного кадра
+        let sy = origin.y + (usize_to_f32(y) + 0.5) / scale - 0.5;
+        for x in 0..width {
+            let sx = origin.x + (usize_to_f32(x) + 0.5) / scale - 0.5;
+            data.push(sample_bilinear(img, sx, sy));
+        }
+    }
+    Some((
+        LumaImage {
+            data,
+            width,
+            height,
+        },
+        origin,
+    ))
+}
+
+/// Минимальная проверяемая тихая зона QR, модулей (стандарт — 4; берём с запасом).
+pub const MIN_QUIET_MODULES: u16 = 2;
+
+/// Есть ли тихая зона вокруг QR v1: кольцо [`MIN_QUIET_MODULES`] модулей за краем
+/// сетки 21×21 целиком в кадре и светлое (до 5% тёмных центров модулей — шум).
+/// «Тёмное» — по тому же порогу, что при семплинге (см. [`QrOptions::dark_threshold`]),
+/// посчитанному по модулям самого символа.
+#[must_use]
+pub fn qr_v1_has_quiet_zone(img: &GrayImage<'_>, opts: &QrOptions, finders: &[PointF]) -> bool {
+    const SIDE: u16 = 21;
+    if finders.len() < 3 {
+        return false;
+    }
+    let [bl, tl, tr] = finder::order_finders([finders[0], finders[1], finders[2]]);
     let ux = PointF { x: (tr.x - tl.x) / 14.0, y: (tr.y - tl.y) / 14.0 };
     let uy = PointF { x: (bl.x - tl.x) / 14.0, y: (bl.y - tl.y) / 14.0 };
-    let ux_len = (ux.x * ux.x + ux.y * ux.y).sqrt();
-    let uy_len = (uy.x * uy.x + uy.y * uy.y).sqrt();
 
-    // Внешние углы всего символа (0..20 по осям)
-    let c00 = PointF { x: tl.x - 3.5*ux.x - 3.5*uy.x, y: tl.y - 3.5*ux.y - 3.5*uy.y }; // (0,0)
-    let c10 = PointF { x: tl.x + 17.5*ux.x - 3.5*uy.x, y: tl.y + 17.5*ux.y - 3.5*uy.y }; // (20,0)
-    let c01 = PointF { x: tl.x - 3.5*ux.x + 17.5*uy.x, y: tl.y - 3.5*ux.y + 17.5*uy.y }; // (0,20)
-    let c11 = PointF { x: tl.x + 17.5*ux.x + 17.5*uy.x, y: tl.y + 17.5*ux.y + 17.5*uy.y }; // (20,20)
+    // модули кольца в координатах с началом в его углу: символ — [ring, ring + 21)
+    let ring = MIN_QUIET_MODULES;
+    let in_symbol = |v: u16| (ring..ring + SIDE).contains(&v);
+    let (max_x, max_y) = (usize_to_f32(img.width - 1), usize_to_f32(img.height - 1));
+    let (mut quiet, mut symbol) = (Vec::new(), Vec::with_capacity(usize::from(SIDE * SIDE)));
+    for my in 0..SIDE + 2 * ring {
+        for mx in 0..SIDE + 2 * ring {
+            // центр модуля: центр TL-finder'а — модуль 3.5 символа
+            let du = f32::from(mx) - f32::from(ring + 3);
+            let dv = f32::from(my) - f32::from(ring + 3);
+            let px = tl.x + du * ux.x + dv * uy.x;
+            let py = tl.y + du * ux.y + dv * uy.y;
+            if in_symbol(mx) && in_symbol(my) {
+                symbol.push(sample_bilinear(img, px, py));
+                continue;
+            }
+            if px < 0.0 || py < 0.0 || px > max_x || py > max_y {
+                return false;
+            }
+            quiet.push(sample_bilinear(img, px, py));
+        }
+    }
+    let threshold = module_threshold(opts, &symbol);
+    let dark = quiet.iter().filter(|&&l| l < threshold).count();
+    dark * 20 <= quiet.len()
+}
 
-    let pm = build_projective(Quad { p00: c00, p10: c10, p01: c01, p11: c11 });
+/// Доля модулей таймингов (строка и столбец 6, модули 8..=12), совпавших с
+/// эталоном «чёрный на чётных» — проверка, что сетка легла на символ.
+#[must_use]
+pub fn v1_timing_score(grid: &[bool]) -> f32 {
+    if grid.len() != N1 * N1 {
+        return 0.0;
+    }
+    let hits = (8..=12)
+        .flat_map(|k| [grid[6 * N1 + k], grid[k * N1 + 6]].map(|b| b == (k % 2 == 0)))
+        .filter(|&ok| ok)
+        .count();
+    usize_to_f32(hits) / 10.0
+}
 
-    eprintln!(
-        "[sample] corners: C00=({:.2},{:.2}) C10=({:.2},{:.2}) C01=({:.2},{:.2}) C11=({:.2},{:.2}) |ux|={:.3}px |uy|={:.3}px",
-        c00.x, c00.y, c10.x, c10.y, c01.x, c01.y, c11.x, c11.y, ux_len, uy_len
+/// Число модулей стороны символа по таймингам — независимо от оценки версии
+/// и от сетки семплера. Вдоль строки 6 (от центра TL к центру TR) и столбца 6
+/// (от TL к BL) считаются run'ы между рамками finder'ов: сепаратор, тайминг
+/// `1 + 4v` модулей, сепаратор — всего `3 + 4v` = `grid_n − 14` run'ов. Модуль
+/// (для сдвига на 3 модуля от центра к тайминговой линии и отсева шума) — по
+/// полуширине TL-finder'а, как в [`estimate_version`].
+///
+/// `None`, если строка и столбец не сошлись, число не вида `17 + 4v` или линию
+/// не удалось промерить — измерение неубедительно, судить по нему нельзя.
+#[must_use]
+pub fn timing_module_count(
+    img: &GrayImage<'_>,
+    opts: &QrOptions,
+    finders: &[PointF],
+) -> Option<usize> {
+    if finders.len() < 3 {
+        return None;
+    }
+    let [bl, tl, tr] = finder::order_finders([finders[0], finders[1], finders[2]]);
+    let row = timing_runs(img, opts, tl, tr, bl);
+    let col = timing_runs(img, opts, tl, bl, tr);
+    diag!("[sample] timing runs: row={:?} col={:?}", row, col);
+    let runs = row.filter(|&r| Some(r) == col)?;
+    (runs % 4 == 3).then_some(runs + 14)
+}
+
+/// Run'ы тайминга вдоль линии TL → `to`, сдвинутой на 3 модуля к `side`
+/// (крайние run'ы — тёмные рамки finder'ов — не считаются).
+fn timing_runs(
+    img: &GrayImage<'_>,
+    opts: &QrOptions,
+    tl: PointF,
+    to: PointF,
+    side: PointF,
+) -> Option<usize> {
+    const STEPS_PER_PX: f32 = 2.0;
+    let unit = |p: PointF| {
+        let d = tl.dist2(p).sqrt();
+        (d >= 1.0).then(|| {
+            (
+                PointF {
+                    x: (p.x - tl.x) / d,
+                    y: (p.y - tl.y) / d,
+                },
+                d,
+            )
+        })
+    };
+    let (dir, len) = unit(to)?;
+    let (down, side_len) = unit(side)?;
+    let module = finder_half_width(img, opts, tl, down, side_len)? / 3.5;
+    let off = PointF {
+        x: 3.0 * module * down.x,
+        y: 3.0 * module * down.y,
+    };
+
+    let n = f32_to_usize((len * STEPS_PER_PX).round()) + 1;
+    let (max_x, max_y) = (usize_to_f32(img.width - 1), usize_to_f32(img.height - 1));
+    let mut luma = Vec::with_capacity(n + 1);
+    for i in 0..=n {
+        let t = len * usize_to_f32(i) / usize_to_f32(n);
+        let (x, y) = (tl.x + off.x + t * dir.x, tl.y + off.y + t * dir.y);
+        if x < 0.0 || y < 0.0 || x > max_x || y > max_y {
+            return None;
+        }
+        luma.push(sample_bilinear(img, x, y));
+    }
+    // порог — середина яркостей вдоль линии (устойчиво к общей засветке)
+    let (lo, hi) = luma
+        .iter()
+        .fold((u8::MAX, 0u8), |(lo, hi), &l| (lo.min(l), hi.max(l)));
+    let threshold = lo + (hi - lo) / 2;
+
+    // run'ы короче полумодуля — шум: сливаются с предыдущим
+    let min_run = f32_to_usize(module * STEPS_PER_PX / 2.0).max(1);
+    let mut runs: Vec<(bool, usize)> = Vec::new();
+    for &l in &luma {
+        let dark = l < threshold;
+        match runs.last_mut() {
+            Some((d, k)) if *d == dark => *k += 1,
+            _ => runs.push((dark, 1)),
+        }
+    }
+    let mut merged: Vec<(bool, usize)> = Vec::new();
+    for (dark, k) in runs {
+        match merged.last_mut() {
+            Some((d, m)) if *d == dark || k < min_run => *m += k,
+            _ => merged.push((dark, k)),
+        }
+    }
+    let (first, last) = (merged.first()?.0, merged.last()?.0);
+    (merged.len() >= 3 && first && last).then(|| merged.len() - 2)
+}
+
+/// Версия символа по геометрии finder'ов: между центрами TL и TR `10 + 4v`
+/// модулей, а модуль — по полуширине самих finder'ов (от центра до внешнего края
+/// рамки 3.5 модуля), измеренной вдоль TL→TR у TL и обратно у TR.
+/// `None`, если рамку не удалось промерить.
+#[must_use]
+pub fn estimate_version(img: &GrayImage<'_>, opts: &QrOptions, finders: &[PointF]) -> Option<u8> {
+    if finders.len() < 3 {
+        return None;
+    }
+    let [_, tl, tr] = finder::order_finders([finders[0], finders[1], finders[2]]);
+    let d = tl.dist2(tr).sqrt();
+    if d < 1.0 {
+        return None;
+    }
+    let dir = PointF {
+        x: (tr.x - tl.x) / d,
+        y: (tr.y - tl.y) / d,
+    };
+    let back = PointF {
+        x: -dir.x,
+        y: -dir.y,
+    };
+    let half =
+        finder_half_width(img, opts, tl, dir, d)? + finder_half_width(img, opts, tr, back, d)?;
+    let module = half / 7.0;
+
+    let v = ((d / module - 10.0) / 4.0).round();
+    diag!(
+        "[sample] version estimate: d={:.1}px module={:.2}px -> v={}",
+        d,
+        module,
+        v
     );
+    u8::try_from(f32_to_usize(v))
+        .ok()
+        .filter(|v| (1..=40).contains(v))
+}
 
+/// Информация о версии (см. [`format::decode_version_info`]) прямо с кадра, для
+/// версий от 7. Оба блока 6×3 читаются от своих finder'ов (правый верхний — от TR,
+/// левый нижний — от BL), так что перспектива почти не мешает. Модуль зависит от
+/// версии, поэтому пробуются `estimate` и соседние версии; годится только блок,
+/// который декодируется в ту же версию, по которой его семплировали (из нескольких —
+/// ближайший к валидному слову). `None` — блоки не читаются.
+#[must_use]
+pub fn read_version_info(
+    img: &GrayImage<'_>,
+    opts: &QrOptions,
+    finders: &[PointF],
+    estimate: u8,
+) -> Option<u8> {
+    if finders.len() < 3 {
+        return None;
+    }
+    let [bl, tl, tr] = finder::order_finders([finders[0], finders[1], finders[2]]);
+    let mut best: Option<(u8, u32)> = None;
+    for v in estimate.saturating_sub(1).max(format::VERSION_INFO_MIN)
+        ..=estimate.saturating_add(1).min(40)
+    {
+        let n = 17 + 4 * u16::from(v);
+        let span = f32::from(n - 7);
+        let ux = PointF {
+            x: (tr.x - tl.x) / span,
+            y: (tr.y - tl.y) / span,
+        };
+        let uy = PointF {
+            x: (bl.x - tl.x) / span,
+            y: (bl.y - tl.y) / span,
+        };
+        // Центры finder'ов в координатах модулей: TR — (n−3.5, 3.5), BL — (3.5, n−3.5).
+        let far = f32::from(n) - 3.5;
+        let blocks = format::version_info_positions(usize::from(n));
+        for (block, anchor, origin) in [(&blocks[0], tr, (far, 3.5)), (&blocks[1], bl, (3.5, far))]
+        {
+            let luma: Vec<u8> = block
+                .iter()
+                .map(|&(x, y)| {
+                    let du = usize_to_f32(x) + 0.5 - origin.0;
+                    let dv = usize_to_f32(y) + 0.5 - origin.1;
+                    sample_bilinear(
+                        img,
+                        anchor.x + du * ux.x + dv * uy.x,
+                        anchor.y + du * ux.y + dv * uy.y,
+                    )
+                })
+                .collect();
+            let threshold = module_threshold(opts, &luma);
+            let word =
+                luma.iter().enumerate().fold(
+                    0u32,
+                    |w, (i, &l)| if l < threshold { w | 1 << i } else { w },
+                );
+            match format::decode_version_info_with_distance(word) {
+                Some((got, d)) if got == v && best.map_or(true, |(_, bd)| d < bd) => {
+                    best = Some((v, d))
+                }
+                _ => {}
+            }
+        }
+    }
+    diag!("[sample] version info (estimate {}): {:?}", estimate, best);
+    best.map(|(v, _)| v)
+}
+
+/// Расстояние от центра finder'а `c` вдоль `dir` до конца его внешней рамки:
+/// ядро (тёмное) → кольцо (светлое) → рамка (тёмная) → светло. Шаг 0.25 px,
+/// не дальше половины `limit`; порог — [`module_threshold`] по самому лучу.
+fn finder_half_width(
+    img: &GrayImage<'_>,
+    opts: &QrOptions,
+    c: PointF,
+    dir: PointF,
+    limit: f32,
+) -> Option<f32> {
+    const STEP: f32 = 0.25;
+    let steps = f32_to_usize((limit * 0.5 / STEP).ceil());
+    let luma: Vec<u8> = (0..steps)
+        .map(|i| usize_to_f32(i) * STEP)
+        .take_while(|&t| t < limit * 0.5)
+        .map(|t| sample_bilinear(img, c.x + t * dir.x, c.y + t * dir.y))
+        .collect();
+    let threshold = module_threshold(opts, &luma);
+    let mut phase = 0u8;
+    for (i, &l) in luma.iter().enumerate() {
+        match (phase, l < threshold) {
+            (0, false) => phase = 1,
+            (1, true) => phase = 2,
+            (2, false) => return Some(usize_to_f32(i) * STEP),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Минимальный [`v1_timing_score`], при котором достроенный третий finder принимается.
+const RECOVERY_MIN_TIMING: f32 = 0.9;
+
+/// Достроить третий finder по двум (`QrOptions::recover_two_finders`).
+///
+/// Пара может быть катетом (TL+TR или TL+BL: третий — на перпендикуляре той же
+/// длины у любого из концов, 4 варианта) или гипотенузой (TR+BL: TL — на
+/// перпендикуляре из середины, 2 варианта). Каждый вариант семплируется, побеждает
+/// лучший по таймингам, если он не ниже `RECOVERY_MIN_TIMING`.
+#[must_use]
+pub fn complete_two_finders(
+    img: &GrayImage<'_>,
+    opts: &QrOptions,
+    pair: [PointF; 2],
+) -> Option<[PointF; 3]> {
+    let [p, q] = pair;
+    let n = PointF {
+        x: -(q.y - p.y),
+        y: q.x - p.x,
+    };
+    let mid = PointF {
+        x: (p.x + q.x) * 0.5,
+        y: (p.y + q.y) * 0.5,
+    };
+    let at = |o: PointF, k: f32| PointF {
+        x: o.x + k * n.x,
+        y: o.y + k * n.y,
+    };
+    let guesses = [
+        at(p, 1.0),
+        at(p, -1.0),
+        at(q, 1.0),
+        at(q, -1.0),
+        at(mid, 0.5),
+        at(mid, -0.5),
+    ];
+
+    let mut best: Option<([PointF; 3], f32)> = None;
+    for g in guesses {
+        let full = [p, q, g];
+        let Some((bits, _)) = sample_qr_v1_grid_with_luma(img, opts, &full) else {
+            continue;
+        };
+        let score = v1_timing_score(&bits);
+        diag!(
+            "[sample] recovery guess ({:.1},{:.1}): timing={:.2}",
+            g.x,
+            g.y,
+            score
+        );
+        if score >= RECOVERY_MIN_TIMING && best.map_or(true, |(_, s)| score > s) {
+            best = Some((full, score));
+        }
+    }
+    best.map(|(full, _)| full)
+}
+
+/// Семплинг сетки 21×21 (flatten, `y * 21 + x`; true = чёрный модуль).
+/// Тонкая обёртка над [`sample_qr_v1_grid_with_luma`].
+#[must_use]
+pub fn sample_qr_v1_grid(
+    img: &GrayImage<'_>,
+    opts: &QrOptions,
+    finders: &[PointF],
+) -> Option<Vec<bool>> {
+    sample_qr_v1_grid_with_luma(img, opts, finders).map(|(bits, _)| bits)
+}
+
+/// То же, что [`sample_qr_v1_grid`], но вместе с битами возвращает усреднённую
+/// яркость каждого модуля (тот же порядок). Бит = `luma < порог` (см.
+/// [`QrOptions::dark_threshold`]; авто-порог — [`otsu_like_threshold`] этих же
+/// яркостей), так что близость яркости к порогу показывает, насколько бит
+/// «неуверенный» (для erasure-декодирования и тепловых карт качества семплинга).
+#[must_use]
+pub fn sample_qr_v1_grid_with_luma(
+    img: &GrayImage<'_>,
+    opts: &QrOptions,
+    finders: &[PointF],
+) -> Option<(Vec<bool>, Vec<u8>)> {
+    sample_qr_v1_grid_calibrated(img, opts, finders).map(|(bits, luma, _)| (bits, luma))
+}
+
+/// Как [`sample_qr_v1_grid_with_luma`], но вместе с калибровкой, по которой
+/// снята сетка, — её берут повторы ([`resample_qr_v1_grid`],
+/// [`sample_qr_v1_grid_shifted`]). У осевой синтетики сетка читается напрямую,
+/// без калибровки (`None`).
+pub(crate) fn sample_qr_v1_grid_calibrated(
+    img: &GrayImage<'_>,
+    opts: &QrOptions,
+    finders: &[PointF],
+) -> Option<(Vec<bool>, Vec<u8>, Option<GridCalibration>)> {
+    let mapper = GridMapper::new(img, opts, finders)?;
     // Фоллбэк, если кадр реально осевой
-    if (img.width % 29 == 0 && img.height % 29 == 0) && is_near_axis_aligned(ux, uy) {
-        if let Some(bits) = sample_axis_aligned_qr_v1(img) { return Some(bits); }
+    if mapper.axis_aligned {
+        if let Some((bits, luma)) = sample_axis_aligned_qr_v1(img, opts) {
+            return Some((bits, luma, None));
+        }
     }
+    let calibration = mapper.calibrate();
+    let (bits, luma) = mapper.sample(calibration, (0, 0));
+    Some((bits, luma, Some(calibration)))
+}
 
-    // ======= Автокалибровка (анизотропные масштабы + сдвиги в норм. коорд) =======
-    // u,v в [0..1], где u=(x+0.5)/21, v=(y+0.5)/21
-    const SCALES: [f32; 5] = [0.985, 0.995, 1.000, 1.005, 1.015];
-    const OFFS:   [f32; 5] = [-0.012, -0.006, 0.0, 0.006, 0.012]; // ~±0.25 модуля
-
-    // суперсэмплинг: ±0.18 модуля в u,v → в норм. величинах:
-    const SS: f32 = 0.18 / 21.0;
-    const SS_OFFS: [f32; 3] = [-SS, 0.0, SS];
-
-    let get_bit_with = |su: f32, sv: f32, du: f32, dv: f32, xx: usize, yy: usize| -> bool {
-        let mut u0 = (xx as f32 + 0.5) / 21.0;
-        let mut v0 = (yy as f32 + 0.5) / 21.0;
-        u0 = (u0 * su + du).clamp(-0.02, 1.02);
-        v0 = (v0 * sv + dv).clamp(-0.02, 1.02);
-
-        let mut sum: u32 = 0;
-        for dv_ in SS_OFFS {
-            for du_ in SS_OFFS {
-                let p = map_uv(&pm, u0 + du_, v0 + dv_);
-                sum += sample_bilinear(img, p.x, p.y) as u32;
+/// Калибровка сетки по таймингам: масштабы (`su`, `sv`) и сдвиги (`du`, `dv`)
+/// нормированных координат модуля, `u = (x + 0.5) / 21 · su + du` (и так же по v).
+/// Подбор — 625 семплингов таймингов, поэтому повторы чтения (поправки для
+/// формата, сдвиги сетки) берут калибровку несдвинутой сетки, а не ищут свою.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GridCalibration {
+    pub su: f32,
+    pub sv: f32,
+    pub du: f32,
+    pub dv: f32,
+}
+
+/// Калибровка несдвинутой сетки 21×21 по finder'ам (см. [`GridCalibration`]):
+/// лучшая по таймингам среди масштабов ±1.5% и сдвигов ±0.25 модуля, при равном
+/// балле — ближайшая к тождественной. `None` — finder'ов меньше трёх.
+#[must_use]
+pub fn calibrate_qr_v1_grid(
+    img: &GrayImage<'_>,
+    opts: &QrOptions,
+    finders: &[PointF],
+) -> Option<GridCalibration> {
+    GridMapper::new(img, opts, finders).map(|mapper| mapper.calibrate())
+}
+
+/// Семплинг сетки, сдвинутой на целое число модулей `shift = (dx, dy)` (обычно
+/// ±1), с калибровкой `calibration` несдвинутой сетки (см. [`calibrate_qr_v1_grid`]):
+/// модуль `(x, y)` читается там, где по finder'ам стоит `(x + dx, y + dy)`.
+/// Автокалибровка (±0.25 модуля) целый модуль не покрывает, а ошибка центров
+/// finder'ов или перспектива иногда сдвигает всю сетку ровно на модуль.
+/// Биты и яркости — как у [`sample_qr_v1_grid_with_luma`].
+#[must_use]
+pub fn sample_qr_v1_grid_shifted(
+    img: &GrayImage<'_>,
+    opts: &QrOptions,
+    finders: &[PointF],
+    calibration: GridCalibration,
+    shift: (i8, i8),
+) -> Option<(Vec<bool>, Vec<u8>)> {
+    Some(GridMapper::new(img, opts, finders)?.sample(calibration, shift))
+}
+
+/// Поправки (Δsu, Δsv, Δdu, Δdv) к найденной калибровке для повторных чтений
+/// формата: сдвиги на ~полмодуля по осям и диагоналям, затем масштабы чуть за
+/// пределами перебора автокалибровки.
+const RETRY_NUDGES: [(f32, f32, f32, f32); 8] = [
+    (0.0, 0.0, 0.018, 0.0),
+    (0.0, 0.0, -0.018, 0.0),
+    (0.0, 0.0, 0.0, 0.018),
+    (0.0, 0.0, 0.0, -0.018),
+    (0.0, 0.0, 0.018, 0.018),
+    (0.0, 0.0, -0.018, -0.018),
+    (-0.02, -0.02, 0.0, 0.0),
+    (0.02, 0.02, 0.0, 0.0),
+];
+
+/// Сколько разных повторных семплингов умеет [`resample_qr_v1_grid`].
+pub const MAX_FORMAT_RETRIES: usize = RETRY_NUDGES.len();
+
+/// Повторный семплинг сетки с возмущённой калибровкой — для попытки `attempt`
+/// (`0..MAX_FORMAT_RETRIES`) к калибровке первого чтения (см.
+/// [`calibrate_qr_v1_grid`]) добавляется своя поправка. Нужен, когда формат не
+/// читается: тайминги (по ним идёт калибровка) сошлись, а модули формата легли на
+/// границу. Биты и яркости — как у [`sample_qr_v1_grid_with_luma`]; `None` —
+/// попытки кончились (или семплинг не удался).
+#[must_use]
+pub fn resample_qr_v1_grid(
+    img: &GrayImage<'_>,
+    opts: &QrOptions,
+    finders: &[PointF],
+    calibration: GridCalibration,
+    attempt: usize,
+) -> Option<(Vec<bool>, Vec<u8>)> {
+    let (nsu, nsv, ndu, ndv) = *RETRY_NUDGES.get(attempt)?;
+    let GridCalibration { su, sv, du, dv } = calibration;
+    let nudged = GridCalibration {
+        su: su + nsu,
+        sv: sv + nsv,
+        du: du + ndu,
+        dv: dv + ndv,
+    };
+    diag!(
+        "[sample] retry {}: su={:.3} sv={:.3} du={:.3} dv={:.3}",
+        attempt,
+        nudged.su,
+        nudged.sv,
+        nudged.du,
+        nudged.dv
+    );
+    sample_qr_v1_grid_shifted(img, opts, finders, nudged, (0, 0))
+}
+
+/// Насколько близко к порогу (в единицах яркости) лежит «неуверенный» модуль,
+/// см. [`borderline_ratio`].
+pub const BORDERLINE_MARGIN: u8 = 24;
+
+/// Доля «неуверенных» модулей сетки — тех, чья яркость ближе [`BORDERLINE_MARGIN`]
+/// к порогу, по которому решались биты (тот же, что при семплинге, см.
+/// [`QrOptions::dark_threshold`]). `0.0` — чистый снимок; чем больше, тем
+/// вероятнее ошибки в битах.
+#[must_use]
+pub fn borderline_ratio(opts: &QrOptions, luma: &[u8]) -> f32 {
+    if luma.is_empty() {
+        return 0.0;
+    }
+    let threshold = module_threshold(opts, luma);
+    let near = luma
+        .iter()
+        .filter(|&&l| l.abs_diff(threshold) < BORDERLINE_MARGIN)
+        .count();
+    usize_to_f32(near) / usize_to_f32(luma.len())
+}
+
+/// Сетка 21×21 на кадре по трём finder'ам: проективная рамка символа и точки
+/// суперсэмплинга модуля.
+struct GridMapper<'a> {
+    img: GrayImage<'a>,
+    opts: &'a QrOptions,
+    pm: ProjMap,
+    ss_offs: Vec<f32>,
+    /// Кадр — осевая синтетика (см. [`sample_axis_aligned_qr_v1`]).
+    axis_aligned: bool,
+}
+
+impl<'a> GridMapper<'a> {
+    fn new(img: &GrayImage<'a>, opts: &'a QrOptions, finders: &[PointF]) -> Option<Self> {
+        // суперсэмплинг: n×n точек в пределах ±0.18 модуля в u,v → в норм. величинах
+        const SS: f32 = 0.18 / 21.0;
+
+        if finders.len() < 3 {
+            diag!("[sample] ERROR: need 3 finders, got {}", finders.len());
+            return None;
+        }
+
+        // Упорядочим как [BL, TL, TR]
+        let [bl, tl, tr] = finder::order_finders([finders[0], finders[1], finders[2]]);
+
+        // Векторы модуля (из центров фиднеров)
+        let ux = PointF {
+            x: (tr.x - tl.x) / 14.0,
+            y: (tr.y - tl.y) / 14.0,
+        };
+        let uy = PointF {
+            x: (bl.x - tl.x) / 14.0,
+            y: (bl.y - tl.y) / 14.0,
+        };
+
+        // Внешние углы всего символа (0..20 по осям)
+        let corners = symbol_corners(tl, ux, uy);
+        let pm = build_projective(corners);
+
+        diag!(
+            "[sample] corners: C00=({:.2},{:.2}) C10=({:.2},{:.2}) C11=({:.2},{:.2}) C01=({:.2},{:.2}) |ux|={:.3}px |uy|={:.3}px",
+            corners.p0.x, corners.p0.y, corners.p1.x, corners.p1.y,
+            corners.p2.x, corners.p2.y, corners.p3.x, corners.p3.y,
+            (ux.x * ux.x + ux.y * ux.y).sqrt(), (uy.x * uy.x + uy.y * uy.y).sqrt()
+        );
+
+        let axis_aligned =
+            img.width % 29 == 0 && img.height % 29 == 0 && is_near_axis_aligned(ux, uy);
+        Some(Self {
+            img: *img,
+            opts,
+            pm,
+            ss_offs: supersample_offsets(opts.supersample, SS),
+            axis_aligned,
+        })
+    }
+
+    /// Усреднённая яркость модуля `(xx, yy)` при калибровке `cal` и целом сдвиге
+    /// `shift` (в нормированных координатах).
+    fn luma_at(&self, cal: GridCalibration, shift: (f32, f32), xx: usize, yy: usize) -> u8 {
+        // целый сдвиг — после прижатия: сдвинутая сетка выходит за рамку по finder'ам
+        let u0 = ((usize_to_f32(xx) + 0.5) / 21.0 * cal.su + cal.du).clamp(-0.02, 1.02) + shift.0;
+        let v0 = ((usize_to_f32(yy) + 0.5) / 21.0 * cal.sv + cal.dv).clamp(-0.02, 1.02) + shift.1;
+
+        let mut sum = 0usize;
+        for &dv_ in &self.ss_offs {
+            for &du_ in &self.ss_offs {
+                let p = map_uv(&self.pm, u0 + du_, v0 + dv_);
+                sum += usize::from(sample_bilinear(&self.img, p.x, p.y));
             }
         }
-        let avg = (sum / 9) as u8;
-        is_dark(avg)
-    };
+        u8::try_from(sum / (self.ss_offs.len() * self.ss_offs.len())).unwrap_or(u8::MAX)
+    }
+
+    fn grid_luma(&self, cal: GridCalibration, shift: (i8, i8)) -> Vec<u8> {
+        let shift = (f32::from(shift.0) / 21.0, f32::from(shift.1) / 21.0);
+        (0..N1 * N1)
+            .map(|i| self.luma_at(cal, shift, i % N1, i / N1))
+            .collect()
+    }
 
-    let mut best = (f32::NEG_INFINITY, 1.0, 1.0, 0.0, 0.0, String::new(), String::new());
-    for &su in &SCALES {
-        for &sv in &SCALES {
-            for &du in &OFFS {
-                for &dv in &OFFS {
-                    let (score, row_s, col_s) = timing_score_row_col(|x, y| get_bit_with(su, sv, du, dv, x, y));
-                    if score > best.0 {
-                        best = (score, su, sv, du, dv, row_s, col_s);
+    // ======= Автокалибровка (анизотропные масштабы + сдвиги в норм. коорд) =======
+    fn calibrate(&self) -> GridCalibration {
+        // u,v в [0..1], где u=(x+0.5)/21, v=(y+0.5)/21
+        const SCALES: [f32; 5] = [0.985, 0.995, 1.000, 1.005, 1.015];
+        const OFFS: [f32; 5] = [-0.012, -0.006, 0.0, 0.006, 0.012]; // ~±0.25 модуля
+
+        // Порог калибровки — по сетке без поправок; окончательный — по откалиброванной.
+        let identity = GridCalibration {
+            su: 1.0,
+            sv: 1.0,
+            du: 0.0,
+            dv: 0.0,
+        };
+        let tuning_threshold = module_threshold(self.opts, &self.grid_luma(identity, (0, 0)));
+
+        let mut best = (f32::NEG_INFINITY, identity, String::new(), String::new());
+        let mut best_dist = f32::INFINITY;
+        for &su in &SCALES {
+            for &sv in &SCALES {
+                for &du in &OFFS {
+                    for &dv in &OFFS {
+                        let cal = GridCalibration { su, sv, du, dv };
+                        let (score, row_s, col_s) = timing_score_row_col(|x, y| {
+                            self.luma_at(cal, (0.0, 0.0), x, y) < tuning_threshold
+                        });
+                        // при равном балле — ближайшие к тождественной калибровке параметры
+                        let dist = (su - 1.0).abs() + (sv - 1.0).abs() + du.abs() + dv.abs();
+                        if score > best.0 + 1e-6 || (score > best.0 - 1e-6 && dist < best_dist) {
+                            best = (score, cal, row_s, col_s);
+                            best_dist = dist;
+                        }
                     }
                 }
             }
         }
-    }
 
-    let (score, su, sv, du, dv, row_s, col_s) = best;
-    eprintln!(
-        "[sample] tuning: su={:.3} sv={:.3} du={:.3} dv={:.3} timing_score={:.3}",
-        su, sv, du, dv, score
-    );
-    eprintln!("[sample] row y=6 (x=8..12): {}", row_s);
-    eprintln!("[sample] col x=6 (y=8..12): {}", col_s);
+        let (score, cal, row_s, col_s) = best;
+        diag!(
+            "[sample] tuning: su={:.3} sv={:.3} du={:.3} dv={:.3} timing_score={:.3}",
+            cal.su,
+            cal.sv,
+            cal.du,
+            cal.dv,
+            score
+        );
+        diag!("[sample] row y=6 (x=8..12): {}", row_s);
+        diag!("[sample] col x=6 (y=8..12): {}", col_s);
+        cal
+    }
 
     // ======================= Окончательный сэмплинг =======================
-    let mut out = vec![false; N1 * N1];
+    fn sample(&self, cal: GridCalibration, shift: (i8, i8)) -> (Vec<bool>, Vec<u8>) {
+        let luma = self.grid_luma(cal, shift);
+        let threshold = module_threshold(self.opts, &luma);
+        let out: Vec<bool> = luma.iter().map(|&l| l < threshold).collect();
+
+        diag!(
+            "[sample] threshold={} preview 8x8 (1=black,0=white):\n{}",
+            threshold,
+            preview_8x8(&out)
+        );
+        (out, luma)
+    }
+}
+
+/// Левый верхний угол сетки 8×8 (finder и формат) строками `0`/`1` — для логов.
+fn preview_8x8(bits: &[bool]) -> String {
     let mut preview = String::new();
+    for y in 0..8 {
+        for x in 0..8 {
+            preview.push(if bits[y * N1 + x] { '1' } else { '0' });
+        }
+        preview.push('\n');
+    }
+    preview
+}
 
-    for y in 0..N1 {
-        for x in 0..N1 {
-            let bit = get_bit_with(su, sv, du, dv, x, y);
-            out[y * N1 + x] = bit;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::qr::encode::encode_qr_v1;
+    use crate::qr::format::EcLevel;
+
+    #[test]
+    fn luma_is_consistent_with_bits() {
+        let img = encode_qr_v1(b"HELLO", EcLevel::L, 3, 4, 4).unwrap();
+        let img = img.as_gray();
+        let opts = QrOptions::default();
+        let finders = finder::find_finder_patterns(&img, &opts);
+        let (bits, luma) = sample_qr_v1_grid_with_luma(&img, &opts, &finders).expect("sample");
+        assert_eq!(bits.len(), N1 * N1);
+        assert_eq!(luma.len(), N1 * N1);
+        let threshold = otsu_like_threshold(&luma);
+        for (i, (&b, &l)) in bits.iter().zip(&luma).enumerate() {
+            assert_eq!(b, l < threshold, "module {i}: bit/luma mismatch");
+        }
+        // Чистая синтетика: рамка finder'а — чёрная, сепаратор — белый.
+        assert!(luma[0] < 64 && luma[7] > 192);
+        assert_eq!(sample_qr_v1_grid(&img, &opts, &finders), Some(bits));
+    }
+
+    #[test]
+    fn supersample_grid_is_odd_and_symmetric() {
+        assert_eq!(supersample_offsets(1, 0.18), [0.0]);
+        assert_eq!(supersample_offsets(0, 0.18), [0.0]);
+        assert_eq!(supersample_offsets(3, 0.18), [-0.18, 0.0, 0.18]);
+        assert_eq!(supersample_offsets(2, 0.18).len(), 3);
+        let five = supersample_offsets(5, 0.18);
+        assert_eq!(five.len(), 5);
+        assert!((five[1] + 0.09).abs() < 1e-6 && (five[4] - 0.18).abs() < 1e-6);
+        assert_eq!(supersample_offsets(9, 0.18), five);
+    }
+
+    #[test]
+    fn every_supersample_grid_reads_the_same_symbol() {
+        // Сдвигаем символ на 3 px в холсте 121×121: размер не кратен 29, так что
+        // осевой фоллбэк не срабатывает и работает проективный семплинг.
+        let src = encode_qr_v1(b"HELLO", EcLevel::L, 3, 4, 4).unwrap();
+        let src = src.as_gray();
+        let w = 121;
+        let mut data = vec![255u8; w * w];
+        for y in 0..src.height {
+            let dst = (y + 3) * w + 3;
+            data[dst..dst + src.width].copy_from_slice(src.row(y));
+        }
+        let img = GrayImage {
+            data: &data,
+            width: w,
+            height: w,
+        };
+        let base = QrOptions::default();
+        let reference = sample_qr_v1_grid(&src, &base, &finder::find_finder_patterns(&src, &base));
+        assert!(reference.is_some());
+        for supersample in [1, 3, 5] {
+            let opts = QrOptions {
+                supersample,
+                ..base
+            };
+            let finders = finder::find_finder_patterns(&img, &opts);
+            assert_eq!(
+                sample_qr_v1_grid(&img, &opts, &finders),
+                reference,
+                "supersample={supersample}"
+            );
+        }
+    }
+
+    #[test]
+    fn shifted_grid_recovers_finders_off_by_one_module() {
+        // Тот же холст 121×121; центры finder'ов «промахнулись» на модуль (4 px).
+        let src = encode_qr_v1(b"HELLO", EcLevel::L, 3, 4, 4).unwrap();
+        let src = src.as_gray();
+        let w = 121;
+        let mut data = vec![255u8; w * w];
+        for y in 0..src.height {
+            let dst = (y + 3) * w + 3;
+            data[dst..dst + src.width].copy_from_slice(src.row(y));
+        }
+        let img = GrayImage {
+            data: &data,
+            width: w,
+            height: w,
+        };
+        let opts = QrOptions::default();
+        let finders = finder::find_finder_patterns(&img, &opts);
+        let reference = sample_qr_v1_grid(&img, &opts, &finders).expect("reference");
+        let bits = |grid: Option<(Vec<bool>, Vec<u8>)>| grid.map(|(bits, _)| bits);
+        let calibration = calibrate_qr_v1_grid(&img, &opts, &finders).expect("calibration");
+        assert_eq!(
+            bits(sample_qr_v1_grid_shifted(
+                &img,
+                &opts,
+                &finders,
+                calibration,
+                (0, 0)
+            ))
+            .as_ref(),
+            Some(&reference)
+        );
+
+        for (mx, my) in [(1i8, 0i8), (-1, 0), (0, 1), (0, -1)] {
+            let (dx, dy) = (f32::from(mx) * 4.0, f32::from(my) * 4.0);
+            let off: Vec<PointF> = finders
+                .iter()
+                .map(|p| PointF {
+                    x: p.x + dx,
+                    y: p.y + dy,
+                })
+                .collect();
+            assert_ne!(
+                sample_qr_v1_grid(&img, &opts, &off).as_ref(),
+                Some(&reference),
+                "({mx}, {my})"
+            );
+            // калибровка — несдвинутой (промахнувшейся) сетки, как у повторов в пайплайне
+            let calibration = calibrate_qr_v1_grid(&img, &opts, &off).expect("calibration");
+            let back = bits(sample_qr_v1_grid_shifted(
+                &img,
+                &opts,
+                &off,
+                calibration,
+                (-mx, -my),
+            ));
+            assert_eq!(back.as_ref(), Some(&reference), "({mx}, {my})");
+        }
+    }
 
-            if y < 8 && x < 8 {
-                preview.push(if bit { '1' } else { '0' });
-                if x == 7 { preview.push('\n'); }
+    /// Только функциональные узоры (finder'ы + тайминги) символа `n`×`n`,
+    /// тихая зона 4 модуля.
+    fn render_function_patterns(n: usize, unit: usize) -> Vec<u8> {
+        let side = (n + 8) * unit;
+        let mut data = vec![255u8; side * side];
+        let timing =
+            |on: usize, along: usize| on == 6 && (8..n - 8).contains(&along) && along % 2 == 0;
+        let dark = |r: usize, c: usize| {
+            let finder = |r0: usize, c0: usize| {
+                let (dr, dc) = (r.wrapping_sub(r0), c.wrapping_sub(c0));
+                (dr < 7 && dc < 7).then(|| dr.min(dc).min(6 - dr).min(6 - dc) != 1)
+            };
+            finder(0, 0)
+                .or_else(|| finder(0, n - 7))
+                .or_else(|| finder(n - 7, 0))
+                .unwrap_or(timing(r, c) || timing(c, r))
+        };
+        for y in 0..side {
+            for x in 0..side {
+                let (r, c) = ((y / unit).wrapping_sub(4), (x / unit).wrapping_sub(4));
+                if r < n && c < n && dark(r, c) {
+                    data[y * side + x] = 0;
+                }
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn version_info_is_read_from_both_blocks() {
+        // Функциональные узоры v7 (45×45) плюс блоки версии; unit = 3 px.
+        let (n, unit) = (45, 3);
+        let side = (n + 8) * unit;
+        let mut data = render_function_patterns(n, unit);
+        let word = format::encode_version_info(7).unwrap();
+        let [tr_block, bl_block] = format::version_info_positions(n);
+        for (i, (&a, &b)) in tr_block.iter().zip(&bl_block).enumerate() {
+            if (word >> i) & 1 == 1 {
+                for (x, y) in [a, b] {
+                    for py in (y + 4) * unit..(y + 5) * unit {
+                        data[py * side + (x + 4) * unit..py * side + (x + 5) * unit].fill(0);
+                    }
+                }
             }
         }
+        let img = GrayImage {
+            data: &data,
+            width: side,
+            height: side,
+        };
+        let opts = QrOptions::default();
+        let finders = finder::find_finder_patterns(&img, &opts);
+        // Пограничная оценка по разносу finder'ов (6 или 8) не мешает: берётся та
+        // соседняя версия, чей блок декодируется сам в себя.
+        for estimate in [6, 7, 8] {
+            assert_eq!(
+                read_version_info(&img, &opts, &finders, estimate),
+                Some(7),
+                "estimate {estimate}"
+            );
+        }
+        assert_eq!(read_version_info(&img, &opts, &finders, 12), None);
+
+        // У символа без блоков (v1) читать нечего.
+        let v1 = encode_qr_v1(b"HELLO", EcLevel::L, 3, 4, 4).unwrap();
+        let v1 = v1.as_gray();
+        let finders = finder::find_finder_patterns(&v1, &opts);
+        assert_eq!(read_version_info(&v1, &opts, &finders, 7), None);
+    }
+
+    #[test]
+    fn timing_patterns_count_modules() {
+        let img = encode_qr_v1(b"HELLO", EcLevel::L, 3, 4, 4).unwrap();
+        let img = img.as_gray();
+        let finders = finder::find_finder_patterns(&img, &QrOptions::default());
+        assert_eq!(
+            timing_module_count(&img, &QrOptions::default(), &finders),
+            Some(N1)
+        );
+
+        // Сетка v2 (25×25): тайминг длиннее на 4 модуля.
+        let data = render_function_patterns(25, 4);
+        let side = 33 * 4;
+        let img = GrayImage {
+            data: &data,
+            width: side,
+            height: side,
+        };
+        let finders = finder::find_finder_patterns(&img, &QrOptions::default());
+        assert_eq!(
+            timing_module_count(&img, &QrOptions::default(), &finders),
+            Some(25)
+        );
     }
 
-    eprintln!("[sample] preview 8x8 (1=black,0=white):\n{}", preview);
-    Some(out)
+    #[test]
+    fn corners_cover_symbol_and_round_to_quad() {
+        // quiet = 4 модуля, unit = 4 px: символ занимает пиксели 16..100;
+        // центры finder'ов известны с точностью ~полмодуля.
+        let img = encode_qr_v1(b"HELLO", EcLevel::L, 3, 4, 4).unwrap();
+        let img = img.as_gray();
+        let finders = finder::find_finder_patterns(&img, &QrOptions::default());
+        let corners = qr_v1_corners(&finders).expect("corners");
+        let quad = corners.to_quad();
+        let expect = [(16, 16), (100, 16), (100, 100), (16, 100)];
+        for (p, (ex, ey)) in [quad.p0, quad.p1, quad.p2, quad.p3].into_iter().zip(expect) {
+            assert!(
+                (p.x - ex).abs() <= 2 && (p.y - ey).abs() <= 2,
+                "{p:?} vs ({ex},{ey})"
+            );
+        }
+        let (min, max) = quad.bounding_box();
+        assert!(min.x <= quad.p0.x && max.y >= quad.p2.y);
+        let (fmin, fmax) = corners.bounding_box();
+        assert_eq!((fmin.to_point(), fmax.to_point()), (min, max));
+        assert!(qr_v1_corners(&finders[..2]).is_none());
+    }
+
+    #[test]
+    fn geometry_reports_module_size_and_grid() {
+        let img = encode_qr_v1(b"HELLO", EcLevel::L, 3, 4, 4).unwrap();
+        let img = img.as_gray();
+        let finders = finder::find_finder_patterns(&img, &QrOptions::default());
+        let g = qr_geometry(&finders, 1).expect("geometry");
+        assert_eq!((g.version, g.grid_n), (1, 21));
+        assert!(
+            (g.module_px - 4.0).abs() < 0.25,
+            "module_px = {}",
+            g.module_px
+        );
+        // та же раскладка, прочитанная как v2: сетка 25, модуль мельче
+        let g2 = qr_geometry(&finders, 2).expect("geometry v2");
+        assert_eq!(g2.grid_n, 25);
+        assert!(g2.module_px < g.module_px);
+        assert_eq!(qr_geometry(&finders[..2], 1), None);
+        assert_eq!(qr_geometry(&finders, 0), None);
+    }
+
+    #[test]
+    fn version_is_estimated_from_finder_spacing() {
+        let img = encode_qr_v1(b"HELLO", EcLevel::L, 3, 4, 4).unwrap();
+        let img = img.as_gray();
+        let finders = finder::find_finder_patterns(&img, &QrOptions::default());
+        assert_eq!(
+            estimate_version(&img, &QrOptions::default(), &finders),
+            Some(1)
+        );
+
+        // Три finder'а на расстоянии версии 3 (29 модулей, центры через 22).
+        let (unit, size) = (3usize, 29usize);
+        let w = (size + 8) * unit;
+        let mut data = vec![255u8; w * w];
+        for (ox, oy) in [(4, 4), (4 + size - 7, 4), (4, 4 + size - 7)] {
+            for my in 0..7 {
+                for mx in 0..7 {
+                    let ring = mx == 0 || mx == 6 || my == 0 || my == 6;
+                    let core = (2..=4).contains(&mx) && (2..=4).contains(&my);
+                    if ring || core {
+                        for p in 0..unit * unit {
+                            let (x, y) = ((ox + mx) * unit + p % unit, (oy + my) * unit + p / unit);
+                            data[y * w + x] = 0;
+                        }
+                    }
+                }
+            }
+        }
+        let img = GrayImage {
+            data: &data,
+            width: w,
+            height: w,
+        };
+        let finders = finder::find_finder_patterns(&img, &QrOptions::default());
+        assert_eq!(
+            estimate_version(&img, &QrOptions::default(), &finders),
+            Some(3)
+        );
+    }
 }
@@ -0,0 +1,1195 @@
+//! Aztec Code (ISO/IEC 24778): поиск «яблочка» и чтение компактных символов (1–4 слоя).
+//!
+//! Путь декодера:
+//! 1. сканы строк ищут окно 1:1:1:1:1:1:1 — кольца вокруг центрального модуля;
+//!    столбец через найденный центр подтверждает его;
+//! 2. перебор наклона, масштаба и сдвига подгоняет сетку модулей под кольца
+//!    яблочка (аффинно: перспектива сильнее лёгкой не поддерживается);
+//! 3. кольца за яблочком отличают компактный символ от полного: у полного
+//!    кольцо 5 светлое, а 6 — тёмное (полные символы пока не читаются);
+//! 4. ориентационные метки в углах кольца 5 дают поворот, mode message вдоль его
+//!    сторон (RS над GF(16)) — число слоёв и кодвордов данных;
+//! 5. слои читаются спиралью снаружи внутрь парами модулей, кодворды по 6 бит
+//!    (1–2 слоя, GF(64)) или 8 бит (3–4 слоя, GF(256)) исправляются RS;
+//! 6. снимается bit stuffing и разбираются режимы Upper/Lower/Mixed/Punct/Digit
+//!    и Binary Shift.
+//!
+//! Зеркальные символы не поддерживаются. [`encode_aztec_compact`] — энкодер
+//! компактного Aztec для тестов и демо.
+
+use crate::binarize::{binarize_row, binarize_row_adaptive, runs};
+use crate::core::cast::{f32_to_usize, usize_to_f32};
+use crate::core::gf::{GaloisField, ReedSolomon};
+#[cfg(not(feature = "std"))]
+use crate::core::math::FloatExt;
+use crate::core::types::{GrayImage, LumaImage, PointF, QuadF, SynthError};
+use crate::qr::bytes::{decode_byte_payload, Charset};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Поле mode message: GF(16), x^4 + x + 1.
+const GF_PARAM: GaloisField = GaloisField::new(4, 0x13);
+/// Кодворды 1–2 слоёв: GF(64), x^6 + x + 1.
+const GF_DATA6: GaloisField = GaloisField::new(6, 0x43);
+/// Кодворды 3–8 слоёв: GF(256), x^8 + x^5 + x^3 + x^2 + 1.
+const GF_DATA8: GaloisField = GaloisField::new(8, 0x12D);
+
+/// Максимум слоёв данных компактного символа (27×27 модулей).
+pub const MAX_COMPACT_LAYERS: u8 = 4;
+
+/// Радиус яблочка компактного символа в модулях (по Чебышёву): тёмные кольца 0, 2, 4.
+const BULLSEYE_RADIUS: i32 = 4;
+/// Кольцо с ориентационными метками и mode message.
+const MODE_RING: i32 = BULLSEYE_RADIUS + 1;
+/// Сколько модулей яблочка (из 81) должно совпасть, чтобы сетка считалась найденной.
+const MIN_BULLSEYE_MATCHES: usize = 77;
+/// Сколько строк кадра сканировать при поиске яблочка (чаще — через строку и реже).
+const MAX_SCAN_LINES: usize = 512;
+/// Доля EC-битов от данных при кодировании, плюс фиксированный запас (как у `ZXing`).
+const MIN_EC_PERCENT: usize = 33;
+const EC_EXTRA_BITS: usize = 11;
+
+/// Раскладка символа по кольцам за яблочком.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AztecLayout {
+    /// Яблочко 9×9 и mode message из 28 бит; 1–4 слоя.
+    Compact,
+    /// Яблочко 13×13, mode message из 40 бит, опорная решётка; 1–32 слоя.
+    Full,
+}
+
+/// Прочитанный компактный Aztec-символ.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AztecCode {
+    pub text: String,
+    /// Байты после разбора режимов (до перевода в текст).
+    pub bytes: Vec<u8>,
+    /// Кодировка, в которой прочитан текст (см. [`decode_byte_payload`]).
+    pub charset: Charset,
+    /// Число слоёв данных (1..=[`MAX_COMPACT_LAYERS`]).
+    pub layers: u8,
+    /// Кодвордов данных (остальные — коррекция).
+    pub data_words: usize,
+    /// Исправлено RS кодвордов данных и коррекции.
+    pub corrected_words: usize,
+    /// Размер модуля в пикселях.
+    pub module_px: f32,
+    /// Внешние углы символа: p0 — верхний левый (угол с тремя тёмными метками),
+    /// дальше по часовой.
+    pub corners: QuadF,
+}
+
+/// Найти и прочитать первый компактный Aztec-символ на изображении.
+/// Кадр уже самого мелкого символа (15 модулей по пикселю) не просматривается.
+#[must_use]
+pub fn decode(img: &GrayImage<'_>) -> Option<AztecCode> {
+    if img.width.min(img.height) < 11 + 4 {
+        diag!(
+            "[aztec] image {}x{} smaller than a 1-layer symbol -> skip",
+            img.width,
+            img.height
+        );
+        return None;
+    }
+    let candidates = find_bullseyes(img);
+    diag!("[aztec] bullseye candidates={}", candidates.len());
+    candidates
+        .into_iter()
+        .find_map(|(center, module)| decode_at(img, center, module))
+}
+
+/// Число слоёв компактного символа по его стороне в модулях (`11 + 4·layers`).
+#[must_use]
+pub fn compact_layers_for_size(size: usize) -> Option<u8> {
+    let layers = size.checked_sub(11).filter(|d| d % 4 == 0)? / 4;
+    u8::try_from(layers)
+        .ok()
+        .filter(|l| (1..=MAX_COMPACT_LAYERS).contains(l))
+}
+
+// ---------------------------------------------------------------------------
+// Поиск яблочка
+// ---------------------------------------------------------------------------
+
+/// Центры яблочек с оценкой размера модуля, сильнейшие (по числу подтверждений) первыми.
+fn find_bullseyes(img: &GrayImage<'_>) -> Vec<(PointF, f32)> {
+    if img.width == 0 || img.height == 0 {
+        return Vec::new();
+    }
+    let step = img.height.div_ceil(MAX_SCAN_LINES).max(1);
+    let mut col = Vec::new();
+    let mut cands: Vec<(PointF, f32)> = Vec::new();
+    for y in (0..img.height).step_by(step) {
+        for (x, m_h) in line_bullseyes(img.row(y)) {
+            // Подтверждение столбцом через центр: тот же узор вокруг той же y.
+            let cx = f32_to_usize(x).min(img.width - 1);
+            let column = img.col(cx, &mut col);
+            let yf = usize_to_f32(y);
+            let hit = line_bullseyes(column)
+                .into_iter()
+                .filter(|&(yc, m_v)| {
+                    (yc - yf).abs() <= m_h.max(m_v) && (m_v / m_h - 1.0).abs() < 0.5
+                })
+                .min_by(|a, b| (a.0 - yf).abs().total_cmp(&(b.0 - yf).abs()));
+            if let Some((yc, m_v)) = hit {
+                cands.push((PointF { x, y: yc }, 0.5 * (m_h + m_v)));
+            }
+        }
+    }
+
+    // Кластеры: кандидаты ближе полумодуля — один центр (скользящее среднее). Шире
+    // нельзя: данные у яблочка сами дают похожие окна со сдвигом на пару модулей,
+    // и усреднение с ними уводит центр.
+    let mut clusters: Vec<(PointF, f32, usize)> = Vec::new();
+    for (p, m) in cands {
+        if let Some((c, cm, n)) = clusters
+            .iter_mut()
+            .find(|(c, cm, _)| p.dist2(*c) <= 0.25 * cm * cm)
+        {
+            let k = usize_to_f32(*n);
+            c.x = (c.x * k + p.x) / (k + 1.0);
+            c.y = (c.y * k + p.y) / (k + 1.0);
+            *cm = (*cm * k + m) / (k + 1.0);
+            *n += 1;
+        } else {
+            clusters.push((p, m, 1));
+        }
+    }
+    clusters.sort_by_key(|&(_, _, n)| core::cmp::Reverse(n));
+    clusters.into_iter().map(|(c, m, _)| (c, m)).collect()
+}
+
+/// Центры окон «свет-тьма-свет-ТЬМА-свет-тьма-свет» равной ширины, окружённых тёмным
+/// (кольца 3..0..3 яблочка и тёмное кольцо 4 по краям), с шириной модуля.
+///
+/// Узор держится на любой прямой через центр — кольца квадратные, но при повороте
+/// все они растягиваются одинаково. Бинаризация, как у finder'а QR, двумя порогами.
+/// Центр — в координатах центров пикселей (пиксель `i` — точка `i`).
+fn line_bullseyes(line: &[u8]) -> Vec<(f32, f32)> {
+    let mut out: Vec<(f32, f32)> = Vec::new();
+    for rb in [binarize_row_adaptive(line), binarize_row(line)] {
+        let rl = runs(&rb);
+        if rl.len() < 9 {
+            continue;
+        }
+        let starts_black = rb.first().copied().unwrap_or(false);
+        let is_dark = |idx: usize| (idx % 2 == 0) == starts_black;
+        let mut start = 0usize;
+        for r0 in 0..rl.len() {
+            if r0 >= 1 && r0 + 8 <= rl.len() && !is_dark(r0) {
+                let win = &rl[r0..r0 + 7];
+                let m = usize_to_f32(win.iter().sum::<usize>()) / 7.0;
+                let even = win
+                    .iter()
+                    .all(|&w| (usize_to_f32(w) - m).abs() <= 0.35 * m + 0.5);
+                let flanked =
+                    usize_to_f32(rl[r0 - 1]) >= 0.5 * m && usize_to_f32(rl[r0 + 7]) >= 0.5 * m;
+                if even && flanked {
+                    let center = usize_to_f32(start + win[0] + win[1] + win[2])
+                        + usize_to_f32(win[3]) * 0.5
+                        - 0.5;
+                    if out.iter().all(|&(c, _)| (c - center).abs() > 1.0) {
+                        out.push((center, m));
+                    }
+                }
+            }
+            start += rl[r0];
+        }
+    }
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Сетка модулей
+// ---------------------------------------------------------------------------
+
+/// Аффинная сетка: модуль `(a, b)` относительно центрального — в `center + a·u + b·v`.
+#[derive(Clone, Copy, Debug)]
+struct Grid {
+    center: PointF,
+    u: PointF,
+    v: PointF,
+    /// Порог тёмного по яркости.
+    threshold: f32,
+}
+
+impl Grid {
+    /// Сетка с вектором модуля `m·(1, t)/√(1+t²)` — наклон без тригонометрии (`|t| ≤ 1`
+    /// покрывает ±45°, остальное даёт симметрия квадрата).
+    fn new(center: PointF, m: f32, t: f32, threshold: f32) -> Self {
+        let k = m / (1.0 + t * t).sqrt();
+        let u = PointF { x: k, y: k * t };
+        Self {
+            center,
+            u,
+            v: PointF { x: -u.y, y: u.x },
+            threshold,
+        }
+    }
+
+    #[inline]
+    fn at(&self, a: f32, b: f32) -> PointF {
+        PointF {
+            x: self.center.x + a * self.u.x + b * self.v.x,
+            y: self.center.y + a * self.u.y + b * self.v.y,
+        }
+    }
+
+    /// Та же сетка, повёрнутая на 90°: новый модуль `(a, b)` — старый `(−b, a)`.
+    fn rotated(self) -> Self {
+        Self {
+            u: self.v,
+            v: PointF {
+                x: -self.u.x,
+                y: -self.u.y,
+            },
+            ..self
+        }
+    }
+
+    /// Средняя яркость модуля: центр и четыре точки на ±`spread` модуля.
+    fn luma_spread(&self, img: &GrayImage<'_>, a: i32, b: i32, spread: f32) -> f32 {
+        let (a, b) = (a as f32, b as f32);
+        [
+            (0.0, 0.0),
+            (-spread, 0.0),
+            (spread, 0.0),
+            (0.0, -spread),
+            (0.0, spread),
+        ]
+        .iter()
+        .map(|&(da, db)| sample_bilinear(img, self.at(a + da, b + db)))
+        .sum::<f32>()
+            / 5.0
+    }
+
+    /// Яркость модуля для чтения: точки на ±0.25 модуля.
+    #[inline]
+    fn luma(&self, img: &GrayImage<'_>, a: i32, b: i32) -> f32 {
+        self.luma_spread(img, a, b, 0.25)
+    }
+
+    #[inline]
+    fn dark(&self, img: &GrayImage<'_>, a: i32, b: i32) -> bool {
+        self.luma(img, a, b) < self.threshold
+    }
+
+    /// Совпадение с яблочком: (контраст со знаком ожидаемого цвета, число совпавших модулей).
+    /// Точки берутся у краёв модуля (±0.4): на резком снимке сдвиг сетки больше чем
+    /// на десятую модуля выводит их в соседние кольца, и максимум контраста узкий.
+    fn bullseye_fit(&self, img: &GrayImage<'_>) -> (f32, usize) {
+        let mut score = 0.0f32;
+        let mut matches = 0usize;
+        for b in -BULLSEYE_RADIUS..=BULLSEYE_RADIUS {
+            for a in -BULLSEYE_RADIUS..=BULLSEYE_RADIUS {
+                let want_dark = a.abs().max(b.abs()) % 2 == 0;
+                let contrast = self.threshold - self.luma_spread(img, a, b, 0.4);
+                score += if want_dark { contrast } else { -contrast };
+                if (contrast > 0.0) == want_dark {
+                    matches += 1;
+                }
+            }
+        }
+        (score, matches)
+    }
+
+    /// Доля тёмных модулей кольца радиуса `r`.
+    fn ring_dark_share(&self, img: &GrayImage<'_>, r: i32) -> f32 {
+        let mut dark = 0usize;
+        for i in -r..r {
+            // четыре стороны по 2r модулей, углы — по разу
+            dark += usize::from(self.dark(img, i, -r))
+                + usize::from(self.dark(img, r, i))
+                + usize::from(self.dark(img, -i, r))
+                + usize::from(self.dark(img, -r, -i));
+        }
+        usize_to_f32(dark) / usize_to_f32(8 * usize::try_from(r).unwrap_or(1))
+    }
+}
+
+#[inline]
+fn sample_bilinear(img: &GrayImage<'_>, p: PointF) -> f32 {
+    let w = usize_to_f32(img.width) - 1.0;
+    let h = usize_to_f32(img.height) - 1.0;
+    let x = p.x.clamp(0.0, w.max(0.0));
+    let y = p.y.clamp(0.0, h.max(0.0));
+    let (x0, y0) = (f32_to_usize(x.floor()), f32_to_usize(y.floor()));
+    let (x1, y1) = ((x0 + 1).min(img.width - 1), (y0 + 1).min(img.height - 1));
+    let (dx, dy) = (x - usize_to_f32(x0), y - usize_to_f32(y0));
+    let px = |xx: usize, yy: usize| f32::from(img.data[yy * img.width + xx]);
+    let top = px(x0, y0) * (1.0 - dx) + px(x1, y0) * dx;
+    let bot = px(x0, y1) * (1.0 - dx) + px(x1, y1) * dx;
+    top * (1.0 - dy) + bot * dy
+}
+
+/// Средняя яркость квадрата ±`r` px вокруг `c` — порог для яблочка (тёмного и светлого
+/// в нём поровну).
+fn local_threshold(img: &GrayImage<'_>, c: PointF, r: f32) -> f32 {
+    let (x0, y0) = (f32_to_usize(c.x - r), f32_to_usize(c.y - r));
+    let x1 = f32_to_usize(c.x + r).min(img.width - 1);
+    let y1 = f32_to_usize(c.y + r).min(img.height - 1);
+    if x1 < x0 || y1 < y0 {
+        return 128.0;
+    }
+    let mut sum = 0usize;
+    for y in y0..=y1 {
+        sum += img.row(y)[x0..=x1]
+            .iter()
+            .map(|&v| usize::from(v))
+            .sum::<usize>();
+    }
+    usize_to_f32(sum) / usize_to_f32((x1 - x0 + 1) * (y1 - y0 + 1))
+}
+
+/// Подогнать сетку под яблочко вокруг `center`: грубый, затем точный перебор
+/// наклона `t`, масштаба и сдвига центра; лучшая — по знаковому контрасту.
+fn fit_grid(img: &GrayImage<'_>, center: PointF, module: f32) -> Option<Grid> {
+    let threshold = local_threshold(img, center, (BULLSEYE_RADIUS as f32 + 0.5) * module);
+
+    // Вдоль строки через центр кольца шире модуля в √(1+t²) раз, `search_grid`
+    // на это делит: масштаб 1.0 — ширина колец по строке.
+    let coarse = search_grid(
+        img,
+        center,
+        module,
+        threshold,
+        &steps(-1.0, 1.0, 41),
+        &steps(0.92, 1.08, 5),
+        &steps(-0.25, 0.25, 3),
+    );
+    let t0 = coarse.u.y / coarse.u.x;
+    let s0 = (coarse.u.x * coarse.u.x + coarse.u.y * coarse.u.y).sqrt() * (1.0 + t0 * t0).sqrt()
+        / module;
+    let grid = search_grid(
+        img,
+        coarse.center,
+        module,
+        threshold,
+        &steps(t0 - 0.05, t0 + 0.05, 9),
+        &steps(s0 - 0.03, s0 + 0.03, 7),
+        &steps(-0.15, 0.15, 5),
+    );
+
+    let (_, matches) = grid.bullseye_fit(img);
+    diag!(
+        "[aztec] grid at ({:.1},{:.1}): module=({:.2},{:.2}) matches={}/81",
+        grid.center.x,
+        grid.center.y,
+        grid.u.x,
+        grid.u.y,
+        matches
+    );
+    (matches >= MIN_BULLSEYE_MATCHES).then_some(grid)
+}
+
+/// `n` равных шагов от `from` до `to` включительно.
+fn steps(from: f32, to: f32, n: usize) -> Vec<f32> {
+    (0..n)
+        .map(|i| from + (to - from) * usize_to_f32(i) / usize_to_f32((n - 1).max(1)))
+        .collect()
+}
+
+/// Лучшая по [`Grid::bullseye_fit`] сетка среди наклонов `ts`, масштабов `scales`
+/// и сдвигов центра на `offs` модуля по каждой оси.
+fn search_grid(
+    img: &GrayImage<'_>,
+    center: PointF,
+    module: f32,
+    threshold: f32,
+    ts: &[f32],
+    scales: &[f32],
+    offs: &[f32],
+) -> Grid {
+    let mut best = (f32::MIN, Grid::new(center, module, 0.0, threshold));
+    for &t in ts {
+        for &s in scales {
+            for &oy in offs {
+                for &ox in offs {
+                    let c = PointF {
+                        x: center.x + ox * module,
+                        y: center.y + oy * module,
+                    };
+                    let g = Grid::new(c, module * s / (1.0 + t * t).sqrt(), t, threshold);
+                    let (score, _) = g.bullseye_fit(img);
+                    if score > best.0 {
+                        best = (score, g);
+                    }
+                }
+            }
+        }
+    }
+    best.1
+}
+
+// ---------------------------------------------------------------------------
+// Раскладка, ориентация, mode message
+// ---------------------------------------------------------------------------
+
+/// Компактный или полный: у полного за яблочком 9×9 идут светлое кольцо 5
+/// и тёмное кольцо 6, у компактного кольцо 5 несёт метки и mode message.
+fn classify_layout(img: &GrayImage<'_>, grid: &Grid) -> AztecLayout {
+    let r5 = grid.ring_dark_share(img, MODE_RING);
+    let r6 = grid.ring_dark_share(img, MODE_RING + 1);
+    diag!("[aztec] rings: r5 dark={:.2} r6 dark={:.2}", r5, r6);
+    if r5 <= 0.1 && r6 >= 0.9 {
+        AztecLayout::Full
+    } else {
+        AztecLayout::Compact
+    }
+}
+
+/// Ориентационные метки кольца 5 (модуль `(a, b)`, тёмный?): три тёмных в верхнем
+/// левом углу, два в верхнем правом, один у нижнего правого, ни одного в нижнем левом.
+const ORIENTATION_MARKS: [(i32, i32, bool); 12] = [
+    (-5, -5, true),
+    (-4, -5, true),
+    (-5, -4, true),
+    (5, -5, true),
+    (5, -4, true),
+    (4, -5, false),
+    (5, 4, true),
+    (5, 5, false),
+    (4, 5, false),
+    (-5, 5, false),
+    (-4, 5, false),
+    (-5, 4, false),
+];
+
+/// Повернуть сетку так, чтобы метки стали на место (до двух несовпадений из 12).
+fn orient(img: &GrayImage<'_>, grid: Grid) -> Option<Grid> {
+    let mut g = grid;
+    let mut best: Option<(usize, Grid)> = None;
+    for _ in 0..4 {
+        let errors = ORIENTATION_MARKS
+            .iter()
+            .filter(|&&(a, b, dark)| g.dark(img, a, b) != dark)
+            .count();
+        if best.map_or(true, |(e, _)| errors < e) {
+            best = Some((errors, g));
+        }
+        g = g.rotated();
+    }
+    let (errors, g) = best?;
+    diag!("[aztec] orientation marks: {} errors", errors);
+    (errors <= 2).then_some(g)
+}
+
+/// 28 бит mode message по сторонам кольца 5 (по часовой от верхней, по 7 на сторону).
+fn read_mode_bits(img: &GrayImage<'_>, grid: &Grid) -> [bool; 28] {
+    let mut bits = [false; 28];
+    for (i, off) in (-3..=3).enumerate() {
+        bits[i] = grid.dark(img, off, -MODE_RING);
+        bits[i + 7] = grid.dark(img, MODE_RING, off);
+        bits[20 - i] = grid.dark(img, off, MODE_RING);
+        bits[27 - i] = grid.dark(img, -MODE_RING, off);
+    }
+    bits
+}
+
+/// Mode message → (слоёв, кодвордов данных). 7 слов GF(16): 2 данных, 5 коррекции;
+/// в данных 2 бита `layers − 1` и 6 бит `data_words − 1`.
+fn decode_mode_message(bits: &[bool; 28]) -> Option<(u8, usize)> {
+    let mut words: Vec<u16> = bits.chunks(4).map(read_word).collect();
+    ReedSolomon::new(GF_PARAM).correct(&mut words, 5).ok()?;
+    let data = (words[0] << 4) | words[1];
+    let layers = u8::try_from(data >> 6).ok()? + 1;
+    Some((layers, usize::from(data & 0x3F) + 1))
+}
+
+/// MSB-first биты → слово.
+fn read_word(bits: &[bool]) -> u16 {
+    bits.iter().fold(0, |w, &b| (w << 1) | u16::from(b))
+}
+
+// ---------------------------------------------------------------------------
+// Данные
+// ---------------------------------------------------------------------------
+
+/// Бит данных в `layers` слоях компактного символа: `(88 + 16·L)·L`.
+fn total_bits(layers: u8) -> usize {
+    let l = usize::from(layers);
+    (88 + 16 * l) * l
+}
+
+/// Размер кодворда: 6 бит для 1–2 слоёв, 8 — для 3–8.
+fn word_size(layers: u8) -> usize {
+    if layers <= 2 {
+        6
+    } else {
+        8
+    }
+}
+
+fn data_field(layers: u8) -> GaloisField {
+    if layers <= 2 {
+        GF_DATA6
+    } else {
+        GF_DATA8
+    }
+}
+
+/// Обход модулей данных: для каждого бита потока — `(x, y)` в матрице `size×size`.
+/// Слои снаружи внутрь; в слое — левая полоса сверху вниз, нижняя слева направо,
+/// правая снизу вверх, верхняя справа налево; в каждой позиции пара модулей
+/// поперёк полосы, внешний первым.
+fn data_module_order(layers: u8) -> Vec<(usize, usize)> {
+    let size = 11 + 4 * usize::from(layers);
+    let mut quarters: Vec<Vec<(usize, usize)>> = Vec::new();
+    let mut order = Vec::with_capacity(total_bits(layers));
+    for i in 0..usize::from(layers) {
+        let row = (usize::from(layers) - i) * 4 + 9;
+        let (low, high) = (2 * i, size - 1 - 2 * i);
+        quarters.clear();
+        quarters.resize(4, Vec::with_capacity(2 * row));
+        for j in 0..row {
+            for k in 0..2 {
+                quarters[0].push((low + k, low + j));
+                quarters[1].push((low + j, high - k));
+                quarters[2].push((high - k, high - j));
+                quarters[3].push((high - j, low + k));
+            }
+        }
+        for q in &quarters {
+            order.extend_from_slice(q);
+        }
+    }
+    order
+}
+
+/// Кодворды из потока бит: первые `len % word` бит — выравнивание, дальше слова MSB-first.
+fn bits_to_words(bits: &[bool], word: usize) -> Vec<u16> {
+    bits[bits.len() % word..]
+        .chunks(word)
+        .map(read_word)
+        .collect()
+}
+
+/// Снять bit stuffing: слово `0…01`/`1…10` несёт `word − 1` одинаковых бит,
+/// слова из одних нулей или единиц в данных недопустимы.
+fn unstuff(words: &[u16], word: usize) -> Option<Vec<bool>> {
+    let mask = (1u16 << word) - 1;
+    let mut out = Vec::with_capacity(words.len() * word);
+    for &w in words {
+        if w == 0 || w == mask {
+            return None;
+        }
+        if w == 1 || w == mask - 1 {
+            out.extend(core::iter::repeat(w > 1).take(word - 1));
+        } else {
+            out.extend((0..word).rev().map(|b| (w >> b) & 1 != 0));
+        }
+    }
+    Some(out)
+}
+
+/// Режим таблицы символов.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    Upper,
+    Lower,
+    Mixed,
+    Punct,
+    Digit,
+    Binary,
+}
+
+/// Значение кода в таблице режима.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Code {
+    Chars(&'static [u8]),
+    Latch(Mode),
+    Shift(Mode),
+    /// FLG(n): FNC1 или ECI.
+    Flg,
+}
+
+/// Mixed 2..=27: управляющие, `@\^_` `` ` `` `|~` и DEL.
+const MIXED_CHARS: &[u8; 26] =
+    b"\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0A\x0B\x0C\x0D\x1B\x1C\x1D\x1E\x1F@\\^_`|~\x7F";
+/// Punct 6..=30.
+const PUNCT_CHARS: &[u8; 25] = b"!\"#$%&'()*+,-./:;<=>?[]{}";
+const UPPER_CHARS: &[u8; 26] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const LOWER_CHARS: &[u8; 26] = b"abcdefghijklmnopqrstuvwxyz";
+const DIGIT_CHARS: &[u8; 12] = b"0123456789,.";
+
+fn lookup(mode: Mode, code: u16) -> Code {
+    let c = usize::from(code);
+    let one = |table: &'static [u8], i: usize| Code::Chars(&table[i..=i]);
+    match (mode, code) {
+        (Mode::Punct, 0) => Code::Flg,
+        (Mode::Punct, 1) => Code::Chars(b"\r"),
+        (Mode::Punct, 2) => Code::Chars(b"\r\n"),
+        (Mode::Punct, 3) => Code::Chars(b". "),
+        (Mode::Punct, 4) => Code::Chars(b", "),
+        (Mode::Punct, 5) => Code::Chars(b": "),
+        (Mode::Punct, 31) => Code::Latch(Mode::Upper),
+        (Mode::Punct, _) => one(PUNCT_CHARS, c - 6),
+        (_, 0) => Code::Shift(Mode::Punct),
+        (_, 1) => Code::Chars(b" "),
+        (Mode::Digit, 14) => Code::Latch(Mode::Upper),
+        (Mode::Digit, 15) => Code::Shift(Mode::Upper),
+        (Mode::Digit, _) => one(DIGIT_CHARS, c - 2),
+        (_, 31) => Code::Shift(Mode::Binary),
+        (Mode::Upper, 28) => Code::Latch(Mode::Lower),
+        (Mode::Lower, 28) => Code::Shift(Mode::Upper),
+        (Mode::Mixed, 28) => Code::Latch(Mode::Lower),
+        (Mode::Mixed, 29) => Code::Latch(Mode::Upper),
+        (Mode::Mixed, 30) => Code::Latch(Mode::Punct),
+        (_, 29) => Code::Latch(Mode::Mixed),
+        (_, 30) => Code::Latch(Mode::Digit),
+        (Mode::Upper, _) => one(UPPER_CHARS, c - 2),
+        (Mode::Lower, _) => one(LOWER_CHARS, c - 2),
+        (_, _) => one(MIXED_CHARS, c - 2),
+    }
+}
+
+/// Разбор потока бит по режимам. Shift действует на один символ (или один блок
+/// Binary Shift) и возвращает в режим, из которого был вызван; FLG(0) — FNC1
+/// (отдаётся как GS, 0x1D), ECI пропускается.
+fn decode_high_level(bits: &[bool]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut latch = Mode::Upper;
+    let mut shift = Mode::Upper;
+    let mut pos = 0usize;
+    let take = |n: usize, pos: &mut usize| -> Option<u16> {
+        let w = bits.get(*pos..*pos + n).map(read_word);
+        *pos += n;
+        w
+    };
+    loop {
+        if shift == Mode::Binary {
+            let Some(mut len) = take(5, &mut pos).map(usize::from) else {
+                break;
+            };
+            if len == 0 {
+                let Some(long) = take(11, &mut pos) else {
+                    break;
+                };
+                len = usize::from(long) + 31;
+            }
+            for _ in 0..len {
+                let Some(b) = take(8, &mut pos) else {
+                    return out;
+                };
+                out.push(u8::try_from(b).unwrap_or(0));
+            }
+            shift = latch;
+            continue;
+        }
+        let size = if shift == Mode::Digit { 4 } else { 5 };
+        let Some(code) = take(size, &mut pos) else {
+            break;
+        };
+        match lookup(shift, code) {
+            Code::Chars(s) => {
+                out.extend_from_slice(s);
+                shift = latch;
+            }
+            Code::Latch(m) => {
+                latch = m;
+                shift = m;
+            }
+            Code::Shift(m) => {
+                // Shift из shift'а возвращает в режим, откуда вызван первый (ISO/IEC 24778).
+                latch = shift;
+                shift = m;
+            }
+            Code::Flg => {
+                let Some(n) = take(3, &mut pos) else { break };
+                match n {
+                    0 => out.push(0x1D),
+                    7 => break,
+                    _ => pos += 4 * usize::from(n),
+                }
+                shift = latch;
+            }
+        }
+    }
+    out
+}
+
+/// Прочитать символ вокруг найденного центра яблочка.
+fn decode_at(img: &GrayImage<'_>, center: PointF, module: f32) -> Option<AztecCode> {
+    let grid = fit_grid(img, center, module)?;
+    if classify_layout(img, &grid) == AztecLayout::Full {
+        diag!(
+            "[aztec] full-range symbol at ({:.1},{:.1}) -> not supported yet",
+            center.x,
+            center.y
+        );
+        return None;
+    }
+    let grid = orient(img, grid)?;
+    let Some((layers, data_words)) = decode_mode_message(&read_mode_bits(img, &grid)) else {
+        diag!("[aztec] mode message uncorrectable");
+        return None;
+    };
+    diag!(
+        "[aztec] compact: layers={} data_words={}",
+        layers,
+        data_words
+    );
+
+    let size = 11 + 4 * usize::from(layers);
+    let c = i32::try_from(size / 2).unwrap_or(0);
+    let rel = |v: usize| i32::try_from(v).unwrap_or(i32::MAX) - c;
+    let raw: Vec<bool> = data_module_order(layers)
+        .into_iter()
+        .map(|(x, y)| grid.dark(img, rel(x), rel(y)))
+        .collect();
+
+    let word = word_size(layers);
+    let mut words = bits_to_words(&raw, word);
+    if words.len() <= data_words {
+        return None;
+    }
+    let ec_words = words.len() - data_words;
+    let Ok(corrected_words) = ReedSolomon::new(data_field(layers)).correct(&mut words, ec_words)
+    else {
+        diag!(
+            "[aztec] RS uncorrectable ({} data + {} ec words)",
+            data_words,
+            ec_words
+        );
+        return None;
+    };
+    let bits = unstuff(&words[..data_words], word)?;
+    let bytes = decode_high_level(&bits);
+    let (text, charset) = decode_byte_payload(&bytes);
+    diag!(
+        "[aztec] OK: text=\"{}\" corrected_words={}",
+        text,
+        corrected_words
+    );
+
+    let edge = usize_to_f32(size / 2) + 0.5;
+    let corners = QuadF {
+        p0: grid.at(-edge, -edge),
+        p1: grid.at(edge, -edge),
+        p2: grid.at(edge, edge),
+        p3: grid.at(-edge, edge),
+    };
+    Some(AztecCode {
+        text,
+        bytes,
+        charset,
+        layers,
+        data_words,
+        corrected_words,
+        module_px: (grid.u.x * grid.u.x + grid.u.y * grid.u.y).sqrt(),
+        corners,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Энкодер (компактный Aztec)
+// ---------------------------------------------------------------------------
+
+/// Закодировать `text` в компактный Aztec (наименьшее подходящее число слоёв,
+/// коррекция — не меньше 33% данных) и отрисовать: `unit` px на модуль,
+/// `quiet_zone` модулей белого поля с каждой стороны (стандарт его не требует).
+///
+/// Буквы, цифры и пробел идут режимами Upper/Lower/Digit, остальное — Binary Shift.
+///
+/// # Errors
+///
+/// [`SynthError`] для пустого текста, переполнения и нулевого `unit`.
+pub fn encode_aztec_compact(
+    text: &str,
+    quiet_zone: usize,
+    unit: usize,
+) -> Result<LumaImage, SynthError> {
+    if unit == 0 {
+        return Err(SynthError::ZeroUnit);
+    }
+    let (size, grid) = build_matrix(text.as_bytes())?;
+    Ok(render_matrix(&grid, size, quiet_zone, unit))
+}
+
+/// Матрица `size×size` (`y * size + x`, true = тёмный).
+fn build_matrix(data: &[u8]) -> Result<(usize, Vec<bool>), SynthError> {
+    if data.is_empty() {
+        return Err(SynthError::EmptyPayload);
+    }
+    let bits = encode_high_level(data);
+    let ec_bits = bits.len() * MIN_EC_PERCENT / 100 + EC_EXTRA_BITS;
+    for layers in 1..=MAX_COMPACT_LAYERS {
+        let word = word_size(layers);
+        let total = total_bits(layers);
+        let stuffed = stuff_bits(&bits, word);
+        // число кодвордов данных в mode message — 6 бит
+        if stuffed.len() > 64 * word || stuffed.len() + ec_bits > total - total % word {
+            continue;
+        }
+        let data_words = bits_to_words(&stuffed, word);
+        let mut words = data_words.clone();
+        words.extend(
+            ReedSolomon::new(data_field(layers))
+                .encode(&data_words, total / word - data_words.len()),
+        );
+
+        let mut message = vec![false; total % word];
+        for &w in &words {
+            push_bits(&mut message, w, word);
+        }
+
+        let size = 11 + 4 * usize::from(layers);
+        let mut grid = vec![false; size * size];
+        for (&bit, (x, y)) in message.iter().zip(data_module_order(layers)) {
+            grid[y * size + x] = bit;
+        }
+        draw_finder(&mut grid, size, layers, data_words.len());
+        return Ok((size, grid));
+    }
+    Err(SynthError::AztecOverflow { len: data.len() })
+}
+
+/// Яблочко, ориентационные метки и mode message.
+fn draw_finder(grid: &mut [bool], size: usize, layers: u8, data_words: usize) {
+    let c = i32::try_from(size / 2).unwrap_or(0);
+    let at = |d: i32| usize::try_from(c + d).unwrap_or(0);
+    let mut set = |a: i32, b: i32, dark: bool| grid[at(b) * size + at(a)] = dark;
+    for b in -BULLSEYE_RADIUS..=BULLSEYE_RADIUS {
+        for a in -BULLSEYE_RADIUS..=BULLSEYE_RADIUS {
+            set(a, b, a.abs().max(b.abs()) % 2 == 0);
+        }
+    }
+    for &(a, b, dark) in &ORIENTATION_MARKS {
+        set(a, b, dark);
+    }
+
+    let info = (u16::from(layers - 1) << 6) | u16::try_from(data_words - 1).unwrap_or(0);
+    let mut words = vec![info >> 4, info & 0xF];
+    words.extend(ReedSolomon::new(GF_PARAM).encode(&words, 5));
+    let mut bits = Vec::with_capacity(28);
+    for &w in &words {
+        push_bits(&mut bits, w, 4);
+    }
+    for (i, off) in (-3..=3).enumerate() {
+        set(off, -MODE_RING, bits[i]);
+        set(MODE_RING, off, bits[i + 7]);
+        set(off, MODE_RING, bits[20 - i]);
+        set(-MODE_RING, off, bits[27 - i]);
+    }
+}
+
+fn push_bits(out: &mut Vec<bool>, value: u16, n: usize) {
+    out.extend((0..n).rev().map(|b| (value >> b) & 1 != 0));
+}
+
+/// Bit stuffing: слово, чьи старшие `word − 1` бит одинаковы, обрывается на них
+/// с дополняющим младшим битом, а последний бит уходит в следующее слово.
+/// Хвост дополняется единицами.
+fn stuff_bits(bits: &[bool], word: usize) -> Vec<bool> {
+    let mask = (1u16 << word) - 2;
+    let mut out = Vec::with_capacity(bits.len() + bits.len() / word + word);
+    let mut i = 0usize;
+    while i < bits.len() {
+        let mut w = 0u16;
+        for j in 0..word {
+            if bits.get(i + j).map_or(true, |&b| b) {
+                w |= 1 << (word - 1 - j);
+            }
+        }
+        if w & mask == mask {
+            push_bits(&mut out, w & mask, word);
+            i += word - 1;
+        } else if w & mask == 0 {
+            push_bits(&mut out, w | 1, word);
+            i += word - 1;
+        } else {
+            push_bits(&mut out, w, word);
+            i += word;
+        }
+    }
+    out
+}
+
+/// Режимы энкодера: Upper, Lower и Digit с защёлками между ними, остальное —
+/// блоками Binary Shift (из Digit в него не переходят — сначала U/L).
+fn encode_high_level(data: &[u8]) -> Vec<bool> {
+    /// Самый длинный блок Binary Shift: 11-битная длина плюс 31.
+    const MAX_BINARY_RUN: usize = 2047 + 31;
+
+    let mut out = Vec::new();
+    let mut mode = Mode::Upper;
+    let mut i = 0usize;
+    while i < data.len() {
+        let b = data[i];
+        let target = match b {
+            b' ' => mode,
+            b'A'..=b'Z' => Mode::Upper,
+            b'a'..=b'z' => Mode::Lower,
+            b'0'..=b'9' => Mode::Digit,
+            _ => Mode::Binary,
+        };
+        if target == Mode::Binary {
+            let run = data[i..]
+                .iter()
+                .take_while(|b| !b.is_ascii_alphanumeric() && **b != b' ')
+                .count()
+                .min(MAX_BINARY_RUN);
+            if mode == Mode::Digit {
+                push_bits(&mut out, 14, 4); // U/L
+                mode = Mode::Upper;
+            }
+            push_bits(&mut out, 31, 5); // B/S
+            if run <= 31 {
+                push_bits(&mut out, u16::try_from(run).unwrap_or(0), 5);
+            } else {
+                push_bits(&mut out, 0, 5);
+                push_bits(&mut out, u16::try_from(run - 31).unwrap_or(0), 11);
+            }
+            for &byte in &data[i..i + run] {
+                push_bits(&mut out, u16::from(byte), 8);
+            }
+            i += run;
+            continue;
+        }
+        // Защёлки: Upper→Lower L/L, →Digit D/L; Lower→Upper через D/L + U/L;
+        // Digit→Upper U/L, →Lower U/L + L/L.
+        match (mode, target) {
+            (Mode::Upper | Mode::Lower, Mode::Digit) => push_bits(&mut out, 30, 5),
+            (Mode::Upper, Mode::Lower) => push_bits(&mut out, 28, 5),
+            (Mode::Lower, Mode::Upper) => {
+                push_bits(&mut out, 30, 5);
+                push_bits(&mut out, 14, 4);
+            }
+            (Mode::Digit, Mode::Upper) => push_bits(&mut out, 14, 4),
+            (Mode::Digit, Mode::Lower) => {
+                push_bits(&mut out, 14, 4);
+                push_bits(&mut out, 28, 5);
+            }
+            _ => {}
+        }
+        mode = target;
+        let code = match b {
+            b' ' => 1,
+            b'A'..=b'Z' => u16::from(b - b'A') + 2,
+            b'a'..=b'z' => u16::from(b - b'a') + 2,
+            _ => u16::from(b - b'0') + 2,
+        };
+        push_bits(&mut out, code, if mode == Mode::Digit { 4 } else { 5 });
+        i += 1;
+    }
+    out
+}
+
+/// Матрица → изображение: `unit` px на модуль, `qz` модулей белого поля.
+fn render_matrix(grid: &[bool], size: usize, qz: usize, unit: usize) -> LumaImage {
+    let total = size + 2 * qz;
+    let w = total * unit;
+    let mut data = Vec::with_capacity(w * w);
+    for my in 0..total {
+        let mut line = Vec::with_capacity(w);
+        for mx in 0..total {
+            let dark = (qz..qz + size).contains(&mx)
+                && (qz..qz + size).contains(&my)
+                && grid[(my - qz) * size + mx - qz];
+            line.extend(core::iter::repeat(if dark { 0u8 } else { 255u8 }).take(unit));
+        }
+        for _ in 0..unit {
+            data.extend_from_slice(&line);
+        }
+    }
+    LumaImage {
+        data,
+        width: w,
+        height: w,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_text(img: &LumaImage) -> Option<AztecCode> {
+        decode(&img.as_gray())
+    }
+
+    #[test]
+    fn roundtrip_every_compact_layer_count() {
+        let texts = [
+            ("AZTEC", 1),
+            ("Ticket 42 zone B", 2),
+            ("TRANSIT-2024/06/01 route 17, seat 4C", 3),
+            (
+                "TRANSIT TICKET 0042 ROUTE 17 ZONE B VALID 2024 06 01 SEAT 4C CAR 08",
+                4,
+            ),
+        ];
+        for (text, layers) in texts {
+            let img = encode_aztec_compact(text, 2, 4).unwrap();
+            assert_eq!(
+                compact_layers_for_size(img.width / 4 - 4),
+                Some(layers),
+                "{text}"
+            );
+            let code = decode_text(&img).unwrap_or_else(|| panic!("не прочитан: {text}"));
+            assert_eq!(code.text, text);
+            assert_eq!(code.layers, layers);
+            assert_eq!(code.corrected_words, 0);
+            assert!((code.module_px - 4.0).abs() < 0.2, "{}", code.module_px);
+        }
+    }
+
+    /// Компактный символ из 1 слоя с текстом `Transit 42` (U, L/L, D/L), собранный
+    /// по раскладке ZXing (`aztec.encoder.Encoder`), а не нашим энкодером: ловит
+    /// ошибку, симметрично внесённую в [`build_matrix`] и в декодер.
+    const REFERENCE_TRANSIT_42: [&str; 15] = [
+        "..X....X.X.X..X",
+        "X...XXX...X.XX.",
+        "X.XX....X...X.X",
+        "XXXXXXXXXXXXXX.",
+        "XXXX.......XXXX",
+        "...X.XXXXX.XX.X",
+        "X..X.X...X.XXX.",
+        ".XXX.X.X.X.X.XX",
+        "X.XX.X...X.X...",
+        "...X.XXXXX.X.XX",
+        "X..X.......X.XX",
+        ".X.XXXXXXXXXX..",
+        "XX...XX...X..X.",
+        "XX.XXXXX..XX..X",
+        "X.........XXXX.",
+    ];
+
+    #[test]
+    fn decodes_external_reference_symbol() {
+        let size = REFERENCE_TRANSIT_42.len();
+        let grid: Vec<bool> = REFERENCE_TRANSIT_42
+            .iter()
+            .flat_map(|row| row.bytes().map(|b| b == b'X'))
+            .collect();
+        let img = render_matrix(&grid, size, 3, 4);
+        let code = decode_text(&img).expect("эталонный символ не прочитан");
+        assert_eq!(code.text, "Transit 42");
+        assert_eq!(code.layers, 1);
+        assert_eq!(code.corrected_words, 0);
+    }
+
+    #[test]
+    fn binary_shift_carries_utf8_and_controls() {
+        for text in ["Привет", "a\tb\r\nc", "€5 ~ ok"] {
+            let img = encode_aztec_compact(text, 2, 3).unwrap();
+            let code = decode_text(&img).unwrap();
+            assert_eq!(code.text, text);
+            assert_eq!(code.charset, Charset::Utf8);
+        }
+    }
+
+    #[test]
+    fn mixed_and_punct_tables_decode() {
+        // U: P/S '!' ; M/L '@' ; U/L 'B' ; P/S ". " ; D/L '7' ',' ; U/S 'Z' ; '1'
+        let mut bits = Vec::new();
+        for (v, n) in [
+            (0, 5),
+            (6, 5),
+            (29, 5),
+            (20, 5),
+            (29, 5),
+            (3, 5),
+            (0, 5),
+            (3, 5),
+            (30, 5),
+            (9, 4),
+            (12, 4),
+            (15, 4),
+            (27, 5),
+            (3, 4),
+        ] {
+            push_bits(&mut bits, v, n);
+        }
+        assert_eq!(decode_high_level(&bits), b"!@B. 7,Z1");
+    }
+
+    #[test]
+    fn stuffing_roundtrips_runs_of_equal_bits() {
+        let bits: Vec<bool> = [vec![true; 13], vec![false; 9], vec![true, false, true]].concat();
+        for word in [6, 8] {
+            let stuffed = stuff_bits(&bits, word);
+            assert_eq!(stuffed.len() % word, 0);
+            let words = bits_to_words(&stuffed, word);
+            let back = unstuff(&words, word).unwrap();
+            assert_eq!(&back[..bits.len()], &bits[..]);
+            assert!(back[bits.len()..].iter().all(|&b| b), "хвост — единицы");
+        }
+    }
+
+    #[test]
+    fn damaged_modules_are_corrected() {
+        let text = "CORRECT ME 123";
+        let (size, mut grid) = build_matrix(text.as_bytes()).unwrap();
+        // Портим три модуля в разных кодвордах внешнего слоя.
+        for &(x, y) in &data_module_order(compact_layers_for_size(size).unwrap())[6..30] {
+            if (x + y) % 7 == 0 {
+                grid[y * size + x] = !grid[y * size + x];
+            }
+        }
+        let img = render_matrix(&grid, size, 3, 4);
+        let code = decode_text(&img).unwrap();
+        assert_eq!(code.text, text);
+        assert!(code.corrected_words > 0);
+    }
+
+    #[test]
+    fn quarter_turns_are_oriented_by_marks() {
+        let text = "ORIENT 90";
+        let (size, grid) = build_matrix(text.as_bytes()).unwrap();
+        let mut g = grid;
+        for turn in 0..4 {
+            let img = render_matrix(&g, size, 2, 5);
+            let code = decode_text(&img).unwrap_or_else(|| panic!("поворот {}°", turn * 90));
+            assert_eq!(code.text, text);
+            // (x, y) -> (size-1-y, x): поворот на 90° по часовой
+            let mut next = vec![false; size * size];
+            for y in 0..size {
+                for x in 0..size {
+                    next[x * size + (size - 1 - y)] = g[y * size + x];
+                }
+            }
+            g = next;
+        }
+    }
+
+    #[test]
+    fn full_range_bullseye_is_recognized_and_skipped() {
+        // Яблочко полного символа: тёмные кольца 0, 2, 4, 6 — кольцо 5 светлое.
+        let size = 19usize;
+        let c = (size / 2) as i32;
+        let mut grid = vec![false; size * size];
+        for y in 0..size as i32 {
+            for x in 0..size as i32 {
+                let r = (x - c).abs().max((y - c).abs());
+                grid[(y * size as i32 + x) as usize] = r <= 6 && r % 2 == 0;
+            }
+        }
+        let img = render_matrix(&grid, size, 4, 4);
+        let gray = img.as_gray();
+        let (center, module) = find_bullseyes(&gray)[0];
+        let fitted = fit_grid(&gray, center, module).unwrap();
+        assert_eq!(classify_layout(&gray, &fitted), AztecLayout::Full);
+        assert!(decode(&gray).is_none());
+    }
+
+    #[test]
+    fn encoder_rejects_bad_input() {
+        assert_eq!(
+            encode_aztec_compact("", 2, 3).unwrap_err(),
+            SynthError::EmptyPayload
+        );
+        assert_eq!(
+            encode_aztec_compact("A", 2, 0).unwrap_err(),
+            SynthError::ZeroUnit
+        );
+        let long = "x".repeat(200);
+        assert_eq!(
+            encode_aztec_compact(&long, 2, 3).unwrap_err(),
+            SynthError::AztecOverflow { len: 200 }
+        );
+        assert_eq!(compact_layers_for_size(19), Some(2));
+        assert_eq!(compact_layers_for_size(31), None);
+        assert_eq!(compact_layers_for_size(16), None);
+    }
+}
@@ -0,0 +1,5 @@
+//! 2D-символики помимо QR.
+
+pub mod aztec;
+
+pub use self::aztec::{encode_aztec_compact, AztecCode};
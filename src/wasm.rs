@@ -0,0 +1,69 @@
+// src/wasm.rs
+//
+// Точка входа для браузера (feature `wasm`, через wasm-bindgen).
+// Кадр приходит как байты яркости (например, канал Y из ImageData), результат —
+// массив JS-объектов. Диагностика декодеров в wasm32 не печатается
+// (см. `log::emit`); при необходимости её перехватывают через `log::set_log_hook`.
+
+#![allow(unsafe_code)] // код, который генерирует #[wasm_bindgen]
+
+use crate::api::PipelineBuilder;
+use crate::core::types::{GrayImage, Symbology};
+use js_sys::{Array, Object, Reflect};
+use wasm_bindgen::prelude::*;
+
+/// Имя символогии для JS.
+fn symbology_name(s: Symbology) -> &'static str {
+    match s {
+        Symbology::QR => "QR",
+        Symbology::Code128 => "Code128",
+        Symbology::Code93 => "Code93",
+        Symbology::Ean13 => "EAN13",
+        Symbology::UpcA => "UPCA",
+        Symbology::Msi => "MSI",
+        Symbology::Aztec => "Aztec",
+        Symbology::Custom(name) => name,
+    }
+}
+
+/// Распознать кадр `width×height` (8 бит на пиксель, построчно) пайплайном
+/// по умолчанию. Возвращает массив объектов
+/// `{ symbology, text, confidence, extras: { ключ: значение } }` в порядке
+/// [`crate::api::sort_symbols`].
+///
+/// # Errors
+///
+/// JS-исключение, если `data` короче `width·height` или кадр больше
+/// [`GrayImage::DEFAULT_MAX_PIXELS`].
+#[wasm_bindgen(js_name = decodeLuma)]
+pub fn decode_luma(data: &[u8], width: usize, height: usize) -> Result<JsValue, JsValue> {
+    let Ok(img) = GrayImage::from_slice(data, width, height) else {
+        return Err(JsValue::from_str(
+            "ultracode: буфер меньше width*height или кадр слишком велик",
+        ));
+    };
+    let found = PipelineBuilder::new().build().decode_all_gray(&img);
+
+    let out = Array::new();
+    for s in found {
+        let obj = Object::new();
+        Reflect::set(
+            &obj,
+            &"symbology".into(),
+            &symbology_name(s.symbology).into(),
+        )?;
+        Reflect::set(&obj, &"text".into(), &s.text.as_str().into())?;
+        Reflect::set(
+            &obj,
+            &"confidence".into(),
+            &JsValue::from_f64(f64::from(s.confidence)),
+        )?;
+        let extras = Object::new();
+        for (k, v) in &s.extras.properties {
+            Reflect::set(&extras, &k.as_str().into(), &v.as_str().into())?;
+        }
+        Reflect::set(&obj, &"extras".into(), &extras)?;
+        out.push(&obj);
+    }
+    Ok(out.into())
+}
@@ -5,86 +5,17 @@
 // Тесты с реальными изображениями помечены #[ignore] — снимем, когда положим файлы.
 
 use std::fs;
-use std::io::{self, Read};
+use std::io;
 use ultracode::api::PipelineBuilder;
 use ultracode::prelude::*;
+use ultracode::qr::format::EcLevel;
 
 // Helper function to load a PGM file into a LumaImage for testing.
-// This is a simplified version of the one in src/bin/scan_pgm.rs.
+// Parsing is shared with src/bin/scan_pgm.rs (`ultracode::core::pgm::read_pgm`).
 fn load_pgm_as_luma(path: &str) -> io::Result<LumaImage> {
-    let mut file = fs::File::open(path)?;
-    let mut buf = Vec::new();
-    file.read_to_end(&mut buf)?;
-
-    let mut i = 0usize;
-
-    fn read_token(buf: &[u8], i: &mut usize) -> Option<String> {
-        while *i < buf.len() {
-            let c = buf[*i];
-            if c == b'#' {
-                while *i < buf.len() && buf[*i] != b'\n' {
-                    *i += 1;
-                }
-            } else if c.is_ascii_whitespace() {
-                *i += 1;
-            } else {
-                break;
-            }
-        }
-        if *i >= buf.len() {
-            return None;
-        }
-        let start = *i;
-        while *i < buf.len() && !buf[*i].is_ascii_whitespace() {
-            *i += 1;
-        }
-        Some(String::from_utf8_lossy(&buf[start..*i]).to_string())
-    }
-
-    let magic = read_token(&buf, &mut i)
-        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "PGM: no magic"))?;
-    if magic != "P5" {
-        return Err(io::Error::new(
-            io::ErrorKind::InvalidData,
-            "PGM: must be P5",
-        ));
-    }
-    let width: usize = read_token(&buf, &mut i)
-        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "PGM: no width"))?
-        .parse()
-        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "PGM: bad width"))?;
-    let height: usize = read_token(&buf, &mut i)
-        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "PGM: no height"))?
-        .parse()
-        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "PGM: bad height"))?;
-    let maxval: usize = read_token(&buf, &mut i)
-        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "PGM: no maxval"))?
-        .parse()
-        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "PGM: bad maxval"))?;
-    if maxval != 255 {
-        return Err(io::Error::new(
-            io::ErrorKind::InvalidData,
-            "PGM: must be maxval=255",
-        ));
-    }
-
-    if i < buf.len() && buf[i] == b'\n' {
-        i += 1;
-    }
-
-    let data = buf[i..].to_vec();
-    if data.len() != width * height {
-        return Err(io::Error::new(
-            io::ErrorKind::InvalidData,
-            "PGM: data size mismatch",
-        ));
-    }
-
-    Ok(LumaImage {
-        data,
-        width,
-        height,
-    })
+    let bytes = fs::read(path)?;
+    ultracode::core::pgm::read_pgm(&bytes, GrayImage::DEFAULT_MAX_PIXELS)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
 }
 
 // Function to create a test image if it doesn't exist.
@@ -98,7 +29,7 @@ fn ensure_test_qr_exists() {
     fs::create_dir_all(dir).expect("Could not create tests/assets directory");
 
     // Synthesize the QR code
-    let gray_img = ultracode::qr::encode::synthesize_qr_v1_from_text("HELLO", 3, 4);
+    let gray_img = ultracode::qr::encode::encode_qr_v1(b"HELLO", EcLevel::L, 3, 4, 4).unwrap();
 
     // Write it as a PGM file
     use std::io::Write;
@@ -109,7 +40,31 @@ fn ensure_test_qr_exists() {
         gray_img.width, gray_img.height
     )
     .unwrap();
-    f.write_all(gray_img.data).unwrap();
+    f.write_all(&gray_img.data).unwrap();
+}
+
+// Ряд EAN-13 (13 цифр) или UPC-A (12 цифр) через публичный синтезатор.
+fn ean_row(digits: &str, unit: usize) -> Vec<u8> {
+    use ultracode::one_d::ean13::{synthesize_ean13_row, synthesize_upca_row};
+    let row = if digits.len() == 12 {
+        synthesize_upca_row(digits, 9, unit)
+    } else {
+        synthesize_ean13_row(digits, 9, unit)
+    };
+    row.expect("valid EAN/UPC digits")
+}
+
+// "5901234123458" — EAN-13 с неверной контрольной цифрой: синтезатор такой не
+// рисует, поэтому к целому "5901234123457" пришиваем последний знак из
+// "5901234123488" (правая половина — набор C, соседей он не зависит).
+fn broken_ean_row(unit: usize) -> Vec<u8> {
+    let mut row = ean_row("5901234123457", unit);
+    let donor = ean_row("5901234123488", unit);
+    // тихая зона, старт 3, левая половина 6·7, центр 5, пять знаков справа по 7
+    let start = (9 + 3 + 42 + 5 + 35) * unit;
+    let end = start + 7 * unit;
+    row[start..end].copy_from_slice(&donor[start..end]);
+    row
 }
 
 #[test]
@@ -169,4 +124,2127 @@ fn decode_real_qr_v1_l_from_pgm() {
 #[test]
 fn decode_real_qr_v1_m_from_png() {
     // TODO
-}
\ No newline at end of file
+}
+
+#[test]
+fn decode_iter_matches_decode_all_and_dedups() {
+    // Один и тот же Code128 на всех строках — должен прийти ровно один раз.
+    let row = ultracode::synthesize_row_code128("ITER-128", 'B', 10, 2).unwrap();
+    let height = 32;
+    let img = LumaImage {
+        data: row.repeat(height),
+        width: row.len(),
+        height,
+    };
+
+    let pipe = PipelineBuilder::new().build();
+    let lazy: Vec<DecodedSymbol> = pipe.decode_iter(&img).collect();
+    assert_eq!(lazy, pipe.decode_all(&img));
+    assert_eq!(
+        lazy.iter()
+            .filter(|s| s.symbology == Symbology::Code128)
+            .count(),
+        1
+    );
+
+    // Ранняя остановка: первый элемент — тот же, что и в decode_all.
+    let first = pipe.decode_iter(&img).next().expect("Code128 not found");
+    assert_eq!(first.text, "ITER-128");
+    assert_eq!(Some(first), pipe.decode_first(&img));
+}
+
+#[test]
+fn decode_all_order_is_stable() {
+    // Два Code128 друг над другом: верхняя y важнее текста ("ORDER" > "ALPHA"),
+    // и повторный вызов даёт тот же порядок.
+    let top = ultracode::synthesize_row_code128("ORDER", 'B', 10, 2).unwrap();
+    let bottom = ultracode::synthesize_row_code128("ALPHA", 'B', 10, 2).unwrap();
+    let width = top.len().max(bottom.len()) + 8;
+    let band = |row: &[u8]| {
+        let mut r = row.to_vec();
+        r.resize(width, 255);
+        r.repeat(16)
+    };
+    let mut data = band(&bottom);
+    data.splice(0..0, band(&top));
+    let img = LumaImage {
+        data,
+        width,
+        height: 32,
+    };
+
+    let pipe = PipelineBuilder::new().build();
+    let all = pipe.decode_all(&img);
+    let texts: Vec<&str> = all.iter().map(|s| s.text.as_str()).collect();
+    assert_eq!(texts, ["ORDER", "ALPHA"], "верхний символ раньше нижнего");
+    assert_eq!(all, pipe.decode_all(&img));
+
+    // Перемешанный вход сортируется в тот же порядок.
+    let mut shuffled = all.clone();
+    shuffled.reverse();
+    ultracode::api::sort_symbols(&mut shuffled);
+    assert_eq!(shuffled, all);
+}
+
+#[test]
+fn qr_with_uncorrectable_rs_block_is_rejected() {
+    // v1-L (quiet=4, unit=4). Инвертируем строку модулей y=9, x=0..=8 (кроме
+    // тайминга x=6) — она задевает хвостовые EC-кодворды сильнее, чем RS
+    // может исправить. Данные целы, байтовый режим парсится — раньше такое
+    // возвращалось как успешное чтение с qr.rs_corrected=false.
+    let unit = 4usize;
+    let gray = ultracode::qr::encode::encode_qr_v1(b"HELLO", EcLevel::L, 3, 4, unit).unwrap();
+    let mut img: LumaImage = gray.into();
+
+    let mut flip_module = |mx: usize, my: usize| {
+        for py in (4 + my) * unit..(5 + my) * unit {
+            for px in (4 + mx) * unit..(5 + mx) * unit {
+                let v = &mut img.data[py * img.width + px];
+                *v = 255 - *v;
+            }
+        }
+    };
+    for mx in (0..=8).filter(|&x| x != 6) {
+        flip_module(mx, 9);
+    }
+
+    // qr_verify_rs управляет только отчётом — отбраковка от него не зависит
+    for verify in [true, false] {
+        let pipe = PipelineBuilder::new()
+            .enable_ean13_upca(false)
+            .enable_code128(false)
+            .qr_verify_rs(verify)
+            .build();
+        let results = pipe.decode_all(&img);
+        assert!(
+            results.iter().all(|s| s.symbology != Symbology::QR),
+            "uncorrectable QR must be rejected (verify={verify}), got {results:?}"
+        );
+    }
+}
+
+#[test]
+fn decode_tiled_dedups_straddling_code_and_maps_coordinates() {
+    // Code128 шириной cw лежит в перекрытии всех трёх фрагментов верхнего ряда по x;
+    // QR — в правом нижнем углу, вне верхнего ряда; его углы должны вернуться
+    // в глобальных координатах.
+    let row = ultracode::synthesize_row_code128("TILE-128", 'B', 10, 2).unwrap();
+    let cw = row.len();
+    let (w, h) = (3 * cw, 3 * cw);
+    let mut img = LumaImage {
+        data: vec![255; w * h],
+        width: w,
+        height: h,
+    };
+    for y in 0..40 {
+        img.data[y * w + cw..y * w + 2 * cw].copy_from_slice(&row);
+    }
+    let qr = ultracode::qr::encode::encode_qr_v1(b"TILED", EcLevel::L, 3, 4, 8).unwrap();
+    let (qx, qy) = (w - qr.width - 10, h - qr.height - 10);
+    for y in 0..qr.height {
+        let dst = (qy + y) * w + qx;
+        img.data[dst..dst + qr.width].copy_from_slice(qr.row(y));
+    }
+
+    let pipe = PipelineBuilder::new().build();
+    // Фрагменты 2cw×2cw с началами 0, cw-10, cw по обеим осям.
+    let found = pipe.decode_tiled(&img, 2 * cw, 2 * cw, cw + 10);
+
+    let codes: Vec<_> = found
+        .iter()
+        .filter(|s| s.symbology == Symbology::Code128)
+        .collect();
+    assert_eq!(
+        codes.len(),
+        1,
+        "straddling code must be reported once: {found:?}"
+    );
+    assert_eq!(codes[0].text, "TILE-128");
+    let row_y: usize = codes[0].extras.properties["row"].parse().unwrap();
+    assert!(row_y < 40);
+
+    let qr_sym = found
+        .iter()
+        .find(|s| s.symbology == Symbology::QR)
+        .expect("QR in the last tile must decode");
+    assert_eq!(qr_sym.text, "TILED");
+    let (min, max) = qr_sym.quad.expect("QR quad").bounding_box();
+    assert!(
+        min.x >= qx as i32 && max.x <= (qx + qr.width) as i32,
+        "{min:?}..{max:?}"
+    );
+    assert!(
+        min.y >= qy as i32 && max.y <= (qy + qr.height) as i32,
+        "{min:?}..{max:?}"
+    );
+}
+
+#[test]
+fn decode_tiled_keeps_confident_read_of_straddling_code() {
+    // Первый фрагмент обрезает правую тихую зону (остаётся 2 модуля из 10) —
+    // чтение со штрафом; второй видит код целиком. Порог между ними не должен
+    // выбросить код: слабое чтение не занимает место в дедупликации.
+    let row = ultracode::synthesize_row_code128("TILE-QZ", 'B', 10, 2).unwrap();
+    let (x0, len) = (40, row.len());
+    let (w, h) = (len + 70, 40);
+    let mut img = LumaImage {
+        data: vec![255; w * h],
+        width: w,
+        height: h,
+    };
+    for y in 0..h {
+        img.data[y * w + x0..y * w + x0 + len].copy_from_slice(&row);
+    }
+    let tile_w = len + 24;
+    // фрагменты по x: 0 и 40
+    let tiled = |pipe: &ultracode::api::Pipeline| pipe.decode_tiled(&img, tile_w, h, tile_w - 40);
+
+    let clipped = tiled(
+        &PipelineBuilder::new()
+            .quiet_zone(QuietZone::Relaxed)
+            .build(),
+    );
+    assert_eq!(clipped.len(), 1, "{clipped:?}");
+    assert!(
+        clipped[0].confidence < 0.9,
+        "first tile must see the clipped quiet zone: {clipped:?}"
+    );
+
+    let found = tiled(
+        &PipelineBuilder::new()
+            .quiet_zone(QuietZone::Relaxed)
+            .min_confidence(0.9)
+            .build(),
+    );
+    assert_eq!(found.len(), 1, "{found:?}");
+    assert_eq!(found[0].text, "TILE-QZ");
+    assert!(found[0].confidence >= 0.9, "{found:?}");
+}
+
+#[test]
+fn compat_decode_honors_scan_rows_and_formats() {
+    use ultracode::{decode_any, decode_formats, BarcodeFormat, DecodeOptions, GrayImage};
+    // Код только в полосе y=40..60: при scan_rows=2 сканируются лишь y=0 и y=99.
+    let row = ultracode::synthesize_row_code128("ROWS-128", 'B', 10, 2).unwrap();
+    let (w, h) = (row.len(), 100);
+    let mut data = vec![255u8; w * h];
+    for y in 40..60 {
+        data[y * w..(y + 1) * w].copy_from_slice(&row);
+    }
+    let img = || GrayImage {
+        data: &data,
+        width: w,
+        height: h,
+    };
+
+    let sparse = DecodeOptions {
+        scan_rows: 2,
+        ..DecodeOptions::default()
+    };
+    assert!(decode_any(img(), sparse).is_empty());
+
+    let found = decode_any(img(), DecodeOptions::default());
+    assert!(found
+        .iter()
+        .any(|b| b.format == BarcodeFormat::Code128 && b.text == "ROWS-128"));
+
+    let only_qr = decode_formats(img(), DecodeOptions::default(), &[BarcodeFormat::QR]);
+    assert!(
+        only_qr.is_empty(),
+        "Code128 decoder must be off: {only_qr:?}"
+    );
+}
+
+// Тусклый EAN "4006381333931" высотой 20 строк: штрихи всего на 6 уровней
+// темнее фона, поверх — плавная засветка 100→112 слева направо. Адаптивный порог
+// (смещение 5) почти не видит штрихов, глобальный режет по засветке.
+fn dim_ean_image() -> LumaImage {
+    let ideal = ean_row("4006381333931", 3);
+    let w = ideal.len();
+    let dim: Vec<u8> = ideal
+        .iter()
+        .enumerate()
+        .map(|(x, &v)| (100 + 12 * x / w) as u8 + if v > 127 { 6 } else { 0 })
+        .collect();
+    let h = 20;
+    LumaImage {
+        data: dim.repeat(h),
+        width: w,
+        height: h,
+    }
+}
+
+#[test]
+fn auto_contrast_rescues_dim_low_contrast_barcode() {
+    let img = dim_ean_image();
+
+    let plain = PipelineBuilder::new().enable_qr(false).build();
+    assert!(plain.decode_all(&img).is_empty());
+
+    let pipe = PipelineBuilder::new()
+        .enable_qr(false)
+        .auto_contrast(true)
+        .build();
+    let found = pipe.decode_all(&img);
+    assert_eq!(found.len(), 1, "{found:?}");
+    assert_eq!(found[0].text, "4006381333931");
+}
+
+#[test]
+fn qr_latin1_payload_decodes_with_charset() {
+    // «café» в ISO-8859-1: байт 0xE9 не валиден как UTF-8.
+    let payload = b"caf\xe9".to_vec();
+    let gray = ultracode::qr::encode::encode_qr_v1(&payload, EcLevel::L, 3, 4, 4).unwrap();
+    let img: LumaImage = gray.into();
+
+    let pipe = PipelineBuilder::new()
+        .enable_ean13_upca(false)
+        .enable_code128(false)
+        .build();
+    let results = pipe.decode_all(&img);
+    let qr = results
+        .iter()
+        .find(|s| s.symbology == Symbology::QR)
+        .expect("Latin-1 QR must decode");
+    assert_eq!(qr.text, "café");
+    assert_eq!(qr.bytes.as_deref(), Some(&payload[..]));
+    assert_eq!(
+        qr.extras.properties.get("qr.charset").map(String::as_str),
+        Some("ISO-8859-1")
+    );
+}
+
+#[test]
+fn qr_recovers_from_two_finders_when_enabled() {
+    // v1-L (quiet=4, unit=4) со смещением в кадре (чтобы не сработал фоллбэк
+    // для синтетики) и закрытым белым нижним левым finder'ом.
+    let unit = 4usize;
+    let gray = ultracode::qr::encode::encode_qr_v1(b"HELLO", EcLevel::L, 3, 4, unit).unwrap();
+    let (pad_x, pad_y) = (24usize, 12usize);
+    let width = gray.width + pad_x;
+    let height = gray.height + pad_y;
+    let mut data = vec![255u8; width * height];
+    for y in 0..gray.height {
+        let src = &gray.data[y * gray.width..(y + 1) * gray.width];
+        data[(y + pad_y) * width + pad_x..(y + pad_y) * width + pad_x + gray.width]
+            .copy_from_slice(src);
+    }
+    // BL finder: модули x=0..7, y=14..21 (+ quiet 4)
+    for y in (4 + 14) * unit..(4 + 21) * unit {
+        for x in 4 * unit..(4 + 7) * unit {
+            data[(y + pad_y) * width + x + pad_x] = 255;
+        }
+    }
+    let img = LumaImage {
+        data,
+        width,
+        height,
+    };
+
+    let strict = PipelineBuilder::new().build();
+    assert!(strict
+        .decode_all(&img)
+        .iter()
+        .all(|s| s.symbology != Symbology::QR));
+
+    let qr = ultracode::qr::QrOptions {
+        recover_two_finders: true,
+        ..Default::default()
+    };
+    let lenient = PipelineBuilder::new().qr_options(qr).build();
+    let found = lenient.decode_all(&img);
+    assert!(
+        found
+            .iter()
+            .any(|s| s.symbology == Symbology::QR && s.text == "HELLO"),
+        "got {found:?}"
+    );
+}
+
+#[test]
+fn code128_gs1_bytes_and_fnc_positions() {
+    use ultracode::one_d::code128::synthesize_row_code128_values;
+    // GS1-128: FNC1, "10" "AB" (партия, переменная длина), FNC1, "17" "261231"
+    // Start C, FNC1, 10, CODE B, 'A' 'B', CODE C, FNC1, 17 26 12 31
+    let row =
+        synthesize_row_code128_values(&[105, 102, 10, 100, 33, 34, 99, 102, 17, 26, 12, 31], 10, 2)
+            .unwrap();
+    let height = 16;
+    let img = LumaImage {
+        data: row.repeat(height),
+        width: row.len(),
+        height,
+    };
+
+    let all = PipelineBuilder::new().build().decode_all(&img);
+    let sym = all
+        .iter()
+        .find(|s| s.symbology == Symbology::Code128)
+        .unwrap_or_else(|| panic!("got {all:?}"));
+    assert_eq!(sym.text, "\x1d10AB\x1d17261231");
+    assert_eq!(sym.bytes.as_deref(), Some(&b"10AB17261231"[..]));
+    assert_eq!(sym.extras.get_str("c128.gs1"), Some("true"));
+    assert_eq!(
+        sym.extras.properties.get("code128.fnc").map(String::as_str),
+        Some("0:FNC1,4:FNC1")
+    );
+}
+
+#[test]
+fn qr_version_range_gates_detection() {
+    let img: LumaImage =
+        ultracode::qr::encode::encode_qr_v1(b"HELLO", EcLevel::L, 3, 4, 4).unwrap();
+    let decode = |range: (u8, u8)| {
+        let qr = ultracode::qr::QrOptions {
+            version_range: range,
+            ..Default::default()
+        };
+        PipelineBuilder::new()
+            .qr_options(qr)
+            .build()
+            .decode_all(&img)
+            .iter()
+            .any(|s| s.symbology == Symbology::QR && s.text == "HELLO")
+    };
+    assert!(decode((1, 1)));
+    assert!(decode((1, 4)));
+    assert!(!decode((2, 4)), "v1 вне диапазона не должен читаться");
+}
+
+#[test]
+fn qr_version_range_ignores_unconfirmed_estimate() {
+    // Тёмные рамки finder'ов сужены с обращённых друг к другу сторон — разнос
+    // в модулях выходит как у v2; вертикальный тайминг залит, так что тайминги
+    // оценку не поправляют. Неподтверждённая оценка не должна отсекать v1.
+    let (m, q) = (4, 16);
+    let mut img: LumaImage =
+        ultracode::qr::encode::encode_qr_v1(b"HELLO", EcLevel::L, 3, 4, m).unwrap();
+    let w = img.width;
+    for y in q + 2 * m..q + 5 * m {
+        for x in [q + 6 * m + 2, q + 6 * m + 3, q + 14 * m, q + 14 * m + 1] {
+            img.data[y * w + x] = 255;
+        }
+    }
+    for y in q + 8 * m..q + 13 * m {
+        img.data[y * w + q + 6 * m..y * w + q + 7 * m].fill(0);
+    }
+    let gray = img.as_gray();
+    let opts = ultracode::qr::QrOptions::default();
+    let finders = ultracode::qr::finder::find_finder_patterns(&gray, &opts);
+    assert_eq!(
+        ultracode::qr::sample::estimate_version(&gray, &opts, &finders),
+        Some(2)
+    );
+    assert_eq!(
+        ultracode::qr::sample::timing_module_count(&gray, &opts, &finders),
+        None
+    );
+
+    let sym = PipelineBuilder::new()
+        .build()
+        .decode_first(&img)
+        .expect("v1 with a v2-looking finder spacing");
+    assert_eq!(sym.text, "HELLO");
+}
+
+#[test]
+fn qr_encode_api_renders_decodable_images() {
+    use ultracode::qr::encode::v1_byte_capacity;
+    use ultracode::qr::encode_qr_v1;
+
+    let qr_only = PipelineBuilder::new()
+        .enable_ean13_upca(false)
+        .enable_code128(false)
+        .enable_code93(false)
+        .build();
+    for (ec, text) in [
+        (EcLevel::L, "LABEL-0001-ABCDEF"),
+        (EcLevel::M, "LABEL-0001-ABC"),
+        (EcLevel::Q, "LABEL-00001"),
+        (EcLevel::H, "LBL-001"),
+    ] {
+        assert_eq!(text.len(), v1_byte_capacity(ec));
+        let img = encode_qr_v1(text.as_bytes(), ec, 5, 6, 3).expect("encode");
+        assert_eq!((img.width, img.height), ((21 + 12) * 3, (21 + 12) * 3));
+        let found = qr_only.decode_all(&img);
+        assert!(found.iter().any(|s| s.text == text), "{ec:?}: {found:?}");
+    }
+}
+
+#[test]
+fn progress_reports_steps_and_cancels_early() {
+    use std::sync::{Arc, Mutex};
+    // Code128 только в полосе y=40..60; 15 строк идут через y=0,7,..,98.
+    let row = ultracode::synthesize_row_code128("PROG-128", 'B', 10, 2).unwrap();
+    let (w, h) = (row.len(), 100);
+    let mut img = LumaImage {
+        data: vec![255; w * h],
+        width: w,
+        height: h,
+    };
+    for y in 40..60 {
+        img.data[y * w..(y + 1) * w].copy_from_slice(&row);
+    }
+    let only_128 = || {
+        PipelineBuilder::new()
+            .enable_ean13_upca(false)
+            .enable_code93(false)
+            .enable_qr(false)
+    };
+
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let log = Arc::clone(&calls);
+    let pipe = only_128()
+        .progress(move |done, total| {
+            log.lock().unwrap().push((done, total));
+            true
+        })
+        .build();
+    assert_eq!(pipe.decode_all(&img).len(), 1);
+    let calls = calls.lock().unwrap().clone();
+    assert_eq!(calls.len(), 15);
+    assert_eq!(calls.last(), Some(&(15, 15)));
+
+    // Отмена после трёх строк (y=0,7,14) — до полосы с кодом не доходим.
+    let cancelled = only_128().progress(|done, _| done < 3).build();
+    assert!(cancelled.decode_all(&img).is_empty());
+
+    // 1D-декодер напрямую: тот же контракт через DecodeOptions.
+    let seen = Arc::new(Mutex::new(0usize));
+    let counter = Arc::clone(&seen);
+    let opts = ultracode::DecodeOptions {
+        progress: Some(Progress::new(move |_, _| {
+            *counter.lock().unwrap() += 1;
+            true
+        })),
+        ..Default::default()
+    };
+    assert!(!ultracode::one_d::decode_code128(&img.as_gray(), &opts).is_empty());
+    assert_eq!(*seen.lock().unwrap(), 15);
+
+    // decode_tiled: шаг — фрагмент (y0 = 0 и 40); отмена после первого.
+    let tiles = Arc::new(Mutex::new(Vec::new()));
+    let log = Arc::clone(&tiles);
+    let tiled = only_128()
+        .progress(move |done, total| {
+            log.lock().unwrap().push((done, total));
+            false
+        })
+        .build();
+    let _ = tiled.decode_tiled(&img, w, 60, 20);
+    assert_eq!(*tiles.lock().unwrap(), vec![(1, 2)]);
+}
+
+#[test]
+fn decode_all_gray_matches_owned_decode() {
+    // Code128 сверху, QR снизу — оба пути (1D и QR) по заимствованному кадру.
+    let row = ultracode::synthesize_row_code128("GRAY-128", 'B', 10, 2).unwrap();
+    let qr = ultracode::qr::encode::encode_qr_v1(b"GRAY", EcLevel::L, 3, 4, 6).unwrap();
+    let w = row.len().max(qr.width);
+    let mut data = vec![255u8; w * (40 + qr.height)];
+    for y in 0..40 {
+        data[y * w..y * w + row.len()].copy_from_slice(&row);
+    }
+    for y in 0..qr.height {
+        let dst = (40 + y) * w;
+        data[dst..dst + qr.width].copy_from_slice(qr.row(y));
+    }
+    let gray = GrayImage {
+        data: &data,
+        width: w,
+        height: 40 + qr.height,
+    };
+
+    let pipe = PipelineBuilder::new().build();
+    let found = pipe.decode_all_gray(&gray);
+    assert_eq!(found, pipe.decode_all(&gray.into()));
+    let texts: Vec<&str> = found.iter().map(|s| s.text.as_str()).collect();
+    assert_eq!(texts, ["GRAY-128", "GRAY"]);
+    // Итератор и первый символ — тоже без LumaImage; GrayImage собирается на месте.
+    let lazy: Vec<DecodedSymbol> = pipe
+        .decode_iter_gray(&GrayImage::from_slice(&data, w, gray.height).unwrap())
+        .collect();
+    assert_eq!(lazy, found);
+    assert_eq!(pipe.decode_first_gray(&gray).as_ref(), found.first());
+
+    // Второй проход auto_contrast тоже работает от заимствованного кадра.
+    let dim = dim_ean_image();
+    let dim = dim.as_gray();
+    assert!(pipe.decode_all_gray(&dim).is_empty());
+    let rescued = PipelineBuilder::new()
+        .auto_contrast(true)
+        .build()
+        .decode_all_gray(&dim);
+    assert_eq!(rescued.len(), 1, "{rescued:?}");
+    assert_eq!(rescued[0].text, "4006381333931");
+}
+
+#[test]
+fn quiet_zone_policy_reports_missing_margins() {
+    // Ряд без белых полей: символ вплотную к краям кадра.
+    fn strip(row: &[u8]) -> Vec<u8> {
+        let first = row.iter().position(|&v| v < 128).unwrap();
+        let last = row.iter().rposition(|&v| v < 128).unwrap();
+        row[first..=last].to_vec()
+    }
+    fn tile(row: &[u8]) -> LumaImage {
+        LumaImage {
+            data: row.repeat(16),
+            width: row.len(),
+            height: 16,
+        }
+    }
+    let pipe = |mode: QuietZone| {
+        PipelineBuilder::new()
+            .enable_code93(true)
+            .quiet_zone(mode)
+            .build()
+    };
+
+    let rows = [
+        (
+            Symbology::Ean13,
+            "4006381333931",
+            ean_row("4006381333931", 3),
+        ),
+        (
+            Symbology::Code128,
+            "LABEL-128",
+            ultracode::synthesize_row_code128("LABEL-128", 'B', 10, 3).unwrap(),
+        ),
+        (
+            Symbology::Code93,
+            "LABEL-93",
+            ultracode::one_d::code93::synthesize_row_code93("LABEL-93", 10, 3).unwrap(),
+        ),
+    ];
+    for (sym, text, row) in rows {
+        let framed = pipe(QuietZone::Relaxed).decode_all(&tile(&row));
+        let ok = framed.iter().find(|s| s.symbology == sym).expect("framed");
+        assert!(!ok.extras.properties.contains_key("quiet_zone"), "{sym:?}");
+
+        let bare = tile(&strip(&row));
+        let relaxed = pipe(QuietZone::Relaxed).decode_all(&bare);
+        let missing = relaxed
+            .iter()
+            .find(|s| s.symbology == sym)
+            .unwrap_or_else(|| panic!("{sym:?}: {relaxed:?}"));
+        assert_eq!(missing.text, text);
+        assert_eq!(
+            missing
+                .extras
+                .properties
+                .get("quiet_zone")
+                .map(String::as_str),
+            Some("missing")
+        );
+        assert!(missing.confidence < ok.confidence);
+
+        assert!(
+            pipe(QuietZone::Strict)
+                .decode_all(&bare)
+                .iter()
+                .all(|s| s.symbology != sym),
+            "{sym:?}"
+        );
+        let ignored = pipe(QuietZone::Ignore).decode_all(&bare);
+        let plain = ignored.iter().find(|s| s.symbology == sym).expect("ignore");
+        assert!(!plain.extras.properties.contains_key("quiet_zone"));
+    }
+
+    // QR без тихой зоны (quiet_zone = 0 модулей) и со стандартной (4).
+    let bare = ultracode::qr::encode_qr_v1(b"NO-QZ", EcLevel::M, 3, 0, 6).unwrap();
+    let found = pipe(QuietZone::Relaxed).decode_all(&bare);
+    assert_eq!(found.len(), 1, "{found:?}");
+    assert_eq!(
+        found[0]
+            .extras
+            .properties
+            .get("quiet_zone")
+            .map(String::as_str),
+        Some("missing")
+    );
+    assert!(pipe(QuietZone::Strict).decode_all(&bare).is_empty());
+    let framed = ultracode::qr::encode_qr_v1(b"NO-QZ", EcLevel::M, 3, 4, 6).unwrap();
+    let found = pipe(QuietZone::Strict).decode_all(&framed);
+    assert_eq!(found.len(), 1);
+    assert!(!found[0].extras.properties.contains_key("quiet_zone"));
+}
+
+#[test]
+fn auto_contrast_skips_flat_frames() {
+    // Однотонный кадр с auto_contrast: второго прохода нет, ничего не найдено.
+    let flat = LumaImage {
+        data: vec![128; 12],
+        width: 4,
+        height: 3,
+    };
+    assert_eq!(flat.stats().dynamic_range(), 0);
+    let pipe = PipelineBuilder::new().auto_contrast(true).build();
+    assert!(pipe.decode_all(&flat).is_empty());
+}
+
+#[test]
+fn qr_geometry_is_reported_in_extras_and_when_decoding_fails() {
+    use ultracode::qr::encode_qr_v1;
+
+    let (unit, quiet) = (4, 4);
+    let mut img = encode_qr_v1(b"HELLO", EcLevel::L, 3, quiet, unit).expect("HELLO fits v1");
+    let pipe = PipelineBuilder::new()
+        .enable_ean13_upca(false)
+        .enable_code128(false)
+        .build();
+    let qr = pipe.decode_first(&img).expect("QR must decode");
+    assert_eq!(qr.extras.get_parsed::<usize>("qr.grid_n"), Some(21));
+    assert_eq!(qr.extras.get_parsed::<usize>("qr.timing_modules"), Some(21));
+    let module_px: f32 = qr.extras.get_parsed("qr.module_px").expect("qr.module_px");
+    assert!(
+        (module_px - unit as f32).abs() < 0.25,
+        "module_px = {module_px}"
+    );
+
+    // стираем правый нижний угол (данные, не finder'ы и не тайминги): RS не спасёт
+    let lo = (quiet + 9) * unit;
+    let hi = (quiet + 21) * unit;
+    for y in lo..hi {
+        for x in lo..hi {
+            img.data[y * img.width + x] = 255;
+        }
+    }
+    assert!(
+        pipe.decode_first(&img).is_none(),
+        "стёртый символ не должен читаться"
+    );
+    let geometry = pipe.qr_geometry(&img).expect("finder'ы на месте");
+    assert_eq!(geometry.grid_n, 21);
+    assert!(
+        (geometry.module_px - unit as f32).abs() < 0.25,
+        "module_px = {}",
+        geometry.module_px
+    );
+}
+
+#[test]
+fn one_d_extras_report_module_count_and_size() {
+    let row = ean_row("5901234123457", 2);
+    let img = LumaImage {
+        width: row.len(),
+        height: 8,
+        data: row.repeat(8),
+    };
+    let ean = PipelineBuilder::new()
+        .build()
+        .decode_first(&img)
+        .expect("EAN must decode");
+    assert_eq!(ean.extras.get_parsed::<usize>("modules"), Some(95));
+    assert_eq!(ean.extras.get_str("module_px"), Some("2.00"));
+    assert_eq!(ean.extras.get_str("gtin14"), Some("05901234123457"));
+    // у EAN байты — ASCII-цифры текста
+    assert_eq!(ean.bytes.as_deref(), Some(&b"5901234123457"[..]));
+
+    // compat-слой восстанавливает ширину из extras
+    let barcodes = ultracode::decode_any(img.as_gray(), ultracode::one_d::DecodeOptions::default());
+    assert_eq!((barcodes[0].modules, barcodes[0].width_px), (95, 190));
+}
+
+#[cfg(feature = "debug-capture")]
+#[test]
+fn captured_scan_debug_survives_pipeline_and_compat() {
+    let row = ean_row("5901234123457", 2);
+    let img = LumaImage {
+        width: row.len(),
+        height: 8,
+        data: row.repeat(8),
+    };
+    let opts = ultracode::one_d::DecodeOptions {
+        capture_debug: true,
+        ..Default::default()
+    };
+    let ean = PipelineBuilder::new()
+        .one_d_options(opts.clone())
+        .build()
+        .decode_first(&img)
+        .expect("EAN must decode");
+    let runs: Vec<f32> = ean
+        .extras
+        .get_str("debug.runs")
+        .expect("runs")
+        .split(' ')
+        .map(|r| r.parse().unwrap())
+        .collect();
+    // 3 + 24 + 5 + 24 + 3 штриха и пробела, по 1..4 модуля в 2 px
+    assert_eq!(runs.len(), 59);
+    assert_eq!(runs.iter().sum::<f32>(), 190.0);
+    assert!(ean.extras.get_parsed::<usize>("debug.scan_row").is_some());
+
+    let barcodes = ultracode::decode_any(img.as_gray(), opts);
+    assert_eq!(barcodes[0].debug.as_ref().map(|d| &d.runs), Some(&runs));
+    assert_eq!(
+        ultracode::decode_any(img.as_gray(), Default::default())[0].debug,
+        None
+    );
+}
+
+#[test]
+fn decode_batch_keeps_input_order_and_stops_on_cancel() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let row = ean_row("5901234123457", 2);
+    let ean = LumaImage {
+        width: row.len(),
+        height: 8,
+        data: row.repeat(8),
+    };
+    let qr: LumaImage = ultracode::qr::encode::encode_qr_v1(b"HELLO", EcLevel::L, 3, 4, 4).unwrap();
+    let blank = LumaImage {
+        data: vec![255; 64 * 64],
+        width: 64,
+        height: 64,
+    };
+    let imgs = vec![qr.clone(), blank, ean.clone(), qr];
+
+    let pipe = PipelineBuilder::new().build();
+    let batch = pipe.decode_batch(&imgs);
+    assert_eq!(batch.len(), imgs.len());
+    for (got, img) in batch.iter().zip(&imgs) {
+        assert_eq!(got, &pipe.decode_all(img));
+    }
+    assert_eq!(batch[0][0].text, "HELLO");
+    assert!(batch[1].is_empty());
+    assert_eq!(batch[2][0].text, "5901234123457");
+    assert!(pipe.decode_batch(&[]).is_empty());
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let seen = Arc::clone(&calls);
+    let pipe = PipelineBuilder::new()
+        .progress(move |done, total| {
+            assert_eq!(total, 4);
+            seen.fetch_add(1, Ordering::Relaxed);
+            done < 4
+        })
+        .build();
+    assert_eq!(pipe.decode_batch(&imgs).len(), 4);
+    assert_eq!(calls.load(Ordering::Relaxed), 4);
+
+    // Отмена после первого кадра. Под rayon «первым» может оказаться любой кадр,
+    // поэтому проверяем только длину.
+    let pipe = PipelineBuilder::new().progress(|_, _| false).build();
+    let batch = pipe.decode_batch(&imgs);
+    assert_eq!(batch.len(), 4);
+    #[cfg(not(feature = "rayon"))]
+    {
+        assert_eq!(batch[0][0].text, "HELLO");
+        assert!(batch[1..].iter().all(Vec::is_empty));
+    }
+}
+
+#[test]
+fn custom_decoders_run_after_built_ins() {
+    use ultracode::api::{ImageDecoder, RowDecoder, RowScan};
+
+    /// «Своя» симвология: число штрихов на строке (3..=9).
+    struct BarCount;
+    impl RowDecoder for BarCount {
+        fn decode_row(
+            &self,
+            row: &[u8],
+            _opts: &ultracode::DecodeOptions,
+        ) -> Option<DecodedSymbol> {
+            let bars = row.windows(2).filter(|w| w[0] >= 128 && w[1] < 128).count();
+            (3..=9)
+                .contains(&bars)
+                .then(|| DecodedSymbol::new(Symbology::Custom("Bars"), bars.to_string()))
+        }
+    }
+    /// Кадровый декодер: повтор чужого символа и свой.
+    struct Stamp;
+    impl ImageDecoder for Stamp {
+        fn decode(&self, _img: &GrayImage<'_>) -> Vec<DecodedSymbol> {
+            vec![
+                DecodedSymbol::new(Symbology::Custom("Bars"), "3"),
+                DecodedSymbol::new(Symbology::Custom("Stamp"), "ok"),
+            ]
+        }
+    }
+
+    // сверху EAN-13, снизу три штриха
+    let ean = ean_row("5901234123457", 2);
+    let width = ean.len();
+    let mut bars = vec![255u8; width];
+    for x in [20, 40, 60] {
+        bars[x..x + 6].fill(0);
+    }
+    let data = [ean.repeat(10), bars.repeat(10)].concat();
+    let img = LumaImage {
+        width,
+        height: 20,
+        data,
+    };
+
+    let pipeline = PipelineBuilder::new()
+        .add_decoder(Box::new(RowScan::new(
+            BarCount,
+            ultracode::DecodeOptions::default(),
+        )))
+        .add_decoder(Box::new(Stamp))
+        .build();
+    let found: Vec<(Symbology, String)> = pipeline
+        .decode_all(&img)
+        .into_iter()
+        .map(|s| (s.symbology, s.text))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (Symbology::Ean13, "5901234123457".to_string()),
+            (Symbology::Custom("Stamp"), "ok".to_string()),
+            (Symbology::Custom("Bars"), "3".to_string()),
+        ]
+    );
+    let bars = pipeline
+        .decode_iter(&img)
+        .find(|s| s.symbology == Symbology::Custom("Bars"))
+        .unwrap();
+    assert!(bars
+        .extras
+        .get_parsed::<usize>("row")
+        .is_some_and(|y| y >= 10));
+}
+
+#[test]
+fn aztec_decodes_only_when_enabled() {
+    let text = "AZTEC 2D ticket #7";
+    let img = ultracode::two_d::encode_aztec_compact(text, 4, 4).expect("encode");
+    assert!(PipelineBuilder::new()
+        .build()
+        .decode_all(&img)
+        .iter()
+        .all(|s| s.symbology != Symbology::Aztec));
+
+    let pipe = PipelineBuilder::new().enable_aztec(true).build();
+    let az = pipe
+        .decode_all(&img)
+        .into_iter()
+        .find(|s| s.symbology == Symbology::Aztec)
+        .expect("Aztec must decode");
+    assert_eq!(az.text, text);
+    assert!(az.extras.get_parsed::<u8>("aztec.layers").is_some());
+    assert_eq!(
+        az.extras.get_parsed::<usize>("aztec.rs_corrected_words"),
+        Some(0)
+    );
+    assert!(az.quad.is_some());
+}
+
+#[test]
+fn checksum_policy_applies_to_broken_ean() {
+    let broken = broken_ean_row(2);
+    let img = LumaImage {
+        width: broken.len(),
+        height: 8,
+        data: broken.repeat(8),
+    };
+    let with_policy = |checksum| {
+        let one_d = ultracode::one_d::DecodeOptions {
+            checksum,
+            ..Default::default()
+        };
+        PipelineBuilder::new()
+            .enable_qr(false)
+            .one_d_options(one_d)
+            .build()
+    };
+
+    assert!(with_policy(ChecksumPolicy::Require)
+        .decode_all(&img)
+        .is_empty());
+    for policy in [ChecksumPolicy::Prefer, ChecksumPolicy::Ignore] {
+        let all = with_policy(policy).decode_all(&img);
+        assert_eq!(all.len(), 1, "{policy:?}");
+        assert_eq!(all[0].text, "5901234123458");
+        assert_eq!(all[0].extras.get_str("checksum"), Some("failed"));
+        assert!(all[0].confidence < 0.6);
+    }
+
+    // Верхняя половина кадра — целый код, нижняя — битый: `Prefer` берёт целый,
+    // `Ignore` отдаёт оба.
+    let good = ean_row("5901234123457", 2);
+    let mut data = good.repeat(8);
+    data.extend(broken.repeat(8));
+    let img = LumaImage {
+        width: good.len(),
+        height: 16,
+        data,
+    };
+    let texts = |policy, img: &LumaImage| -> Vec<String> {
+        with_policy(policy)
+            .decode_all(img)
+            .into_iter()
+            .map(|s| s.text)
+            .collect()
+    };
+    assert_eq!(texts(ChecksumPolicy::Require, &img), ["5901234123457"]);
+    assert_eq!(texts(ChecksumPolicy::Prefer, &img), ["5901234123457"]);
+    assert_eq!(texts(ChecksumPolicy::Ignore, &img).len(), 2);
+
+    // Целый и битый коды рядом в одних строках: место битого не занято
+    // сошедшимся прочтением — `Prefer` отдаёт оба.
+    let row: Vec<u8> = good.iter().chain(&broken).copied().collect();
+    let img = LumaImage {
+        width: row.len(),
+        height: 8,
+        data: row.repeat(8),
+    };
+    assert_eq!(
+        texts(ChecksumPolicy::Prefer, &img),
+        ["5901234123457", "5901234123458"]
+    );
+    assert_eq!(texts(ChecksumPolicy::Require, &img), ["5901234123457"]);
+}
+
+#[test]
+fn qr_struct_score_is_reported_and_gates_rs() {
+    let img: LumaImage =
+        ultracode::qr::encode::encode_qr_v1(b"STRUCT", ultracode::qr::format::EcLevel::L, 4, 4, 4)
+            .unwrap();
+    let with_min = |min_struct_score| {
+        let qr = ultracode::qr::QrOptions {
+            min_struct_score,
+            ..Default::default()
+        };
+        PipelineBuilder::new().qr_options(qr).build()
+    };
+    let sym = with_min(0.8).decode_first(&img).expect("clean QR");
+    assert_eq!(sym.extras.get_str("qr.struct_score"), Some("1.00"));
+    // Недостижимый порог: сетка отбрасывается до RS на всех попытках.
+    assert!(with_min(1.01).decode_first(&img).is_none());
+}
+
+#[test]
+fn builtin_symbols_carry_typed_meta() {
+    let pipe = PipelineBuilder::new().build();
+    let band = |row: Vec<u8>| LumaImage {
+        data: row.repeat(16),
+        width: row.len(),
+        height: 16,
+    };
+
+    let qr: LumaImage = ultracode::qr::encode::encode_qr_v1(b"META", EcLevel::Q, 5, 4, 4).unwrap();
+    let sym = pipe.decode_first(&qr).expect("QR");
+    assert_eq!(
+        sym.meta,
+        Some(SymbolMeta::Qr {
+            ec: EcLevel::Q,
+            mask: 5,
+            rs_corrected: 0,
+            version: 1
+        })
+    );
+    assert_eq!(sym.extras.get_str("qr.mask"), Some("5"));
+
+    let sym = pipe
+        .decode_first(&band(
+            ultracode::synthesize_row_code128("0042", 'C', 10, 2).unwrap(),
+        ))
+        .expect("Code128");
+    assert_eq!(
+        sym.meta,
+        Some(SymbolMeta::Code128 {
+            start_set: 'C',
+            fnc1_gs1: false
+        })
+    );
+    // Start B, FNC1, "01" — GS1-128.
+    let gs1 = ultracode::one_d::code128::synthesize_row_code128_values(&[104, 102, 16, 17], 10, 2)
+        .unwrap();
+    let sym = pipe.decode_first(&band(gs1)).expect("GS1-128");
+    assert_eq!(
+        sym.meta,
+        Some(SymbolMeta::Code128 {
+            start_set: 'B',
+            fnc1_gs1: true
+        })
+    );
+
+    let sym = pipe
+        .decode_first(&band(ean_row("9780306406157", 2)))
+        .expect("EAN");
+    assert_eq!(
+        sym.meta,
+        Some(SymbolMeta::Ean {
+            number_system: 9,
+            addon: None
+        })
+    );
+    assert_eq!(sym.extras.get_str("ean.number_system"), Some("978"));
+    let sym = pipe
+        .decode_first(&band(ean_row("036000291452", 2)))
+        .expect("UPC-A");
+    assert_eq!(
+        sym.meta,
+        Some(SymbolMeta::Ean {
+            number_system: 0,
+            addon: None
+        })
+    );
+
+    // У символик без типизированных метаданных — None.
+    let c93 = ultracode::one_d::code93::synthesize_row_code93("META", 10, 2).unwrap();
+    let sym = PipelineBuilder::new()
+        .enable_code93(true)
+        .enable_code128(false)
+        .build()
+        .decode_first(&band(c93))
+        .expect("Code 93");
+    assert_eq!(sym.meta, None);
+}
+
+#[test]
+fn custom_confidence_model_rescores_builtin_symbols() {
+    use ultracode::api::{ConfidenceModel, DefaultConfidence, OneDEvidence, QrEvidence};
+    // Своя шкала только для QR: штраф за исправленные кодворды и «пограничные» модули.
+    struct Strict;
+    impl ConfidenceModel for Strict {
+        fn qr(&self, e: &QrEvidence) -> f32 {
+            assert!((0.0..=1.0).contains(&e.borderline_ratio));
+            let base = if e.corrected_bytes > 0 { 0.5 } else { 0.9 };
+            base - e.borderline_ratio
+        }
+    }
+    let qr: LumaImage =
+        ultracode::qr::encode::encode_qr_v1(b"SCORE", ultracode::qr::format::EcLevel::M, 0, 4, 4)
+            .unwrap();
+    let row = ean_row("5901234123457", 2);
+    let ean = LumaImage {
+        data: row.repeat(16),
+        width: row.len(),
+        height: 16,
+    };
+
+    let default_pipe = PipelineBuilder::new().build();
+    let strict_pipe = PipelineBuilder::new()
+        .confidence_model(Box::new(Strict))
+        .build();
+    let default_qr = default_pipe.decode_first(&qr).expect("QR");
+    let strict_qr = strict_pipe.decode_first(&qr).expect("QR");
+    assert_eq!(strict_qr.text, default_qr.text);
+    assert!(
+        (default_qr.confidence - 0.92).abs() < 1e-6,
+        "{}",
+        default_qr.confidence
+    );
+    assert!(
+        strict_qr.confidence <= 0.9 && strict_qr.confidence > 0.5,
+        "{}",
+        strict_qr.confidence
+    );
+
+    // 1D не переопределён — методы по умолчанию совпадают с DefaultConfidence.
+    let strict_ean = strict_pipe.decode_first(&ean).expect("EAN");
+    assert_eq!(
+        strict_ean.confidence,
+        default_pipe.decode_first(&ean).unwrap().confidence
+    );
+    let clean = OneDEvidence {
+        symbology: Symbology::Ean13,
+        recovered: false,
+        quiet_zone_missing: false,
+        checksum_failed: false,
+    };
+    assert_eq!(strict_ean.confidence, DefaultConfidence.one_d(&clean));
+}
+
+#[test]
+fn gtin13_normalization_pads_upca_and_keeps_raw_text() {
+    let band = |digits: &str| {
+        let row = ean_row(digits, 2);
+        LumaImage {
+            data: row.repeat(16),
+            width: row.len(),
+            height: 16,
+        }
+    };
+    let upca = band("036000291452");
+    let ean = band("5901234123457");
+
+    let plain = PipelineBuilder::new()
+        .build()
+        .decode_first(&upca)
+        .expect("UPC-A");
+    assert_eq!(
+        (plain.symbology, plain.text.as_str()),
+        (Symbology::UpcA, "036000291452")
+    );
+    assert_eq!(plain.extras.get_str("raw"), None);
+
+    let pipe = PipelineBuilder::new().normalize_gtin13(true).build();
+    let sym = pipe.decode_first(&upca).expect("UPC-A");
+    assert_eq!(sym.symbology, Symbology::UpcA);
+    assert_eq!(sym.text, "0036000291452");
+    assert_eq!(sym.bytes.as_deref(), Some(&b"0036000291452"[..]));
+    assert_eq!(sym.extras.get_str("raw"), Some("036000291452"));
+
+    // EAN-13 уже 13-значный — текст тот же, raw совпадает с ним.
+    let sym = pipe.decode_first(&ean).expect("EAN-13");
+    assert_eq!(sym.text, "5901234123457");
+    assert_eq!(sym.extras.get_str("raw"), Some("5901234123457"));
+}
+
+#[test]
+fn in_store_price_extras_are_opt_in() {
+    let band = |digits: &str| {
+        let row = ean_row(digits, 2);
+        LumaImage {
+            data: row.repeat(16),
+            width: row.len(),
+            height: 16,
+        }
+    };
+    // префикс 21, товар 12345, цена 0199 с контрольной 0
+    let priced = band("2112345001999");
+
+    let plain = PipelineBuilder::new()
+        .build()
+        .decode_first(&priced)
+        .expect("EAN-13");
+    assert_eq!(plain.extras.get_str("ean.in_store_price"), None);
+
+    let pipe = PipelineBuilder::new().parse_in_store_price(true).build();
+    let sym = pipe.decode_first(&priced).expect("EAN-13");
+    assert_eq!(sym.text, "2112345001999");
+    assert_eq!(sym.extras.get_str("ean.in_store_item"), Some("12345"));
+    assert_eq!(sym.extras.get_str("ean.in_store_price"), Some("199"));
+    assert_eq!(sym.extras.get_str("ean.in_store_price_check"), Some("ok"));
+
+    // контрольная цифра цены не сошлась — поля есть, но помечены
+    let sym = pipe.decode_first(&band("2112345301990")).expect("EAN-13");
+    assert_eq!(
+        sym.extras.get_str("ean.in_store_price_check"),
+        Some("failed")
+    );
+
+    // обычный товарный префикс не трогаем
+    let sym = pipe.decode_first(&band("5901234123457")).expect("EAN-13");
+    assert_eq!(sym.extras.get_str("ean.in_store_item"), None);
+}
+
+#[test]
+fn primary_symbology_runs_first_and_can_stop_the_pass() {
+    use std::sync::{Arc, Mutex};
+    // EAN-13 сверху, Code128 снизу: по умолчанию первым находится EAN.
+    let ean = ean_row("5901234123457", 2);
+    let c128 = ultracode::synthesize_row_code128("PRIMARY", 'B', 10, 2).unwrap();
+    let width = ean.len().max(c128.len()) + 8;
+    let band = |row: &[u8]| {
+        let mut r = row.to_vec();
+        r.resize(width, 255);
+        r.repeat(16)
+    };
+    let mut data = band(&ean);
+    data.extend(band(&c128));
+    let img = LumaImage {
+        data,
+        width,
+        height: 32,
+    };
+
+    let first = |b: PipelineBuilder| b.build().decode_first(&img).map(|s| s.symbology);
+    assert_eq!(first(PipelineBuilder::new()), Some(Symbology::Ean13));
+    assert_eq!(
+        first(PipelineBuilder::new().primary(Symbology::Code128)),
+        Some(Symbology::Code128)
+    );
+
+    // Без stop_after_primary находится всё, decode_all сортирует как обычно.
+    let all = PipelineBuilder::new()
+        .primary(Symbology::Code128)
+        .build()
+        .decode_all(&img);
+    assert_eq!(all, PipelineBuilder::new().build().decode_all(&img));
+
+    // Со stop_after_primary остальные стадии не запускаются: шаги — только строки Code128.
+    let steps = Arc::new(Mutex::new(0usize));
+    let counter = Arc::clone(&steps);
+    let pipe = PipelineBuilder::new()
+        .primary(Symbology::Code128)
+        .stop_after_primary(true)
+        .one_d_options(ultracode::DecodeOptions {
+            scan_rows: 8,
+            ..Default::default()
+        })
+        .progress(move |_, _| {
+            *counter.lock().unwrap() += 1;
+            true
+        })
+        .build();
+    let texts: Vec<String> = pipe.decode_all(&img).into_iter().map(|s| s.text).collect();
+    assert_eq!(texts, ["PRIMARY"]);
+    assert_eq!(*steps.lock().unwrap(), 8);
+
+    // Основная ничего не нашла — обход идёт дальше.
+    let only_ean = LumaImage {
+        data: band(&ean),
+        width,
+        height: 16,
+    };
+    let pipe = PipelineBuilder::new()
+        .primary(Symbology::QR)
+        .stop_after_primary(true)
+        .build();
+    let found: Vec<Symbology> = pipe
+        .decode_all(&only_ean)
+        .into_iter()
+        .map(|s| s.symbology)
+        .collect();
+    assert_eq!(found, [Symbology::Ean13]);
+}
+
+#[test]
+fn single_scan_line_images_decode_through_pipeline() {
+    // Кадр высотой 1 — одна извлечённая строка скана: 1D читается, 2D-стадии
+    // пропускаются: кадр меньше любого символа.
+    let mut reversed = ultracode::synthesize_row_code128("LINE-128", 'B', 10, 2).unwrap();
+    reversed.reverse();
+    let rows = [
+        (
+            ean_row("5901234123457", 2),
+            Symbology::Ean13,
+            "5901234123457",
+        ),
+        (ean_row("036000291452", 2), Symbology::UpcA, "036000291452"),
+        (reversed, Symbology::Code128, "LINE-128"),
+        (
+            ultracode::one_d::code93::synthesize_row_code93("LINE93", 10, 2).unwrap(),
+            Symbology::Code93,
+            "LINE93",
+        ),
+    ];
+    let pipe = PipelineBuilder::new()
+        .enable_code93(true)
+        .enable_aztec(true)
+        .auto_contrast(true)
+        .one_d_options(ultracode::DecodeOptions {
+            deskew: true,
+            ..Default::default()
+        })
+        .build();
+    for (row, symbology, text) in rows {
+        let img = LumaImage {
+            width: row.len(),
+            height: 1,
+            data: row,
+        };
+        let all = pipe.decode_all(&img);
+        assert_eq!(all.len(), 1, "{text}: {all:?}");
+        assert_eq!((all[0].symbology, all[0].text.as_str()), (symbology, text));
+        assert_eq!(all[0].extras.get_parsed::<usize>("row"), Some(0));
+        assert_eq!(
+            ultracode::one_d::decode_center_row(&img.as_gray(), &Default::default()).is_some(),
+            symbology != Symbology::Code93
+        );
+
+        let only_2d = PipelineBuilder::new()
+            .enable_ean13_upca(false)
+            .enable_code128(false)
+            .enable_code93(false);
+        assert!(only_2d
+            .enable_aztec(true)
+            .build()
+            .decode_all(&img)
+            .is_empty());
+    }
+
+    for width in [0, 1, 40] {
+        let img = LumaImage {
+            width,
+            height: 1,
+            data: vec![255; width],
+        };
+        assert!(pipe.decode_all(&img).is_empty());
+    }
+}
+
+#[test]
+fn dim_qr_reads_with_auto_module_threshold() {
+    // Тусклый кадр: вся шкала в 20..110, светлые модули тоже ниже 128.
+    let bright: LumaImage =
+        ultracode::qr::encode::encode_qr_v1(b"DIM QR", ultracode::qr::format::EcLevel::M, 1, 4, 4)
+            .unwrap();
+    let dim_px = |v: u8| 20 + u8::try_from(u16::from(v) * 90 / 255).unwrap();
+    let dim = LumaImage {
+        data: bright.data.iter().map(|&v| dim_px(v)).collect(),
+        ..bright.clone()
+    };
+    // Тот же символ со сдвигом в холсте 121×121 — не осевой фоллбэк, а проективный семплинг.
+    let w = 121;
+    let mut shifted = LumaImage {
+        data: vec![dim_px(255); w * w],
+        width: w,
+        height: w,
+    };
+    for y in 0..dim.height {
+        let dst = (y + 3) * w + 3;
+        shifted.data[dst..dst + dim.width]
+            .copy_from_slice(&dim.data[y * dim.width..(y + 1) * dim.width]);
+    }
+
+    let qr_only = |dark_threshold| {
+        let qr = ultracode::qr::QrOptions {
+            dark_threshold,
+            ..Default::default()
+        };
+        PipelineBuilder::new()
+            .enable_ean13_upca(false)
+            .enable_code128(false)
+            .enable_code93(false)
+            .qr_options(qr)
+            .build()
+    };
+    for img in [&dim, &shifted] {
+        assert!(
+            qr_only(Some(128)).decode_first(img).is_none(),
+            "fixed 128 sees every module as dark"
+        );
+        let sym = qr_only(None).decode_first(img).expect("auto threshold");
+        assert_eq!(sym.text, "DIM QR");
+        // тихая зона меряется тем же порогом — тусклое поле вокруг не «тёмное»
+        assert_eq!(sym.extras.get_str("quiet_zone"), None, "{sym:?}");
+        assert_eq!(
+            qr_only(Some(65))
+                .decode_first(img)
+                .map(|s| s.text)
+                .as_deref(),
+            Some("DIM QR")
+        );
+    }
+}
+
+#[test]
+fn qr_format_read_retries_with_shifted_sampling() {
+    use ultracode::qr::format::FORMAT_READ_PATHS_V1;
+    // Левые 5 из 8 px первых четырёх модулей формата на обеих дорожках инвертированы:
+    // центр модуля читается неверно (4 ошибки — больше, чем исправляет BCH), правый край — верно.
+    let unit = 8;
+    let img = ultracode::qr::encode::encode_qr_v1(b"RETRY", EcLevel::M, 2, 4, unit).unwrap();
+    let w = img.width + 3; // не кратно 29 — без осевого фоллбэка
+    let mut luma = LumaImage {
+        data: vec![255; w * (img.height + 3)],
+        width: w,
+        height: img.height + 3,
+    };
+    for y in 0..img.height {
+        luma.data[y * w..y * w + img.width]
+            .copy_from_slice(&img.data[y * img.width..(y + 1) * img.width]);
+    }
+    for path in FORMAT_READ_PATHS_V1 {
+        for &(x, y) in &path[..4] {
+            for py in (y + 4) * unit..(y + 5) * unit {
+                for px in (x + 4) * unit..(x + 4) * unit + 5 {
+                    luma.data[py * w + px] = 255 - luma.data[py * w + px];
+                }
+            }
+        }
+    }
+
+    let with_retries = |format_retries| {
+        let qr = ultracode::qr::QrOptions {
+            format_retries,
+            ..Default::default()
+        };
+        PipelineBuilder::new().qr_options(qr).build()
+    };
+    assert!(with_retries(0).decode_first(&luma).is_none());
+    let sym = with_retries(4)
+        .decode_first(&luma)
+        .expect("shifted resample reads the format");
+    assert_eq!(sym.text, "RETRY");
+    assert_eq!(sym.extras.get_str("qr.format_retries"), Some("1"));
+    // Чистый символ повторов не тратит.
+    let clean = with_retries(4).decode_first(&img).expect("clean");
+    assert_eq!(clean.extras.get_str("qr.format_retries"), None);
+}
+
+#[test]
+fn qr_with_unparsed_mode_keeps_corrected_codewords() {
+    // Alphanumeric (0010) — разборщик знает только Byte mode, но RS верный:
+    // символ отдаётся с пустым текстом и data-кодвордами в `bytes`.
+    let mut data_cw = vec![
+        0x20, 0x5B, 0x0B, 0x78, 0xD1, 0x72, 0xDC, 0x4D, 0x43, 0x40, 0xEC, 0x11, 0xEC,
+    ];
+    let img: LumaImage =
+        ultracode::qr::encode::encode_qr_v1_data_codewords(&data_cw, EcLevel::Q, 2, 4, 4).unwrap();
+    let sym = PipelineBuilder::new()
+        .build()
+        .decode_first(&img)
+        .expect("QR symbol");
+    assert_eq!(sym.symbology, Symbology::QR);
+    assert_eq!(sym.text, "");
+    assert_eq!(sym.bytes.as_deref(), Some(data_cw.as_slice()));
+    assert_eq!(sym.extras.get_str("qr.parse"), Some("failed"));
+    assert_eq!(sym.extras.get_parsed::<u8>("qr.mode"), Some(2));
+    assert_eq!(sym.extras.get_str("qr.charset"), None);
+
+    // Byte mode с длиной 200 — сегмент не помещается в блок.
+    data_cw = vec![
+        0x4C, 0x84, 0x10, 0xEC, 0x11, 0xEC, 0x11, 0xEC, 0x11, 0xEC, 0x11, 0xEC, 0x11,
+    ];
+    let img: LumaImage =
+        ultracode::qr::encode::encode_qr_v1_data_codewords(&data_cw, EcLevel::Q, 5, 4, 4).unwrap();
+    let sym = PipelineBuilder::new()
+        .build()
+        .decode_first(&img)
+        .expect("QR symbol");
+    assert_eq!(
+        (sym.text.as_str(), sym.bytes.as_deref()),
+        ("", Some(data_cw.as_slice()))
+    );
+    assert_eq!(sym.extras.get_parsed::<u8>("qr.mode"), Some(4));
+}
+
+#[test]
+fn decode_tiled_keeps_distinct_unparsed_qr_symbols() {
+    // Два разных символа в Alphanumeric: оба с пустым текстом, но кодворды
+    // у них разные — дедупликация не должна склеить их в один.
+    let first = vec![
+        0x20, 0x5B, 0x0B, 0x78, 0xD1, 0x72, 0xDC, 0x4D, 0x43, 0x40, 0xEC, 0x11, 0xEC,
+    ];
+    let second = vec![
+        0x20, 0x5B, 0x0B, 0x78, 0xD1, 0x72, 0xDC, 0x4D, 0x43, 0x40, 0xEC, 0x11, 0x11,
+    ];
+    let encode = |cw: &[u8]| {
+        ultracode::qr::encode::encode_qr_v1_data_codewords(cw, EcLevel::Q, 2, 4, 4).unwrap()
+    };
+    let (a, b) = (encode(&first), encode(&second));
+    let (w, h) = (a.width + b.width, a.height);
+    let mut img = LumaImage {
+        data: vec![255; w * h],
+        width: w,
+        height: h,
+    };
+    for y in 0..h {
+        img.data[y * w..y * w + a.width].copy_from_slice(a.row(y));
+        img.data[y * w + a.width..(y + 1) * w].copy_from_slice(b.row(y));
+    }
+
+    let found = PipelineBuilder::new()
+        .build()
+        .decode_tiled(&img, a.width, h, 0);
+    let bytes: Vec<_> = found
+        .iter()
+        .filter(|s| s.symbology == Symbology::QR)
+        .map(|s| s.bytes.as_deref())
+        .collect();
+    assert_eq!(
+        bytes,
+        [Some(first.as_slice()), Some(second.as_slice())],
+        "{found:?}"
+    );
+}
+
+#[test]
+fn code128_reports_orientation_for_flipped_and_rotated_labels() {
+    // Этикетка в верхней полосе кадра, ниже — белое: отражение сверху вниз
+    // уводит её к нижнему краю, и строки должны дойти и туда.
+    let row = ultracode::synthesize_row_code128("FLIP-128", 'B', 10, 2).unwrap();
+    let (width, height, band) = (row.len(), 40, 12);
+    let mut data = row.repeat(band);
+    data.resize(width * height, 255);
+    let flip_v = |d: &[u8]| {
+        d.chunks(width)
+            .rev()
+            .flatten()
+            .copied()
+            .collect::<Vec<u8>>()
+    };
+    let flip_h = |d: &[u8]| {
+        d.chunks(width)
+            .flat_map(|r| r.iter().rev())
+            .copied()
+            .collect::<Vec<u8>>()
+    };
+
+    let cases = [
+        ("Rot0", data.clone(), Orientation::Rot0, false),
+        ("MirrorV", flip_v(&data), Orientation::Rot0, true),
+        ("MirrorH", flip_h(&data), Orientation::Rot180, false),
+        ("Rot180", flip_v(&flip_h(&data)), Orientation::Rot180, true),
+    ];
+    let pipe = PipelineBuilder::new().build();
+    for (name, data, expected, at_bottom) in cases {
+        let img = LumaImage {
+            data,
+            width,
+            height,
+        };
+        let sym = pipe
+            .decode_first(&img)
+            .unwrap_or_else(|| panic!("{name}: Code128 not found"));
+        assert_eq!(sym.text, "FLIP-128", "{name}");
+        assert_eq!(sym.orientation, Some(expected), "{name}");
+        let row: usize = sym.extras.get_parsed("row").expect("row");
+        assert_eq!(row >= height - band, at_bottom, "{name}: row {row}");
+
+        let barcodes =
+            ultracode::one_d::decode_code128(&img.as_gray(), &ultracode::DecodeOptions::default());
+        assert!(
+            !barcodes.is_empty() && barcodes.iter().all(|b| b.orientation() == expected),
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn qr_mask_search_recovers_corrupted_mask_bits() {
+    use ultracode::qr::format::{decode_format_word, encode_format_bits_for_tests};
+    // Формат M/маска 2 переписан словом на расстоянии 3 от M/маски `wrong`
+    // (и 4 от верного): BCH уверенно отдаёт чужую маску.
+    let (unit, quiet) = (4, 4);
+    let truth = encode_format_bits_for_tests(EcLevel::M, 2);
+    let wrong = (0..8u8)
+        .find(|&m| (truth ^ encode_format_bits_for_tests(EcLevel::M, m)).count_ones() >= 7)
+        .expect("far mask");
+    let mut word = encode_format_bits_for_tests(EcLevel::M, wrong);
+    let mut diff = truth ^ word;
+    for _ in 0..3 {
+        let bit = diff & diff.wrapping_neg();
+        word ^= bit;
+        diff ^= bit;
+    }
+    assert_eq!(decode_format_word(word), Some((EcLevel::M, wrong, 3)));
+
+    let img = ultracode::qr::encode::encode_qr_v1(b"MASKED", EcLevel::M, 2, quiet, unit).unwrap();
+    let mut luma = img.clone();
+    overwrite_qr_format(&mut luma, word, quiet, unit);
+
+    let with_search = |mask_search_distance| {
+        let qr = ultracode::qr::QrOptions {
+            mask_search_distance,
+            ..Default::default()
+        };
+        PipelineBuilder::new().qr_options(qr).build()
+    };
+    assert!(with_search(None).decode_first(&luma).is_none());
+    let sym = with_search(Some(3))
+        .decode_first(&luma)
+        .expect("mask found by RS");
+    assert_eq!(sym.text, "MASKED");
+    assert_eq!(sym.extras.get_parsed::<u8>("qr.mask"), Some(2));
+    assert_eq!(sym.extras.get_parsed::<u8>("qr.format_mask"), Some(wrong));
+    assert_eq!(sym.extras.get_str("qr.mask_search"), Some("true"));
+    // Чистый формат (расстояние 0) перебор не запускает.
+    let clean = with_search(Some(3)).decode_first(&img).expect("clean");
+    assert_eq!(clean.extras.get_str("qr.mask_search"), None);
+}
+
+/// Переписать оба экземпляра слова формата v1 (`word`, старший бит первым).
+fn overwrite_qr_format(img: &mut LumaImage, word: u16, quiet: usize, unit: usize) {
+    for path in ultracode::qr::format::FORMAT_READ_PATHS_V1 {
+        for (i, &(x, y)) in path.iter().enumerate() {
+            let v = if word >> (14 - i) & 1 == 1 { 0 } else { 255 };
+            for py in (y + quiet) * unit..(y + quiet + 1) * unit {
+                let row = py * img.width;
+                img.data[row + (x + quiet) * unit..row + (x + quiet + 1) * unit].fill(v);
+            }
+        }
+    }
+}
+
+#[test]
+fn qr_format_distance_lowers_confidence() {
+    use ultracode::qr::format::{decode_format_word, encode_format_bits_for_tests};
+    // Слово формата с двумя битыми битами: BCH исправляет его сам (без перебора
+    // масок), но символ на пределе коррекции читается с меньшей уверенностью.
+    let (unit, quiet) = (4, 4);
+    let word = encode_format_bits_for_tests(EcLevel::M, 2) ^ 0b100_0000_0000_0001;
+    assert_eq!(decode_format_word(word), Some((EcLevel::M, 2, 2)));
+    let img = ultracode::qr::encode::encode_qr_v1(b"FORMAT", EcLevel::M, 2, quiet, unit).unwrap();
+    let mut worn = img.clone();
+    overwrite_qr_format(&mut worn, word, quiet, unit);
+
+    let pipe = PipelineBuilder::new().build();
+    let clean = pipe.decode_first(&img).expect("clean");
+    let sym = pipe.decode_first(&worn).expect("format corrected by BCH");
+    assert_eq!(sym.text, "FORMAT");
+    assert_eq!(clean.extras.get_parsed::<u32>("qr.format_dist"), Some(0));
+    assert_eq!(sym.extras.get_parsed::<u32>("qr.format_dist"), Some(2));
+    assert!(
+        sym.confidence < clean.confidence,
+        "{} vs {}",
+        sym.confidence,
+        clean.confidence
+    );
+}
+
+#[test]
+fn qr_grid_shift_recovers_finders_off_by_one_module() {
+    // Все три finder'а перерисованы на модуль правее: рамка по ним съезжает на
+    // модуль, данные читаются только сеткой, сдвинутой обратно. Ширина не кратна
+    // 29 — осевой fallback (он не смотрит на finder'ы) не срабатывает.
+    let (unit, quiet) = (4, 4);
+    let src = ultracode::qr::encode::encode_qr_v1(b"SHIFT", EcLevel::H, 2, quiet, unit).unwrap();
+    let w = src.width + 3;
+    let mut img = LumaImage {
+        data: vec![255; w * src.height],
+        width: w,
+        height: src.height,
+    };
+    for (dst, row) in img.data.chunks_mut(w).zip(src.data.chunks(src.width)) {
+        dst[..src.width].copy_from_slice(row);
+    }
+    let mut put = |mx: usize, my: usize, dark: bool| {
+        for py in (my + quiet) * unit..(my + quiet + 1) * unit {
+            img.data[py * w + (mx + quiet) * unit..py * w + (mx + quiet + 1) * unit]
+                .fill(if dark { 0 } else { 255 });
+        }
+    };
+    for (ox, oy) in [(0, 0), (14, 0), (0, 14)] {
+        for (mx, my) in (0..7).flat_map(|x| (0..7).map(move |y| (x, y))) {
+            put(ox + mx, oy + my, false);
+        }
+        for (mx, my) in (0..7).flat_map(|x| (0..7).map(move |y| (x, y))) {
+            let ring = mx == 0 || mx == 6 || my == 0 || my == 6;
+            let core = (2..=4).contains(&mx) && (2..=4).contains(&my);
+            put(ox + mx + 1, oy + my, ring || core);
+        }
+    }
+
+    // Сдвинутые finder'ы портят структурный балл — порог снимаем.
+    let qr = ultracode::qr::QrOptions {
+        min_struct_score: 0.0,
+        ..Default::default()
+    };
+    let sym = PipelineBuilder::new()
+        .qr_options(qr)
+        .build()
+        .decode_first(&img)
+        .expect("QR via grid shift");
+    assert_eq!(sym.text, "SHIFT");
+    assert_eq!(sym.extras.get_str("qr.grid_shift"), Some("-1,0"));
+
+    let qr = ultracode::qr::QrOptions {
+        grid_shift_recovery: false,
+        ..qr
+    };
+    assert!(PipelineBuilder::new()
+        .qr_options(qr)
+        .build()
+        .decode_first(&img)
+        .is_none());
+}
+
+#[test]
+fn qr_raw_codewords_show_what_rs_corrected() {
+    // Инвертирован модуль (20, 20) — с него начинается обход данных v1.
+    let (unit, quiet) = (4, 4);
+    let mut luma: LumaImage =
+        ultracode::qr::encode::encode_qr_v1(b"RAW-CW", EcLevel::M, 0, 4, unit).unwrap();
+    for py in (20 + quiet) * unit..(21 + quiet) * unit {
+        for px in (20 + quiet) * unit..(21 + quiet) * unit {
+            luma.data[py * luma.width + px] = 255 - luma.data[py * luma.width + px];
+        }
+    }
+
+    let sym = PipelineBuilder::new()
+        .build()
+        .decode_first(&luma)
+        .expect("QR");
+    assert_eq!(sym.extras.get_str("qr.cw_orig"), None);
+
+    let sym = PipelineBuilder::new()
+        .include_raw_codewords(true)
+        .build()
+        .decode_first(&luma)
+        .expect("QR");
+    assert_eq!(sym.text, "RAW-CW");
+    let orig = sym.extras.get_str("qr.cw_orig").expect("cw_orig");
+    let corrected = sym.extras.get_str("qr.cw_corrected").expect("cw_corrected");
+    assert_eq!((orig.len(), corrected.len()), (52, 52));
+    let differ = (0..26)
+        .filter(|i| orig[2 * i..2 * i + 2] != corrected[2 * i..2 * i + 2])
+        .count();
+    assert_eq!(differ, 1, "{orig} vs {corrected}");
+    assert_eq!(
+        sym.extras.get_parsed::<usize>("qr.rs_corrected_bytes"),
+        Some(1)
+    );
+}
+
+#[test]
+fn synthesized_quiet_zone_width_drives_quiet_zone_policy() {
+    // qz=8 — поля хватает и строгой политике; qz=0 — символ вплотную к краю:
+    // `Strict` его не берёт, `Relaxed` читает с пометкой `quiet_zone=missing`.
+    let band = |row: Vec<u8>| LumaImage {
+        width: row.len(),
+        height: 8,
+        data: row.repeat(8),
+    };
+    let symbols = |qz: usize| -> Vec<(&str, LumaImage)> {
+        vec![
+            (
+                "QZ-128",
+                band(
+                    ultracode::one_d::code128::synthesize_row_code128("QZ-128", 'B', qz, 2)
+                        .unwrap(),
+                ),
+            ),
+            (
+                "5901234123457",
+                band(ultracode::one_d::ean13::synthesize_ean13_row("590123412345", qz, 2).unwrap()),
+            ),
+            (
+                "QZ93",
+                band(ultracode::one_d::code93::synthesize_row_code93("QZ93", qz, 2).unwrap()),
+            ),
+            (
+                "QZ",
+                ultracode::qr::encode::encode_qr_v1(b"QZ", EcLevel::M, 1, qz, 4).unwrap(),
+            ),
+        ]
+    };
+    let pipe = |mode| {
+        PipelineBuilder::new()
+            .enable_code93(true)
+            .enable_msi(true)
+            .quiet_zone(mode)
+            .build()
+    };
+    let (strict, relaxed) = (pipe(QuietZone::Strict), pipe(QuietZone::Relaxed));
+
+    for (text, img) in symbols(8) {
+        let sym = strict
+            .decode_first(&img)
+            .unwrap_or_else(|| panic!("qz=8 {text}"));
+        assert_eq!(
+            (sym.text.as_str(), sym.extras.get_str("quiet_zone")),
+            (text, None)
+        );
+    }
+    for (text, img) in symbols(0) {
+        assert!(
+            strict.decode_first(&img).is_none(),
+            "qz=0 {text} under Strict"
+        );
+        let sym = relaxed
+            .decode_first(&img)
+            .unwrap_or_else(|| panic!("qz=0 {text}"));
+        assert_eq!(
+            (sym.text.as_str(), sym.extras.get_str("quiet_zone")),
+            (text, Some("missing"))
+        );
+    }
+    // У MSI тихие зоны — часть разметки: без них символ не читается ни при какой политике.
+    let msi = |qz| {
+        use ultracode::one_d::msi::{synthesize_row_msi, MsiCheck};
+        band(synthesize_row_msi("1234", MsiCheck::Mod10, qz, 2).unwrap())
+    };
+    assert_eq!(
+        strict.decode_first(&msi(8)).map(|s| s.text).as_deref(),
+        Some("12344")
+    );
+    assert!(relaxed.decode_first(&msi(0)).is_none());
+    // Ширина ряда: по `quiet_zone·unit` px белого с каждой стороны.
+    let narrow = ultracode::one_d::code128::synthesize_row_code128("QZ-128", 'B', 0, 2).unwrap();
+    let wide = ultracode::synthesize_row_code128("QZ-128", 'B', 10, 2).unwrap();
+    assert_eq!(wide.len(), narrow.len() + 2 * 10 * 2);
+}
+
+#[test]
+fn default_pipeline_reads_qr_without_quiet_zone() {
+    // Обрезанный вплотную QR читался и до проверки тихой зоны — по умолчанию читается
+    // и сейчас, с пометкой.
+    let img: LumaImage = ultracode::qr::encode::encode_qr_v1(b"QZ", EcLevel::M, 1, 0, 4).unwrap();
+    let sym = PipelineBuilder::new()
+        .build()
+        .decode_first(&img)
+        .expect("cropped QR");
+    assert_eq!(
+        (sym.text.as_str(), sym.extras.get_str("quiet_zone")),
+        ("QZ", Some("missing"))
+    );
+}
+
+#[test]
+fn tiny_qr_is_read_after_upscaling() {
+    // Модуль 2 px на кадре 200×200: суперсэмплингу не хватает деталей.
+    let qr = ultracode::qr::encode::encode_qr_v1(b"TINY", EcLevel::L, 2, 4, 2).unwrap();
+    let mut img = LumaImage {
+        data: vec![255; 200 * 200],
+        width: 200,
+        height: 200,
+    };
+    for y in 0..qr.height {
+        img.data[(y + 40) * 200 + 50..(y + 40) * 200 + 50 + qr.width].copy_from_slice(qr.row(y));
+    }
+    let upscaled = |threshold| {
+        let qr = ultracode::qr::QrOptions {
+            upscale_small_codes: threshold,
+            ..Default::default()
+        };
+        PipelineBuilder::new()
+            .qr_options(qr)
+            .build()
+            .decode_first(&img)
+    };
+    assert!(upscaled(None).is_none());
+    // модуль не мельче порога — увеличивать нечего
+    assert!(upscaled(Some(1.5)).is_none());
+
+    let sym = upscaled(Some(3.0)).expect("upscaled QR");
+    assert_eq!(sym.text, "TINY");
+    assert_eq!(sym.extras.get_str("qr.upscale"), Some("2"));
+    let module_px: f32 = sym.extras.get_parsed("qr.module_px").expect("module_px");
+    assert!((module_px - 2.0).abs() < 0.1, "{module_px}");
+    // углы — в координатах кадра (±1 px): символ 21×2 px после тихой зоны 4×2 px
+    let (min, max) = sym.quad.expect("quad").bounding_box();
+    for (got, want) in [(min.x, 58), (min.y, 48), (max.x, 100), (max.y, 90)] {
+        assert!(got.abs_diff(want) <= 1, "{:?}", (min, max));
+    }
+}
+
+#[test]
+fn tiny_qr_is_read_when_its_finders_merge() {
+    // Модуль 1 px на кадре 300×300: finder'ы ближе порога кластеризации
+    // (5% стороны кадра) и сливаются — трёх finder'ов на всём кадре нет.
+    let qr = ultracode::qr::encode::encode_qr_v1(b"TINY", EcLevel::L, 2, 4, 1).unwrap();
+    let mut img = LumaImage {
+        data: vec![255; 300 * 300],
+        width: 300,
+        height: 300,
+    };
+    for y in 0..qr.height {
+        img.data[(y + 60) * 300 + 75..(y + 60) * 300 + 75 + qr.width].copy_from_slice(qr.row(y));
+    }
+    let opts = ultracode::qr::QrOptions::default();
+    assert!(ultracode::qr::finder::find_finder_patterns(&img.as_gray(), &opts).len() < 3);
+    let upscaled = |threshold| {
+        let qr = ultracode::qr::QrOptions {
+            upscale_small_codes: threshold,
+            ..opts
+        };
+        PipelineBuilder::new()
+            .qr_options(qr)
+            .build()
+            .decode_first(&img)
+    };
+    assert!(upscaled(None).is_none());
+
+    let sym = upscaled(Some(3.0)).expect("QR found near the merged finder cluster");
+    assert_eq!(sym.text, "TINY");
+    assert!(sym.extras.get_str("qr.upscale").is_some());
+    // углы — в координатах кадра (±1 px): символ 21×1 px после тихой зоны 4×1 px
+    let (min, max) = sym.quad.expect("quad").bounding_box();
+    for (got, want) in [(min.x, 79), (min.y, 64), (max.x, 100), (max.y, 85)] {
+        assert!(got.abs_diff(want) <= 1, "{:?}", (min, max));
+    }
+}
+
+#[test]
+fn per_symbology_confidence_thresholds_override_global() {
+    // QR с тремя инвертированными модулями (RS исправляет — уверенность ниже)
+    // над чистым EAN-13.
+    let (unit, quiet) = (4, 4);
+    let mut qr: LumaImage =
+        ultracode::qr::encode::encode_qr_v1(b"LOW", EcLevel::L, 0, 4, unit).unwrap();
+    for (mx, my) in [(20, 20), (19, 18), (20, 16)] {
+        for py in (my + quiet) * unit..(my + 1 + quiet) * unit {
+            for px in (mx + quiet) * unit..(mx + 1 + quiet) * unit {
+                qr.data[py * qr.width + px] = 255 - qr.data[py * qr.width + px];
+            }
+        }
+    }
+    let ean = ean_row("5901234123457", 1);
+    let width = qr.width.max(ean.len());
+    let mut data = Vec::new();
+    for row in (0..qr.height)
+        .map(|y| qr.row(y))
+        .chain(std::iter::repeat(ean.as_slice()).take(16))
+    {
+        data.extend_from_slice(row);
+        data.resize(data.len() + width - row.len(), 255);
+    }
+    let img = LumaImage {
+        height: data.len() / width,
+        width,
+        data,
+    };
+
+    let found = |b: PipelineBuilder| -> Vec<(Symbology, f32)> {
+        b.build()
+            .decode_all(&img)
+            .into_iter()
+            .map(|s| (s.symbology, s.confidence))
+            .collect()
+    };
+    let all = found(PipelineBuilder::new());
+    assert_eq!(
+        all.iter().map(|s| s.0).collect::<Vec<_>>(),
+        [Symbology::Ean13, Symbology::QR]
+    );
+    let (ean_conf, qr_conf) = (all[0].1, all[1].1);
+    assert!(qr_conf < ean_conf, "{all:?}");
+    let between = (qr_conf + ean_conf) / 2.0;
+
+    // порог только для QR
+    let strict_qr = found(PipelineBuilder::new().min_confidence_for(Symbology::QR, between));
+    assert_eq!(strict_qr, [(Symbology::Ean13, ean_conf)]);
+    // общий порог выше обоих, но у EAN — свой, ниже его уверенности
+    let lenient_ean = PipelineBuilder::new()
+        .min_confidence(ean_conf + 0.01)
+        .min_confidence_for(Symbology::Ean13, between);
+    assert_eq!(found(lenient_ean), [(Symbology::Ean13, ean_conf)]);
+    assert!(found(PipelineBuilder::new().min_confidence(ean_conf + 0.01)).is_empty());
+
+    // decode_first пропускает EAN ниже его порога и отдаёт следующий за ним QR
+    let pipe = PipelineBuilder::new()
+        .min_confidence_for(Symbology::Ean13, ean_conf + 0.01)
+        .build();
+    assert_eq!(
+        pipe.decode_first(&img).map(|s| s.symbology),
+        Some(Symbology::QR)
+    );
+    assert_eq!(
+        pipe.decode_first_gray(&img.as_gray()).map(|s| s.symbology),
+        Some(Symbology::QR)
+    );
+
+    // ленивый обход порогов не применяет
+    let pipe = PipelineBuilder::new().min_confidence(1.0).build();
+    assert_eq!(pipe.decode_iter(&img).count(), 2);
+    assert!(pipe.decode_first(&img).is_none());
+}
+
+#[test]
+fn compat_upca_only_drops_ean13_reads() {
+    use ultracode::{decode_formats, BarcodeFormat, DecodeOptions, GrayImage};
+    let formats = |digits: &str, formats: &[BarcodeFormat]| {
+        let row = ean_row(digits, 2);
+        let data = row.repeat(10);
+        let img = GrayImage {
+            data: &data,
+            width: row.len(),
+            height: 10,
+        };
+        decode_formats(img, DecodeOptions::default(), formats)
+            .into_iter()
+            .map(|b| (b.format, b.text))
+            .collect::<Vec<_>>()
+    };
+    let upca = || (BarcodeFormat::UPCA, "036000291452".to_string());
+    assert_eq!(formats("036000291452", &[BarcodeFormat::UPCA]), [upca()]);
+    assert_eq!(formats("036000291452", &[BarcodeFormat::EAN13]), [upca()]);
+    assert!(formats("5901234123457", &[BarcodeFormat::UPCA]).is_empty());
+    assert_eq!(
+        formats("5901234123457", &[BarcodeFormat::EAN13]),
+        [(BarcodeFormat::EAN13, "5901234123457".to_string())]
+    );
+
+    // Barcode закрыт для литералов снаружи — собирается конструктором.
+    let b = ultracode::Barcode::new(BarcodeFormat::Code128, "ABC".to_string(), 7);
+    assert_eq!((b.row, b.modules, b.module_px()), (7, 0, 0.0));
+}
+
+#[test]
+fn scan_pgm_csv_matches_decode_formats() {
+    use std::process::Command;
+    use ultracode::{decode_formats, BarcodeFormat, DecodeOptions, GrayImage};
+    let row = ean_row("036000291452", 2);
+    let data = row.repeat(10);
+    let img = GrayImage {
+        data: &data,
+        width: row.len(),
+        height: 10,
+    };
+    let path = std::env::temp_dir().join(format!("ultracode_csv_upca_{}.pgm", std::process::id()));
+    let mut pgm = format!("P5\n{} {}\n255\n", img.width, img.height).into_bytes();
+    pgm.extend_from_slice(&data);
+    fs::write(&path, pgm).unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_scan_pgm"))
+        .arg(&path)
+        .args(["--formats", "ean13", "--csv"])
+        .output()
+        .expect("scan_pgm runs");
+    fs::remove_file(&path).ok();
+    assert!(out.status.success());
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    // symbology,text,confidence,row,x_start,x_end
+    let csv: Vec<Vec<String>> = stdout
+        .lines()
+        .skip(1)
+        .map(|line| line.split(',').map(String::from).collect())
+        .collect();
+    let picked: Vec<[&str; 4]> = csv
+        .iter()
+        .map(|f| [&*f[0], &*f[1], &*f[4], &*f[5]])
+        .collect();
+
+    // Те же прочтения, что у decode_formats: UPC-A приходит и при одном ean13;
+    // x — положение 1D-символа в строке.
+    let expected: Vec<[String; 4]> =
+        decode_formats(img, DecodeOptions::default(), &[BarcodeFormat::EAN13])
+            .into_iter()
+            .map(|b| {
+                [
+                    format!("{:?}", b.format).to_ascii_lowercase(),
+                    b.text,
+                    b.x.to_string(),
+                    (b.x + b.width_px).to_string(),
+                ]
+            })
+            .collect();
+    let q = 9 * 2;
+    assert_eq!(
+        expected,
+        [[
+            "upca".to_string(),
+            "036000291452".to_string(),
+            q.to_string(),
+            (q + 190).to_string()
+        ]]
+    );
+    assert_eq!(
+        picked,
+        expected
+            .iter()
+            .map(|e| [&*e[0], &*e[1], &*e[2], &*e[3]])
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn shelf_label_with_two_eans_on_one_line_yields_both() {
+    let first = ean_row("5901234123457", 2);
+    let second = ean_row("4006381333931", 2);
+    let row = [first.as_slice(), second.as_slice()].concat();
+    let img = LumaImage {
+        width: row.len(),
+        height: 12,
+        data: row.repeat(12),
+    };
+
+    let found: Vec<(String, Option<usize>, Option<usize>)> = PipelineBuilder::new()
+        .build()
+        .decode_all(&img)
+        .into_iter()
+        .map(|s| {
+            (
+                s.text,
+                s.extras.get_parsed("x_start"),
+                s.extras.get_parsed("x_end"),
+            )
+        })
+        .collect();
+    let q = 9 * 2;
+    assert_eq!(
+        found,
+        [
+            ("5901234123457".to_string(), Some(q), Some(q + 190)),
+            (
+                "4006381333931".to_string(),
+                Some(first.len() + q),
+                Some(first.len() + q + 190)
+            ),
+        ]
+    );
+}